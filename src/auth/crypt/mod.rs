@@ -0,0 +1,58 @@
+//! The password hashes found in `/etc/shadow`, checked without libcrypt.
+
+mod bcrypt;
+mod sha1;
+mod sha2;
+mod sha_crypt;
+mod yescrypt;
+
+pub use sha1::hmac_sha1;
+
+use std::fmt;
+
+/// The base64 alphabet of crypt(3) hashes.
+const ITOA64: &[u8; 64] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The hash is of a kind, or uses settings, that cannot be checked.
+    Unsupported,
+    Malformed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "unsupported password hash"),
+            Self::Malformed => write!(f, "malformed password hash"),
+        }
+    }
+}
+
+/// Whether `password` hashes to `hash`, a crypt(3) string such as `$y$j9T$…`.
+pub fn verify(password: &[u8], hash: &str) -> Result<bool, Error> {
+    if let Some(rest) = hash.strip_prefix("$y$") {
+        yescrypt::verify(password, rest)
+    } else if let Some(rest) = hash.strip_prefix("$6$") {
+        sha_crypt::verify_sha512(password, rest)
+    } else if let Some(rest) = hash.strip_prefix("$5$") {
+        sha_crypt::verify_sha256(password, rest)
+    } else if let Some(rest) = hash.strip_prefix("$2") {
+        bcrypt::verify(password, rest)
+    } else {
+        Err(Error::Unsupported)
+    }
+}
+
+/// Compares two byte strings in time that depends only on their lengths.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a
+        .iter()
+        .zip(b)
+        .fold(0, |difference, (a, b)| difference | (a ^ b));
+    // Keep the compiler from turning the fold into an early exit.
+    std::hint::black_box(difference) == 0
+}
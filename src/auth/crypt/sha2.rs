@@ -0,0 +1,402 @@
+//! SHA-256 and SHA-512, with the HMAC and PBKDF2 constructions the password hashes build on.
+
+/// Generates a SHA-2 hash over one word size: the message schedule, compression and padding
+/// are the same for both, with different constants and rotations.
+macro_rules! sha2 {
+    (
+        $name:ident, $word:ty, $block:literal, $output:literal, $k:ident, $iv:ident,
+        $big:expr, $small0:expr, $small1:expr
+    ) => {
+        #[derive(Clone)]
+        pub struct $name {
+            state: [$word; 8],
+            buffer: [u8; $block],
+            filled: usize,
+            length: u128,
+        }
+
+        impl $name {
+            pub fn new() -> Self {
+                Self {
+                    state: $iv,
+                    buffer: [0; $block],
+                    filled: 0,
+                    length: 0,
+                }
+            }
+
+            pub fn update(&mut self, mut data: &[u8]) {
+                self.length += data.len() as u128;
+                while !data.is_empty() {
+                    let take = ($block - self.filled).min(data.len());
+                    self.buffer[self.filled..self.filled + take].copy_from_slice(&data[..take]);
+                    self.filled += take;
+                    data = &data[take..];
+                    if self.filled == $block {
+                        self.compress();
+                        self.filled = 0;
+                    }
+                }
+            }
+
+            pub fn finish(mut self) -> [u8; $output] {
+                const WORD: usize = size_of::<$word>();
+                let bits = self.length * 8;
+                self.buffer[self.filled] = 0x80;
+                self.buffer[self.filled + 1..].fill(0);
+                // The length takes the last two words of a block.
+                if self.filled + 1 > $block - 2 * WORD {
+                    self.compress();
+                    self.buffer.fill(0);
+                }
+                let length = bits.to_be_bytes();
+                self.buffer[$block - 2 * WORD..].copy_from_slice(&length[16 - 2 * WORD..]);
+                self.compress();
+                let mut output = [0; $output];
+                for (chunk, word) in output.chunks_exact_mut(WORD).zip(self.state) {
+                    chunk.copy_from_slice(&word.to_be_bytes());
+                }
+                self.buffer.fill(0);
+                output
+            }
+
+            fn compress(&mut self) {
+                const WORD: usize = size_of::<$word>();
+                let [big0, big1]: [[u32; 3]; 2] = $big;
+                let [s0, s1]: [[u32; 3]; 2] = [$small0, $small1];
+                let mut w = [0; $k.len()];
+                for (word, chunk) in w.iter_mut().zip(self.buffer.chunks_exact(WORD)) {
+                    *word = <$word>::from_be_bytes(chunk.try_into().unwrap());
+                }
+                for i in 16..w.len() {
+                    let (a, b) = (w[i - 15], w[i - 2]);
+                    let sigma0 = a.rotate_right(s0[0]) ^ a.rotate_right(s0[1]) ^ (a >> s0[2]);
+                    let sigma1 = b.rotate_right(s1[0]) ^ b.rotate_right(s1[1]) ^ (b >> s1[2]);
+                    w[i] = w[i - 16]
+                        .wrapping_add(sigma0)
+                        .wrapping_add(w[i - 7])
+                        .wrapping_add(sigma1);
+                }
+                let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+                for (k, w) in $k.iter().zip(w) {
+                    let sigma1 =
+                        e.rotate_right(big1[0]) ^ e.rotate_right(big1[1]) ^ e.rotate_right(big1[2]);
+                    let choice = (e & f) ^ (!e & g);
+                    let t1 = h
+                        .wrapping_add(sigma1)
+                        .wrapping_add(choice)
+                        .wrapping_add(*k)
+                        .wrapping_add(w);
+                    let sigma0 =
+                        a.rotate_right(big0[0]) ^ a.rotate_right(big0[1]) ^ a.rotate_right(big0[2]);
+                    let majority = (a & b) ^ (a & c) ^ (b & c);
+                    let t2 = sigma0.wrapping_add(majority);
+                    (h, g, f, e) = (g, f, e, d.wrapping_add(t1));
+                    (d, c, b, a) = (c, b, a, t1.wrapping_add(t2));
+                }
+                for (state, word) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+                    *state = state.wrapping_add(word);
+                }
+            }
+        }
+    };
+}
+
+sha2!(
+    Sha256,
+    u32,
+    64,
+    32,
+    K256,
+    IV256,
+    [[2, 13, 22], [6, 11, 25]],
+    [7, 18, 3],
+    [17, 19, 10]
+);
+sha2!(
+    Sha512,
+    u64,
+    128,
+    64,
+    K512,
+    IV512,
+    [[28, 34, 39], [14, 18, 41]],
+    [1, 8, 7],
+    [19, 61, 6]
+);
+
+impl Sha256 {
+    pub fn digest(data: &[u8]) -> [u8; 32] {
+        let mut hash = Self::new();
+        hash.update(data);
+        hash.finish()
+    }
+}
+
+/// HMAC-SHA-256 of `message` under `key`.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut hmac = HmacSha256::new(key);
+    hmac.update(message);
+    hmac.finish()
+}
+
+/// An HMAC-SHA-256 computation, for messages fed in pieces.
+#[derive(Clone)]
+pub struct HmacSha256 {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl HmacSha256 {
+    pub fn new(key: &[u8]) -> Self {
+        let mut block = [0; 64];
+        if key.len() > block.len() {
+            block[..32].copy_from_slice(&Sha256::digest(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let (mut inner, mut outer) = (Sha256::new(), Sha256::new());
+        inner.update(&block.map(|byte| byte ^ 0x36));
+        outer.update(&block.map(|byte| byte ^ 0x5c));
+        block.fill(0);
+        Self { inner, outer }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finish(mut self) -> [u8; 32] {
+        let inner = self.inner.finish();
+        self.outer.update(&inner);
+        self.outer.finish()
+    }
+}
+
+/// PBKDF2 with HMAC-SHA-256, filling `output`.
+pub fn pbkdf2_sha256(password: &[u8], salt: &[u8], rounds: u32, output: &mut [u8]) {
+    let keyed = HmacSha256::new(password);
+    for (index, chunk) in output.chunks_mut(32).enumerate() {
+        let mut hmac = keyed.clone();
+        hmac.update(salt);
+        hmac.update(&(index as u32 + 1).to_be_bytes());
+        let mut u = hmac.finish();
+        let mut t = u;
+        for _ in 1..rounds {
+            let mut hmac = keyed.clone();
+            hmac.update(&u);
+            u = hmac.finish();
+            t.iter_mut().zip(u).for_each(|(t, u)| *t ^= u);
+        }
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+}
+
+const IV256: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const K256: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const IV512: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const K512: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn sha512(data: &[u8]) -> String {
+        let mut hash = Sha512::new();
+        hash.update(data);
+        hex(&hash.finish())
+    }
+
+    const TWO_BLOCKS: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+
+    #[test]
+    fn sha256_fips_180_vectors() {
+        assert_eq!(
+            hex(&Sha256::digest(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(&Sha256::digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(&Sha256::digest(TWO_BLOCKS)),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        let mut hash = Sha256::new();
+        for _ in 0..10000 {
+            hash.update(&[b'a'; 100]);
+        }
+        assert_eq!(
+            hex(&hash.finish()),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn sha512_fips_180_vectors() {
+        assert_eq!(
+            sha512(b""),
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+             47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"
+        );
+        assert_eq!(
+            sha512(b"abc"),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        assert_eq!(
+            sha512(TWO_BLOCKS),
+            "204a8fc6dda82f0a0ced7beb8e08a41657c16ef468b228a8279be331a703c335\
+             96fd15c13b1b07f9aa1d3bea57789ca031ad85c7a71dd70354ec631238ca3445"
+        );
+        assert_eq!(
+            sha512(&[b'a'; 1_000_000]),
+            "e718483d0ce769644e2e42c7bc15b4638e1f98b13b2044285632a803afa973eb\
+             de0ff244877ea60a4cb0432ce577c31beb009c5c2c49aa2e4eadb217ad8cc09b"
+        );
+    }
+
+    #[test]
+    fn rfc4231_hmac_vectors() {
+        assert_eq!(
+            hex(&hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn pbkdf2_vectors() {
+        // RFC 7914, section 11.
+        let mut output = [0; 64];
+        pbkdf2_sha256(b"passwd", b"salt", 1, &mut output);
+        assert_eq!(
+            hex(&output),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+        // An output that ends partway through a block.
+        let mut output = [0; 20];
+        pbkdf2_sha256(b"password", b"salt", 4096, &mut output);
+        assert_eq!(hex(&output), "c5e478d59288c841aa530db6845c4c8d962893a0");
+    }
+}
@@ -0,0 +1,288 @@
+//! SHA-crypt, `$5$` (SHA-256) and `$6$` (SHA-512), as specified by Ulrich Drepper and used by
+//! glibc and libxcrypt.
+
+use super::{
+    Error,
+    sha2::{Sha256, Sha512},
+};
+
+const DEFAULT_ROUNDS: u32 = 5000;
+const MIN_ROUNDS: u32 = 1000;
+const MAX_ROUNDS: u32 = 999_999_999;
+const MAX_SALT: usize = 16;
+
+/// The parts of SHA-crypt that differ between the two hashes.
+trait Digest: Clone {
+    const LENGTH: usize;
+    /// Which digest bytes go into each group of four characters, most significant first.
+    const ORDER: &[[usize; 3]];
+    fn new() -> Self;
+    fn update(&mut self, data: &[u8]);
+    fn finish(self) -> Vec<u8>;
+}
+
+impl Digest for Sha256 {
+    const LENGTH: usize = 32;
+    const ORDER: &[[usize; 3]] = &[
+        [0, 10, 20],
+        [21, 1, 11],
+        [12, 22, 2],
+        [3, 13, 23],
+        [24, 4, 14],
+        [15, 25, 5],
+        [6, 16, 26],
+        [27, 7, 17],
+        [18, 28, 8],
+        [9, 19, 29],
+    ];
+
+    fn new() -> Self {
+        Sha256::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Sha256::update(self, data);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        Sha256::finish(self).to_vec()
+    }
+}
+
+impl Digest for Sha512 {
+    const LENGTH: usize = 64;
+    const ORDER: &[[usize; 3]] = &[
+        [0, 21, 42],
+        [22, 43, 1],
+        [44, 2, 23],
+        [3, 24, 45],
+        [25, 46, 4],
+        [47, 5, 26],
+        [6, 27, 48],
+        [28, 49, 7],
+        [50, 8, 29],
+        [9, 30, 51],
+        [31, 52, 10],
+        [53, 11, 32],
+        [12, 33, 54],
+        [34, 55, 13],
+        [56, 14, 35],
+        [15, 36, 57],
+        [37, 58, 16],
+        [59, 17, 38],
+        [18, 39, 60],
+        [40, 61, 19],
+        [62, 20, 41],
+    ];
+
+    fn new() -> Self {
+        Sha512::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        Sha512::update(self, data);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        Sha512::finish(self).to_vec()
+    }
+}
+
+/// Checks `password` against `hash`, the part of a `$5$` string after the prefix.
+pub fn verify_sha256(password: &[u8], hash: &str) -> Result<bool, Error> {
+    verify::<Sha256>(password, hash)
+}
+
+/// Checks `password` against `hash`, the part of a `$6$` string after the prefix.
+pub fn verify_sha512(password: &[u8], hash: &str) -> Result<bool, Error> {
+    verify::<Sha512>(password, hash)
+}
+
+fn verify<D: Digest>(password: &[u8], hash: &str) -> Result<bool, Error> {
+    let (rounds, rest) = match hash.strip_prefix("rounds=") {
+        Some(rest) => {
+            let (rounds, rest) = rest.split_once('$').ok_or(Error::Malformed)?;
+            let rounds: u32 = rounds.parse().map_err(|_| Error::Malformed)?;
+            (rounds.clamp(MIN_ROUNDS, MAX_ROUNDS), rest)
+        }
+        None => (DEFAULT_ROUNDS, hash),
+    };
+    let (salt, checksum) = rest.rsplit_once('$').ok_or(Error::Malformed)?;
+    // Longer salts were cut when the hash was made.
+    let salt = &salt.as_bytes()[..salt.len().min(MAX_SALT)];
+    let mut result = compute::<D>(password, salt, rounds);
+    let mut encoded = String::new();
+    for &[a, b, c] in D::ORDER {
+        encode(&mut encoded, [result[a], result[b], result[c]], 4);
+    }
+    match D::LENGTH {
+        32 => encode(&mut encoded, [0, result[31], result[30]], 3),
+        _ => encode(&mut encoded, [0, 0, result[63]], 2),
+    }
+    result.fill(0);
+    Ok(super::constant_time_eq(
+        encoded.as_bytes(),
+        checksum.as_bytes(),
+    ))
+}
+
+fn compute<D: Digest>(password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+    let length = D::LENGTH;
+    let mut alternate = D::new();
+    alternate.update(password);
+    alternate.update(salt);
+    alternate.update(password);
+    let mut b = alternate.finish();
+
+    let mut a = D::new();
+    a.update(password);
+    a.update(salt);
+    for chunk in (0..password.len()).step_by(length) {
+        a.update(&b[..length.min(password.len() - chunk)]);
+    }
+    let mut bits = password.len();
+    while bits > 0 {
+        if bits & 1 == 1 {
+            a.update(&b);
+        } else {
+            a.update(password);
+        }
+        bits >>= 1;
+    }
+    let mut c = a.finish();
+
+    let mut p = D::new();
+    for _ in 0..password.len() {
+        p.update(password);
+    }
+    let mut dp = p.finish();
+    let mut p_bytes = repeat(&dp, password.len());
+
+    let mut s = D::new();
+    for _ in 0..16 + c[0] as usize {
+        s.update(salt);
+    }
+    let mut ds = s.finish();
+    let s_bytes = repeat(&ds, salt.len());
+
+    for round in 0..rounds {
+        let mut hash = D::new();
+        if round % 2 == 1 {
+            hash.update(&p_bytes);
+        } else {
+            hash.update(&c);
+        }
+        if round % 3 != 0 {
+            hash.update(&s_bytes);
+        }
+        if round % 7 != 0 {
+            hash.update(&p_bytes);
+        }
+        if round % 2 == 1 {
+            hash.update(&c);
+        } else {
+            hash.update(&p_bytes);
+        }
+        c.fill(0);
+        c = hash.finish();
+    }
+    for secret in [&mut b, &mut dp, &mut p_bytes, &mut ds] {
+        secret.fill(0);
+    }
+    c
+}
+
+/// `digest` repeated to `length` bytes.
+fn repeat(digest: &[u8], length: usize) -> Vec<u8> {
+    digest.iter().copied().cycle().take(length).collect()
+}
+
+/// Appends `count` characters for the 24 bits of `bytes`, least significant first.
+fn encode(output: &mut String, bytes: [u8; 3], count: usize) {
+    let mut value = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+    for _ in 0..count {
+        output.push(super::ITOA64[(value & 0x3f) as usize] as char);
+        value >>= 6;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::crypt::{Error, verify};
+
+    /// Hashes made by libxcrypt's crypt(3), with the passwords they were made from.
+    const HASHES: &[(&str, &str)] = &[
+        (
+            "correct horse",
+            "$5$saltstring$vc6YOOogU4kWVvwga8e9zTFgKcy4tb5LaPxIiPJzqEC",
+        ),
+        (
+            "correct horse",
+            "$5$rounds=1000$short$cMPBGnEsf0Llc7/9de4cHWjS1ZlySiS3pL6RjBpcUa4",
+        ),
+        (
+            "",
+            "$5$rounds=1000$empty$FXha5uPEquvEe/9u4fbnefx.bVTs6qjD6G8cTcCjKH/",
+        ),
+        (
+            "pässwörd",
+            "$5$rounds=1000$utf8$mBziV0Goi56tSPuFYnjQfbIpZG11LQ8PSFSpkMKnSRD",
+        ),
+        (
+            "correct horse",
+            "$6$saltstring$.r0X7ub5wGR2v4Xz7svCIozfnlorFh19V89t8KC9Umd81uGhFgsyTRNyzQDXDIm17fFcR9z3\
+             z7oBNdsyYBbtm/",
+        ),
+        (
+            "correct horse",
+            "$6$rounds=1000$short$0e8c4VH2.9WECWJ4hEU7wwl83rldSLVuQftBmF/xkj96Wh4.yNfDVKXLBTXD0R1YG\
+             Fzxp1NCeSFbNup.13awx1",
+        ),
+        (
+            "",
+            "$6$rounds=1000$empty$mD/mfMVeNHBUgTII19Wa60uGXYFiKQekh3g31uXM.1DkGrKMw1ICxo7N9UEq9xthp\
+             eZV2NXWk/C7RFot9C.JR.",
+        ),
+        (
+            "pässwörd",
+            "$6$rounds=1000$utf8$yfePPclWT9o4WMKOUoG0ovIM7w/0JCHOP.doxEQ7DWz/JfyLpWp26YsHCNcIoJmsgr\
+             egmLBlspecU7iMqSrtD.",
+        ),
+    ];
+
+    #[test]
+    fn libxcrypt_vectors() {
+        for &(password, hash) in HASHES {
+            assert_eq!(verify(password.as_bytes(), hash), Ok(true), "{hash}");
+            assert_eq!(verify(b"correct horses", hash), Ok(false), "{hash}");
+        }
+    }
+
+    #[test]
+    fn long_passwords_and_salts() {
+        // Passwords longer than a digest are fed in digest-sized pieces.
+        let password = [b'x'; 100];
+        let sha256 = "$5$rounds=1000$long$Y/qMG/EDYIrQvpRWK71iB7zjjUnvgw4v5QiPoK/joS3";
+        assert_eq!(verify(&password, sha256), Ok(true));
+        let sha512 = "$6$rounds=1000$long$UKr4Zfo/ioVs7ChCCo/HpRNwy62wUYJzMfjfnrhaNuuZ4jcEz7UOrqYs2a\
+                      bMMxSoAb/SK9FT8jLnsLoRPJVBg.";
+        assert_eq!(verify(&password, sha512), Ok(true));
+        assert_eq!(verify(&password[..99], sha512), Ok(false));
+        // Salts are cut to 16 characters when the hash is made, and again when it is checked.
+        let cut = "$5$rounds=5000$toolongsaltstrin$mcwn7GU6ZZJsdLyuDcnWeMTXt6iS54gpCL9qqfB6vqB";
+        let uncut = cut.replace("toolongsaltstrin", "toolongsaltstringtoolong");
+        assert_eq!(verify(b"correct horse", cut), Ok(true));
+        assert_eq!(verify(b"correct horse", &uncut), Ok(true));
+    }
+
+    #[test]
+    fn rejects_malformed_hashes() {
+        for hash in ["$5$rounds=many$salt$x", "$5$rounds=1000", "$6$nochecksum"] {
+            assert_eq!(
+                verify(b"correct horse", hash),
+                Err(Error::Malformed),
+                "{hash}"
+            );
+        }
+    }
+}
@@ -0,0 +1,460 @@
+//! Privilege separation: the real [`Authenticator`] runs in a helper process forked at launch,
+//! before the locker talks to the compositor, so the process that parses Wayland messages never
+//! loads PAM modules or reads the shadow file and can drop any privileges it was started with.
+//!
+//! The two processes talk over a pair of pipes in frames of a kind byte, a little-endian `u32`
+//! payload length and the payload. The locker sends a password and answers to prompts; the
+//! helper sends prompts, messages and the result.
+
+use crate::auth::{Authenticator, Conversation, Error};
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    os::fd::{FromRawFd, OwnedFd},
+};
+
+/// Frames larger than this are a protocol error; nothing legitimate comes close.
+const MAX_PAYLOAD: usize = 64 * 1024;
+
+const PASSWORD: u8 = 1;
+const ANSWER: u8 = 2;
+const CANCEL: u8 = 3;
+const PROMPT_HIDDEN: u8 = 4;
+const PROMPT_ECHO: u8 = 5;
+const INFO: u8 = 6;
+const ERROR: u8 = 7;
+const READY: u8 = 8;
+const ACCEPTED: u8 = 9;
+const REJECTED: u8 = 10;
+const DENIED: u8 = 11;
+const UNAVAILABLE: u8 = 12;
+
+enum Frame {
+    Password(Vec<u8>),
+    /// The user's answer to the last prompt, or `None` if they gave up.
+    Answer(Option<Vec<u8>>),
+    Prompt {
+        text: String,
+        echo: bool,
+    },
+    Message {
+        text: String,
+        error: bool,
+    },
+    /// The helper set up its backend and waits for passwords.
+    Ready,
+    Done(Result<(), Error>),
+}
+
+impl Frame {
+    /// What the frame is, for error messages; the payload may be secret.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Password(_) => "a password",
+            Self::Answer(_) => "an answer",
+            Self::Prompt { .. } => "a prompt",
+            Self::Message { .. } => "a message",
+            Self::Ready => "a ready notice",
+            Self::Done(_) => "a result",
+        }
+    }
+
+    /// Sends the frame, wiping a password or answer in it once sent.
+    fn write(&mut self, writer: &mut impl Write) -> io::Result<()> {
+        let (kind, payload): (u8, &[u8]) = match self {
+            Self::Password(password) => (PASSWORD, password),
+            Self::Answer(Some(answer)) => (ANSWER, answer),
+            Self::Answer(None) => (CANCEL, &[]),
+            Self::Prompt { text, echo } => (
+                if *echo { PROMPT_ECHO } else { PROMPT_HIDDEN },
+                text.as_bytes(),
+            ),
+            Self::Message { text, error } => (if *error { ERROR } else { INFO }, text.as_bytes()),
+            Self::Ready => (READY, &[]),
+            Self::Done(Ok(())) => (ACCEPTED, &[]),
+            Self::Done(Err(Error::Rejected)) => (REJECTED, &[]),
+            Self::Done(Err(Error::Denied(reason))) => (DENIED, reason.as_bytes()),
+            Self::Done(Err(Error::Unavailable(reason))) => (UNAVAILABLE, reason.as_bytes()),
+        };
+        let mut header = [kind, 0, 0, 0, 0];
+        header[1..].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+        // The payload goes out from where it is so that no copy of a secret is left behind.
+        let written = writer
+            .write_all(&header)
+            .and_then(|()| writer.write_all(payload));
+        if let Self::Password(secret) | Self::Answer(Some(secret)) = self {
+            secret.fill(0);
+        }
+        written
+    }
+
+    fn read(reader: &mut impl Read) -> io::Result<Self> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+        let [kind, length @ ..] = header;
+        let length = u32::from_le_bytes(length) as usize;
+        if length > MAX_PAYLOAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("oversized frame of {length} bytes"),
+            ));
+        }
+        let mut payload = vec![0; length];
+        if let Err(e) = reader.read_exact(&mut payload) {
+            // Part of a password may have arrived.
+            payload.fill(0);
+            return Err(e);
+        }
+        let text = |payload: Vec<u8>| String::from_utf8_lossy(&payload).into_owned();
+        Ok(match kind {
+            PASSWORD => Self::Password(payload),
+            ANSWER => Self::Answer(Some(payload)),
+            CANCEL => Self::Answer(None),
+            PROMPT_HIDDEN | PROMPT_ECHO => Self::Prompt {
+                text: text(payload),
+                echo: kind == PROMPT_ECHO,
+            },
+            INFO | ERROR => Self::Message {
+                text: text(payload),
+                error: kind == ERROR,
+            },
+            READY => Self::Ready,
+            ACCEPTED => Self::Done(Ok(())),
+            REJECTED => Self::Done(Err(Error::Rejected)),
+            DENIED => Self::Done(Err(Error::Denied(text(payload)))),
+            UNAVAILABLE => Self::Done(Err(Error::Unavailable(text(payload)))),
+            kind => {
+                payload.fill(0);
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown frame kind {kind}"),
+                ));
+            }
+        })
+    }
+}
+
+/// The locker's end of the helper process, checking passwords by handing them over.
+pub struct Helper {
+    requests: File,
+    replies: File,
+}
+
+impl Helper {
+    /// Forks the helper, which builds its backend with `backend` and then serves requests until
+    /// the locker goes away. Call before starting any threads.
+    pub fn spawn(
+        backend: impl FnOnce() -> Result<Box<dyn Authenticator>, Error>,
+    ) -> Result<Self, Error> {
+        let unavailable = |e: io::Error| Error::Unavailable(format!("authentication helper: {e}"));
+        let (requests_read, requests) = pipe().map_err(unavailable)?;
+        let (replies, replies_write) = pipe().map_err(unavailable)?;
+        // SAFETY: the process is single-threaded, so the child may run arbitrary code.
+        match unsafe { libc::fork() } {
+            -1 => Err(unavailable(io::Error::last_os_error())),
+            0 => {
+                drop((requests, replies));
+                // SAFETY: the name is NUL-terminated and shorter than 16 bytes.
+                unsafe { libc::prctl(libc::PR_SET_NAME, c"lock-auth".as_ptr()) };
+                serve(backend, requests_read, replies_write);
+                // SAFETY: skips atexit handlers and buffers inherited from the locker.
+                unsafe { libc::_exit(0) }
+            }
+            _ => {
+                drop((requests_read, replies_write));
+                let mut helper = Self { requests, replies };
+                match Frame::read(&mut helper.replies).map_err(unavailable)? {
+                    Frame::Ready => Ok(helper),
+                    Frame::Done(Err(e)) => Err(e),
+                    frame => Err(Error::Unavailable(format!(
+                        "authentication helper sent {} on startup",
+                        frame.name()
+                    ))),
+                }
+            }
+        }
+    }
+
+    fn exchange(
+        &mut self,
+        password: &[u8],
+        conversation: &mut dyn Conversation,
+    ) -> io::Result<Result<(), Error>> {
+        Frame::Password(password.to_vec()).write(&mut self.requests)?;
+        loop {
+            match Frame::read(&mut self.replies)? {
+                Frame::Prompt { text, echo } => {
+                    let answer = conversation.prompt(&text, echo);
+                    Frame::Answer(answer).write(&mut self.requests)?;
+                }
+                Frame::Message { text, error } => conversation.message(&text, error),
+                Frame::Done(result) => return Ok(result),
+                frame => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unexpected {}", frame.name()),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+impl Authenticator for Helper {
+    fn authenticate(
+        &mut self,
+        password: &[u8],
+        conversation: &mut dyn Conversation,
+    ) -> Result<(), Error> {
+        self.exchange(password, conversation)
+            .unwrap_or_else(|e| Err(Error::Unavailable(format!("authentication helper: {e}"))))
+    }
+}
+
+/// The helper's side: sets up the backend, then checks each password sent until the locker
+/// closes its end of the pipe, which it does by exiting.
+fn serve(
+    backend: impl FnOnce() -> Result<Box<dyn Authenticator>, Error>,
+    mut requests: File,
+    mut replies: File,
+) {
+    let mut authenticator = match backend() {
+        Ok(authenticator) => authenticator,
+        Err(e) => {
+            let _ = Frame::Done(Err(e)).write(&mut replies);
+            return;
+        }
+    };
+    if Frame::Ready.write(&mut replies).is_err() {
+        return;
+    }
+    loop {
+        let mut password = match Frame::read(&mut requests) {
+            Ok(Frame::Password(password)) => password,
+            Ok(frame) => {
+                tracing::error!(
+                    "Authentication helper got {} instead of a password",
+                    frame.name()
+                );
+                return;
+            }
+            // The locker exited or unlocked.
+            Err(_) => return,
+        };
+        let mut relay = Relay {
+            requests: &mut requests,
+            replies: &mut replies,
+        };
+        let result = authenticator.authenticate(&password, &mut relay);
+        password.fill(0);
+        if Frame::Done(result).write(&mut replies).is_err() {
+            return;
+        }
+    }
+}
+
+/// Passes a backend's conversation through to the locker.
+struct Relay<'a> {
+    requests: &'a mut File,
+    replies: &'a mut File,
+}
+
+impl Conversation for Relay<'_> {
+    fn prompt(&mut self, prompt: &str, echo: bool) -> Option<Vec<u8>> {
+        let text = prompt.to_string();
+        Frame::Prompt { text, echo }.write(self.replies).ok()?;
+        match Frame::read(self.requests) {
+            Ok(Frame::Answer(answer)) => answer,
+            _ => None,
+        }
+    }
+
+    fn message(&mut self, message: &str, error: bool) {
+        let text = message.to_string();
+        if let Err(e) = (Frame::Message { text, error }).write(self.replies) {
+            tracing::error!("Failed to pass on a message: {e}");
+        }
+    }
+}
+
+/// A pipe's read and write ends, closed on exec.
+fn pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors.
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: pipe2 succeeded, so both descriptors are open and ours.
+    let [read, write] = fds.map(|fd| File::from(unsafe { OwnedFd::from_raw_fd(fd) }));
+    Ok((read, write))
+}
+
+/// Whether the locker was started setuid or setgid, with privileges the user does not have.
+pub fn is_privileged() -> bool {
+    // SAFETY: these calls only read the process's credentials.
+    unsafe { libc::getuid() != libc::geteuid() || libc::getgid() != libc::getegid() }
+}
+
+/// Gives up any setuid or setgid privileges for good, so that a compromise of the locker does
+/// not reach beyond the user's own account.
+pub fn drop_privileges() -> io::Result<()> {
+    // SAFETY: these calls only read and change the process's credentials.
+    unsafe {
+        let (uid, gid) = (libc::getuid(), libc::getgid());
+        let (euid, egid) = (libc::geteuid(), libc::getegid());
+        // Groups root was in must not outlive root; nobody else can change them.
+        if euid == 0 && libc::setgroups(0, std::ptr::null()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // The saved ids too: with setgid and setuid alone an unprivileged setgid binary keeps
+        // its group as the saved set-group-ID and could switch back to it.
+        if libc::setresgid(gid, gid, gid) != 0 || libc::setresuid(uid, uid, uid) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if egid != gid && libc::setegid(egid) == 0 {
+            return Err(io::Error::other("group privileges could be regained"));
+        }
+        if uid != 0 && libc::setuid(0) == 0 {
+            return Err(io::Error::other("root privileges could be regained"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(frame: &mut Frame) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        frame.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn decode(mut bytes: &[u8]) -> io::Result<Frame> {
+        let frame = Frame::read(&mut bytes)?;
+        assert!(bytes.is_empty(), "{} bytes left over", bytes.len());
+        Ok(frame)
+    }
+
+    fn round_trip(mut frame: Frame) -> Frame {
+        decode(&encode(&mut frame)).unwrap()
+    }
+
+    #[test]
+    fn encodes_kind_length_and_payload() {
+        let bytes = encode(&mut Frame::Prompt {
+            text: "Code: ".into(),
+            echo: true,
+        });
+        assert_eq!(bytes, b"\x05\x06\0\0\0Code: ");
+        assert_eq!(encode(&mut Frame::Ready), [READY, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn round_trips_every_frame() {
+        let frame = round_trip(Frame::Password(b"hunter2".to_vec()));
+        assert!(matches!(frame, Frame::Password(password) if password == b"hunter2"));
+        let frame = round_trip(Frame::Answer(Some("42 ä".into())));
+        assert!(matches!(frame, Frame::Answer(Some(answer)) if answer == "42 ä".as_bytes()));
+        assert!(matches!(
+            round_trip(Frame::Answer(None)),
+            Frame::Answer(None)
+        ));
+        for echo in [false, true] {
+            let frame = round_trip(Frame::Prompt {
+                text: "Password: ".into(),
+                echo,
+            });
+            assert!(
+                matches!(frame, Frame::Prompt { text, echo: e } if text == "Password: " && e == echo)
+            );
+        }
+        for error in [false, true] {
+            let frame = round_trip(Frame::Message {
+                text: String::new(),
+                error,
+            });
+            assert!(
+                matches!(frame, Frame::Message { text, error: e } if text.is_empty() && e == error)
+            );
+        }
+        assert!(matches!(round_trip(Frame::Ready), Frame::Ready));
+        assert!(matches!(
+            round_trip(Frame::Done(Ok(()))),
+            Frame::Done(Ok(()))
+        ));
+        let frame = round_trip(Frame::Done(Err(Error::Rejected)));
+        assert!(matches!(frame, Frame::Done(Err(Error::Rejected))));
+        let frame = round_trip(Frame::Done(Err(Error::Denied("Account expired".into()))));
+        assert!(
+            matches!(frame, Frame::Done(Err(Error::Denied(reason))) if reason == "Account expired")
+        );
+        let frame = round_trip(Frame::Done(Err(Error::Unavailable("no PAM".into()))));
+        assert!(
+            matches!(frame, Frame::Done(Err(Error::Unavailable(reason))) if reason == "no PAM")
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        let bytes = encode(&mut Frame::Password(b"hunter2".to_vec()));
+        for end in 0..bytes.len() {
+            let e = decode(&bytes[..end]).err().unwrap();
+            assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof, "{end} bytes");
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_frames() {
+        let mut bytes = vec![INFO];
+        bytes.extend_from_slice(&(MAX_PAYLOAD as u32).to_le_bytes());
+        bytes.resize(5 + MAX_PAYLOAD, b'x');
+        assert!(
+            matches!(decode(&bytes).unwrap(), Frame::Message { text, .. } if text.len() == MAX_PAYLOAD)
+        );
+
+        bytes[1..5].copy_from_slice(&(MAX_PAYLOAD as u32 + 1).to_le_bytes());
+        let e = Frame::read(&mut &bytes[..]).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            e.to_string(),
+            format!("oversized frame of {} bytes", MAX_PAYLOAD + 1)
+        );
+        let e = decode(&[PASSWORD, 0xff, 0xff, 0xff, 0xff]).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_unknown_kinds() {
+        for kind in [0, UNAVAILABLE + 1, 0xff] {
+            let e = decode(&[kind, 2, 0, 0, 0, b'h', b'i']).err().unwrap();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(e.to_string(), format!("unknown frame kind {kind}"));
+        }
+    }
+
+    #[test]
+    fn wipes_secrets_once_sent() {
+        let mut frame = Frame::Password(b"hunter2".to_vec());
+        assert_eq!(&encode(&mut frame)[5..], b"hunter2");
+        assert!(matches!(&frame, Frame::Password(password) if password == &[0; 7]));
+
+        let mut frame = Frame::Answer(Some(b"42".to_vec()));
+        assert_eq!(&encode(&mut frame)[5..], b"42");
+        assert!(matches!(&frame, Frame::Answer(Some(answer)) if answer == &[0; 2]));
+
+        // Also when sending fails, as when the other side went away.
+        let mut frame = Frame::Password(b"hunter2".to_vec());
+        assert!(frame.write(&mut &mut [0u8; 8][..]).is_err());
+        assert!(matches!(&frame, Frame::Password(password) if password == &[0; 7]));
+
+        // Prompts are not secret and stay as they were.
+        let mut frame = Frame::Prompt {
+            text: "Code: ".into(),
+            echo: true,
+        };
+        encode(&mut frame);
+        assert!(matches!(&frame, Frame::Prompt { text, .. } if text == "Code: "));
+    }
+}
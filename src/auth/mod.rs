@@ -0,0 +1,248 @@
+//! Password verification. Backends implement [`Authenticator`]; a [`Verifier`] runs one on a
+//! worker thread so a slow check never stalls drawing or input. The backend itself lives in a
+//! separate [`Helper`] process.
+
+mod crypt;
+mod helper;
+mod pam;
+mod shadow;
+mod totp;
+
+pub use helper::{Helper, drop_privileges, is_privileged};
+pub use pam::Pam;
+pub use shadow::Shadow;
+pub use totp::{Totp, TotpConfig};
+
+use mio::Waker;
+use std::{
+    ffi::CStr,
+    fmt, io, mem,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The password is wrong.
+    Rejected,
+    /// The password is right but the account may not log in now, such as when it has expired.
+    Denied(String),
+    /// The backend could not check the password at all, such as when its library is missing.
+    Unavailable(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rejected => write!(f, "Wrong password"),
+            Self::Denied(reason) => write!(f, "Login denied: {reason}"),
+            Self::Unavailable(reason) => write!(f, "Cannot verify passwords: {reason}"),
+        }
+    }
+}
+
+/// Which [`Authenticator`] checks passwords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    #[default]
+    Pam,
+    /// The hash in a shadow file, for systems without PAM.
+    Shadow,
+}
+
+impl Backend {
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "pam" => Self::Pam,
+            "shadow" => Self::Shadow,
+            _ => return None,
+        })
+    }
+}
+
+/// A way of checking the password of the user whose session is locked.
+pub trait Authenticator: Send {
+    /// Checks `password`, blocking for as long as the backend needs. Anything else the backend
+    /// wants to ask or tell the user goes through `conversation`.
+    fn authenticate(
+        &mut self,
+        password: &[u8],
+        conversation: &mut dyn Conversation,
+    ) -> Result<(), Error>;
+}
+
+/// The user's side of an authentication, for backends that ask more than the password.
+pub trait Conversation {
+    /// Asks the user `prompt`, showing the answer as it is typed if `echo` is set. `None` means
+    /// the user gave up.
+    fn prompt(&mut self, prompt: &str, echo: bool) -> Option<Vec<u8>>;
+    /// Shows the user a message, which `error` marks as a problem rather than information.
+    fn message(&mut self, message: &str, error: bool);
+}
+
+/// What the verification thread reports to the event loop.
+#[derive(Debug)]
+pub enum Event {
+    /// The backend asks a question; answer it with [`Verifier::answer`].
+    Prompt {
+        text: String,
+        echo: bool,
+    },
+    Message {
+        text: String,
+        error: bool,
+    },
+    /// The check is over.
+    Done(Result<(), Error>),
+}
+
+/// Runs an [`Authenticator`] on its own thread, waking the event loop with each of its
+/// [`Event`]s.
+pub struct Verifier {
+    requests: Sender<Vec<u8>>,
+    answers: Sender<Option<Vec<u8>>>,
+    events: Receiver<Event>,
+    busy: bool,
+}
+
+impl Verifier {
+    pub fn new(mut authenticator: Box<dyn Authenticator>, waker: Waker) -> io::Result<Self> {
+        let (requests, pending) = mpsc::channel::<Vec<u8>>();
+        let (answers, answered) = mpsc::channel();
+        let (sender, events) = mpsc::channel();
+        thread::Builder::new()
+            .name("auth".to_string())
+            .spawn(move || {
+                let mut channel = Channel {
+                    events: sender,
+                    answers: answered,
+                    waker,
+                };
+                for mut password in pending {
+                    let result = authenticator.authenticate(&password, &mut channel);
+                    password.fill(0);
+                    if !channel.send(Event::Done(result)) {
+                        break;
+                    }
+                }
+            })?;
+        Ok(Self {
+            requests,
+            answers,
+            events,
+            busy: false,
+        })
+    }
+
+    /// Whether a password is being checked.
+    pub fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    /// Starts checking `password`; what follows arrives through [`Verifier::next_event`].
+    pub fn verify(&mut self, password: Vec<u8>) {
+        if let Err(mpsc::SendError(mut password)) = self.requests.send(password) {
+            password.fill(0);
+            tracing::error!("Password verification thread is gone");
+            return;
+        }
+        self.busy = true;
+    }
+
+    /// Answers the last [`Event::Prompt`], with `None` if the user gave up.
+    pub fn answer(&mut self, answer: Option<Vec<u8>>) {
+        if let Err(mpsc::SendError(Some(mut answer))) = self.answers.send(answer) {
+            answer.fill(0);
+        }
+    }
+
+    /// The next report from the check in progress. Call until `None` when the waker's token
+    /// fires.
+    pub fn next_event(&mut self) -> Option<Event> {
+        match self.events.try_recv() {
+            Ok(event) => {
+                if let Event::Done(_) = event {
+                    self.busy = false;
+                }
+                Some(event)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                let busy = mem::replace(&mut self.busy, false);
+                busy.then(|| {
+                    Event::Done(Err(Error::Unavailable(
+                        "verification thread exited".to_string(),
+                    )))
+                })
+            }
+        }
+    }
+}
+
+/// The verification thread's end of the conversation, relaying to the event loop.
+struct Channel {
+    events: Sender<Event>,
+    answers: Receiver<Option<Vec<u8>>>,
+    waker: Waker,
+}
+
+impl Channel {
+    /// Queues `event` and wakes the event loop, returning `false` once the loop is gone.
+    fn send(&self, event: Event) -> bool {
+        if self.events.send(event).is_err() {
+            return false;
+        }
+        if let Err(e) = self.waker.wake() {
+            tracing::error!("Failed to wake the event loop: {e}");
+        }
+        true
+    }
+}
+
+impl Conversation for Channel {
+    fn prompt(&mut self, prompt: &str, echo: bool) -> Option<Vec<u8>> {
+        let text = prompt.to_string();
+        if !self.send(Event::Prompt { text, echo }) {
+            return None;
+        }
+        self.answers.recv().ok().flatten()
+    }
+
+    fn message(&mut self, message: &str, error: bool) {
+        let text = message.to_string();
+        self.send(Event::Message { text, error });
+    }
+}
+
+/// The login name of the user running the locker.
+pub fn current_user() -> io::Result<String> {
+    let mut buffer = vec![0; 1024];
+    loop {
+        // SAFETY: passwd is plain data that getpwuid_r fills in.
+        let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        // SAFETY: every pointer refers to live memory of the advertised size.
+        let error = unsafe {
+            libc::getpwuid_r(
+                libc::getuid(),
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+        match error {
+            0 if result.is_null() => {
+                return Err(io::Error::other("the current user has no passwd entry"));
+            }
+            // SAFETY: on success pw_name points at a NUL-terminated string inside `buffer`.
+            0 => {
+                return Ok(unsafe { CStr::from_ptr(passwd.pw_name) }
+                    .to_string_lossy()
+                    .into_owned());
+            }
+            libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+            error => return Err(io::Error::from_raw_os_error(error)),
+        }
+    }
+}
@@ -0,0 +1,554 @@
+//! PAM, loaded at runtime so the locker neither links against libpam nor needs its headers to
+//! build.
+
+use crate::auth::{Authenticator, Conversation, Error};
+use std::{
+    ffi::{CStr, CString, c_char, c_int, c_void},
+    mem,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    ptr,
+};
+
+const LIBRARY: &CStr = c"libpam.so.0";
+
+const PAM_SUCCESS: c_int = 0;
+const PAM_BUF_ERR: c_int = 5;
+const PAM_AUTH_ERR: c_int = 7;
+const PAM_CRED_INSUFFICIENT: c_int = 8;
+const PAM_USER_UNKNOWN: c_int = 10;
+const PAM_MAXTRIES: c_int = 11;
+const PAM_NEW_AUTHTOK_REQD: c_int = 12;
+const PAM_CONV_ERR: c_int = 19;
+
+const PAM_REFRESH_CRED: c_int = 0x0010;
+
+const PAM_PROMPT_ECHO_OFF: c_int = 1;
+const PAM_PROMPT_ECHO_ON: c_int = 2;
+const PAM_ERROR_MSG: c_int = 3;
+const PAM_TEXT_INFO: c_int = 4;
+
+#[repr(C)]
+struct Message {
+    style: c_int,
+    text: *const c_char,
+}
+
+#[repr(C)]
+struct Response {
+    text: *mut c_char,
+    retcode: c_int,
+}
+
+type Converse =
+    unsafe extern "C" fn(c_int, *mut *const Message, *mut *mut Response, *mut c_void) -> c_int;
+
+/// A `struct pam_conv`.
+#[repr(C)]
+struct Conv {
+    converse: Converse,
+    data: *mut c_void,
+}
+
+/// An opaque `pam_handle_t`.
+enum Handle {}
+
+type Start =
+    unsafe extern "C" fn(*const c_char, *const c_char, *const Conv, *mut *mut Handle) -> c_int;
+type StartConfdir = unsafe extern "C" fn(
+    *const c_char,
+    *const c_char,
+    *const Conv,
+    *const c_char,
+    *mut *mut Handle,
+) -> c_int;
+type Call = unsafe extern "C" fn(*mut Handle, c_int) -> c_int;
+type StrError = unsafe extern "C" fn(*mut Handle, c_int) -> *const c_char;
+
+/// The libpam entry points the backend calls.
+struct Library {
+    handle: *mut c_void,
+    start: Start,
+    /// Linux-PAM 1.4 and later can read services from a directory other than `/etc/pam.d`.
+    start_confdir: Option<StartConfdir>,
+    authenticate: Call,
+    acct_mgmt: Call,
+    setcred: Call,
+    end: Call,
+    strerror: StrError,
+}
+
+// SAFETY: the library handle and function pointers may be used from any thread; PAM handles
+// never outlive a single call on one thread.
+unsafe impl Send for Library {}
+
+impl Library {
+    fn open() -> Result<Self, String> {
+        // SAFETY: LIBRARY is NUL-terminated.
+        let handle = unsafe { libc::dlopen(LIBRARY.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(dl_error());
+        }
+        let symbol = |name: &CStr| {
+            // SAFETY: the handle is open and the name NUL-terminated.
+            let symbol = unsafe { libc::dlsym(handle, name.as_ptr()) };
+            if symbol.is_null() {
+                Err(dl_error())
+            } else {
+                Ok(symbol)
+            }
+        };
+        let symbols = (|| {
+            Ok::<_, String>((
+                symbol(c"pam_start")?,
+                symbol(c"pam_authenticate")?,
+                symbol(c"pam_acct_mgmt")?,
+                symbol(c"pam_setcred")?,
+                symbol(c"pam_end")?,
+                symbol(c"pam_strerror")?,
+            ))
+        })();
+        let (start, authenticate, acct_mgmt, setcred, end, strerror) = match symbols {
+            Ok(symbols) => symbols,
+            Err(e) => {
+                // SAFETY: nothing from the library is in use yet.
+                unsafe { libc::dlclose(handle) };
+                return Err(e);
+            }
+        };
+        let start_confdir = symbol(c"pam_start_confdir").ok();
+        // SAFETY: the symbols are the libpam functions with these C signatures.
+        unsafe {
+            Ok(Self {
+                handle,
+                start: mem::transmute::<*mut c_void, Start>(start),
+                start_confdir: start_confdir
+                    .map(|symbol| mem::transmute::<*mut c_void, StartConfdir>(symbol)),
+                authenticate: mem::transmute::<*mut c_void, Call>(authenticate),
+                acct_mgmt: mem::transmute::<*mut c_void, Call>(acct_mgmt),
+                setcred: mem::transmute::<*mut c_void, Call>(setcred),
+                end: mem::transmute::<*mut c_void, Call>(end),
+                strerror: mem::transmute::<*mut c_void, StrError>(strerror),
+            })
+        }
+    }
+
+    fn error(&self, handle: *mut Handle, status: c_int) -> String {
+        // SAFETY: pam_strerror returns a static string for any status.
+        let text = unsafe { (self.strerror)(handle, status) };
+        if text.is_null() {
+            format!("PAM error {status}")
+        } else {
+            // SAFETY: checked for NULL above.
+            unsafe { CStr::from_ptr(text) }
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        // SAFETY: no PAM handle outlives `authenticate`.
+        unsafe { libc::dlclose(self.handle) };
+    }
+}
+
+fn dl_error() -> String {
+    // SAFETY: dlerror returns NULL or a NUL-terminated message.
+    let message = unsafe { libc::dlerror() };
+    if message.is_null() {
+        "unknown dynamic loader error".to_string()
+    } else {
+        // SAFETY: checked for NULL above.
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Verifies passwords through a PAM service, running whatever conversation its stack needs.
+pub struct Pam {
+    library: Library,
+    service: CString,
+    /// Where to look for the service instead of `/etc/pam.d`.
+    config_dir: Option<CString>,
+    user: CString,
+}
+
+impl Pam {
+    pub fn new(service: &str, config_dir: Option<&Path>, user: &str) -> Result<Self, Error> {
+        let nul = |what| Error::Unavailable(format!("{what} contains a NUL byte"));
+        let service = CString::new(service).map_err(|_| nul("PAM service name"))?;
+        let config_dir = config_dir
+            .map(|dir| CString::new(dir.as_os_str().as_bytes()))
+            .transpose()
+            .map_err(|_| nul("PAM config directory"))?;
+        let user = CString::new(user).map_err(|_| nul("user name"))?;
+        let library = Library::open().map_err(Error::Unavailable)?;
+        if config_dir.is_some() && library.start_confdir.is_none() {
+            return Err(Error::Unavailable(
+                "this libpam cannot read services from another directory".to_string(),
+            ));
+        }
+        Ok(Self {
+            library,
+            service,
+            config_dir,
+            user,
+        })
+    }
+
+    /// Runs the service's auth and account stacks, then refreshes the user's credentials.
+    fn run(&self, handle: *mut Handle) -> (Result<(), Error>, c_int) {
+        let library = &self.library;
+        // SAFETY: the handle was started successfully.
+        let status = unsafe { (library.authenticate)(handle, 0) };
+        match status {
+            PAM_SUCCESS => {}
+            // A conversation error means a prompt went unanswered, usually because the user
+            // gave up on it.
+            PAM_AUTH_ERR
+            | PAM_CRED_INSUFFICIENT
+            | PAM_USER_UNKNOWN
+            | PAM_MAXTRIES
+            | PAM_CONV_ERR => return (Err(Error::Rejected), status),
+            _ => {
+                return (
+                    Err(Error::Unavailable(library.error(handle, status))),
+                    status,
+                );
+            }
+        }
+        // SAFETY: as above.
+        let status = unsafe { (library.acct_mgmt)(handle, 0) };
+        match status {
+            PAM_SUCCESS => {}
+            // Changing it is up to the unlocked session.
+            PAM_NEW_AUTHTOK_REQD => tracing::warn!("The password has expired"),
+            _ => {
+                return (Err(Error::Denied(library.error(handle, status))), status);
+            }
+        }
+        // SAFETY: as above.
+        let status = unsafe { (library.setcred)(handle, PAM_REFRESH_CRED) };
+        if status != PAM_SUCCESS {
+            // The password was right; stale Kerberos tickets should not keep the session locked.
+            tracing::warn!(
+                "Failed to refresh credentials: {}",
+                library.error(handle, status)
+            );
+        }
+        (Ok(()), PAM_SUCCESS)
+    }
+}
+
+impl Authenticator for Pam {
+    fn authenticate(
+        &mut self,
+        password: &[u8],
+        conversation: &mut dyn Conversation,
+    ) -> Result<(), Error> {
+        // A password with a NUL byte cannot be passed to PAM, and so cannot be right.
+        if password.contains(&0) {
+            return Err(Error::Rejected);
+        }
+        let mut state = State {
+            password: Some(password),
+            conversation,
+        };
+        let conv = Conv {
+            converse,
+            data: (&raw mut state).cast(),
+        };
+        let library = &self.library;
+        let mut handle = ptr::null_mut();
+        // SAFETY: the strings and conversation outlive the PAM handle, which ends below.
+        let status = unsafe {
+            match (&self.config_dir, library.start_confdir) {
+                (Some(dir), Some(start_confdir)) => start_confdir(
+                    self.service.as_ptr(),
+                    self.user.as_ptr(),
+                    &conv,
+                    dir.as_ptr(),
+                    &mut handle,
+                ),
+                _ => (library.start)(
+                    self.service.as_ptr(),
+                    self.user.as_ptr(),
+                    &conv,
+                    &mut handle,
+                ),
+            }
+        };
+        if status != PAM_SUCCESS {
+            return Err(Error::Unavailable(library.error(handle, status)));
+        }
+        let (result, status) = self.run(handle);
+        // SAFETY: the handle is not used again.
+        unsafe { (library.end)(handle, status) };
+        result
+    }
+}
+
+/// What the conversation function needs during one authentication.
+struct State<'a> {
+    /// The typed password, which answers the first hidden prompt.
+    password: Option<&'a [u8]>,
+    /// Asks the user everything else.
+    conversation: &'a mut dyn Conversation,
+}
+
+/// Answers PAM's prompts and passes its messages on: the first hidden prompt gets the typed
+/// password, and later prompts are put to the user.
+///
+/// # Safety
+///
+/// Called by libpam with `count` messages and `data` pointing at the [`State`] of the
+/// authentication in progress.
+unsafe extern "C" fn converse(
+    count: c_int,
+    messages: *mut *const Message,
+    responses: *mut *mut Response,
+    data: *mut c_void,
+) -> c_int {
+    if count <= 0 || messages.is_null() || responses.is_null() || data.is_null() {
+        return PAM_CONV_ERR;
+    }
+    // SAFETY: `data` is the State, which nothing else touches while PAM runs.
+    let state = unsafe { &mut *data.cast::<State>() };
+    let count = count as usize;
+    // SAFETY: PAM frees the responses with free(), so they come from calloc.
+    let replies = unsafe { libc::calloc(count, mem::size_of::<Response>()) }.cast::<Response>();
+    if replies.is_null() {
+        return PAM_BUF_ERR;
+    }
+    for index in 0..count {
+        // SAFETY: Linux-PAM passes an array of `count` message pointers.
+        let message = unsafe { &**messages.add(index) };
+        // SAFETY: `replies` holds `count` zeroed responses.
+        let reply = unsafe { &mut *replies.add(index) };
+        let text = if message.text.is_null() {
+            String::new()
+        } else {
+            // SAFETY: message texts are NUL-terminated.
+            unsafe { CStr::from_ptr(message.text) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        let status = match message.style {
+            PAM_PROMPT_ECHO_OFF if state.password.is_some() => {
+                reply_with(reply, state.password.take().unwrap_or_default())
+            }
+            PAM_PROMPT_ECHO_OFF | PAM_PROMPT_ECHO_ON => {
+                let echo = message.style == PAM_PROMPT_ECHO_ON;
+                match state.conversation.prompt(text.trim_end(), echo) {
+                    Some(mut answer) => {
+                        let status = reply_with(reply, &answer);
+                        answer.fill(0);
+                        status
+                    }
+                    None => PAM_CONV_ERR,
+                }
+            }
+            PAM_ERROR_MSG => {
+                state.conversation.message(&text, true);
+                PAM_SUCCESS
+            }
+            PAM_TEXT_INFO => {
+                state.conversation.message(&text, false);
+                PAM_SUCCESS
+            }
+            _ => PAM_CONV_ERR,
+        };
+        if status != PAM_SUCCESS {
+            // SAFETY: the first `index + 1` responses were filled in here.
+            unsafe { free_responses(replies, index + 1) };
+            return status;
+        }
+    }
+    // SAFETY: checked for NULL above; PAM takes ownership.
+    unsafe { *responses = replies };
+    PAM_SUCCESS
+}
+
+/// Copies `answer` into a NUL-terminated string from malloc, which PAM frees.
+fn reply_with(reply: &mut Response, answer: &[u8]) -> c_int {
+    if answer.contains(&0) {
+        return PAM_CONV_ERR;
+    }
+    // SAFETY: one more byte than the answer, for the NUL.
+    let text = unsafe { libc::malloc(answer.len() + 1) }.cast::<u8>();
+    if text.is_null() {
+        return PAM_BUF_ERR;
+    }
+    // SAFETY: `text` has room for the answer and its NUL.
+    unsafe {
+        ptr::copy_nonoverlapping(answer.as_ptr(), text, answer.len());
+        *text.add(answer.len()) = 0;
+    }
+    reply.text = text.cast();
+    PAM_SUCCESS
+}
+/// Wipes and frees responses that will not be handed to PAM.
+///
+/// # Safety
+///
+/// `responses` must come from calloc and hold at least `count` responses.
+unsafe fn free_responses(responses: *mut Response, count: usize) {
+    for index in 0..count {
+        // SAFETY: within the array, per the contract above.
+        let response = unsafe { &mut *responses.add(index) };
+        if !response.text.is_null() {
+            // SAFETY: the text came from reply_with and is NUL-terminated.
+            unsafe {
+                let length = libc::strlen(response.text);
+                ptr::write_bytes(response.text, 0, length);
+                libc::free(response.text.cast());
+            }
+        }
+    }
+    // SAFETY: allocated with calloc.
+    unsafe { libc::free(responses.cast()) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        fs,
+        path::PathBuf,
+        process::{self, Command},
+        sync::OnceLock,
+    };
+
+    /// Builds `testdata/pam/stub.c` once, next to the test binary, returning the directory
+    /// the services using it are written to.
+    fn stub_dir() -> &'static Path {
+        static DIR: OnceLock<PathBuf> = OnceLock::new();
+        DIR.get_or_init(|| {
+            let exe = std::env::current_exe().unwrap();
+            let dir = exe.parent().unwrap().join("pam-stub");
+            fs::create_dir_all(&dir).unwrap();
+            let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/pam/stub.c");
+            // Built under another name first, as another test run may be loading the module.
+            let building = dir.join(format!("stub.so.{}", process::id()));
+            let status = Command::new("cc")
+                .args(["-shared", "-fPIC", "-o"])
+                .arg(&building)
+                .arg(source)
+                .arg("-l:libpam.so.0")
+                .status()
+                .expect("cannot run cc to build the stub PAM module");
+            assert!(status.success(), "cannot build the stub PAM module");
+            fs::rename(building, dir.join("stub.so")).unwrap();
+            dir
+        })
+    }
+
+    /// A `Pam` for a service that runs the stub for authentication and, with `account`,
+    /// for account management.
+    fn pam(service: &str, account: &str) -> Pam {
+        let dir = stub_dir();
+        let module = dir.join("stub.so");
+        let stack = format!(
+            "auth required {module}\naccount required {module} {account}\n",
+            module = module.display()
+        );
+        fs::write(dir.join(service), stack).unwrap();
+        Pam::new(service, Some(dir), "tester").unwrap()
+    }
+
+    /// Answers prompts from a list and records everything it is told.
+    #[derive(Default)]
+    struct Script {
+        answers: Vec<&'static str>,
+        prompts: Vec<(String, bool)>,
+        messages: Vec<(String, bool)>,
+    }
+
+    impl Conversation for Script {
+        fn prompt(&mut self, prompt: &str, echo: bool) -> Option<Vec<u8>> {
+            self.prompts.push((prompt.to_string(), echo));
+            (!self.answers.is_empty()).then(|| self.answers.remove(0).as_bytes().to_vec())
+        }
+
+        fn message(&mut self, message: &str, error: bool) {
+            self.messages.push((message.to_string(), error));
+        }
+    }
+
+    fn script(answers: &[&'static str]) -> Script {
+        Script {
+            answers: answers.to_vec(),
+            ..Script::default()
+        }
+    }
+
+    fn info(message: &str) -> (String, bool) {
+        (message.to_string(), false)
+    }
+
+    #[test]
+    fn runs_the_whole_conversation() {
+        let mut conversation = script(&["42"]);
+        let result = pam("accept", "").authenticate(b"hunter2", &mut conversation);
+        assert!(result.is_ok(), "{result:?}");
+        // The password answers the hidden prompt; only the visible one reaches the user.
+        assert_eq!(conversation.prompts, [("Code:".to_string(), true)]);
+        assert_eq!(
+            conversation.messages,
+            [info("Welcome to the stub"), info("Credentials refreshed")]
+        );
+    }
+
+    #[test]
+    fn rejects_a_wrong_password() {
+        let mut conversation = script(&[]);
+        let result = pam("wrong", "").authenticate(b"hunter3", &mut conversation);
+        assert!(matches!(result, Err(Error::Rejected)), "{result:?}");
+        assert!(conversation.prompts.is_empty());
+        assert_eq!(
+            conversation.messages.last(),
+            Some(&("Wrong stub password".to_string(), true))
+        );
+    }
+
+    #[test]
+    fn rejects_a_wrong_answer() {
+        let mut conversation = script(&["41"]);
+        let result = pam("answer", "").authenticate(b"hunter2", &mut conversation);
+        assert!(matches!(result, Err(Error::Rejected)), "{result:?}");
+    }
+
+    #[test]
+    fn gives_up_with_the_user() {
+        let mut conversation = script(&[]);
+        let result = pam("cancel", "").authenticate(b"hunter2", &mut conversation);
+        assert!(matches!(result, Err(Error::Rejected)), "{result:?}");
+        assert_eq!(conversation.prompts.len(), 1);
+    }
+
+    #[test]
+    fn denies_expired_accounts() {
+        let mut conversation = script(&["42"]);
+        let result = pam("expired", "expired").authenticate(b"hunter2", &mut conversation);
+        assert!(matches!(result, Err(Error::Denied(_))), "{result:?}");
+    }
+
+    #[test]
+    fn unlocks_with_an_expired_password() {
+        let mut conversation = script(&["42"]);
+        let result =
+            pam("new-password", "new-password").authenticate(b"hunter2", &mut conversation);
+        assert!(result.is_ok(), "{result:?}");
+    }
+
+    #[test]
+    fn refuses_passwords_with_nul_bytes() {
+        let mut conversation = script(&["42"]);
+        let result = pam("nul", "").authenticate(b"hunter2\0", &mut conversation);
+        assert!(matches!(result, Err(Error::Rejected)), "{result:?}");
+        assert!(conversation.messages.is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+//! Checks passwords against the user's hash in a shadow(5) file, for systems without PAM.
+//! Account expiry is enforced the way pam_unix does it.
+
+use crate::auth::{Authenticator, Conversation, Error, crypt};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The fields of a shadow entry that decide whether its user may log in.
+struct Entry {
+    hash: String,
+    /// Dates in days since the epoch, and ages in days; unset fields are `None`.
+    last_change: Option<i64>,
+    max_age: Option<i64>,
+    inactive: Option<i64>,
+    expire: Option<i64>,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<_> = line.split(':').collect();
+        let &[_, hash, last_change, _, max_age, _, inactive, expire, ..] = fields.as_slice() else {
+            return None;
+        };
+        let day = |field: &str| match field {
+            "" => Some(None),
+            field => field.parse().ok().map(Some),
+        };
+        Some(Self {
+            hash: hash.to_string(),
+            last_change: day(last_change)?,
+            max_age: day(max_age)?,
+            inactive: day(inactive)?,
+            expire: day(expire)?,
+        })
+    }
+
+    /// Why the account may not log in on `today`, if it may not.
+    fn expired(&self, today: i64) -> Option<&'static str> {
+        if self.expire.is_some_and(|expire| today >= expire) {
+            return Some("the account has expired");
+        }
+        let age = today - self.last_change?;
+        let max_age = self.max_age?;
+        match self.inactive {
+            Some(inactive) if age > max_age + inactive => {
+                Some("the password expired and the account is inactive")
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the password is due to be changed, which does not stop it unlocking.
+    fn must_change(&self, today: i64) -> bool {
+        match (self.last_change, self.max_age) {
+            (Some(0), _) => true,
+            (Some(last_change), Some(max_age)) => today - last_change > max_age,
+            _ => false,
+        }
+    }
+}
+
+/// Verifies passwords by hashing them the way the user's entry in a shadow file says.
+pub struct Shadow {
+    /// Usually `/etc/shadow`, read on every check so password changes apply straight away.
+    path: PathBuf,
+    user: String,
+}
+
+impl Shadow {
+    pub fn new(path: PathBuf, user: &str) -> Self {
+        Self {
+            path,
+            user: user.to_string(),
+        }
+    }
+
+    fn entry(&self) -> Result<Entry, Error> {
+        let path = self.path.display();
+        let mut contents = fs::read(&self.path)
+            .map_err(|e| Error::Unavailable(format!("failed to read {path}: {e}")))?;
+        // Other users' hashes are in there too; only the one entry is kept.
+        let entry = contents
+            .split(|&byte| byte == b'\n')
+            .filter_map(|line| std::str::from_utf8(line).ok())
+            .find(|line| line.split(':').next() == Some(self.user.as_str()))
+            .map(Entry::parse);
+        contents.fill(0);
+        match entry {
+            Some(Some(entry)) => Ok(entry),
+            Some(None) => Err(Error::Unavailable(format!(
+                "malformed entry for {} in {path}",
+                self.user
+            ))),
+            None => Err(Error::Unavailable(format!(
+                "no entry for {} in {path}",
+                self.user
+            ))),
+        }
+    }
+}
+
+impl Authenticator for Shadow {
+    fn authenticate(
+        &mut self,
+        password: &[u8],
+        _conversation: &mut dyn Conversation,
+    ) -> Result<(), Error> {
+        let mut entry = self.entry()?;
+        let hash = std::mem::take(&mut entry.hash);
+        let result = if hash.is_empty() {
+            Err(Error::Denied("the account has no password".to_string()))
+        } else if hash.starts_with(['!', '*']) {
+            Err(Error::Denied("the account is locked".to_string()))
+        } else {
+            match crypt::verify(password, &hash) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(Error::Rejected),
+                Err(e) => Err(Error::Unavailable(e.to_string())),
+            }
+        };
+        let mut hash = hash.into_bytes();
+        hash.fill(0);
+        result?;
+        let today = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| (now.as_secs() / 86400) as i64);
+        if let Some(reason) = entry.expired(today) {
+            return Err(Error::Denied(reason.to_string()));
+        }
+        if entry.must_change(today) {
+            // Changing it is up to the unlocked session.
+            tracing::warn!("The password has expired");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// `correct horse`, as made by libxcrypt's crypt(3).
+    const HASH: &str = "$5$rounds=1000$short$cMPBGnEsf0Llc7/9de4cHWjS1ZlySiS3pL6RjBpcUa4";
+
+    struct Silent;
+
+    impl Conversation for Silent {
+        fn prompt(&mut self, _: &str, _: bool) -> Option<Vec<u8>> {
+            panic!("the shadow backend asks nothing");
+        }
+
+        fn message(&mut self, _: &str, _: bool) {}
+    }
+
+    fn today() -> i64 {
+        (SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            / 86400) as i64
+    }
+
+    /// Checks `password` against a shadow file holding `entry` for the user `tester`, among
+    /// other users' entries.
+    fn check(entry: &str, password: &str) -> Result<(), Error> {
+        let dir = TempDir::new();
+        let contents = format!("root:*:19000:0:99999:7:::\n{entry}\nother:{HASH}:19000::::::\n");
+        let path = dir.write("shadow", contents, 0o600);
+        Shadow::new(path, "tester").authenticate(password.as_bytes(), &mut Silent)
+    }
+
+    fn denied(result: Result<(), Error>) -> String {
+        match result {
+            Err(Error::Denied(reason)) => reason,
+            result => panic!("expected the login to be denied, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn checks_the_users_hash() {
+        let entry = format!("tester:{HASH}:19000:0:99999:7:::");
+        assert!(check(&entry, "correct horse").is_ok());
+        assert!(matches!(
+            check(&entry, "correct horses"),
+            Err(Error::Rejected)
+        ));
+        // Another user's entry is not the user's, even with the same password.
+        assert!(matches!(
+            check(&format!("tester2:{HASH}:19000::::::"), "correct horse"),
+            Err(Error::Unavailable(_))
+        ));
+    }
+
+    #[test]
+    fn denies_locked_accounts() {
+        for hash in ["!", "*", "!!", &format!("!{HASH}"), &format!("*{HASH}")] {
+            let reason = denied(check(
+                &format!("tester:{hash}:19000::::::"),
+                "correct horse",
+            ));
+            assert_eq!(reason, "the account is locked", "{hash}");
+        }
+        let reason = denied(check("tester::19000::::::", ""));
+        assert_eq!(reason, "the account has no password");
+    }
+
+    #[test]
+    fn denies_expired_accounts() {
+        let entry = format!("tester:{HASH}:19000:::::{}:", today());
+        assert_eq!(
+            denied(check(&entry, "correct horse")),
+            "the account has expired"
+        );
+        // Expiry dates are the first day the account cannot be used.
+        let entry = format!("tester:{HASH}:19000:::::{}:", today() + 1);
+        assert!(check(&entry, "correct horse").is_ok());
+        // A wrong password says nothing about the account.
+        let entry = format!("tester:{HASH}:19000:::::1:");
+        assert!(matches!(check(&entry, "wrong"), Err(Error::Rejected)));
+    }
+
+    #[test]
+    fn denies_inactive_accounts() {
+        // The password expired 11 days ago and the account went inactive a day later.
+        let changed = today() - 41;
+        let entry = format!("tester:{HASH}:{changed}:0:30:7:10::");
+        assert_eq!(
+            denied(check(&entry, "correct horse")),
+            "the password expired and the account is inactive"
+        );
+        // Still within the inactive period.
+        let entry = format!("tester:{HASH}:{changed}:0:30:7:11::");
+        assert!(check(&entry, "correct horse").is_ok());
+    }
+
+    #[test]
+    fn unlocks_when_the_password_must_change() {
+        // Forced by the administrator, or past its maximum age without an inactive period.
+        for entry in [
+            format!("tester:{HASH}:0:0:99999:7:::"),
+            format!("tester:{HASH}:{}:0:30:7:::", today() - 41),
+        ] {
+            let parsed = Entry::parse(&entry).unwrap();
+            assert!(parsed.must_change(today()), "{entry}");
+            assert!(parsed.expired(today()).is_none(), "{entry}");
+            assert!(check(&entry, "correct horse").is_ok(), "{entry}");
+        }
+        let fresh = Entry::parse(&format!("tester:{HASH}:{}:0:30:7:::", today())).unwrap();
+        assert!(!fresh.must_change(today()));
+    }
+
+    #[test]
+    fn reports_unusable_files() {
+        let reason = |result| match result {
+            Err(Error::Unavailable(reason)) => reason,
+            result => panic!("expected the check to be impossible, got {result:?}"),
+        };
+        assert!(reason(check("tester:x:notadate::::::", "")).contains("malformed entry"));
+        assert!(reason(check("tester:x", "")).contains("malformed entry"));
+        let unsupported = check("tester:$1$salt$hash:19000::::::", "correct horse");
+        assert_eq!(reason(unsupported), "unsupported password hash");
+        let dir = TempDir::new();
+        let mut missing = Shadow::new(dir.path().join("shadow"), "tester");
+        let result = missing.authenticate(b"correct horse", &mut Silent);
+        assert!(reason(result).contains("failed to read"));
+    }
+}
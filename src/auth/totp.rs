@@ -0,0 +1,439 @@
+//! Time-based one-time codes (RFC 6238) as a second factor after the password, checked against
+//! a secret only the user can read.
+
+use crate::{
+    auth::{Authenticator, Conversation, Error, crypt},
+    config,
+};
+use std::{
+    fs::{File, OpenOptions},
+    io::Read,
+    os::unix::fs::{MetadataExt, OpenOptionsExt},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const PROMPT: &str = "Verification code:";
+
+/// Whether codes are asked for and which are accepted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TotpConfig {
+    pub enabled: bool,
+    /// Digits in a code, 6 to 8.
+    pub digits: u32,
+    /// Seconds each code is valid for.
+    pub period: u64,
+    /// Periods before and after the current one whose codes are accepted too, for clocks that
+    /// drift apart.
+    pub window: u32,
+    /// The base32 secret on its first line, as written by google-authenticator(1) and most
+    /// enrollment tools. It must belong to the user and be unreadable by anyone else.
+    pub secret_file: Option<PathBuf>,
+}
+
+impl Default for TotpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            digits: 6,
+            period: 30,
+            window: 1,
+            secret_file: secret_file(),
+        }
+    }
+}
+
+/// Asks for a code once `inner` accepted the password. A wrong code is a wrong password as far
+/// as throttling goes.
+pub struct Totp {
+    inner: Box<dyn Authenticator>,
+    config: TotpConfig,
+    secret: Vec<u8>,
+    /// The time step of the last code accepted; a code is never accepted twice.
+    last_step: Option<u64>,
+}
+
+impl Totp {
+    /// Reads the secret now, so a missing or exposed file stops the locker from starting rather
+    /// than from unlocking.
+    pub fn new(inner: Box<dyn Authenticator>, config: TotpConfig) -> Result<Self, Error> {
+        let secret = read_secret(&config)?;
+        Ok(Self {
+            inner,
+            config,
+            secret,
+            last_step: None,
+        })
+    }
+
+    /// The time step of the code `code` within the window around `now`, in seconds since the
+    /// epoch, if it is one.
+    fn check(&self, code: &[u8], now: u64) -> Option<u64> {
+        let current = now / self.config.period.max(1);
+        let window = u64::from(self.config.window);
+        let mut matched = None;
+        // Every step is tried, so the time taken says nothing about which matched.
+        for step in current.saturating_sub(window)..=current.saturating_add(window) {
+            let expected = format!(
+                "{:0width$}",
+                hotp(&self.secret, step, self.config.digits),
+                width = self.config.digits as usize
+            );
+            if crypt::constant_time_eq(expected.as_bytes(), code) && matched.is_none() {
+                matched = Some(step);
+            }
+        }
+        matched.filter(|&step| self.last_step.is_none_or(|last| step > last))
+    }
+}
+
+impl Authenticator for Totp {
+    fn authenticate(
+        &mut self,
+        password: &[u8],
+        conversation: &mut dyn Conversation,
+    ) -> Result<(), Error> {
+        self.inner.authenticate(password, conversation)?;
+        let Some(mut code) = conversation.prompt(PROMPT, true) else {
+            return Err(Error::Denied("no verification code given".to_string()));
+        };
+        // Apps often show codes in groups of three.
+        code.retain(|&byte| byte != b' ');
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        let step = self.check(&code, now);
+        code.fill(0);
+        match step {
+            Some(step) => {
+                self.last_step = Some(step);
+                Ok(())
+            }
+            None => {
+                conversation.message("Wrong verification code", true);
+                Err(Error::Rejected)
+            }
+        }
+    }
+}
+
+impl Drop for Totp {
+    fn drop(&mut self) {
+        self.secret.fill(0);
+    }
+}
+
+/// Reads and decodes the secret, refusing files that are not the user's own or that others
+/// could read or change.
+fn read_secret(config: &TotpConfig) -> Result<Vec<u8>, Error> {
+    let path = config
+        .secret_file
+        .as_ref()
+        .ok_or_else(|| Error::Unavailable("no verification code secret file".to_string()))?;
+    let display = path.display();
+    let unavailable = |reason: String| Error::Unavailable(format!("{display}: {reason}"));
+    // Checked on the open file, so it cannot be swapped between the check and the read.
+    let mut file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)
+        .map_err(|e| unavailable(e.to_string()))?;
+    check_permissions(&file).map_err(unavailable)?;
+    let mut contents = Vec::new();
+    let read = file.read_to_end(&mut contents);
+    let secret = read.map_err(|e| unavailable(e.to_string())).and_then(|_| {
+        let line = contents.split(|&b| b == b'\n').next().unwrap_or_default();
+        base32_decode(line).ok_or_else(|| unavailable("malformed base32 secret".to_string()))
+    });
+    contents.fill(0);
+    let mut secret = secret?;
+    if secret.len() < 10 {
+        secret.fill(0);
+        return Err(unavailable(
+            "the secret is shorter than 80 bits".to_string(),
+        ));
+    }
+    Ok(secret)
+}
+
+fn check_permissions(file: &File) -> Result<(), String> {
+    let metadata = file.metadata().map_err(|e| e.to_string())?;
+    // SAFETY: getuid cannot fail.
+    let uid = unsafe { libc::getuid() };
+    if !metadata.is_file() {
+        return Err("not a regular file".to_string());
+    }
+    if metadata.uid() != uid {
+        return Err(format!("owned by uid {}, not {uid}", metadata.uid()));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        return Err(format!(
+            "mode {:o} lets others at it; chmod 600 it",
+            metadata.mode() & 0o777
+        ));
+    }
+    Ok(())
+}
+
+/// `$XDG_CONFIG_HOME/lock/totp`, falling back to `~/.config`.
+fn secret_file() -> Option<PathBuf> {
+    config::xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("lock").join("totp"))
+}
+
+/// RFC 4648 base32, in either case, with spaces and padding ignored.
+fn base32_decode(text: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u64, 0);
+    for &c in text.iter().filter(|&&c| !matches!(c, b' ' | b'=' | b'\r')) {
+        let value = match c.to_ascii_uppercase() {
+            c @ b'A'..=b'Z' => c - b'A',
+            c @ b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | u64::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// The HOTP value (RFC 4226) of `counter`, as a number of `digits` digits.
+fn hotp(key: &[u8], counter: u64, digits: u32) -> u32 {
+    let mac = crypt::hmac_sha1(key, &counter.to_be_bytes());
+    let offset = usize::from(mac[19] & 0xf);
+    let value = u32::from_be_bytes(mac[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+    value % 10u32.pow(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::os::unix::fs::symlink;
+
+    /// The secret of RFC 4226 and RFC 6238's SHA-1 test vectors, `12345678901234567890`.
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    struct Accept;
+
+    impl Authenticator for Accept {
+        fn authenticate(&mut self, _: &[u8], _: &mut dyn Conversation) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    /// Answers the code prompt with `code` and records the messages.
+    struct Answer {
+        code: Option<String>,
+        messages: Vec<String>,
+    }
+
+    impl Conversation for Answer {
+        fn prompt(&mut self, prompt: &str, echo: bool) -> Option<Vec<u8>> {
+            assert_eq!((prompt, echo), (PROMPT, true));
+            self.code.take().map(String::into_bytes)
+        }
+
+        fn message(&mut self, message: &str, _: bool) {
+            self.messages.push(message.to_string());
+        }
+    }
+
+    fn config(dir: &TempDir, contents: &str, mode: u32) -> TotpConfig {
+        TotpConfig {
+            enabled: true,
+            secret_file: Some(dir.write("totp", contents, mode)),
+            ..TotpConfig::default()
+        }
+    }
+
+    fn totp(config: TotpConfig) -> Totp {
+        Totp::new(Box::new(Accept), config).unwrap()
+    }
+
+    fn code(step: u64) -> String {
+        format!("{:06}", hotp(b"12345678901234567890", step, 6))
+    }
+
+    fn unavailable(config: TotpConfig) -> String {
+        match Totp::new(Box::new(Accept), config) {
+            Err(Error::Unavailable(reason)) => reason,
+            Err(e) => panic!("expected the secret to be refused, got {e:?}"),
+            Ok(_) => panic!("expected the secret to be refused"),
+        }
+    }
+
+    #[test]
+    fn rfc4226_vectors() {
+        let expected = [
+            755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489,
+        ];
+        for (counter, expected) in expected.into_iter().enumerate() {
+            assert_eq!(hotp(b"12345678901234567890", counter as u64, 6), expected);
+        }
+    }
+
+    #[test]
+    fn rfc6238_sha1_vectors() {
+        let vectors = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+            (20000000000, "65353130"),
+        ];
+        for (time, expected) in vectors {
+            let code = format!("{:08}", hotp(b"12345678901234567890", time / 30, 8));
+            assert_eq!(code, expected, "at {time}");
+        }
+    }
+
+    #[test]
+    fn decodes_base32() {
+        // RFC 4648's test vectors.
+        let vectors = [
+            ("", ""),
+            ("MY======", "f"),
+            ("MZXQ====", "fo"),
+            ("MZXW6===", "foo"),
+            ("MZXW6YQ=", "foob"),
+            ("MZXW6YTB", "fooba"),
+            ("MZXW6YTBOI======", "foobar"),
+        ];
+        for (text, expected) in vectors {
+            assert_eq!(base32_decode(text.as_bytes()).unwrap(), expected.as_bytes());
+        }
+        // As enrollment tools show secrets: lower case, in groups, unpadded.
+        assert_eq!(base32_decode(b"mzxw 6ytb oi").unwrap(), b"foobar");
+        assert_eq!(base32_decode(b"MZXW6YTBOI\r").unwrap(), b"foobar");
+        assert_eq!(base32_decode(b"MZXW1YTB"), None);
+        assert_eq!(base32_decode(b"MZXW-YTB"), None);
+    }
+
+    #[test]
+    fn accepts_codes_within_the_window() {
+        let dir = TempDir::new();
+        let now = 1111111111;
+        let step = now / 30;
+        let lenient = totp(config(&dir, SECRET, 0o600));
+        for accepted in [step - 1, step, step + 1] {
+            assert_eq!(
+                lenient.check(code(accepted).as_bytes(), now),
+                Some(accepted)
+            );
+        }
+        for rejected in [step - 2, step + 2] {
+            assert_eq!(lenient.check(code(rejected).as_bytes(), now), None);
+        }
+        assert_eq!(lenient.check(b"", now), None);
+
+        let strict = totp(TotpConfig {
+            window: 0,
+            ..config(&dir, SECRET, 0o600)
+        });
+        assert_eq!(strict.check(code(step).as_bytes(), now), Some(step));
+        assert_eq!(strict.check(code(step + 1).as_bytes(), now), None);
+    }
+
+    #[test]
+    fn never_accepts_a_code_twice() {
+        let dir = TempDir::new();
+        let mut totp = totp(config(&dir, SECRET, 0o600));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        // Grouped as apps show it.
+        let code = code(now / 30);
+        let grouped = format!("{} {}", &code[..3], &code[3..]);
+        let mut answer = Answer {
+            code: Some(grouped.clone()),
+            messages: Vec::new(),
+        };
+        assert!(totp.authenticate(b"", &mut answer).is_ok());
+        answer.code = Some(grouped);
+        let result = totp.authenticate(b"", &mut answer);
+        assert!(matches!(result, Err(Error::Rejected)), "{result:?}");
+        assert_eq!(answer.messages, ["Wrong verification code"]);
+        // Giving up on the code is not a wrong code.
+        let result = totp.authenticate(b"", &mut answer);
+        assert!(matches!(result, Err(Error::Denied(_))), "{result:?}");
+    }
+
+    #[test]
+    fn reads_the_first_line_of_the_secret_file() {
+        let dir = TempDir::new();
+        let totp = totp(config(
+            &dir,
+            &format!("{SECRET}\n\" RATE_LIMIT 3 30\n"),
+            0o400,
+        ));
+        assert_eq!(totp.secret, b"12345678901234567890");
+    }
+
+    #[test]
+    fn refuses_secrets_others_can_read() {
+        let dir = TempDir::new();
+        for mode in [0o640, 0o604, 0o620, 0o660] {
+            let reason = unavailable(config(&dir, SECRET, mode));
+            assert!(reason.contains("chmod 600"), "{mode:o}: {reason}");
+        }
+    }
+
+    #[test]
+    fn refuses_secrets_of_other_users() {
+        let dir = TempDir::new();
+        let config = config(&dir, SECRET, 0o600);
+        // SAFETY: getuid cannot fail.
+        let secret_file = if unsafe { libc::getuid() } == 0 {
+            let path = config.secret_file.clone().unwrap();
+            std::os::unix::fs::chown(&path, Some(65534), Some(65534)).unwrap();
+            path
+        } else {
+            // Owned by root, which the tests are not running as.
+            PathBuf::from("/etc/passwd")
+        };
+        let reason = unavailable(TotpConfig {
+            secret_file: Some(secret_file),
+            ..config
+        });
+        assert!(reason.contains("owned by uid"), "{reason}");
+    }
+
+    #[test]
+    fn refuses_unusable_secret_files() {
+        let dir = TempDir::new();
+        let config = config(&dir, SECRET, 0o600);
+        let link = dir.path().join("link");
+        symlink(config.secret_file.as_ref().unwrap(), &link).unwrap();
+        for secret_file in [link, dir.path().join("missing"), dir.path().to_path_buf()] {
+            unavailable(TotpConfig {
+                secret_file: Some(secret_file),
+                ..config.clone()
+            });
+        }
+        let reason = unavailable(TotpConfig {
+            secret_file: None,
+            ..config
+        });
+        assert!(
+            reason.contains("no verification code secret file"),
+            "{reason}"
+        );
+    }
+
+    #[test]
+    fn refuses_malformed_and_short_secrets() {
+        let dir = TempDir::new();
+        let reason = unavailable(config(&dir, "not base32!", 0o600));
+        assert!(reason.contains("malformed"), "{reason}");
+        // 16 characters are 80 bits; one fewer is too short.
+        let reason = unavailable(config(&dir, &SECRET[..15], 0o600));
+        assert!(reason.contains("shorter than 80 bits"), "{reason}");
+        totp(config(&dir, &SECRET[..16], 0o600));
+    }
+}
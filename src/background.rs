@@ -0,0 +1,127 @@
+//! What the lock screen shows behind the indicator: a solid color, optionally covered by an
+//! image placed according to a swaylock-style scaling mode.
+
+use crate::{
+    image::Image,
+    render::{Canvas, Color, Rect},
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// How an image is fitted to an output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scaling {
+    /// Distorted to exactly the output's size.
+    Stretch,
+    /// Scaled to cover the whole output, cropping what sticks out.
+    #[default]
+    Fill,
+    /// Scaled to fit inside the output, leaving bars of the background color.
+    Fit,
+    /// At its own size, centered.
+    Center,
+    /// At its own size, repeated from the top left corner.
+    Tile,
+    /// Not shown at all.
+    SolidColor,
+}
+
+impl Scaling {
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "stretch" => Self::Stretch,
+            "fill" => Self::Fill,
+            "fit" => Self::Fit,
+            "center" => Self::Center,
+            "tile" => Self::Tile,
+            "solid_color" => Self::SolidColor,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Background {
+    color: Color,
+    image: Option<Rc<Image>>,
+    scaling: Scaling,
+}
+
+impl Background {
+    pub fn new(color: Color, image: Option<Rc<Image>>, scaling: Scaling) -> Self {
+        Self {
+            color,
+            image,
+            scaling,
+        }
+    }
+
+    /// Paints the whole canvas, or the part inside its clip.
+    pub fn draw(&self, canvas: &mut Canvas) {
+        canvas.clear(self.color);
+        let Some(image) = &self.image else {
+            return;
+        };
+        let (width, height) = (canvas.width() as f32, canvas.height() as f32);
+        let (image_width, image_height) = (image.width() as f32, image.height() as f32);
+        let dest = match self.scaling {
+            Scaling::SolidColor => return,
+            Scaling::Tile => return canvas.tile_image(image),
+            Scaling::Stretch => Rect::new(0.0, 0.0, width, height),
+            Scaling::Center => centered(width, height, image_width, image_height, 1.0),
+            Scaling::Fill => {
+                let scale = (width / image_width).max(height / image_height);
+                centered(width, height, image_width, image_height, scale)
+            }
+            Scaling::Fit => {
+                let scale = (width / image_width).min(height / image_height);
+                centered(width, height, image_width, image_height, scale)
+            }
+        };
+        canvas.draw_image(image, dest);
+    }
+}
+
+/// The image scaled by `scale` and centered, on whole pixels so unscaled images stay sharp.
+fn centered(width: f32, height: f32, image_width: f32, image_height: f32, scale: f32) -> Rect {
+    let (w, h) = (image_width * scale, image_height * scale);
+    Rect::new(
+        ((width - w) / 2.0).round(),
+        ((height - h) / 2.0).round(),
+        w,
+        h,
+    )
+}
+
+/// Decoded images by path, so each is read once however often outputs come and go.
+#[derive(Debug, Default)]
+pub struct ImageCache {
+    images: HashMap<PathBuf, Option<Rc<Image>>>,
+}
+
+impl ImageCache {
+    /// The image at `path`, decoding it on first use. Failures are logged once and remembered,
+    /// leaving the background a solid color.
+    pub fn get(&mut self, path: &Path) -> Option<Rc<Image>> {
+        self.images
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                Image::load(path)
+                    .inspect(|image| {
+                        tracing::info!(
+                            "Loaded background {} ({}x{})",
+                            path.display(),
+                            image.width(),
+                            image.height()
+                        )
+                    })
+                    .inspect_err(|e| tracing::warn!("No background image: {e}"))
+                    .ok()
+                    .map(Rc::new)
+            })
+            .clone()
+    }
+}
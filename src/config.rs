@@ -0,0 +1,586 @@
+//! Settings read from a swaylock-style config file: one long option per line, `name=value`,
+//! with `#` starting a comment.
+//!
+//! A line `[output <name>]` starts overrides for the output whose `wl_output` name (such as
+//! `DP-1`) or description is exactly `<name>`. They begin as a copy of the global settings
+//! above the first section and only hold options that can differ between outputs.
+
+use crate::{
+    auth::{Backend, TotpConfig},
+    background::Scaling,
+    hardening::HardeningConfig,
+    indicator::IndicatorStyle,
+    render::Color,
+    throttle::ThrottlePolicy,
+};
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const SYSTEM_CONFIG: &str = "/etc/lock/config";
+
+/// Options that decide what it takes to unlock or how confined the locker is.
+const SECURITY_OPTIONS: &[&str] = &[
+    "auth-backend",
+    "shadow-file",
+    "pam-service",
+    "pam-config-dir",
+    "totp",
+    "totp-digits",
+    "totp-period",
+    "totp-window",
+    "totp-secret-file",
+    "landlock",
+    "seccomp",
+];
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Invalid(PathBuf, usize, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "Failed to read config {}: {e}", path.display()),
+            Self::Invalid(path, line, message) => {
+                write!(f, "{}:{line}: {message}", path.display())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Config {
+    /// Settings for outputs without a section of their own.
+    pub output: OutputConfig,
+    /// Per-output sections by output name or description, in file order.
+    pub outputs: Vec<(String, OutputConfig)>,
+    /// The output that always shows the indicator while connected, instead of the focused one.
+    pub primary_output: Option<String>,
+    pub indicator: IndicatorStyle,
+    pub auth: AuthConfig,
+    pub throttle: ThrottlePolicy,
+    /// How long after an idle lock any input unlocks without a password; zero disables it.
+    pub grace: Duration,
+    /// Whether fingerprints scanned on fprintd's reader unlock too.
+    pub fingerprint: bool,
+    pub hardening: HardeningConfig,
+}
+
+/// How passwords are checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthConfig {
+    pub backend: Backend,
+    /// The PAM service whose stack checks passwords.
+    pub pam_service: String,
+    /// A directory to read the service from instead of `/etc/pam.d`, such as a test stack.
+    pub pam_config_dir: Option<PathBuf>,
+    /// The password file of the shadow backend.
+    pub shadow_file: PathBuf,
+    /// The one-time code asked for after the password.
+    pub totp: TotpConfig,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            backend: Backend::default(),
+            pam_service: "lock".to_string(),
+            pam_config_dir: None,
+            shadow_file: PathBuf::from("/etc/shadow"),
+            totp: TotpConfig::default(),
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Whether a matching fingerprint may unlock on its own. It may not when a code is asked
+    /// for after the password, as the finger would skip that second factor.
+    pub fn allows_fingerprint(&self) -> bool {
+        !self.totp.enabled
+    }
+}
+
+/// Settings that can differ between outputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputConfig {
+    pub background: Color,
+    pub image: Option<PathBuf>,
+    pub scaling: Scaling,
+    /// Where to center the indicator, in logical pixels from the top left corner. Unset
+    /// coordinates center it on the output.
+    pub indicator_x: Option<f32>,
+    pub indicator_y: Option<f32>,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            background: Color::rgb(0x20, 0x20, 0x20),
+            image: None,
+            scaling: Scaling::default(),
+            indicator_x: None,
+            indicator_y: None,
+        }
+    }
+}
+
+impl OutputConfig {
+    /// Applies one option, returning `false` if it is not a per-output one.
+    fn set(&mut self, name: &str, value: &str) -> Result<bool, String> {
+        match name {
+            "color" => {
+                self.background = Color::parse(value)
+                    .ok_or_else(|| format!("{name} needs a color like rrggbb or rrggbbaa"))?
+            }
+            "image" => self.image = (!value.is_empty()).then(|| expand_home(value)),
+            "scaling" => {
+                self.scaling = Scaling::parse(value).ok_or_else(|| {
+                    format!("{name} needs one of stretch, fill, fit, center, tile or solid_color")
+                })?
+            }
+            "indicator-x-position" => self.indicator_x = Some(parse_length(name, value)?),
+            "indicator-y-position" => self.indicator_y = Some(parse_length(name, value)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+impl Config {
+    /// Reads the first config file found, or returns the defaults if there is none.
+    pub fn load() -> Result<Self, Error> {
+        match config_file() {
+            Some(path) => Self::from_file(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Reads only the system-wide config, or returns the defaults if there is none. The user's
+    /// own file must not choose how a privileged helper checks passwords.
+    pub fn system() -> Result<Self, Error> {
+        let path = Path::new(SYSTEM_CONFIG);
+        if path.is_file() {
+            Self::from_file(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Reads `path`, skipping invalid lines with a warning so one typo does not undo the rest.
+    /// An invalid security option fails instead: skipping it could leave the lock weaker than
+    /// configured, such as without its second factor.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let text = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        let mut config = Self::default();
+        // Lines below a malformed section header, which belong to no known output.
+        let mut in_bad_section = false;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message| Error::Invalid(path.to_path_buf(), index + 1, message);
+            if let Some(section) = line.strip_prefix('[') {
+                let output = section
+                    .strip_suffix(']')
+                    .and_then(|section| section.strip_prefix("output "))
+                    .map(str::trim)
+                    .filter(|output| !output.is_empty());
+                in_bad_section = output.is_none();
+                match output {
+                    Some(output) => config
+                        .outputs
+                        .push((output.to_string(), config.output.clone())),
+                    None => {
+                        let e = invalid(format!("expected [output <name>], not {line}"));
+                        tracing::warn!("{e}, skipping the section");
+                    }
+                }
+                continue;
+            }
+            if in_bad_section {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .map_or((line, ""), |(name, value)| (name.trim(), value.trim()));
+            let result = match config.outputs.last_mut() {
+                Some((_, output)) => match output.set(name, value) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err(format!("{name} cannot differ between outputs")),
+                    Err(message) => Err(message),
+                },
+                None => config.set(name, value),
+            };
+            if let Err(message) = result {
+                if SECURITY_OPTIONS.contains(&name) {
+                    return Err(invalid(message));
+                }
+                tracing::warn!("{}, skipping", invalid(message));
+            }
+        }
+        Ok(config)
+    }
+
+    /// The settings for an output, by the name and description it announced.
+    pub fn for_output(&self, name: Option<&str>, description: Option<&str>) -> &OutputConfig {
+        self.outputs
+            .iter()
+            .find(|(pattern, _)| matches_output(pattern, name, description))
+            .map_or(&self.output, |(_, output)| output)
+    }
+
+    /// Whether the output is the configured primary output.
+    pub fn is_primary(&self, name: Option<&str>, description: Option<&str>) -> bool {
+        self.primary_output
+            .as_deref()
+            .is_some_and(|primary| matches_output(primary, name, description))
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if self.output.set(name, value)? {
+            return Ok(());
+        }
+        let indicator = &mut self.indicator;
+        match name {
+            "primary-output" => {
+                self.primary_output = (!value.is_empty()).then(|| value.to_string())
+            }
+            "auth-backend" => {
+                self.auth.backend = Backend::parse(value)
+                    .ok_or_else(|| format!("{name} needs one of pam or shadow"))?
+            }
+            "shadow-file" if !value.is_empty() => self.auth.shadow_file = expand_home(value),
+            "pam-service" if !value.is_empty() => self.auth.pam_service = value.to_string(),
+            "pam-config-dir" => {
+                self.auth.pam_config_dir = (!value.is_empty()).then(|| expand_home(value))
+            }
+            "totp" => self.auth.totp.enabled = parse_flag(name, value)?,
+            "totp-digits" => {
+                self.auth.totp.digits = parse_count(name, value)
+                    .ok()
+                    .filter(|digits| (6..=8).contains(digits))
+                    .ok_or_else(|| format!("{name} needs 6, 7 or 8"))?
+            }
+            "totp-period" => {
+                self.auth.totp.period = parse_count(name, value)
+                    .ok()
+                    .filter(|&period| period > 0)
+                    .ok_or_else(|| format!("{name} needs a positive number of seconds"))?
+                    .into()
+            }
+            "totp-window" => self.auth.totp.window = parse_count(name, value)?,
+            "totp-secret-file" => {
+                self.auth.totp.secret_file = (!value.is_empty()).then(|| expand_home(value))
+            }
+            "grace" => self.grace = parse_seconds(name, value)?,
+            "fingerprint" => self.fingerprint = parse_flag(name, value)?,
+            "landlock" => self.hardening.landlock = parse_flag(name, value)?,
+            "seccomp" => self.hardening.seccomp = parse_flag(name, value)?,
+            "attempts-before-delay" => self.throttle.free_attempts = parse_count(name, value)?,
+            "attempt-delay" => self.throttle.delay = parse_seconds(name, value)?,
+            "max-attempt-delay" => self.throttle.max_delay = parse_seconds(name, value)?,
+            "lockout-attempts" => {
+                self.throttle.lockout_attempts = match value {
+                    "" | "0" => None,
+                    _ => Some(parse_count(name, value)?),
+                }
+            }
+            "lockout-time" => self.throttle.lockout = parse_seconds(name, value)?,
+            "attempts-file" => {
+                self.throttle.state_file = (!value.is_empty()).then(|| expand_home(value))
+            }
+            "font" if !value.is_empty() => indicator.font = value.to_string(),
+            "font-size" => indicator.font_size = parse_length(name, value)?,
+            "indicator-radius" => indicator.radius = parse_length(name, value)?,
+            "indicator-thickness" => indicator.thickness = parse_length(name, value)?,
+            _ => {
+                let color = match name {
+                    "inside-color" => &mut indicator.normal.inside,
+                    "inside-clear-color" => &mut indicator.clear.inside,
+                    "inside-caps-lock-color" => &mut indicator.caps_lock.inside,
+                    "inside-ver-color" => &mut indicator.verifying.inside,
+                    "inside-wrong-color" => &mut indicator.wrong.inside,
+                    "ring-color" => &mut indicator.normal.ring,
+                    "ring-clear-color" => &mut indicator.clear.ring,
+                    "ring-caps-lock-color" => &mut indicator.caps_lock.ring,
+                    "ring-ver-color" => &mut indicator.verifying.ring,
+                    "ring-wrong-color" => &mut indicator.wrong.ring,
+                    "line-color" => &mut indicator.normal.line,
+                    "line-clear-color" => &mut indicator.clear.line,
+                    "line-caps-lock-color" => &mut indicator.caps_lock.line,
+                    "line-ver-color" => &mut indicator.verifying.line,
+                    "line-wrong-color" => &mut indicator.wrong.line,
+                    "text-color" => &mut indicator.normal.text,
+                    "text-clear-color" => &mut indicator.clear.text,
+                    "text-caps-lock-color" => &mut indicator.caps_lock.text,
+                    "text-ver-color" => &mut indicator.verifying.text,
+                    "text-wrong-color" => &mut indicator.wrong.text,
+                    "key-hl-color" => &mut indicator.key_highlight,
+                    "bs-hl-color" => &mut indicator.backspace_highlight,
+                    "caps-lock-key-hl-color" => &mut indicator.caps_lock_key_highlight,
+                    "caps-lock-bs-hl-color" => &mut indicator.caps_lock_backspace_highlight,
+                    "separator-color" => &mut indicator.separator,
+                    _ => return Err(format!("unknown option {name}")),
+                };
+                *color = Color::parse(value)
+                    .ok_or_else(|| format!("{name} needs a color like rrggbb or rrggbbaa"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_length(name: &str, value: &str) -> Result<f32, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|length: &f32| length.is_finite() && *length >= 0.0)
+        .ok_or_else(|| format!("{name} needs a non-negative number"))
+}
+
+/// A bare option name turns it on.
+fn parse_flag(name: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "" | "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        _ => Err(format!("{name} needs true or false")),
+    }
+}
+
+fn parse_count(name: &str, value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{name} needs a whole number"))
+}
+
+fn parse_seconds(name: &str, value: &str) -> Result<Duration, String> {
+    value
+        .parse()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("{name} needs a non-negative number of seconds"))
+}
+
+fn matches_output(pattern: &str, name: Option<&str>, description: Option<&str>) -> bool {
+    name == Some(pattern) || description == Some(pattern)
+}
+
+/// `value` with a leading `~/` replaced by the home directory.
+fn expand_home(value: &str) -> PathBuf {
+    match (value.strip_prefix("~/"), env_path("HOME")) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(value),
+    }
+}
+
+/// The environment variable `name` as a path, unless it is unset or empty.
+pub fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// An XDG base directory: `$variable`, or `fallback` under the home directory when that is
+/// unset, as with `XDG_STATE_HOME` and `.local/state`.
+pub fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    env_path(variable).or_else(|| env_path("HOME").map(|home| home.join(fallback)))
+}
+
+/// `$XDG_CONFIG_HOME/lock/config`, falling back to `~/.config` and then `/etc`.
+fn config_file() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+        .map(|dir| dir.join("lock").join("config"))
+        .into_iter()
+        .chain([PathBuf::from(SYSTEM_CONFIG)])
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn path(file: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/config")
+            .join(file)
+    }
+
+    fn load(file: &str) -> Result<Config, Error> {
+        Config::from_file(&path(file))
+    }
+
+    /// Loads `file`, also returning the warnings logged while reading it.
+    fn load_logged(file: &str) -> (Result<Config, Error>, String) {
+        #[derive(Clone, Default)]
+        struct Log(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Log {
+            fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(bytes);
+                Ok(bytes.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let log = Log::default();
+        let writer = log.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .without_time()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let config = tracing::subscriber::with_default(subscriber, || load(file));
+        let text = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        (config, text)
+    }
+
+    #[test]
+    fn codes_turn_fingerprints_off() {
+        let config = load("fingerprint-totp").unwrap();
+        assert!(config.fingerprint && config.auth.totp.enabled);
+        assert!(!config.auth.allows_fingerprint());
+        assert!(AuthConfig::default().allows_fingerprint());
+    }
+
+    #[test]
+    fn reads_output_sections() {
+        let config = load("outputs").unwrap();
+        let global = OutputConfig {
+            background: Color::rgb(0x11, 0x22, 0x33),
+            image: Some(PathBuf::from("/srv/wallpaper.png")),
+            ..OutputConfig::default()
+        };
+        assert_eq!(config.output, global);
+        assert_eq!(config.indicator.font_size, 20.0);
+        assert_eq!(
+            config.outputs,
+            [
+                (
+                    "DP-1".to_string(),
+                    OutputConfig {
+                        background: Color::rgb(0x44, 0x55, 0x66),
+                        indicator_x: Some(100.0),
+                        ..global.clone()
+                    }
+                ),
+                (
+                    "Dell Inc. DELL U2720Q".to_string(),
+                    OutputConfig {
+                        scaling: Scaling::Center,
+                        image: None,
+                        ..global.clone()
+                    }
+                ),
+                // A section starts from the global settings, not from the one before it.
+                (
+                    "HDMI-A-1".to_string(),
+                    OutputConfig {
+                        indicator_y: Some(20.5),
+                        ..global.clone()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_the_settings_for_an_output() {
+        let config = load("outputs").unwrap();
+        let dell = Some("Dell Inc. DELL U2720Q");
+        assert_eq!(config.for_output(Some("DP-1"), None), &config.outputs[0].1);
+        assert_eq!(config.for_output(Some("DP-2"), dell), &config.outputs[1].1);
+        // The first section that matches by either name wins.
+        assert_eq!(config.for_output(Some("DP-1"), dell), &config.outputs[0].1);
+        assert_eq!(
+            config.for_output(Some("DP-3"), Some("DP-1")),
+            &config.outputs[0].1
+        );
+        // Anything else, including outputs that have not said their name yet, gets the rest.
+        assert_eq!(config.for_output(Some("DP-3"), None), &config.output);
+        assert_eq!(config.for_output(None, None), &config.output);
+        assert_eq!(config.for_output(Some("dp-1"), None), &config.output);
+
+        assert!(config.is_primary(Some("DP-2"), dell));
+        assert!(config.is_primary(Some("Dell Inc. DELL U2720Q"), None));
+        assert!(!config.is_primary(Some("DP-1"), None));
+        assert!(!config.is_primary(None, None));
+        assert!(!Config::default().is_primary(Some("DP-1"), dell));
+    }
+
+    #[test]
+    fn skips_invalid_lines_with_a_warning() {
+        let (config, log) = load_logged("invalid-lines");
+        let config = config.unwrap();
+        assert_eq!(config.output, OutputConfig::default());
+        assert_eq!(config.indicator.radius, IndicatorStyle::default().radius);
+        assert_eq!(config.indicator.font_size, 20.0);
+        assert_eq!(config.grace, Duration::ZERO);
+        // Lines under a malformed section header are skipped along with it.
+        assert_eq!(
+            config.outputs,
+            [(
+                "DP-2".to_string(),
+                OutputConfig {
+                    background: Color::rgb(0, 0xff, 0),
+                    ..OutputConfig::default()
+                }
+            )]
+        );
+
+        let file = path("invalid-lines");
+        let file = file.display();
+        let warnings = [
+            format!("{file}:1: color needs a color like rrggbb or rrggbbaa, skipping"),
+            format!("{file}:2: indicator-radius needs a non-negative number, skipping"),
+            format!("{file}:3: unknown option no-such-option, skipping"),
+            format!("{file}:5: expected [output <name>], not [outputs DP-1], skipping the section"),
+            format!("{file}:9: grace cannot differ between outputs, skipping"),
+            format!("{file}:10: expected [output <name>], not [output ], skipping the section"),
+        ];
+        let lines: Vec<_> = log.lines().collect();
+        assert_eq!(lines.len(), warnings.len(), "{log}");
+        for (line, warning) in lines.iter().zip(&warnings) {
+            assert!(line.starts_with(" WARN "), "{line}");
+            assert!(line.ends_with(warning.as_str()), "{line}");
+        }
+    }
+
+    #[test]
+    fn fails_on_invalid_security_options() {
+        let cases = [
+            ("invalid-totp-digits", 2, "totp-digits needs 6, 7 or 8"),
+            (
+                "invalid-backend",
+                1,
+                "auth-backend needs one of pam or shadow",
+            ),
+            (
+                "seccomp-in-section",
+                3,
+                "seccomp cannot differ between outputs",
+            ),
+        ];
+        for (file, line, message) in cases {
+            match load(file) {
+                Err(Error::Invalid(path, l, m)) => {
+                    assert_eq!((path.file_name().unwrap().to_str(), l), (Some(file), line));
+                    assert_eq!(m, message);
+                }
+                result => panic!("{file}: {result:?}"),
+            }
+        }
+        assert!(
+            matches!(load("missing"), Err(Error::Io(_, e)) if e.kind() == io::ErrorKind::NotFound)
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! The D-Bus wire format, little-endian only, for the handful of types the locker exchanges.
+
+use std::io::{Error, ErrorKind, Result};
+
+/// Messages longer than this are refused; the specification allows 128 MiB, fprintd never
+/// comes close.
+const MAX_LENGTH: usize = 1 << 20;
+const PROTOCOL_VERSION: u8 = 1;
+
+const PATH: u8 = 1;
+const INTERFACE: u8 = 2;
+const MEMBER: u8 = 3;
+const ERROR_NAME: u8 = 4;
+const REPLY_SERIAL: u8 = 5;
+const DESTINATION: u8 = 6;
+const SENDER: u8 = 7;
+const SIGNATURE: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    MethodCall = 1,
+    MethodReturn = 2,
+    Error = 3,
+    Signal = 4,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    String(String),
+    ObjectPath(String),
+    Signature(String),
+    /// The element signature, needed to write empty arrays, and the elements.
+    Array(String, Vec<Value>),
+    Struct(Vec<Value>),
+    Variant(Box<Value>),
+}
+
+impl Value {
+    pub fn signature(&self) -> String {
+        match self {
+            Self::Byte(_) => "y".to_string(),
+            Self::Bool(_) => "b".to_string(),
+            Self::I32(_) => "i".to_string(),
+            Self::U32(_) => "u".to_string(),
+            Self::String(_) => "s".to_string(),
+            Self::ObjectPath(_) => "o".to_string(),
+            Self::Signature(_) => "g".to_string(),
+            Self::Array(element, _) => format!("a{element}"),
+            Self::Struct(fields) => {
+                let fields: String = fields.iter().map(Self::signature).collect();
+                format!("({fields})")
+            }
+            Self::Variant(_) => "v".to_string(),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) | Self::ObjectPath(s) | Self::Signature(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(_, elements) => Some(elements),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub kind: Kind,
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<Value>,
+}
+
+impl Message {
+    pub fn method_call(
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        body: Vec<Value>,
+    ) -> Self {
+        Self {
+            kind: Kind::MethodCall,
+            serial: 0,
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            error_name: None,
+            reply_serial: None,
+            destination: Some(destination.to_string()),
+            sender: None,
+            body,
+        }
+    }
+
+    /// Whether this is the signal `interface.member`.
+    pub fn is_signal(&self, interface: &str, member: &str) -> bool {
+        self.kind == Kind::Signal
+            && self.interface.as_deref() == Some(interface)
+            && self.member.as_deref() == Some(member)
+    }
+
+    /// The error's name and its message, for replies that are errors.
+    pub fn error(&self) -> Option<String> {
+        let name = self.error_name.as_deref()?;
+        Some(match self.body.first().and_then(Value::as_str) {
+            Some(message) => format!("{name}: {message}"),
+            None => name.to_string(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Writer::default();
+        for value in &self.body {
+            body.write(value);
+        }
+        let mut fields = Vec::new();
+        let mut field = |code, value: Option<Value>| {
+            if let Some(value) = value {
+                fields.push(Value::Struct(vec![
+                    Value::Byte(code),
+                    Value::Variant(Box::new(value)),
+                ]));
+            }
+        };
+        field(PATH, self.path.clone().map(Value::ObjectPath));
+        field(INTERFACE, self.interface.clone().map(Value::String));
+        field(MEMBER, self.member.clone().map(Value::String));
+        field(ERROR_NAME, self.error_name.clone().map(Value::String));
+        field(REPLY_SERIAL, self.reply_serial.map(Value::U32));
+        field(DESTINATION, self.destination.clone().map(Value::String));
+        field(SENDER, self.sender.clone().map(Value::String));
+        let signature: String = self.body.iter().map(Value::signature).collect();
+        field(
+            SIGNATURE,
+            (!signature.is_empty()).then_some(Value::Signature(signature)),
+        );
+
+        let mut message = Writer::default();
+        message
+            .bytes
+            .extend([b'l', self.kind as u8, 0, PROTOCOL_VERSION]);
+        message.write(&Value::U32(body.bytes.len() as u32));
+        message.write(&Value::U32(self.serial));
+        message.write(&Value::Array("(yv)".to_string(), fields));
+        message.pad(8);
+        message.bytes.extend(body.bytes);
+        message.bytes
+    }
+
+    /// Decodes the message at the start of `buffer`, with the number of bytes it took, or
+    /// `None` if it is not all there yet.
+    pub fn decode(buffer: &[u8]) -> Result<Option<(Self, usize)>> {
+        if buffer.len() < 16 {
+            return Ok(None);
+        }
+        if buffer[0] != b'l' {
+            return Err(malformed("big-endian messages are not supported"));
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(buffer[at..at + 4].try_into().unwrap());
+        let (body_length, fields_length) = (u32_at(4) as usize, u32_at(12) as usize);
+        let header_length = (16 + fields_length).next_multiple_of(8);
+        let length = header_length + body_length;
+        if length > MAX_LENGTH {
+            return Err(malformed("message too long"));
+        }
+        if buffer.len() < length {
+            return Ok(None);
+        }
+        let kind = match buffer[1] {
+            1 => Kind::MethodCall,
+            2 => Kind::MethodReturn,
+            3 => Kind::Error,
+            4 => Kind::Signal,
+            _ => return Err(malformed("unknown message type")),
+        };
+        let mut message = Self {
+            kind,
+            serial: u32_at(8),
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: None,
+            destination: None,
+            sender: None,
+            body: Vec::new(),
+        };
+        let mut reader = Reader {
+            data: &buffer[..header_length],
+            position: 12,
+        };
+        let Value::Array(_, fields) = reader.read("a(yv)")? else {
+            unreachable!("read returns what the signature says");
+        };
+        let mut signature = String::new();
+        for field in fields {
+            let Value::Struct(field) = field else {
+                unreachable!("read returns what the signature says");
+            };
+            let (Value::Byte(code), Value::Variant(value)) = (&field[0], &field[1]) else {
+                unreachable!("read returns what the signature says");
+            };
+            let text = value.as_str().map(str::to_string);
+            match (code, &**value) {
+                (&PATH, _) => message.path = text,
+                (&INTERFACE, _) => message.interface = text,
+                (&MEMBER, _) => message.member = text,
+                (&ERROR_NAME, _) => message.error_name = text,
+                (&REPLY_SERIAL, Value::U32(serial)) => message.reply_serial = Some(*serial),
+                (&DESTINATION, _) => message.destination = text,
+                (&SENDER, _) => message.sender = text,
+                (&SIGNATURE, _) => signature = text.unwrap_or_default(),
+                // Unknown fields must be ignored.
+                _ => {}
+            }
+        }
+        // Body alignment is relative to the body's start, which is itself 8-aligned.
+        let mut reader = Reader {
+            data: &buffer[header_length..length],
+            position: 0,
+        };
+        let mut rest = signature.as_str();
+        while !rest.is_empty() {
+            let (single, remainder) = split_type(rest)?;
+            message.body.push(reader.read(single)?);
+            rest = remainder;
+        }
+        Ok(Some((message, length)))
+    }
+}
+
+fn malformed(message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("malformed D-Bus message: {message}"),
+    )
+}
+
+/// Splits the first complete type off a signature.
+fn split_type(signature: &str) -> Result<(&str, &str)> {
+    let bytes = signature.as_bytes();
+    let end = match bytes.first() {
+        None => return Err(malformed("missing type in signature")),
+        Some(b'a') => 1 + split_type(&signature[1..])?.0.len(),
+        Some(open @ (b'(' | b'{')) => {
+            let close = if *open == b'(' { b')' } else { b'}' };
+            let mut depth = 0;
+            let mut end = None;
+            for (index, &byte) in bytes.iter().enumerate() {
+                if byte == *open {
+                    depth += 1;
+                } else if byte == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(index + 1);
+                        break;
+                    }
+                }
+            }
+            end.ok_or_else(|| malformed("unbalanced signature"))?
+        }
+        Some(_) => 1,
+    };
+    Ok(signature.split_at(end))
+}
+
+/// Alignment of values of the type starting `signature`.
+fn alignment(signature: &str) -> usize {
+    match signature.as_bytes().first() {
+        Some(b'n' | b'q') => 2,
+        Some(b'b' | b'i' | b'u' | b's' | b'o' | b'a' | b'h') => 4,
+        Some(b'x' | b't' | b'd' | b'(' | b'{') => 8,
+        _ => 1,
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn pad(&mut self, alignment: usize) {
+        let length = self.bytes.len().next_multiple_of(alignment);
+        self.bytes.resize(length, 0);
+    }
+
+    fn write(&mut self, value: &Value) {
+        self.pad(alignment(&value.signature()));
+        match value {
+            Value::Byte(byte) => self.bytes.push(*byte),
+            Value::Bool(b) => self.bytes.extend(u32::from(*b).to_le_bytes()),
+            Value::I32(n) => self.bytes.extend(n.to_le_bytes()),
+            Value::U32(n) => self.bytes.extend(n.to_le_bytes()),
+            Value::String(s) | Value::ObjectPath(s) => {
+                self.bytes.extend((s.len() as u32).to_le_bytes());
+                self.bytes.extend(s.as_bytes());
+                self.bytes.push(0);
+            }
+            Value::Signature(s) => {
+                self.bytes.push(s.len() as u8);
+                self.bytes.extend(s.as_bytes());
+                self.bytes.push(0);
+            }
+            Value::Array(element, elements) => {
+                let at = self.bytes.len();
+                self.bytes.extend([0; 4]);
+                // The length leaves out the padding before the first element.
+                self.pad(alignment(element));
+                let start = self.bytes.len();
+                for element in elements {
+                    self.write(element);
+                }
+                let length = (self.bytes.len() - start) as u32;
+                self.bytes[at..at + 4].copy_from_slice(&length.to_le_bytes());
+            }
+            Value::Struct(fields) => {
+                for field in fields {
+                    self.write(field);
+                }
+            }
+            Value::Variant(value) => {
+                self.write(&Value::Signature(value.signature()));
+                self.write(value);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, length: usize) -> Result<&[u8]> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| malformed("value runs past the end"))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn text(&mut self, length: usize) -> Result<String> {
+        let text = String::from_utf8(self.take(length)?.to_vec())
+            .map_err(|_| malformed("string is not UTF-8"))?;
+        self.take(1)?;
+        Ok(text)
+    }
+
+    /// Reads one value of the complete type `signature`.
+    fn read(&mut self, signature: &str) -> Result<Value> {
+        let padded = self.position.next_multiple_of(alignment(signature));
+        self.take(padded - self.position)?;
+        Ok(match signature.as_bytes()[0] {
+            b'y' => Value::Byte(self.take(1)?[0]),
+            b'b' => Value::Bool(self.u32()? != 0),
+            b'i' => Value::I32(self.u32()? as i32),
+            b'u' => Value::U32(self.u32()?),
+            b's' => {
+                let length = self.u32()? as usize;
+                Value::String(self.text(length)?)
+            }
+            b'o' => {
+                let length = self.u32()? as usize;
+                Value::ObjectPath(self.text(length)?)
+            }
+            b'g' => {
+                let length = self.take(1)?[0] as usize;
+                Value::Signature(self.text(length)?)
+            }
+            b'a' => {
+                let element = &signature[1..];
+                let length = self.u32()? as usize;
+                let padded = self.position.next_multiple_of(alignment(element));
+                self.take(padded - self.position)?;
+                let end = self.position + length;
+                let mut elements = Vec::new();
+                while self.position < end {
+                    elements.push(self.read(element)?);
+                }
+                Value::Array(element.to_string(), elements)
+            }
+            b'(' | b'{' => {
+                let mut rest = &signature[1..signature.len() - 1];
+                let mut fields = Vec::new();
+                while !rest.is_empty() {
+                    let (field, remainder) = split_type(rest)?;
+                    fields.push(self.read(field)?);
+                    rest = remainder;
+                }
+                Value::Struct(fields)
+            }
+            b'v' => {
+                let length = self.take(1)?[0] as usize;
+                let signature = self.text(length)?;
+                let (single, rest) = split_type(&signature)?;
+                if !rest.is_empty() {
+                    return Err(malformed("variant holds more than one value"));
+                }
+                Value::Variant(Box::new(self.read(single)?))
+            }
+            _ => return Err(malformed("unsupported type")),
+        })
+    }
+}
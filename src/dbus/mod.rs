@@ -0,0 +1,204 @@
+//! A minimal D-Bus client for the system bus: enough to call methods and receive signals from
+//! inside the event loop, without pulling in a D-Bus library for a single service.
+
+mod message;
+#[cfg(test)]
+pub mod testing;
+
+pub use message::{Kind, Message, Value};
+
+use mio::{Interest, Registry, Token, event::Source, net::UnixStream};
+use std::{
+    env,
+    ffi::OsStr,
+    io::{BufRead, BufReader, Error, ErrorKind, Read, Result, Write},
+    os::{
+        linux::net::SocketAddrExt,
+        unix::{
+            ffi::OsStrExt,
+            net::{self, SocketAddr},
+        },
+    },
+    time::Duration,
+};
+
+const SYSTEM_BUS: &str = "unix:path=/var/run/dbus/system_bus_socket";
+/// The bus itself, as the destination of its methods and the sender of their replies.
+pub const BUS_NAME: &str = "org.freedesktop.DBus";
+const BUS_PATH: &str = "/org/freedesktop/DBus";
+/// How long connecting may block startup before the bus is given up on.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A connection to a message bus. It is non-blocking once connected: [`Connection::call`]
+/// queues messages and [`Connection::messages`] returns what arrived since the last time.
+#[derive(Debug)]
+pub struct Connection {
+    stream: UnixStream,
+    serial: u32,
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+impl Connection {
+    /// Connects to the system bus, at `$DBUS_SYSTEM_BUS_ADDRESS` if that is set.
+    pub fn system() -> Result<Self> {
+        let address = env::var("DBUS_SYSTEM_BUS_ADDRESS").unwrap_or_else(|_| SYSTEM_BUS.into());
+        Self::connect(&address)
+    }
+
+    /// Connects to the bus at `address`, such as `unix:path=/run/dbus/system_bus_socket`.
+    pub fn connect(address: &str) -> Result<Self> {
+        let stream = address
+            .split(';')
+            .filter_map(socket_address)
+            .find_map(|address| net::UnixStream::connect_addr(&address).ok())
+            .ok_or_else(|| Error::other(format!("cannot connect to D-Bus at {address}")))?;
+        Self::authenticate(&stream)?;
+        stream.set_nonblocking(true)?;
+        let mut connection = Self {
+            stream: UnixStream::from_std(stream),
+            serial: 0,
+            input: Vec::new(),
+            output: Vec::new(),
+        };
+        connection.call(BUS_NAME, BUS_PATH, BUS_NAME, "Hello", Vec::new())?;
+        Ok(connection)
+    }
+
+    /// Queues a method call and returns its serial, which the reply refers to.
+    pub fn call(
+        &mut self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        body: Vec<Value>,
+    ) -> Result<u32> {
+        self.serial += 1;
+        let mut message = Message::method_call(destination, path, interface, member, body);
+        message.serial = self.serial;
+        self.output.extend(message.encode());
+        self.flush()?;
+        Ok(self.serial)
+    }
+
+    /// Asks the bus to pass on signals matching `rule`, such as
+    /// `type='signal',interface='org.example.Foo'`.
+    pub fn add_match(&mut self, rule: &str) -> Result<u32> {
+        let rule = vec![Value::String(rule.to_string())];
+        self.call(BUS_NAME, BUS_PATH, BUS_NAME, "AddMatch", rule)
+    }
+
+    /// Asks the bus which connection owns `name`; the reply holds its unique name, which is
+    /// what that service's messages carry as their sender.
+    pub fn name_owner(&mut self, name: &str) -> Result<u32> {
+        let name = vec![Value::String(name.to_string())];
+        self.call(BUS_NAME, BUS_PATH, BUS_NAME, "GetNameOwner", name)
+    }
+
+    /// Reads everything available and returns the complete messages. Fails once the bus hangs
+    /// up.
+    pub fn messages(&mut self) -> Result<Vec<Message>> {
+        // Only readiness to read is polled for, so writes left over are retried here.
+        self.flush()?;
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(Error::new(ErrorKind::UnexpectedEof, "D-Bus hung up")),
+                Ok(n) => self.input.extend(&buffer[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let mut messages = Vec::new();
+        let mut start = 0;
+        while let Some((message, length)) = Message::decode(&self.input[start..])? {
+            messages.push(message);
+            start += length;
+        }
+        self.input.drain(..start);
+        Ok(messages)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(n) => {
+                    self.output.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// The `EXTERNAL` handshake, in which the bus takes the uid from the socket's credentials.
+    fn authenticate(stream: &net::UnixStream) -> Result<()> {
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        // SAFETY: getuid cannot fail.
+        let uid = unsafe { libc::getuid() }.to_string();
+        let hex: String = uid.bytes().map(|b| format!("{b:02x}")).collect();
+        let mut writer = stream;
+        writer.write_all(format!("\0AUTH EXTERNAL {hex}\r\n").as_bytes())?;
+        // One byte at a time, so nothing after the line is read ahead.
+        let mut reply = Vec::new();
+        BufReader::with_capacity(1, stream).read_until(b'\n', &mut reply)?;
+        if !reply.starts_with(b"OK ") {
+            let reply = String::from_utf8_lossy(&reply);
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!("D-Bus refused authentication: {}", reply.trim_end()),
+            ));
+        }
+        writer.write_all(b"BEGIN\r\n")
+    }
+}
+
+/// The socket of one `unix:` entry of a bus address, `None` for other transports.
+fn socket_address(address: &str) -> Option<SocketAddr> {
+    let options = address.strip_prefix("unix:")?;
+    options.split(',').find_map(|option| {
+        let (key, value) = option.split_once('=')?;
+        let value = unescape(value)?;
+        match key {
+            "path" => SocketAddr::from_pathname(OsStr::from_bytes(&value)).ok(),
+            "abstract" => SocketAddr::from_abstract_name(value).ok(),
+            _ => None,
+        }
+    })
+}
+
+/// Undoes the `%xx` escaping of address values.
+fn unescape(value: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    Some(bytes)
+}
+
+impl Source for Connection {
+    fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        self.stream.register(registry, token, interests)
+    }
+
+    fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        self.stream.reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> Result<()> {
+        self.stream.deregister(registry)
+    }
+}
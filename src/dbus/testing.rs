@@ -0,0 +1,159 @@
+//! A stand-in for a message bus and the services on it, for tests: it accepts one connection
+//! on a private socket and lets the test play both the bus and the service, sending replies and
+//! signals with whatever sender it likes.
+
+use crate::dbus::{BUS_NAME, Kind, Message, Value};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+
+/// The unique name the bus hands the client under test.
+pub const CLIENT: &str = ":1.1";
+
+pub struct FakeBus {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl FakeBus {
+    /// Listens on a fresh socket, returning the bus and its address.
+    pub fn listen() -> (Self, String) {
+        static COUNT: AtomicU32 = AtomicU32::new(0);
+        let name = format!(
+            "lock-test-bus-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        let listener = UnixListener::bind(&path).expect("cannot bind the fake bus");
+        let address = format!("unix:path={}", path.display());
+        (Self { listener, path }, address)
+    }
+
+    /// Accepts the client and answers its handshake and `Hello`.
+    pub fn accept(&self) -> Peer {
+        let (stream, _) = self.listener.accept().expect("no client connected");
+        // A client that never sends what the test waits for fails it rather than hanging it.
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = Vec::new();
+        reader.read_until(b'\n', &mut line).unwrap();
+        assert!(
+            line.starts_with(b"\0AUTH EXTERNAL "),
+            "unexpected handshake"
+        );
+        (&stream)
+            .write_all(b"OK 0123456789abcdef0123456789abcdef\r\n")
+            .unwrap();
+        line.clear();
+        reader.read_until(b'\n', &mut line).unwrap();
+        assert_eq!(line, b"BEGIN\r\n");
+        let mut peer = Peer {
+            reader,
+            stream,
+            serial: 0,
+        };
+        let hello = peer.expect_call("Hello");
+        peer.reply(&hello, BUS_NAME, vec![Value::String(CLIENT.to_string())]);
+        peer
+    }
+}
+
+impl Drop for FakeBus {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The bus's end of the client's connection.
+pub struct Peer {
+    reader: BufReader<UnixStream>,
+    stream: UnixStream,
+    serial: u32,
+}
+
+impl Peer {
+    /// Waits for the client's next message.
+    pub fn receive(&mut self) -> Message {
+        let mut buffer = Vec::new();
+        loop {
+            if let Some((message, _)) = Message::decode(&buffer).expect("malformed message") {
+                return message;
+            }
+            let mut byte = [0];
+            self.reader
+                .read_exact(&mut byte)
+                .expect("the client hung up");
+            buffer.push(byte[0]);
+        }
+    }
+
+    /// Waits for the client to hang up, ignoring whatever else it sends first, so that what was
+    /// sent to it is not cut short by the bus going away.
+    pub fn wait_for_hangup(&mut self) {
+        self.reader
+            .read_to_end(&mut Vec::new())
+            .expect("the client did not hang up");
+    }
+
+    /// Waits for a method call and checks that it is `member`.
+    pub fn expect_call(&mut self, member: &str) -> Message {
+        let call = self.receive();
+        assert_eq!(call.kind, Kind::MethodCall);
+        assert_eq!(call.member.as_deref(), Some(member));
+        call
+    }
+
+    /// Answers `call` as `sender`.
+    pub fn reply(&mut self, call: &Message, sender: &str, body: Vec<Value>) {
+        self.send(Message {
+            kind: Kind::MethodReturn,
+            serial: 0,
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: Some(call.serial),
+            destination: Some(CLIENT.to_string()),
+            sender: Some(sender.to_string()),
+            body,
+        });
+    }
+
+    /// Sends the signal `interface.member` from `path`, as `sender`, to everyone or only to
+    /// `destination`.
+    pub fn signal(
+        &mut self,
+        sender: &str,
+        destination: Option<&str>,
+        (path, interface, member): (&str, &str, &str),
+        body: Vec<Value>,
+    ) {
+        self.send(Message {
+            kind: Kind::Signal,
+            serial: 0,
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            error_name: None,
+            reply_serial: None,
+            destination: destination.map(str::to_string),
+            sender: Some(sender.to_string()),
+            body,
+        });
+    }
+
+    fn send(&mut self, mut message: Message) {
+        self.serial += 1;
+        message.serial = self.serial;
+        // The client may be gone once the test is over.
+        let _ = self.stream.write_all(&message.encode());
+    }
+}
@@ -0,0 +1,406 @@
+//! Fingerprint unlocking through fprintd, alongside the password: the reader is claimed when
+//! the lock starts and kept verifying, starting over after every finger it does not recognize,
+//! until one matches or the lock ends.
+
+use crate::dbus::{BUS_NAME, Connection, Kind, Message, Value};
+use std::io::{Error, Result};
+
+const FPRINTD: &str = "net.reactivated.Fprint";
+const MANAGER_PATH: &str = "/net/reactivated/Fprint/Manager";
+const MANAGER: &str = "net.reactivated.Fprint.Manager";
+const DEVICE: &str = "net.reactivated.Fprint.Device";
+
+/// The call whose reply is awaited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    GetOwner,
+    GetDevice,
+    ListFingers,
+    Claim,
+    VerifyStart,
+    VerifyStop,
+}
+
+/// A claimed fingerprint reader, or the way to one. `bus` is registered with the event loop,
+/// which hands it back to [`Fingerprint::dispatch`] when it is readable.
+#[derive(Debug)]
+pub struct Fingerprint {
+    bus: Connection,
+    user: String,
+    /// fprintd's unique name on the bus. Anyone may send signals, so only its count.
+    owner: Option<String>,
+    device: Option<String>,
+    pending: Option<(u32, Step)>,
+    claimed: bool,
+    verifying: bool,
+    status: Option<&'static str>,
+}
+
+impl Fingerprint {
+    /// Connects to the system bus and finds fprintd; the rest follows from the replies.
+    pub fn start(user: &str) -> Result<Self> {
+        Self::new(Connection::system()?, user)
+    }
+
+    fn new(mut bus: Connection, user: &str) -> Result<Self> {
+        let serial = bus.name_owner(FPRINTD)?;
+        Ok(Self {
+            bus,
+            user: user.to_string(),
+            owner: None,
+            device: None,
+            pending: Some((serial, Step::GetOwner)),
+            claimed: false,
+            verifying: false,
+            status: None,
+        })
+    }
+
+    pub fn bus(&mut self) -> &mut Connection {
+        &mut self.bus
+    }
+
+    /// What to tell the user, such as to scan a finger or to try again.
+    pub fn status(&self) -> Option<&'static str> {
+        self.status
+    }
+
+    /// Called when the bus is readable; returns whether a finger matched. Fails when
+    /// fingerprints cannot be used, after which the reader is left alone.
+    pub fn dispatch(&mut self) -> Result<bool> {
+        for message in self.bus.messages()? {
+            if self.handle(message)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn handle(&mut self, message: Message) -> Result<bool> {
+        if message.is_signal(DEVICE, "VerifyStatus") {
+            // Broadcast by fprintd itself; a signal sent to this connection alone never is.
+            if message.path != self.device
+                || message.sender.is_none()
+                || message.sender != self.owner
+                || message.destination.is_some()
+            {
+                return Ok(false);
+            }
+            let (Some(result), Some(done)) = (
+                message.body.first().and_then(Value::as_str),
+                message.body.get(1).and_then(Value::as_bool),
+            ) else {
+                return Ok(false);
+            };
+            return self.verify_status(result, done);
+        }
+        let Some((serial, step)) = self.pending else {
+            return Ok(false);
+        };
+        let sender = match step {
+            Step::GetOwner => Some(BUS_NAME),
+            _ => self.owner.as_deref(),
+        };
+        if !matches!(message.kind, Kind::MethodReturn | Kind::Error)
+            || message.reply_serial != Some(serial)
+            || message.sender.as_deref() != sender
+        {
+            return Ok(false);
+        }
+        self.pending = None;
+        if let Some(error) = message.error() {
+            // Stopping may fail when verification already ended; starting again is what counts.
+            if step != Step::VerifyStop {
+                return Err(Error::other(match step {
+                    Step::GetOwner => format!("fprintd is not running: {error}"),
+                    Step::GetDevice => format!("no fingerprint reader: {error}"),
+                    Step::ListFingers => format!("no fingerprints enrolled: {error}"),
+                    Step::Claim => format!("cannot claim the fingerprint reader: {error}"),
+                    _ => format!("cannot verify fingerprints: {error}"),
+                }));
+            }
+        }
+        match step {
+            Step::GetOwner => {
+                let owner = message.body.first().and_then(Value::as_str);
+                let owner = owner.ok_or_else(|| Error::other("the bus sent no owner"))?;
+                self.owner = Some(owner.to_string());
+                let serial =
+                    self.bus
+                        .call(owner, MANAGER_PATH, MANAGER, "GetDefaultDevice", Vec::new())?;
+                self.pending = Some((serial, Step::GetDevice));
+            }
+            Step::GetDevice => {
+                let device = message.body.first().and_then(Value::as_str);
+                let device = device.ok_or_else(|| Error::other("fprintd sent no reader"))?;
+                tracing::info!("Using fingerprint reader {device}");
+                self.device = Some(device.to_string());
+                self.call(
+                    "ListEnrolledFingers",
+                    Step::ListFingers,
+                    vec![Value::String(self.user.clone())],
+                )?;
+            }
+            Step::ListFingers => {
+                let fingers = message.body.first().and_then(Value::as_array);
+                if fingers.is_none_or(<[Value]>::is_empty) {
+                    return Err(Error::other("no fingerprints enrolled"));
+                }
+                let rule = format!(
+                    "type='signal',sender='{}',interface='{DEVICE}',member='VerifyStatus',path='{}'",
+                    self.owner.as_deref().unwrap_or_default(),
+                    self.device.as_deref().unwrap_or_default()
+                );
+                self.bus.add_match(&rule)?;
+                self.call("Claim", Step::Claim, vec![Value::String(self.user.clone())])?;
+            }
+            Step::Claim => {
+                self.claimed = true;
+                self.call(
+                    "VerifyStart",
+                    Step::VerifyStart,
+                    vec![Value::String("any".to_string())],
+                )?;
+            }
+            Step::VerifyStart => {
+                self.verifying = true;
+                if self.status.is_none() {
+                    self.status = Some("Scan your finger");
+                }
+            }
+            Step::VerifyStop => {
+                self.verifying = false;
+                self.call(
+                    "VerifyStart",
+                    Step::VerifyStart,
+                    vec![Value::String("any".to_string())],
+                )?;
+            }
+        }
+        Ok(false)
+    }
+
+    fn verify_status(&mut self, result: &str, done: bool) -> Result<bool> {
+        tracing::info!("Fingerprint verification: {result}");
+        self.status = Some(match result {
+            "verify-match" => return Ok(true),
+            "verify-no-match" => "Fingerprint not recognized",
+            "verify-retry-scan" => "Scan your finger again",
+            "verify-swipe-too-short" => "Swipe too short, try again",
+            "verify-finger-not-centered" => "Center your finger, try again",
+            "verify-remove-and-retry" => "Lift your finger, try again",
+            "verify-disconnected" => return Err(Error::other("fingerprint reader disconnected")),
+            _ => return Err(Error::other(format!("fingerprint reader failed: {result}"))),
+        });
+        // A finished verification has to be stopped before the next can start.
+        if done && self.pending.is_none() {
+            self.call("VerifyStop", Step::VerifyStop, Vec::new())?;
+        }
+        Ok(false)
+    }
+
+    /// Calls `member` on the reader and waits for its reply as `step`.
+    fn call(&mut self, member: &str, step: Step, body: Vec<Value>) -> Result<()> {
+        let serial = self.send(member, body)?;
+        self.pending = Some((serial, step));
+        Ok(())
+    }
+
+    /// Calls `member` on the reader without waiting for a reply.
+    fn send(&mut self, member: &str, body: Vec<Value>) -> Result<u32> {
+        let owner = self.owner.as_deref().unwrap_or_default();
+        let device = self.device.as_deref().unwrap_or_default();
+        self.bus.call(owner, device, DEVICE, member, body)
+    }
+}
+
+impl Drop for Fingerprint {
+    /// Hands the reader back. fprintd also does when the connection closes, so failures here
+    /// only leave that to it.
+    fn drop(&mut self) {
+        if self.verifying {
+            let _ = self.send("VerifyStop", Vec::new());
+        }
+        if self.claimed {
+            let _ = self.send("Release", Vec::new());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbus::testing::{FakeBus, Peer};
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    /// fprintd's unique name on the fake bus.
+    const SERVICE: &str = ":1.5";
+    const READER: &str = "/net/reactivated/Fprint/Device/0";
+    const VERIFY_STATUS: (&str, &str, &str) = (READER, DEVICE, "VerifyStatus");
+
+    /// Plays fprintd up to the point the reader is verifying.
+    fn start_verifying(peer: &mut Peer) {
+        let call = peer.expect_call("GetNameOwner");
+        assert_eq!(call.body, [Value::String(FPRINTD.to_string())]);
+        peer.reply(&call, BUS_NAME, vec![Value::String(SERVICE.to_string())]);
+        let call = peer.expect_call("GetDefaultDevice");
+        assert_eq!(call.destination.as_deref(), Some(SERVICE));
+        peer.reply(&call, SERVICE, vec![Value::ObjectPath(READER.to_string())]);
+        let call = peer.expect_call("ListEnrolledFingers");
+        let fingers = vec![Value::String("right-index-finger".to_string())];
+        peer.reply(&call, SERVICE, vec![Value::Array("s".to_string(), fingers)]);
+        let call = peer.expect_call("AddMatch");
+        peer.reply(&call, BUS_NAME, Vec::new());
+        let call = peer.expect_call("Claim");
+        peer.reply(&call, SERVICE, Vec::new());
+        let call = peer.expect_call("VerifyStart");
+        peer.reply(&call, SERVICE, Vec::new());
+    }
+
+    fn status(result: &str, done: bool) -> Vec<Value> {
+        vec![Value::String(result.to_string()), Value::Bool(done)]
+    }
+
+    /// Runs `service` as fprintd on a private bus and dispatches its messages until
+    /// `finished` says so, returning whether a finger matched by then.
+    fn run(
+        service: impl FnOnce(&mut Peer) + Send + 'static,
+        finished: impl Fn(&Fingerprint) -> bool,
+    ) -> bool {
+        let (bus, address) = FakeBus::listen();
+        let service = thread::spawn(move || {
+            let mut peer = bus.accept();
+            service(&mut peer);
+            peer.wait_for_hangup();
+        });
+        let mut fingerprint = Fingerprint::new(Connection::connect(&address).unwrap(), "user")
+            .expect("cannot start fingerprint verification");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let matched = loop {
+            if fingerprint.dispatch().expect("verification failed") {
+                break true;
+            }
+            if finished(&fingerprint) {
+                break false;
+            }
+            assert!(Instant::now() < deadline, "fprintd was not heard from");
+            thread::sleep(Duration::from_millis(5));
+        };
+        // Hanging up first makes a service still waiting for a call fail rather than hang.
+        drop(fingerprint);
+        service.join().expect("fprintd failed");
+        matched
+    }
+
+    #[test]
+    fn match_unlocks() {
+        let matched = run(
+            |peer| {
+                start_verifying(peer);
+                let retry = status("verify-retry-scan", false);
+                peer.signal(SERVICE, None, VERIFY_STATUS, retry);
+                peer.signal(SERVICE, None, VERIFY_STATUS, status("verify-match", true));
+            },
+            |_| false,
+        );
+        assert!(matched);
+    }
+
+    #[test]
+    fn no_match_starts_over() {
+        let matched = run(
+            |peer| {
+                start_verifying(peer);
+                let no_match = status("verify-no-match", true);
+                peer.signal(SERVICE, None, VERIFY_STATUS, no_match);
+                let call = peer.expect_call("VerifyStop");
+                peer.reply(&call, SERVICE, Vec::new());
+                let call = peer.expect_call("VerifyStart");
+                peer.reply(&call, SERVICE, Vec::new());
+            },
+            |fingerprint| {
+                fingerprint.status() == Some("Fingerprint not recognized")
+                    && fingerprint.verifying
+                    && fingerprint.pending.is_none()
+            },
+        );
+        assert!(!matched);
+    }
+
+    #[test]
+    fn spoofed_match_is_ignored() {
+        let matched = run(
+            |peer| {
+                start_verifying(peer);
+                let spoofed = || status("verify-match", true);
+                // Another client, by its unique name or by fprintd's well-known one.
+                peer.signal(":1.9", None, VERIFY_STATUS, spoofed());
+                peer.signal(FPRINTD, None, VERIFY_STATUS, spoofed());
+                // fprintd's name, but sent to the locker alone.
+                let locker = Some(crate::dbus::testing::CLIENT);
+                peer.signal(SERVICE, locker, VERIFY_STATUS, spoofed());
+                // A reader other than the one being verified with.
+                let other = ("/net/reactivated/Fprint/Device/1", DEVICE, "VerifyStatus");
+                peer.signal(SERVICE, None, other, spoofed());
+                let retry = status("verify-retry-scan", false);
+                peer.signal(SERVICE, None, VERIFY_STATUS, retry);
+            },
+            |fingerprint| fingerprint.status() == Some("Scan your finger again"),
+        );
+        assert!(!matched);
+    }
+
+    #[test]
+    fn dropping_releases_the_reader() {
+        let matched = run(
+            |peer| {
+                start_verifying(peer);
+                // Nothing waits for these replies, as the lock is ending.
+                for member in ["VerifyStop", "Release"] {
+                    let call = peer.expect_call(member);
+                    assert_eq!(call.destination.as_deref(), Some(SERVICE));
+                    assert_eq!(call.path.as_deref(), Some(READER));
+                    assert!(call.body.is_empty());
+                }
+            },
+            |fingerprint| fingerprint.verifying,
+        );
+        assert!(!matched);
+    }
+
+    #[test]
+    fn no_enrolled_fingers_fails() {
+        let (bus, address) = FakeBus::listen();
+        let service = thread::spawn(move || {
+            let mut peer = bus.accept();
+            let call = peer.expect_call("GetNameOwner");
+            peer.reply(&call, BUS_NAME, vec![Value::String(SERVICE.to_string())]);
+            let call = peer.expect_call("GetDefaultDevice");
+            peer.reply(&call, SERVICE, vec![Value::ObjectPath(READER.to_string())]);
+            let call = peer.expect_call("ListEnrolledFingers");
+            peer.reply(
+                &call,
+                SERVICE,
+                vec![Value::Array("s".to_string(), Vec::new())],
+            );
+            peer.wait_for_hangup();
+        });
+        let mut fingerprint = Fingerprint::new(Connection::connect(&address).unwrap(), "user")
+            .expect("cannot start fingerprint verification");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let error = loop {
+            match fingerprint.dispatch() {
+                Ok(matched) => assert!(!matched),
+                Err(e) => break e,
+            }
+            assert!(Instant::now() < deadline, "fprintd was not heard from");
+            thread::sleep(Duration::from_millis(5));
+        };
+        assert_eq!(error.to_string(), "no fingerprints enrolled");
+        drop(fingerprint);
+        service.join().expect("fprintd failed");
+    }
+}
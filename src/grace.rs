@@ -0,0 +1,95 @@
+//! The grace period after an idle lock: until it runs out any key press or pointer movement
+//! unlocks without a password, for users who were still at the screen, reading.
+
+use crate::timer::Timer;
+use std::{
+    io::Result,
+    time::{Duration, Instant},
+};
+
+const TICK: Duration = Duration::from_secs(1);
+
+/// A running grace period. `timer` fires on every whole second left and the event loop hands it
+/// back to [`Grace::tick`] so the countdown can be redrawn.
+#[derive(Debug)]
+pub struct Grace {
+    deadline: Instant,
+    timer: Timer,
+}
+
+impl Grace {
+    /// Starts a grace period of `period` from now, which should be when the session got locked.
+    pub fn start(period: Duration) -> Result<Self> {
+        // Taken before arming so the last tick never comes before the deadline.
+        let deadline = Instant::now() + period;
+        let timer = Timer::new()?;
+        let fraction = period - Duration::from_secs(period.as_secs());
+        timer.arm(if fraction.is_zero() { TICK } else { fraction }, TICK)?;
+        Ok(Self { deadline, timer })
+    }
+
+    pub fn timer(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+
+    /// Whole seconds left, rounded up; zero once input no longer unlocks.
+    pub fn remaining(&self) -> u64 {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        left.as_secs() + u64::from(left.subsec_nanos() > 0)
+    }
+
+    /// Whether the deadline passed, so input no longer unlocks.
+    pub fn is_over(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// Called when the timer is readable; returns whether a second went by.
+    pub fn tick(&mut self) -> bool {
+        self.timer.tick("grace period")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn counts_down_whole_seconds() {
+        let grace = Grace::start(Duration::from_millis(2500)).unwrap();
+        assert_eq!(grace.remaining(), 3);
+        assert!(!grace.is_over());
+        // The first tick comes when the half second is up, then one every second.
+        let (next, interval) = grace.timer.setting().unwrap();
+        assert!(next <= Duration::from_millis(500) && next > Duration::ZERO);
+        assert_eq!(interval, TICK);
+
+        let grace = Grace::start(Duration::from_secs(5)).unwrap();
+        assert_eq!(grace.remaining(), 5);
+        let (next, interval) = grace.timer.setting().unwrap();
+        assert!(next <= TICK && next > Duration::from_millis(500));
+        assert_eq!(interval, TICK);
+    }
+
+    #[test]
+    fn input_unlocks_until_the_deadline() {
+        let mut grace = Grace::start(Duration::from_millis(20)).unwrap();
+        assert!(!grace.is_over());
+        assert_eq!(grace.remaining(), 1);
+        thread::sleep(Duration::from_millis(30));
+        assert!(grace.is_over());
+        assert_eq!(grace.remaining(), 0);
+        assert!(grace.tick());
+        // Ticks that have not fired yet say nothing new.
+        assert!(!grace.tick());
+    }
+
+    #[test]
+    fn ends_without_waiting_for_the_timer() {
+        let mut grace = Grace::start(Duration::from_secs(60)).unwrap();
+        grace.deadline = Instant::now();
+        assert!(grace.is_over());
+        assert_eq!(grace.remaining(), 0);
+        assert!(!grace.tick());
+    }
+}
@@ -0,0 +1,651 @@
+//! Hardening of the locker process against the rest of the session: no core dumps or ptrace,
+//! memory kept out of swap, secrets removed from the environment and, when configured, a
+//! Landlock ruleset and a seccomp filter limiting what a compromised locker could do. Each
+//! measure's outcome is kept in a [`Report`] for `--check-hardening`.
+
+use std::{
+    env,
+    ffi::CString,
+    fmt, fs, io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::PathBuf,
+    ptr,
+};
+
+/// Which of the optional measures to apply.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HardeningConfig {
+    pub landlock: bool,
+    pub seccomp: bool,
+}
+
+/// Words, between underscores, that mark an environment variable as holding a secret.
+const SENSITIVE_WORDS: &[&[&str]] = &[
+    &["PASSWORD"],
+    &["PASSWD"],
+    &["SECRET"],
+    &["TOKEN"],
+    &["CREDENTIAL"],
+    &["CREDENTIALS"],
+    &["API", "KEY"],
+    &["ACCESS", "KEY"],
+    &["PRIVATE", "KEY"],
+];
+/// Environment variables removed from the locker because they lead to credentials. The
+/// authentication helper keeps them: PAM modules such as pam_krb5 refresh credentials through
+/// `KRB5CCNAME`.
+const SENSITIVE_VARIABLES: &[&str] = &[
+    "SSH_AUTH_SOCK",
+    "SSH_AGENT_PID",
+    "GPG_AGENT_INFO",
+    "KRB5CCNAME",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Measure {
+    NoDumps,
+    LockedMemory,
+    Environment,
+    Landlock,
+    Seccomp,
+}
+
+impl Measure {
+    fn name(self) -> &'static str {
+        match self {
+            Self::NoDumps => "core dumps and ptrace",
+            Self::LockedMemory => "memory locked",
+            Self::Environment => "environment",
+            Self::Landlock => "landlock",
+            Self::Seccomp => "seccomp",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Applied(String),
+    Disabled,
+    Skipped(String),
+    Failed(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Applied(detail) if detail.is_empty() => write!(f, "applied"),
+            Self::Applied(detail) => write!(f, "applied: {detail}"),
+            Self::Disabled => write!(f, "disabled in the config"),
+            Self::Skipped(reason) => write!(f, "skipped: {reason}"),
+            Self::Failed(reason) => write!(f, "FAILED: {reason}"),
+        }
+    }
+}
+
+/// What each measure came to, in the order they were taken.
+#[derive(Debug, Default)]
+pub struct Report {
+    outcomes: Vec<(Measure, Outcome)>,
+}
+
+impl Report {
+    /// Whether a measure that should have been taken could not be.
+    pub fn has_failures(&self) -> bool {
+        self.outcomes
+            .iter()
+            .any(|(_, outcome)| matches!(outcome, Outcome::Failed(_)))
+    }
+
+    /// Stops core dumps and ptrace attachment by processes of the same user. Changing
+    /// credentials undoes it, so it is taken again after privileges are dropped.
+    pub fn forbid_dumps(&mut self) {
+        let limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: prctl and setrlimit only change process attributes.
+        let outcome = unsafe {
+            if libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) != 0
+                || libc::setrlimit(libc::RLIMIT_CORE, &limit) != 0
+            {
+                Outcome::Failed(io::Error::last_os_error().to_string())
+            } else if libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) != 0 {
+                Outcome::Failed("the process is still dumpable".to_string())
+            } else {
+                Outcome::Applied(String::new())
+            }
+        };
+        self.record(Measure::NoDumps, outcome);
+    }
+
+    /// Keeps every page, now and later, out of swap, when the memory lock limit allows it.
+    /// Under a limit `mlockall` would make allocations fail once it is reached, so it is left
+    /// to the buffers that lock themselves.
+    pub fn lock_memory(&mut self) {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: getrlimit writes into `limit`.
+        unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) };
+        let outcome = if limit.rlim_cur != libc::RLIM_INFINITY && !has_capability(CAP_IPC_LOCK) {
+            Outcome::Skipped(format!(
+                "the memory lock limit is {} KiB and CAP_IPC_LOCK is missing",
+                limit.rlim_cur / 1024
+            ))
+        // SAFETY: mlockall only changes how the process's memory is paged.
+        } else if unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) } != 0 {
+            Outcome::Failed(io::Error::last_os_error().to_string())
+        } else {
+            Outcome::Applied(String::new())
+        };
+        self.record(Measure::LockedMemory, outcome);
+    }
+
+    /// Removes variables that hold secrets or lead to them, which the locker never needs.
+    ///
+    /// # Safety
+    ///
+    /// No other thread may be running, as none may read the environment meanwhile.
+    pub unsafe fn clear_environment(&mut self) {
+        let removed: Vec<_> = env::vars_os()
+            .filter_map(|(name, _)| name.into_string().ok())
+            .filter(|name| SENSITIVE_VARIABLES.contains(&name.as_str()) || is_secret(name))
+            .collect();
+        for name in &removed {
+            // Unsetting only unlinks the variable, and its value would still be readable in
+            // /proc/self/environ, so it is overwritten first.
+            let name = CString::new(name.as_str()).expect("variable names have no NUL");
+            // SAFETY: the caller guarantees there are no other threads; the value getenv points
+            // at is writable and NUL-terminated.
+            unsafe {
+                let value = libc::getenv(name.as_ptr());
+                if !value.is_null() {
+                    ptr::write_bytes(value, 0, libc::strlen(value));
+                }
+                libc::unsetenv(name.as_ptr());
+            }
+        }
+        let detail = if removed.is_empty() {
+            "nothing to remove".to_string()
+        } else {
+            format!("removed {}", removed.join(", "))
+        };
+        self.record(Measure::Environment, Outcome::Applied(detail));
+    }
+
+    /// Applies the configured Landlock ruleset: from then on only `read` files can be opened,
+    /// and only for reading, and files can only be changed beneath `write`. The ruleset binds
+    /// this thread and those it starts later, so call it before starting any.
+    pub fn restrict_files(
+        &mut self,
+        config: &HardeningConfig,
+        read: &[PathBuf],
+        write: &[PathBuf],
+    ) {
+        let outcome = if config.landlock {
+            landlock(read, write)
+        } else {
+            Outcome::Disabled
+        };
+        self.record(Measure::Landlock, outcome);
+    }
+
+    /// Applies the configured seccomp filter, which allows the system calls the event loop and
+    /// the verification thread make, to every thread.
+    pub fn filter_calls(&mut self, config: &HardeningConfig) {
+        let outcome = if config.seccomp {
+            seccomp()
+        } else {
+            Outcome::Disabled
+        };
+        self.record(Measure::Seccomp, outcome);
+    }
+
+    /// Prints every outcome, for `--check-hardening`.
+    pub fn print(&self) {
+        for (measure, outcome) in &self.outcomes {
+            println!("{:<22} {outcome}", measure.name());
+        }
+    }
+
+    fn record(&mut self, measure: Measure, outcome: Outcome) {
+        match &outcome {
+            Outcome::Failed(_) => tracing::warn!("Hardening: {} {outcome}", measure.name()),
+            _ => tracing::debug!("Hardening: {} {outcome}", measure.name()),
+        }
+        self.outcomes.retain(|(taken, _)| *taken != measure);
+        self.outcomes.push((measure, outcome));
+    }
+}
+
+/// Whether a variable's name says it holds a secret, such as `GITHUB_TOKEN`.
+fn is_secret(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    let words: Vec<_> = upper.split('_').collect();
+    SENSITIVE_WORDS.iter().any(|sensitive| {
+        words
+            .windows(sensitive.len())
+            .any(|window| window == *sensitive)
+    })
+}
+
+const CAP_IPC_LOCK: u32 = 14;
+
+/// Whether the process has `capability` in its effective set.
+fn has_capability(capability: u32) -> bool {
+    let Ok(status) = fs::read_to_string("/proc/self/status") else {
+        return false;
+    };
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+        .is_some_and(|mask| mask & (1 << capability) != 0)
+}
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+/// Every right of the first ABI, from executing files to making symlinks.
+const ACCESS_FS_ABI_1: u64 = (1 << 13) - 1;
+const ACCESS_FS_REFER: u64 = 1 << 13;
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+const ACCESS_NET_BIND_TCP: u64 = 1 << 0;
+const ACCESS_NET_CONNECT_TCP: u64 = 1 << 1;
+const SCOPE_ABSTRACT_UNIX_SOCKET: u64 = 1 << 0;
+const SCOPE_SIGNAL: u64 = 1 << 1;
+
+/// `struct landlock_ruleset_attr`, which grew a field in ABIs 4 and 6.
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+    handled_access_net: u64,
+    scoped: u64,
+}
+
+/// `struct landlock_path_beneath_attr`.
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+fn landlock(read: &[PathBuf], write: &[PathBuf]) -> Outcome {
+    // SAFETY: asking for the ABI version takes no ruleset.
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    if abi < 1 {
+        let e = io::Error::last_os_error();
+        return Outcome::Failed(format!("Landlock is unavailable: {e}"));
+    }
+    let mut handled = ACCESS_FS_ABI_1;
+    for (since, access) in [
+        (2, ACCESS_FS_REFER),
+        (3, ACCESS_FS_TRUNCATE),
+        (5, ACCESS_FS_IOCTL_DEV),
+    ] {
+        if abi >= since {
+            handled |= access;
+        }
+    }
+    let attr = RulesetAttr {
+        handled_access_fs: handled,
+        // Nothing is allowed, so no TCP at all.
+        handled_access_net: ACCESS_NET_BIND_TCP | ACCESS_NET_CONNECT_TCP,
+        scoped: SCOPE_ABSTRACT_UNIX_SOCKET | SCOPE_SIGNAL,
+    };
+    let size = match abi {
+        1..4 => size_of::<u64>(),
+        4..6 => 2 * size_of::<u64>(),
+        _ => size_of::<RulesetAttr>(),
+    };
+    // SAFETY: `attr` is valid for `size` bytes, which this ABI understands.
+    let ruleset = unsafe { libc::syscall(libc::SYS_landlock_create_ruleset, &attr, size, 0u32) };
+    if ruleset < 0 {
+        return Outcome::Failed(io::Error::last_os_error().to_string());
+    }
+    // SAFETY: the ruleset fd was just created and nothing else owns it.
+    let ruleset = unsafe { OwnedFd::from_raw_fd(ruleset as i32) };
+
+    let file_access = ACCESS_FS_READ_FILE;
+    let dir_access = (ACCESS_FS_READ_FILE
+        | ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_READ_DIR
+        | ACCESS_FS_REMOVE_FILE
+        | ACCESS_FS_MAKE_REG
+        | ACCESS_FS_TRUNCATE)
+        & handled;
+    let mut allowed = 0;
+    for (path, access) in read
+        .iter()
+        .map(|path| (path, file_access))
+        .chain(write.iter().map(|path| (path, dir_access)))
+    {
+        if access == dir_access
+            && let Err(e) = fs::create_dir_all(path)
+        {
+            tracing::warn!("Failed to create {}: {e}", path.display());
+            continue;
+        }
+        // Paths that cannot be opened, such as images that went missing, stay out of reach.
+        let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+            continue;
+        };
+        // SAFETY: `c_path` is a valid C string.
+        let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+        if fd < 0 {
+            continue;
+        }
+        // SAFETY: the fd was just opened and nothing else owns it.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let rule = PathBeneathAttr {
+            allowed_access: access,
+            parent_fd: fd.as_raw_fd(),
+        };
+        // SAFETY: `rule` is a valid path-beneath rule for the ruleset fd.
+        let added = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                LANDLOCK_RULE_PATH_BENEATH,
+                &rule,
+                0u32,
+            )
+        };
+        if added != 0 {
+            let e = io::Error::last_os_error();
+            return Outcome::Failed(format!("cannot allow {}: {e}", path.display()));
+        }
+        allowed += 1;
+    }
+    // SAFETY: prctl and landlock_restrict_self only restrict this process.
+    unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+            || libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0u32) != 0
+        {
+            return Outcome::Failed(io::Error::last_os_error().to_string());
+        }
+    }
+    Outcome::Applied(format!("ABI {abi}, paths allowed: {allowed}"))
+}
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// What the event loop and the verification thread call once the session is locked: polling,
+/// the Wayland and D-Bus sockets, timers such as the grace period's, shared memory buffers,
+/// saving the attempt count and loading the image of an output plugged in later.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const ALLOWED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_read,
+    libc::SYS_readv,
+    libc::SYS_pread64,
+    libc::SYS_write,
+    libc::SYS_writev,
+    libc::SYS_close,
+    libc::SYS_lseek,
+    libc::SYS_fstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_openat,
+    libc::SYS_mkdirat,
+    libc::SYS_unlinkat,
+    libc::SYS_fcntl,
+    libc::SYS_ftruncate,
+    libc::SYS_fallocate,
+    libc::SYS_memfd_create,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_mprotect,
+    libc::SYS_madvise,
+    libc::SYS_brk,
+    libc::SYS_sendmsg,
+    libc::SYS_recvmsg,
+    libc::SYS_sendto,
+    libc::SYS_recvfrom,
+    libc::SYS_epoll_pwait,
+    libc::SYS_epoll_ctl,
+    libc::SYS_ppoll,
+    libc::SYS_timerfd_create,
+    libc::SYS_timerfd_settime,
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_futex,
+    libc::SYS_sched_yield,
+    libc::SYS_getrandom,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_sigaltstack,
+    libc::SYS_restart_syscall,
+    libc::SYS_getpid,
+    libc::SYS_gettid,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_epoll_wait,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_poll,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_mkdir,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_unlink,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_stat,
+];
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn seccomp() -> Outcome {
+    use libc::{BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W, sock_filter};
+
+    let statement = |code, k| sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    };
+    let jump_if_equal = |k, jt, jf| sock_filter {
+        code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+        jt,
+        jf,
+        k,
+    };
+    // Anything else fails, so that a call missing from the list is an error in the log rather
+    // than a locker that dies without a trace.
+    let deny = statement(
+        BPF_RET | BPF_K,
+        libc::SECCOMP_RET_ERRNO | libc::EPERM as u32,
+    );
+    // `struct seccomp_data` starts with the call number, then the architecture.
+    let mut program = vec![
+        statement(BPF_LD | BPF_W | BPF_ABS, 4),
+        jump_if_equal(AUDIT_ARCH, 1, 0),
+        deny,
+        statement(BPF_LD | BPF_W | BPF_ABS, 0),
+    ];
+    for &call in ALLOWED_SYSCALLS {
+        program.push(jump_if_equal(call as u32, 0, 1));
+        program.push(statement(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW));
+    }
+    program.push(deny);
+    let filter = libc::sock_fprog {
+        len: program.len() as u16,
+        filter: program.as_mut_ptr(),
+    };
+    // SAFETY: `filter` points at `program`, which outlives the call; the filter is copied in.
+    // Synchronizing covers the verification thread, which already runs.
+    unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+            || libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                libc::SECCOMP_FILTER_FLAG_TSYNC,
+                &filter,
+            ) != 0
+        {
+            return Outcome::Failed(io::Error::last_os_error().to_string());
+        }
+    }
+    Outcome::Applied(format!("{} system calls allowed", ALLOWED_SYSCALLS.len()))
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn seccomp() -> Outcome {
+    Outcome::Skipped("no system call list for this architecture".to_string())
+}
+
+#[cfg(all(test, any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod tests {
+    use super::*;
+    use crate::{testing::TempDir, timer::Timer};
+    use std::{io::Write, path::Path, thread, time::Duration};
+
+    /// A named step that returns whether it worked.
+    type Step<'a> = (&'static str, Box<dyn Fn() -> bool + 'a>);
+
+    /// What the locker does once the filter is on. Like the locker's sockets, `pipe` is opened
+    /// before the filter goes on.
+    fn allowed_paths<'a>(
+        dir: &'a Path,
+        pipe: &'a (io::PipeReader, io::PipeWriter),
+    ) -> Vec<Step<'a>> {
+        vec![
+            (
+                "saving the attempt count",
+                Box::new(|| {
+                    let path = dir.join("state/attempts");
+                    fs::create_dir_all(path.parent().unwrap()).is_ok()
+                        && fs::write(&path, "3 0\n").is_ok()
+                        && fs::read_to_string(&path).is_ok_and(|text| text == "3 0\n")
+                        && fs::remove_file(&path).is_ok()
+                }),
+            ),
+            (
+                "a shared memory buffer",
+                Box::new(|| {
+                    // SAFETY: the memfd is mapped and unmapped here and closed after.
+                    unsafe {
+                        let fd = libc::memfd_create(c"buffer".as_ptr(), libc::MFD_CLOEXEC);
+                        let size = 4096;
+                        let mapped = fd >= 0 && libc::ftruncate(fd, size as libc::off_t) == 0 && {
+                            let data = libc::mmap(
+                                ptr::null_mut(),
+                                size,
+                                libc::PROT_READ | libc::PROT_WRITE,
+                                libc::MAP_SHARED,
+                                fd,
+                                0,
+                            );
+                            data != libc::MAP_FAILED && libc::munmap(data, size) == 0
+                        };
+                        mapped && libc::close(fd) == 0
+                    }
+                }),
+            ),
+            (
+                "a timer",
+                Box::new(|| {
+                    let Ok(timer) = Timer::new() else {
+                        return false;
+                    };
+                    let armed = timer.arm(Duration::from_millis(1), Duration::ZERO).is_ok();
+                    thread::sleep(Duration::from_millis(5));
+                    armed && timer.tick("test")
+                }),
+            ),
+            (
+                "polling",
+                Box::new(|| {
+                    let (reader, mut writer) = (&pipe.0, &pipe.1);
+                    let mut poll = libc::pollfd {
+                        fd: reader.as_raw_fd(),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    };
+                    writer.write_all(b"x").is_ok()
+                        // SAFETY: `poll` is one valid pollfd and no timeout or mask is given.
+                        && unsafe { libc::ppoll(&mut poll, 1, ptr::null(), ptr::null()) } == 1
+                }),
+            ),
+            (
+                "random numbers",
+                Box::new(|| {
+                    let mut bytes = [0u8; 16];
+                    // SAFETY: `bytes` is valid for its length.
+                    let n = unsafe { libc::getrandom(bytes.as_mut_ptr().cast(), bytes.len(), 0) };
+                    n == bytes.len() as isize
+                }),
+            ),
+            (
+                "a call outside the list failing with EPERM",
+                Box::new(|| {
+                    // SAFETY: socket has no pointer arguments.
+                    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+                    fd == -1 && io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+                }),
+            ),
+        ]
+    }
+
+    #[test]
+    fn filter_allows_what_the_locker_does() {
+        let dir = TempDir::new();
+        let pipe = io::pipe().unwrap();
+        let paths = allowed_paths(dir.path(), &pipe);
+        let config = HardeningConfig {
+            seccomp: true,
+            ..HardeningConfig::default()
+        };
+        // The filter cannot be lifted, so it goes on in a child, which reports by its exit code
+        // which step failed.
+        // SAFETY: the child only runs the steps above and exits without unwinding.
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork: {}", io::Error::last_os_error()),
+            0 => {
+                let mut report = Report::default();
+                report.filter_calls(&config);
+                let code = if report.has_failures() {
+                    1
+                } else {
+                    paths
+                        .iter()
+                        .position(|(_, works)| !works())
+                        .map_or(0, |step| step as i32 + 2)
+                };
+                // SAFETY: skips the test harness's exit handlers, which belong to the parent.
+                unsafe { libc::_exit(code) }
+            }
+            child => {
+                let mut status = 0;
+                // SAFETY: `status` is valid for writing.
+                assert_eq!(unsafe { libc::waitpid(child, &mut status, 0) }, child);
+                assert!(
+                    libc::WIFEXITED(status),
+                    "the child died with status {status}"
+                );
+                match libc::WEXITSTATUS(status) {
+                    0 => {}
+                    1 => panic!("the filter could not be installed"),
+                    code => panic!("failed at {}", paths[code as usize - 2].0),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! DEFLATE decompression (RFC 1951) inside a zlib stream (RFC 1950), as PNG stores pixels.
+
+use crate::image::Error;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order code length code lengths are stored in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Reads bits least significant first, as DEFLATE packs them.
+struct Bits<'a> {
+    data: &'a [u8],
+    offset: usize,
+    buffer: u64,
+    count: u32,
+}
+
+impl<'a> Bits<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        while self.count <= 56 {
+            let Some(&byte) = self.data.get(self.offset) else {
+                return;
+            };
+            self.offset += 1;
+            self.buffer |= (byte as u64) << self.count;
+            self.count += 8;
+        }
+    }
+
+    /// The next `n` bits without consuming them, zero past the end of the data.
+    fn peek(&mut self, n: u32) -> u32 {
+        if self.count < n {
+            self.refill();
+        }
+        (self.buffer & ((1 << n) - 1)) as u32
+    }
+
+    fn consume(&mut self, n: u32) -> Result<(), Error> {
+        if self.count < n {
+            return Err(Error::Malformed("deflate stream"));
+        }
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(())
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32, Error> {
+        let value = self.peek(n);
+        self.consume(n)?;
+        Ok(value)
+    }
+
+    /// Drops bits up to the next byte boundary, for stored blocks.
+    fn align(&mut self) {
+        let drop = self.count % 8;
+        self.buffer >>= drop;
+        self.count -= drop;
+    }
+
+    /// Bytes consumed so far, once aligned.
+    fn position(&self) -> usize {
+        self.offset - self.count as usize / 8
+    }
+}
+
+/// A canonical Huffman code as a table indexed by the next `bits` bits of input, each entry
+/// holding a symbol and its code length.
+struct Huffman {
+    table: Vec<(u16, u8)>,
+    bits: u32,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, Error> {
+        let bits = lengths.iter().copied().max().unwrap_or(0).max(1) as u32;
+        let mut counts = [0u32; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut next = [0u32; 16];
+        for length in 1..16 {
+            next[length] = (next[length - 1] + counts[length - 1]) << 1;
+        }
+        // Zero-length entries mark codes the lengths leave unassigned.
+        let mut table = vec![(0, 0); 1 << bits];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length == 0 {
+                continue;
+            }
+            let code = next[length as usize];
+            next[length as usize] += 1;
+            if code >= 1 << length {
+                return Err(Error::Malformed("deflate code lengths"));
+            }
+            // Codes are packed most significant bit first into a stream read from the bottom.
+            let reversed = code.reverse_bits() >> (32 - length as u32);
+            for fill in (reversed as usize..1 << bits).step_by(1 << length) {
+                table[fill] = (symbol as u16, length);
+            }
+        }
+        Ok(Self { table, bits })
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, Error> {
+        let (symbol, length) = self.table[bits.peek(self.bits) as usize];
+        if length == 0 {
+            return Err(Error::Malformed("deflate code"));
+        }
+        bits.consume(length as u32)?;
+        Ok(symbol)
+    }
+}
+
+/// Decompresses a zlib stream, refusing to produce more than `limit` bytes.
+pub fn zlib(data: &[u8], limit: usize) -> Result<Vec<u8>, Error> {
+    let [cmf, flg, ..] = *data else {
+        return Err(Error::Malformed("zlib header"));
+    };
+    if cmf & 0x0f != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) || flg & 0x20 != 0 {
+        return Err(Error::Malformed("zlib header"));
+    }
+    let (out, len) = inflate(&data[2..], limit)?;
+    let checksum = data
+        .get(2 + len..6 + len)
+        .ok_or(Error::Malformed("zlib checksum"))?;
+    if u32::from_be_bytes(checksum.try_into().unwrap()) != adler32(&out) {
+        return Err(Error::Malformed("zlib checksum"));
+    }
+    Ok(out)
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before `b` could overflow.
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+/// Decompresses raw DEFLATE data, returning it with the number of input bytes used.
+fn inflate(data: &[u8], limit: usize) -> Result<(Vec<u8>, usize), Error> {
+    let mut bits = Bits::new(data);
+    let mut out = Vec::new();
+    loop {
+        let last = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => {
+                bits.align();
+                let len = bits.bits(16)?;
+                if bits.bits(16)? != !len & 0xffff {
+                    return Err(Error::Malformed("deflate stored block"));
+                }
+                for _ in 0..len {
+                    out.push(bits.bits(8)? as u8);
+                }
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths)?;
+                let distances = Huffman::new(&[5; 30])?;
+                block(&mut bits, &mut out, &literals, &distances, limit)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut bits)?;
+                block(&mut bits, &mut out, &literals, &distances, limit)?;
+            }
+            _ => return Err(Error::Malformed("deflate block type")),
+        }
+        if out.len() > limit {
+            return Err(Error::TooLarge);
+        }
+        if last {
+            bits.align();
+            return Ok((out, bits.position()));
+        }
+    }
+}
+
+fn dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman), Error> {
+    let literal_count = bits.bits(5)? as usize + 257;
+    let distance_count = bits.bits(5)? as usize + 1;
+    let code_count = bits.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_count] {
+        code_lengths[index] = bits.bits(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code.decode(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or(Error::Malformed("deflate code lengths"))?;
+                (previous, 3 + bits.bits(2)?)
+            }
+            17 => (0, 3 + bits.bits(3)?),
+            _ => (0, 11 + bits.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(Error::Malformed("deflate code lengths"));
+    }
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+fn block(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+    limit: usize,
+) -> Result<(), Error> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err(Error::Malformed("deflate length"));
+                }
+                let len =
+                    LENGTH_BASE[index] as usize + bits.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                let index = distances.decode(bits)? as usize;
+                if index >= DISTANCE_BASE.len() {
+                    return Err(Error::Malformed("deflate distance"));
+                }
+                let distance = DISTANCE_BASE[index] as usize
+                    + bits.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(Error::Malformed("deflate distance"));
+                }
+                let start = out.len() - distance;
+                // Copies may overlap their own output, repeating the last `distance` bytes.
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+                if out.len() > limit {
+                    return Err(Error::TooLarge);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,648 @@
+//! JPEG decoding: baseline, extended and progressive Huffman-coded images in grayscale or
+//! YCbCr with any chroma subsampling. Arithmetic coding, lossless and CMYK files are rejected.
+
+use crate::image::{Error, Image, MAX_PIXELS};
+use std::f32::consts::PI;
+
+/// Where each coefficient of the zigzag scan order lives in a row-major block.
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+pub fn is_jpeg(data: &[u8]) -> bool {
+    data.starts_with(&[0xff, 0xd8])
+}
+
+/// A Huffman table as a lookup on the next 16 bits, each entry a symbol and code length.
+struct Huffman {
+    table: Vec<(u8, u8)>,
+}
+
+impl Huffman {
+    fn new(counts: &[u8; 16], symbols: &[u8]) -> Result<Self, Error> {
+        let mut table = vec![(0, 0); 1 << 16];
+        let (mut code, mut symbol) = (0usize, 0usize);
+        for (length, &count) in (1..=16).zip(counts) {
+            for _ in 0..count {
+                let &value = symbols.get(symbol).ok_or(Error::Malformed("JPEG DHT"))?;
+                let shift = 16 - length;
+                if (code + 1) << shift > table.len() {
+                    return Err(Error::Malformed("JPEG DHT"));
+                }
+                table[code << shift..(code + 1) << shift].fill((value, length as u8));
+                code += 1;
+                symbol += 1;
+            }
+            code <<= 1;
+        }
+        Ok(Self { table })
+    }
+}
+
+/// Entropy-coded data, read most significant bit first with stuffed zero bytes removed.
+/// Reaching a marker yields zero bits, which is how truncated files still decode.
+struct Bits<'a> {
+    data: &'a [u8],
+    offset: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn fill(&mut self) {
+        while self.count <= 24 {
+            let mut byte = 0;
+            if let Some(&next) = self.data.get(self.offset) {
+                if next != 0xff {
+                    byte = next;
+                    self.offset += 1;
+                } else if self.data.get(self.offset + 1) == Some(&0) {
+                    byte = 0xff;
+                    self.offset += 2;
+                }
+            }
+            self.buffer |= (byte as u32) << (24 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn bits(&mut self, n: u32) -> i32 {
+        if n == 0 {
+            return 0;
+        }
+        self.fill();
+        let value = self.buffer >> (32 - n);
+        self.buffer <<= n;
+        self.count -= n;
+        value as i32
+    }
+
+    fn bit(&mut self) -> bool {
+        self.bits(1) == 1
+    }
+
+    fn decode(&mut self, huffman: &Huffman) -> Result<u8, Error> {
+        self.fill();
+        let (symbol, length) = huffman.table[(self.buffer >> 16) as usize];
+        if length == 0 {
+            return Err(Error::Malformed("JPEG Huffman code"));
+        }
+        self.buffer <<= length;
+        self.count -= length as u32;
+        Ok(symbol)
+    }
+
+    /// An `n`-bit magnitude category value, sign-extended the JPEG way.
+    fn receive_extend(&mut self, n: u32) -> i32 {
+        let value = self.bits(n);
+        if n > 0 && value < 1 << (n - 1) {
+            value - (1 << n) + 1
+        } else {
+            value
+        }
+    }
+
+    /// Skips to and past the restart marker that should follow, dropping leftover bits.
+    fn restart(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+        while let Some(&byte) = self.data.get(self.offset) {
+            self.offset += 1;
+            if byte == 0xff
+                && let Some(&marker) = self.data.get(self.offset)
+                && (0xd0..=0xd7).contains(&marker)
+            {
+                self.offset += 1;
+                return;
+            }
+        }
+    }
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant: usize,
+    /// Blocks per row and column, padded to whole MCUs.
+    blocks_wide: usize,
+    blocks_high: usize,
+    coefficients: Vec<[i16; 64]>,
+    dc_table: usize,
+    ac_table: usize,
+    dc_prediction: i32,
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    offset: usize,
+    quant: [[u16; 64]; 4],
+    dc_tables: [Option<Huffman>; 4],
+    ac_tables: [Option<Huffman>; 4],
+    components: Vec<Component>,
+    width: usize,
+    height: usize,
+    progressive: bool,
+    restart_interval: usize,
+    max_h: usize,
+    max_v: usize,
+    mcus_wide: usize,
+    mcus_high: usize,
+    eob_run: i32,
+    /// From an Adobe APP14 segment: 0 means the three components are RGB, not YCbCr.
+    adobe_transform: Option<u8>,
+}
+
+pub fn decode(data: &[u8]) -> Result<Image, Error> {
+    let mut decoder = Decoder {
+        data,
+        offset: 2,
+        quant: [[0; 64]; 4],
+        dc_tables: [None, None, None, None],
+        ac_tables: [None, None, None, None],
+        components: Vec::new(),
+        width: 0,
+        height: 0,
+        progressive: false,
+        restart_interval: 0,
+        max_h: 1,
+        max_v: 1,
+        mcus_wide: 0,
+        mcus_high: 0,
+        eob_run: 0,
+        adobe_transform: None,
+    };
+    decoder.run()?;
+    decoder.output()
+}
+
+impl Decoder<'_> {
+    fn run(&mut self) -> Result<(), Error> {
+        loop {
+            let marker = self.next_marker()?;
+            match marker {
+                0xd8 | 0x01 | 0xd0..=0xd7 => continue,
+                0xd9 => break,
+                _ => {}
+            }
+            let len = self.u16_at(self.offset)? as usize;
+            let segment = self
+                .data
+                .get(self.offset + 2..self.offset + len)
+                .ok_or(Error::Malformed("JPEG segment"))?;
+            self.offset += len;
+            match marker {
+                0xdb => self.read_quant(segment)?,
+                0xc4 => self.read_huffman(segment)?,
+                0xc0..=0xc2 => self.read_frame(segment, marker == 0xc2)?,
+                0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf => {
+                    return Err(Error::Unsupported("JPEG coding"));
+                }
+                0xdd => {
+                    let interval = segment.get(..2).ok_or(Error::Malformed("JPEG DRI"))?;
+                    self.restart_interval = u16::from_be_bytes([interval[0], interval[1]]) as usize;
+                }
+                0xee if segment.starts_with(b"Adobe") && segment.len() >= 12 => {
+                    self.adobe_transform = Some(segment[11]);
+                }
+                0xda => self.read_scan(segment)?,
+                _ => {}
+            }
+        }
+        if self.components.is_empty() {
+            return Err(Error::Malformed("JPEG frame"));
+        }
+        Ok(())
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16, Error> {
+        self.data
+            .get(offset..offset + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or(Error::Malformed("JPEG segment"))
+    }
+
+    /// The next marker code, skipping any fill bytes or stray data before it.
+    fn next_marker(&mut self) -> Result<u8, Error> {
+        loop {
+            let byte = *self
+                .data
+                .get(self.offset)
+                .ok_or(Error::Malformed("JPEG end"))?;
+            self.offset += 1;
+            if byte != 0xff {
+                continue;
+            }
+            while self.data.get(self.offset) == Some(&0xff) {
+                self.offset += 1;
+            }
+            let marker = *self
+                .data
+                .get(self.offset)
+                .ok_or(Error::Malformed("JPEG end"))?;
+            self.offset += 1;
+            if marker != 0 {
+                return Ok(marker);
+            }
+        }
+    }
+
+    fn read_quant(&mut self, mut segment: &[u8]) -> Result<(), Error> {
+        while let [info, rest @ ..] = segment {
+            let (precision, id) = (info >> 4, (info & 0x0f) as usize);
+            let len = if precision == 0 { 64 } else { 128 };
+            if id > 3 || rest.len() < len {
+                return Err(Error::Malformed("JPEG DQT"));
+            }
+            for (i, &position) in ZIGZAG.iter().enumerate() {
+                self.quant[id][position] = if precision == 0 {
+                    rest[i] as u16
+                } else {
+                    u16::from_be_bytes([rest[i * 2], rest[i * 2 + 1]])
+                };
+            }
+            segment = &rest[len..];
+        }
+        Ok(())
+    }
+
+    fn read_huffman(&mut self, mut segment: &[u8]) -> Result<(), Error> {
+        while segment.len() >= 17 {
+            let (class, id) = (segment[0] >> 4, (segment[0] & 0x0f) as usize);
+            let counts: [u8; 16] = segment[1..17].try_into().unwrap();
+            let total: usize = counts.iter().map(|&c| c as usize).sum();
+            let symbols = segment
+                .get(17..17 + total)
+                .ok_or(Error::Malformed("JPEG DHT"))?;
+            if id > 3 || class > 1 {
+                return Err(Error::Malformed("JPEG DHT"));
+            }
+            let table = Some(Huffman::new(&counts, symbols)?);
+            if class == 0 {
+                self.dc_tables[id] = table;
+            } else {
+                self.ac_tables[id] = table;
+            }
+            segment = &segment[17 + total..];
+        }
+        Ok(())
+    }
+
+    fn read_frame(&mut self, segment: &[u8], progressive: bool) -> Result<(), Error> {
+        let [precision, h0, h1, w0, w1, count, ref rest @ ..] = *segment else {
+            return Err(Error::Malformed("JPEG SOF"));
+        };
+        if precision != 8 {
+            return Err(Error::Unsupported("JPEG sample precision"));
+        }
+        if !matches!(count, 1 | 3) {
+            return Err(Error::Unsupported("JPEG color space"));
+        }
+        self.width = u16::from_be_bytes([w0, w1]) as usize;
+        self.height = u16::from_be_bytes([h0, h1]) as usize;
+        if self.width == 0 || self.height == 0 || !self.components.is_empty() {
+            return Err(Error::Malformed("JPEG SOF"));
+        }
+        if self.width * self.height > MAX_PIXELS {
+            return Err(Error::TooLarge);
+        }
+        self.progressive = progressive;
+        for spec in rest.chunks_exact(3).take(count as usize) {
+            let (h, v) = ((spec[1] >> 4) as usize, (spec[1] & 0x0f) as usize);
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) || spec[2] > 3 {
+                return Err(Error::Malformed("JPEG SOF"));
+            }
+            self.components.push(Component {
+                id: spec[0],
+                h,
+                v,
+                quant: spec[2] as usize,
+                blocks_wide: 0,
+                blocks_high: 0,
+                coefficients: Vec::new(),
+                dc_table: 0,
+                ac_table: 0,
+                dc_prediction: 0,
+            });
+        }
+        if self.components.len() != count as usize {
+            return Err(Error::Malformed("JPEG SOF"));
+        }
+        self.max_h = self.components.iter().map(|c| c.h).max().unwrap_or(1);
+        self.max_v = self.components.iter().map(|c| c.v).max().unwrap_or(1);
+        self.mcus_wide = self.width.div_ceil(8 * self.max_h);
+        self.mcus_high = self.height.div_ceil(8 * self.max_v);
+        for component in &mut self.components {
+            component.blocks_wide = self.mcus_wide * component.h;
+            component.blocks_high = self.mcus_high * component.v;
+            component.coefficients = vec![[0; 64]; component.blocks_wide * component.blocks_high];
+        }
+        Ok(())
+    }
+
+    fn read_scan(&mut self, segment: &[u8]) -> Result<(), Error> {
+        let count = *segment.first().ok_or(Error::Malformed("JPEG SOS"))? as usize;
+        let specs = segment
+            .get(1..1 + count * 2)
+            .ok_or(Error::Malformed("JPEG SOS"))?;
+        let tail = segment
+            .get(1 + count * 2..4 + count * 2)
+            .ok_or(Error::Malformed("JPEG SOS"))?;
+        let (start, end, approximation) = (tail[0] as usize, tail[1] as usize, tail[2]);
+        let (high, low) = ((approximation >> 4) as u32, (approximation & 0x0f) as u32);
+        if start > end || end > 63 || (!self.progressive && (start != 0 || end != 63)) {
+            return Err(Error::Malformed("JPEG SOS"));
+        }
+
+        let mut scan = Vec::with_capacity(count);
+        for spec in specs.chunks_exact(2) {
+            let index = self
+                .components
+                .iter()
+                .position(|c| c.id == spec[0])
+                .ok_or(Error::Malformed("JPEG SOS"))?;
+            let component = &mut self.components[index];
+            component.dc_table = (spec[1] >> 4) as usize & 3;
+            component.ac_table = (spec[1] & 0x0f) as usize & 3;
+            component.dc_prediction = 0;
+            scan.push(index);
+        }
+        if scan.is_empty() {
+            return Err(Error::Malformed("JPEG SOS"));
+        }
+        self.eob_run = 0;
+
+        let mut bits = Bits {
+            data: self.data,
+            offset: self.offset,
+            buffer: 0,
+            count: 0,
+        };
+        // A scan of one component covers just its own blocks, not whole MCUs.
+        let single = match scan[..] {
+            [index] => {
+                let c = &self.components[index];
+                let wide = (self.width * c.h).div_ceil(8 * self.max_h);
+                let high = (self.height * c.v).div_ceil(8 * self.max_v);
+                Some((index, wide, high))
+            }
+            _ => None,
+        };
+        let units = single.map_or(self.mcus_wide * self.mcus_high, |(_, wide, high)| {
+            wide * high
+        });
+        for n in 0..units {
+            if self.restart_interval > 0 && n > 0 && n % self.restart_interval == 0 {
+                bits.restart();
+                self.eob_run = 0;
+                for &index in &scan {
+                    self.components[index].dc_prediction = 0;
+                }
+            }
+            if let Some((index, wide, _)) = single {
+                self.decode_block(&mut bits, index, n / wide, n % wide, start, end, high, low)?;
+                continue;
+            }
+            let (mcu_y, mcu_x) = (n / self.mcus_wide, n % self.mcus_wide);
+            for &index in &scan {
+                let (h, v) = (self.components[index].h, self.components[index].v);
+                for row in mcu_y * v..(mcu_y + 1) * v {
+                    for column in mcu_x * h..(mcu_x + 1) * h {
+                        self.decode_block(&mut bits, index, row, column, start, end, high, low)?;
+                    }
+                }
+            }
+        }
+        self.offset = bits.offset;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn decode_block(
+        &mut self,
+        bits: &mut Bits,
+        index: usize,
+        row: usize,
+        column: usize,
+        start: usize,
+        end: usize,
+        high: u32,
+        low: u32,
+    ) -> Result<(), Error> {
+        let component = &mut self.components[index];
+        let block = &mut component.coefficients[row * component.blocks_wide + column];
+        fn table(tables: &[Option<Huffman>; 4], id: usize) -> Result<&Huffman, Error> {
+            tables[id]
+                .as_ref()
+                .ok_or(Error::Malformed("JPEG Huffman table"))
+        }
+
+        if start == 0 {
+            if high == 0 {
+                let dc = table(&self.dc_tables, component.dc_table)?;
+                let category = bits.decode(dc)? as u32;
+                component.dc_prediction += bits.receive_extend(category.min(16));
+                block[0] = (component.dc_prediction * (1 << low)) as i16;
+            } else if bits.bit() {
+                block[0] |= 1 << low;
+            }
+            if !self.progressive {
+                let ac = table(&self.ac_tables, component.ac_table)?;
+                let mut k = 1;
+                while k < 64 {
+                    let rs = bits.decode(ac)?;
+                    let (run, size) = ((rs >> 4) as usize, (rs & 0x0f) as u32);
+                    if size == 0 {
+                        if run != 15 {
+                            break;
+                        }
+                        k += 16;
+                        continue;
+                    }
+                    k += run;
+                    if k > 63 {
+                        break;
+                    }
+                    block[ZIGZAG[k]] = bits.receive_extend(size) as i16;
+                    k += 1;
+                }
+            }
+            return Ok(());
+        }
+
+        let ac = table(&self.ac_tables, component.ac_table)?;
+        if high == 0 {
+            // First pass over a band of AC coefficients.
+            if self.eob_run > 0 {
+                self.eob_run -= 1;
+                return Ok(());
+            }
+            let mut k = start;
+            while k <= end {
+                let rs = bits.decode(ac)?;
+                let (run, size) = ((rs >> 4) as u32, (rs & 0x0f) as u32);
+                if size == 0 {
+                    if run < 15 {
+                        self.eob_run = (1 << run) - 1 + bits.bits(run);
+                        break;
+                    }
+                    k += 16;
+                    continue;
+                }
+                k += run as usize;
+                if k > 63 {
+                    break;
+                }
+                block[ZIGZAG[k]] = (bits.receive_extend(size) * (1 << low)) as i16;
+                k += 1;
+            }
+            return Ok(());
+        }
+
+        // Refinement: one more bit for coefficients already nonzero, new ones of magnitude 1.
+        let (plus, minus) = (1i16 << low, -1i16 << low);
+        let refine = |bits: &mut Bits, coefficient: &mut i16| {
+            if bits.bit() && *coefficient & plus == 0 {
+                *coefficient += if *coefficient >= 0 { plus } else { minus };
+            }
+        };
+        let mut k = start;
+        if self.eob_run == 0 {
+            while k <= end {
+                let rs = bits.decode(ac)?;
+                let (mut run, size) = ((rs >> 4) as i32, rs & 0x0f);
+                let mut value = 0;
+                if size != 0 {
+                    value = if bits.bit() { plus } else { minus };
+                } else if run != 15 {
+                    self.eob_run = (1 << run) + bits.bits(run as u32);
+                    break;
+                }
+                while k <= end {
+                    let coefficient = &mut block[ZIGZAG[k]];
+                    if *coefficient != 0 {
+                        refine(bits, coefficient);
+                    } else {
+                        if run == 0 {
+                            break;
+                        }
+                        run -= 1;
+                    }
+                    k += 1;
+                }
+                if value != 0 && k <= end {
+                    block[ZIGZAG[k]] = value;
+                }
+                k += 1;
+            }
+        }
+        if self.eob_run > 0 {
+            while k <= end {
+                let coefficient = &mut block[ZIGZAG[k]];
+                if *coefficient != 0 {
+                    refine(bits, coefficient);
+                }
+                k += 1;
+            }
+            self.eob_run -= 1;
+        }
+        Ok(())
+    }
+
+    /// Dequantizes and transforms every block, then upsamples and converts to RGB.
+    fn output(&self) -> Result<Image, Error> {
+        let idct = Idct::new();
+        let planes: Vec<Vec<u8>> = self
+            .components
+            .iter()
+            .map(|c| {
+                let stride = c.blocks_wide * 8;
+                let mut plane = vec![0u8; stride * c.blocks_high * 8];
+                let quant = &self.quant[c.quant];
+                for (i, block) in c.coefficients.iter().enumerate() {
+                    let (row, column) = (i / c.blocks_wide, i % c.blocks_wide);
+                    let offset = row * 8 * stride + column * 8;
+                    idct.transform(block, quant, &mut plane[offset..], stride);
+                }
+                plane
+            })
+            .collect();
+
+        let rgb =
+            self.adobe_transform == Some(0) || self.components.iter().map(|c| c.id).eq(*b"RGB");
+        let mut image = Image::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut samples = [0u8; 3];
+                for (sample, (c, plane)) in
+                    samples.iter_mut().zip(self.components.iter().zip(&planes))
+                {
+                    let sx = x * c.h / self.max_h;
+                    let sy = y * c.v / self.max_v;
+                    *sample = plane[sy * c.blocks_wide * 8 + sx];
+                }
+                let pixel = match self.components.len() {
+                    1 => [samples[0], samples[0], samples[0], 0xff],
+                    _ if rgb => [samples[0], samples[1], samples[2], 0xff],
+                    _ => ycbcr_to_rgb(samples),
+                };
+                image.set(x, y, pixel);
+            }
+        }
+        Ok(image)
+    }
+}
+
+fn ycbcr_to_rgb([y, cb, cr]: [u8; 3]) -> [u8; 4] {
+    let (y, cb, cr) = (y as f32, cb as f32 - 128.0, cr as f32 - 128.0);
+    let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+    [
+        clamp(y + 1.402 * cr),
+        clamp(y - 0.344_136 * cb - 0.714_136 * cr),
+        clamp(y + 1.772 * cb),
+        0xff,
+    ]
+}
+
+/// A separable floating point inverse DCT.
+struct Idct {
+    /// `cos[x][u]` is `C(u) / 2 * cos((2x + 1) * u * pi / 16)`.
+    cos: [[f32; 8]; 8],
+}
+
+impl Idct {
+    fn new() -> Self {
+        let mut cos = [[0.0; 8]; 8];
+        for (x, row) in cos.iter_mut().enumerate() {
+            for (u, value) in row.iter_mut().enumerate() {
+                let scale = if u == 0 { 1.0 / 2f32.sqrt() } else { 1.0 };
+                *value = scale / 2.0 * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+            }
+        }
+        Self { cos }
+    }
+
+    fn transform(&self, block: &[i16; 64], quant: &[u16; 64], out: &mut [u8], stride: usize) {
+        let mut coefficients = [0f32; 64];
+        for i in 0..64 {
+            coefficients[i] = block[i] as f32 * quant[i] as f32;
+        }
+        // Columns first, then rows.
+        let mut columns = [0f32; 64];
+        for u in 0..8 {
+            for y in 0..8 {
+                columns[y * 8 + u] = (0..8)
+                    .map(|v| self.cos[y][v] * coefficients[v * 8 + u])
+                    .sum();
+            }
+        }
+        for y in 0..8 {
+            for x in 0..8 {
+                let value: f32 = (0..8).map(|u| self.cos[x][u] * columns[y * 8 + u]).sum();
+                out[y * stride + x] = (value + 128.0).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Decoding wallpapers into premultiplied ARGB pixels, without external libraries.
+
+mod inflate;
+mod jpeg;
+mod png;
+mod pnm;
+
+use std::{fmt, fs, io, path::Path, path::PathBuf};
+
+/// Larger images are refused rather than risking the locker running out of memory.
+const MAX_PIXELS: usize = 1 << 26;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    UnknownFormat,
+    Unsupported(&'static str),
+    Malformed(&'static str),
+    TooLarge,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "Failed to read image {}: {e}", path.display()),
+            Self::UnknownFormat => write!(f, "Image is not a PNG, JPEG or PNM file"),
+            Self::Unsupported(what) => write!(f, "Unsupported {what}"),
+            Self::Malformed(what) => write!(f, "Image has a malformed {what}"),
+            Self::TooLarge => write!(f, "Image is too large"),
+        }
+    }
+}
+
+/// A decoded image as premultiplied ARGB8888 pixels, the format shm buffers use.
+#[derive(Debug)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u32>,
+}
+
+impl Image {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let data = fs::read(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        Self::decode(&data)
+    }
+
+    /// Decodes PNG, JPEG or PNM data, recognized by its signature.
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        if png::is_png(data) {
+            png::decode(data)
+        } else if jpeg::is_jpeg(data) {
+            jpeg::decode(data)
+        } else if pnm::is_pnm(data) {
+            pnm::decode(data)
+        } else {
+            Err(Error::UnknownFormat)
+        }
+    }
+
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The pixel at (`x`, `y`), which must be inside the image.
+    pub fn pixel(&self, x: usize, y: usize) -> u32 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Stores a straight-alpha RGBA pixel.
+    fn set(&mut self, x: usize, y: usize, [r, g, b, a]: [u8; 4]) {
+        let premultiply = |c: u8| (c as u32 * a as u32 + 127) / 255;
+        self.pixels[y * self.width + x] =
+            (a as u32) << 24 | premultiply(r) << 16 | premultiply(g) << 8 | premultiply(b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The fixtures were written with libpng and libjpeg. `pattern.ppm` is the source image,
+    // 35 by 21 pixels so that neither interlacing passes nor JPEG blocks divide it evenly; the
+    // other PPM files are what libjpeg decodes each JPEG file to, with the same float IDCT and
+    // unsmoothed chroma upsampling as ours.
+
+    fn load(file: &str) -> Image {
+        Image::load(&path(file)).unwrap_or_else(|e| panic!("{file}: {e}"))
+    }
+
+    fn path(file: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/image")
+            .join(file)
+    }
+
+    /// Checks that the images match, each channel within `tolerance`.
+    fn assert_close(image: &Image, expected: &Image, tolerance: u8) {
+        assert_eq!(
+            (image.width(), image.height()),
+            (expected.width(), expected.height())
+        );
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                let (pixel, expected) = (image.pixel(x, y), expected.pixel(x, y));
+                let close = (0..4).all(|i| {
+                    let channel = |pixel: u32| (pixel >> (i * 8)) as u8;
+                    channel(pixel).abs_diff(channel(expected)) <= tolerance
+                });
+                assert!(close, "at ({x}, {y}): {pixel:08x}, expected {expected:08x}");
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_pnm() {
+        let image = load("pattern.ppm");
+        assert_eq!((image.width(), image.height()), (35, 21));
+        assert_eq!(image.pixel(0, 0), 0xff00_0000);
+        assert_eq!(image.pixel(34, 20), 0xffff_ff28);
+        assert_eq!(image.pixel(20, 5), 0xffff_ffff);
+        assert_close(&load("pattern-plain.ppm"), &image, 0);
+
+        // Samples above 255 take two bytes, and every format scales them to eight bits.
+        let wide = Image::decode(b"P6 2 1 65535\n\xff\xff\x80\x00\x00\x00\x00\x00\x00\x00\x00\x01")
+            .unwrap();
+        assert_eq!(
+            (wide.pixel(0, 0), wide.pixel(1, 0)),
+            (0xffff_7f00, 0xff00_0000)
+        );
+        let gray = Image::decode(b"P2\n2 1\n# max\n4\n1 4").unwrap();
+        assert_eq!(
+            (gray.pixel(0, 0), gray.pixel(1, 0)),
+            (0xff3f_3f3f, 0xffff_ffff)
+        );
+        let bits = Image::decode(b"P1 3 1 101").unwrap();
+        assert_eq!(bits.pixel(0, 0), 0xff00_0000);
+        assert_eq!(bits.pixel(1, 0), 0xffff_ffff);
+    }
+
+    #[test]
+    fn decodes_interlaced_png() {
+        assert_close(&load("interlaced.png"), &load("pattern.ppm"), 0);
+    }
+
+    #[test]
+    fn decodes_paletted_png() {
+        // Two bits per pixel, indexing red, green, half transparent blue and white.
+        let image = load("paletted.png");
+        let colors = [0xffff_0000, 0xff00_ff00, 0x8000_0080, 0xffff_ffff];
+        assert_eq!((image.width(), image.height()), (5, 3));
+        for y in 0..3 {
+            for x in 0..5 {
+                assert_eq!(image.pixel(x, y), colors[(x + y) % 4], "at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_baseline_jpeg() {
+        // With a restart marker after every row of blocks.
+        assert_close(&load("baseline.jpg"), &load("baseline.ppm"), 1);
+    }
+
+    #[test]
+    fn decodes_progressive_jpeg() {
+        assert_close(&load("progressive.jpg"), &load("progressive.ppm"), 1);
+    }
+
+    #[test]
+    fn rejects_truncated_images() {
+        // How many bytes at the end can go unnoticed: a plain file cut inside its last number
+        // still reads as a smaller one.
+        let files = [
+            ("pattern.ppm", 0),
+            ("pattern-plain.ppm", 2),
+            ("interlaced.png", 0),
+            ("paletted.png", 0),
+            ("baseline.jpg", 0),
+            ("progressive.jpg", 0),
+        ];
+        for (file, unnoticed) in files {
+            let data = fs::read(path(file)).unwrap();
+            for end in 0..data.len() - unnoticed {
+                assert!(Image::decode(&data[..end]).is_err(), "{file} cut at {end}");
+            }
+        }
+    }
+}
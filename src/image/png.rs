@@ -0,0 +1,296 @@
+//! PNG decoding: every color type and bit depth, transparency chunks and Adam7 interlacing.
+//! Gamma and color space chunks are ignored.
+
+use crate::image::{Error, Image, MAX_PIXELS, inflate};
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Adam7 passes as (x start, y start, x step, y step).
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+pub fn is_png(data: &[u8]) -> bool {
+    data.starts_with(SIGNATURE)
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    depth: u8,
+    color: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    /// Bytes per complete pixel, at least one, as filters look back that far.
+    fn filter_stride(&self) -> usize {
+        (self.channels() * self.depth as usize).div_ceil(8)
+    }
+
+    fn row_len(&self, width: usize) -> usize {
+        (width * self.channels() * self.depth as usize).div_ceil(8)
+    }
+}
+
+pub fn decode(data: &[u8]) -> Result<Image, Error> {
+    let mut offset = SIGNATURE.len();
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut transparent: Option<[u16; 3]> = None;
+    let mut compressed = Vec::new();
+    loop {
+        let len = data
+            .get(offset..offset + 4)
+            .map(|len| u32::from_be_bytes(len.try_into().unwrap()) as usize)
+            .ok_or(Error::Malformed("PNG chunk"))?;
+        let kind = data
+            .get(offset + 4..offset + 8)
+            .ok_or(Error::Malformed("PNG chunk"))?;
+        // The CRC is not checked, but a chunk cut short before it is as broken as one without
+        // its body.
+        let body = data
+            .get(offset + 8..offset + 12 + len)
+            .map(|body| &body[..len])
+            .ok_or(Error::Malformed("PNG chunk"))?;
+        offset += 12 + len;
+        match kind {
+            b"IHDR" => header = Some(parse_header(body)?),
+            b"PLTE" => {
+                palette = body
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+                    .collect();
+            }
+            b"tRNS" => match header.as_ref().map(|h| h.color) {
+                Some(3) => {
+                    for (entry, &alpha) in palette.iter_mut().zip(body) {
+                        entry[3] = alpha;
+                    }
+                }
+                Some(0) if body.len() >= 2 => {
+                    let gray = u16::from_be_bytes([body[0], body[1]]);
+                    transparent = Some([gray; 3]);
+                }
+                Some(2) if body.len() >= 6 => {
+                    let sample = |i: usize| u16::from_be_bytes([body[i], body[i + 1]]);
+                    transparent = Some([sample(0), sample(2), sample(4)]);
+                }
+                _ => {}
+            },
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            // Unknown critical chunks change how the image must be read.
+            _ if kind[0] & 0x20 == 0 => return Err(Error::Unsupported("PNG chunk")),
+            _ => {}
+        }
+    }
+    let header = header.ok_or(Error::Malformed("PNG header"))?;
+    if header.color == 3 && palette.is_empty() {
+        return Err(Error::Malformed("PNG palette"));
+    }
+
+    let passes: Vec<_> = if header.interlaced {
+        ADAM7.to_vec()
+    } else {
+        vec![(0, 0, 1, 1)]
+    };
+    let pass_size = |&(x0, y0, dx, dy): &(usize, usize, usize, usize)| {
+        (
+            header.width.saturating_sub(x0).div_ceil(dx),
+            header.height.saturating_sub(y0).div_ceil(dy),
+        )
+    };
+    let expected: usize = passes
+        .iter()
+        .map(|pass| {
+            let (width, height) = pass_size(pass);
+            if width == 0 {
+                0
+            } else {
+                (header.row_len(width) + 1) * height
+            }
+        })
+        .sum();
+    let raw = inflate::zlib(&compressed, expected)?;
+    if raw.len() < expected {
+        return Err(Error::Malformed("PNG image data"));
+    }
+
+    let mut image = Image::new(header.width, header.height);
+    let mut offset = 0;
+    for pass in &passes {
+        let (width, height) = pass_size(pass);
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let row_len = header.row_len(width);
+        let mut previous = vec![0u8; row_len];
+        for y in 0..height {
+            let filter = raw[offset];
+            let mut row = raw[offset + 1..offset + 1 + row_len].to_vec();
+            offset += row_len + 1;
+            unfilter(filter, &mut row, &previous, header.filter_stride())?;
+            for x in 0..width {
+                let pixel = pixel(&header, &row, x, &palette, transparent);
+                let (x0, y0, dx, dy) = *pass;
+                image.set(x0 + x * dx, y0 + y * dy, pixel);
+            }
+            previous = row;
+        }
+    }
+    Ok(image)
+}
+
+fn parse_header(body: &[u8]) -> Result<Header, Error> {
+    let [
+        w0,
+        w1,
+        w2,
+        w3,
+        h0,
+        h1,
+        h2,
+        h3,
+        depth,
+        color,
+        compression,
+        filter,
+        interlace,
+    ] = *body
+    else {
+        return Err(Error::Malformed("PNG header"));
+    };
+    let width = u32::from_be_bytes([w0, w1, w2, w3]) as usize;
+    let height = u32::from_be_bytes([h0, h1, h2, h3]) as usize;
+    let valid_depth = match color {
+        0 => matches!(depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(depth, 1 | 2 | 4 | 8),
+        2 | 4 | 6 => matches!(depth, 8 | 16),
+        _ => false,
+    };
+    if !valid_depth || compression != 0 || filter != 0 || interlace > 1 {
+        return Err(Error::Unsupported("PNG format"));
+    }
+    if width == 0 || height == 0 {
+        return Err(Error::Malformed("PNG header"));
+    }
+    if width.saturating_mul(height) > MAX_PIXELS {
+        return Err(Error::TooLarge);
+    }
+    Ok(Header {
+        width,
+        height,
+        depth,
+        color,
+        interlaced: interlace == 1,
+    })
+}
+
+fn unfilter(filter: u8, row: &mut [u8], previous: &[u8], stride: usize) -> Result<(), Error> {
+    for i in 0..row.len() {
+        let left = if i >= stride { row[i - stride] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= stride { previous[i - stride] } else { 0 };
+        row[i] = row[i].wrapping_add(match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err(Error::Malformed("PNG filter")),
+        });
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Pixel `x` of an unfiltered row as straight RGBA.
+fn pixel(
+    header: &Header,
+    row: &[u8],
+    x: usize,
+    palette: &[[u8; 4]],
+    transparent: Option<[u16; 3]>,
+) -> [u8; 4] {
+    let depth = header.depth as usize;
+    // Samples as stored, of which only the top eight bits are kept.
+    let sample = |index: usize| -> u16 {
+        match depth {
+            16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+            8 => row[index] as u16,
+            _ => {
+                let bit = index * depth;
+                let shift = 8 - depth - bit % 8;
+                (row[bit / 8] >> shift) as u16 & ((1 << depth) - 1)
+            }
+        }
+    };
+    let to_u8 = |value: u16| match depth {
+        16 => (value >> 8) as u8,
+        8 => value as u8,
+        _ => (value * 255 / ((1 << depth) - 1)) as u8,
+    };
+    let channels = header.channels();
+    let base = x * channels;
+    match header.color {
+        3 => palette
+            .get(sample(base) as usize)
+            .copied()
+            .unwrap_or([0, 0, 0, 0xff]),
+        0 => {
+            let gray = sample(base);
+            let alpha = if transparent.is_some_and(|t| t[0] == gray) {
+                0
+            } else {
+                0xff
+            };
+            let gray = to_u8(gray);
+            [gray, gray, gray, alpha]
+        }
+        4 => {
+            let gray = to_u8(sample(base));
+            [gray, gray, gray, to_u8(sample(base + 1))]
+        }
+        2 => {
+            let rgb = [sample(base), sample(base + 1), sample(base + 2)];
+            let alpha = if transparent == Some(rgb) { 0 } else { 0xff };
+            [to_u8(rgb[0]), to_u8(rgb[1]), to_u8(rgb[2]), alpha]
+        }
+        _ => [
+            to_u8(sample(base)),
+            to_u8(sample(base + 1)),
+            to_u8(sample(base + 2)),
+            to_u8(sample(base + 3)),
+        ],
+    }
+}
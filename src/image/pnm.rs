@@ -0,0 +1,139 @@
+//! Netpbm images: PBM, PGM and PPM, in both their plain text and binary forms.
+
+use crate::image::{Error, Image, MAX_PIXELS};
+
+pub fn is_pnm(data: &[u8]) -> bool {
+    matches!(data, [b'P', b'1'..=b'6', ..])
+}
+
+/// Header fields and plain-format samples: whitespace-separated numbers with `#` comments.
+struct Tokens<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Tokens<'_> {
+    fn number(&mut self) -> Result<u32, Error> {
+        loop {
+            match self.data.get(self.offset) {
+                Some(b'#') => {
+                    while self.data.get(self.offset).is_some_and(|&b| b != b'\n') {
+                        self.offset += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.offset += 1,
+                _ => break,
+            }
+        }
+        let start = self.offset;
+        while self.data.get(self.offset).is_some_and(u8::is_ascii_digit) {
+            self.offset += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.offset])
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or(Error::Malformed("PNM header"))
+    }
+
+    /// A single plain PBM bit, which need not be separated from the next.
+    fn bit(&mut self) -> Result<u32, Error> {
+        while self
+            .data
+            .get(self.offset)
+            .is_some_and(|&b| b.is_ascii_whitespace())
+        {
+            self.offset += 1;
+        }
+        let bit = match self.data.get(self.offset) {
+            Some(b'0') => 0,
+            Some(b'1') => 1,
+            _ => return Err(Error::Malformed("PBM data")),
+        };
+        self.offset += 1;
+        Ok(bit)
+    }
+}
+
+pub fn decode(data: &[u8]) -> Result<Image, Error> {
+    let kind = data[1];
+    let mut tokens = Tokens { data, offset: 2 };
+    let width = tokens.number()? as usize;
+    let height = tokens.number()? as usize;
+    let max = if matches!(kind, b'1' | b'4') {
+        1
+    } else {
+        tokens.number()?
+    };
+    if width == 0 || height == 0 || max == 0 || max > 65535 {
+        return Err(Error::Malformed("PNM header"));
+    }
+    if width.saturating_mul(height) > MAX_PIXELS {
+        return Err(Error::TooLarge);
+    }
+    let channels = if matches!(kind, b'3' | b'6') { 3 } else { 1 };
+    let scale = |value: u32| (value.min(max) * 255 / max) as u8;
+
+    let mut image = Image::new(width, height);
+    match kind {
+        b'1' | b'2' | b'3' => {
+            for y in 0..height {
+                for x in 0..width {
+                    let mut rgb = [0; 3];
+                    for channel in rgb.iter_mut().take(channels) {
+                        *channel = match kind {
+                            // In bitmaps 1 is black.
+                            b'1' => 255 - scale(tokens.bit()?),
+                            _ => scale(tokens.number()?),
+                        };
+                    }
+                    if channels == 1 {
+                        rgb = [rgb[0]; 3];
+                    }
+                    image.set(x, y, [rgb[0], rgb[1], rgb[2], 0xff]);
+                }
+            }
+        }
+        _ => {
+            // A single whitespace byte separates the header from binary data.
+            let pixels = data.get(tokens.offset + 1..).unwrap_or_default();
+            if kind == b'4' {
+                let row_len = width.div_ceil(8);
+                if pixels.len() < row_len * height {
+                    return Err(Error::Malformed("PBM data"));
+                }
+                for y in 0..height {
+                    for x in 0..width {
+                        let bit = pixels[y * row_len + x / 8] >> (7 - x % 8) & 1;
+                        let value = if bit == 1 { 0 } else { 0xff };
+                        image.set(x, y, [value, value, value, 0xff]);
+                    }
+                }
+                return Ok(image);
+            }
+            let sample_len = if max > 255 { 2 } else { 1 };
+            if pixels.len() < width * height * channels * sample_len {
+                return Err(Error::Malformed("PNM data"));
+            }
+            let sample = |index: usize| {
+                scale(if sample_len == 2 {
+                    u16::from_be_bytes([pixels[index * 2], pixels[index * 2 + 1]]) as u32
+                } else {
+                    pixels[index] as u32
+                })
+            };
+            for y in 0..height {
+                for x in 0..width {
+                    let base = (y * width + x) * channels;
+                    let pixel = if channels == 3 {
+                        [sample(base), sample(base + 1), sample(base + 2), 0xff]
+                    } else {
+                        let gray = sample(base);
+                        [gray, gray, gray, 0xff]
+                    };
+                    image.set(x, y, pixel);
+                }
+            }
+        }
+    }
+    Ok(image)
+}
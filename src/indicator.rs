@@ -0,0 +1,361 @@
+use crate::{
+    password::Edit,
+    render::{Canvas, Color, Rect, text::Font},
+    throttle::ThrottleStatus,
+    timer::Timer,
+};
+use std::{
+    borrow::Cow,
+    f32::consts::{PI, TAU},
+    io::Result,
+    time::Duration,
+};
+
+/// How long feedback such as a keystroke or a wrong password stays before the ring goes idle.
+const FEEDBACK_TIMEOUT: Duration = Duration::from_secs(3);
+/// The highlighted segment covers a sixth of the ring.
+const HIGHLIGHT_SWEEP: f32 = PI / 3.0;
+/// Thin marks at both ends of the highlighted segment.
+const SEPARATOR_SWEEP: f32 = PI / 128.0;
+/// Successive highlights step by the golden angle so they land all over the ring.
+const HIGHLIGHT_STEP: f32 = 2.399_963;
+const LINE_WIDTH: f32 = 2.0;
+
+/// What the ring is showing, swaylock's input states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndicatorState {
+    Idle,
+    /// A key was typed, or with `backspace`, text removed.
+    Typing {
+        backspace: bool,
+    },
+    Clearing,
+    Verifying,
+    Wrong,
+}
+
+/// The colors of the ring in one state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateColors {
+    pub inside: Color,
+    pub ring: Color,
+    pub line: Color,
+    pub text: Color,
+}
+
+/// Size and colors of the password indicator, with swaylock's defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndicatorStyle {
+    /// A family name or a path to a font file.
+    pub font: String,
+    /// Zero sizes the text to the ring.
+    pub font_size: f32,
+    pub radius: f32,
+    pub thickness: f32,
+    pub normal: StateColors,
+    pub clear: StateColors,
+    pub caps_lock: StateColors,
+    pub verifying: StateColors,
+    pub wrong: StateColors,
+    pub key_highlight: Color,
+    pub backspace_highlight: Color,
+    pub caps_lock_key_highlight: Color,
+    pub caps_lock_backspace_highlight: Color,
+    pub separator: Color,
+}
+
+impl Default for IndicatorStyle {
+    fn default() -> Self {
+        let colors = |inside, ring, text| StateColors {
+            inside,
+            ring,
+            line: Color::BLACK,
+            text,
+        };
+        let orange = Color::rgb(0xe5, 0xa4, 0x45);
+        Self {
+            font: "sans-serif".to_string(),
+            font_size: 0.0,
+            radius: 50.0,
+            thickness: 10.0,
+            normal: colors(
+                Color::rgba(0, 0, 0, 0xc0),
+                Color::rgb(0x33, 0x7d, 0x00),
+                orange,
+            ),
+            clear: colors(orange, orange, Color::BLACK),
+            caps_lock: colors(Color::rgba(0, 0, 0, 0xc0), orange, orange),
+            verifying: colors(
+                Color::rgba(0x00, 0x72, 0xff, 0xc0),
+                Color::rgb(0x33, 0x00, 0xfa),
+                Color::BLACK,
+            ),
+            wrong: colors(
+                Color::rgba(0xfa, 0x00, 0x00, 0xc0),
+                Color::rgb(0x7d, 0x33, 0x00),
+                Color::BLACK,
+            ),
+            key_highlight: Color::rgb(0x33, 0xdb, 0x00),
+            backspace_highlight: Color::rgb(0xdb, 0x33, 0x00),
+            caps_lock_key_highlight: Color::rgb(0x33, 0xdb, 0x00),
+            caps_lock_backspace_highlight: Color::rgb(0xdb, 0x33, 0x00),
+            separator: Color::BLACK,
+        }
+    }
+}
+
+/// The password indicator shared by every output: a ring whose colors follow typing,
+/// verification and caps lock, drawn the way swaylock draws it, with a status message inside
+/// when a font is available.
+///
+/// Transient states fall back to idle after a while: `timer` is armed on every change and the
+/// event loop hands it back to [`Indicator::timeout`] when it fires.
+#[derive(Debug)]
+pub struct Indicator {
+    style: IndicatorStyle,
+    font: Option<Font>,
+    state: IndicatorState,
+    caps_lock: bool,
+    /// Where the highlighted segment starts, in radians clockwise from the top.
+    highlight: f32,
+    /// Text from the authenticator, such as a prompt, shown instead of the state's message
+    /// until the next submission or clear.
+    notice: Option<String>,
+    /// Wrong passwords so far and how long the next attempt is held back.
+    throttle: ThrottleStatus,
+    /// Seconds left in which any input unlocks.
+    grace: u64,
+    /// What the fingerprint reader wants, shown while nothing else is going on.
+    fingerprint: Option<&'static str>,
+    timer: Timer,
+}
+
+impl Indicator {
+    pub fn new(style: IndicatorStyle, font: Option<Font>) -> Result<Self> {
+        Ok(Self {
+            style,
+            font,
+            state: IndicatorState::Idle,
+            caps_lock: false,
+            highlight: 0.0,
+            notice: None,
+            throttle: ThrottleStatus::default(),
+            grace: 0,
+            fingerprint: None,
+            timer: Timer::new()?,
+        })
+    }
+
+    pub fn timer(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+
+    /// Updates the ring for an edit of the password, returning whether it changed.
+    pub fn edited(&mut self, edit: Edit) -> bool {
+        match edit {
+            Edit::Submitted => {
+                self.notice = None;
+                self.set_state(IndicatorState::Verifying)
+            }
+            Edit::Cancelled => {
+                self.notice = None;
+                self.set_state(IndicatorState::Clearing)
+            }
+            Edit::Cleared => self.set_state(IndicatorState::Clearing),
+            Edit::Inserted | Edit::Removed => {
+                self.highlight = (self.highlight + HIGHLIGHT_STEP) % TAU;
+                self.set_state(IndicatorState::Typing {
+                    backspace: edit == Edit::Removed,
+                })
+            }
+            Edit::Unchanged => false,
+        }
+    }
+
+    /// Shows that the password was rejected.
+    pub fn wrong(&mut self) -> bool {
+        self.set_state(IndicatorState::Wrong)
+    }
+
+    /// Shows a question from the authenticator and waits for its answer to be typed.
+    pub fn prompt(&mut self, text: String) -> bool {
+        self.set_notice(Some(text)) | self.set_state(IndicatorState::Idle)
+    }
+
+    /// Replaces the text shown inside the ring, returning whether it changed.
+    pub fn set_notice(&mut self, notice: Option<String>) -> bool {
+        let changed = self.notice != notice;
+        self.notice = notice;
+        changed
+    }
+
+    /// Shows how many passwords were wrong and the wait before the next attempt, returning
+    /// whether that changed the ring.
+    pub fn set_throttle(&mut self, throttle: ThrottleStatus) -> bool {
+        let changed = self.throttle != throttle;
+        self.throttle = throttle;
+        changed
+    }
+
+    /// Counts down the grace period, which is over at zero, returning whether that changed the
+    /// ring.
+    pub fn set_grace(&mut self, grace: u64) -> bool {
+        let changed = self.grace != grace;
+        self.grace = grace;
+        changed
+    }
+
+    /// Shows the fingerprint reader's status, such as to scan again, returning whether that
+    /// changed the ring.
+    pub fn set_fingerprint(&mut self, fingerprint: Option<&'static str>) -> bool {
+        let changed = self.fingerprint != fingerprint;
+        self.fingerprint = fingerprint;
+        changed
+    }
+
+    /// Follows the keyboard's caps lock, returning whether that changed the ring.
+    pub fn set_caps_lock(&mut self, caps_lock: bool) -> bool {
+        let changed = self.caps_lock != caps_lock;
+        self.caps_lock = caps_lock;
+        changed
+    }
+
+    /// Called when the timer is readable; returns whether the ring went back to idle.
+    pub fn timeout(&mut self) -> bool {
+        self.timer.tick("indicator") && self.set_state(IndicatorState::Idle)
+    }
+
+    /// Everything the ring may touch when centered on (`cx`, `cy`), with the style's sizes
+    /// scaled by `scale`.
+    pub fn bounds(&self, cx: f32, cy: f32, scale: f32) -> Rect {
+        let outer = (self.style.radius + self.style.thickness / 2.0 + LINE_WIDTH / 2.0) * scale;
+        Rect::around(cx, cy, outer).inflate(1.0)
+    }
+
+    /// Draws the indicator centered on (`cx`, `cy`), with the style's sizes, given in logical
+    /// pixels, multiplied by `scale`.
+    pub fn draw(&self, canvas: &mut Canvas, cx: f32, cy: f32, scale: f32) {
+        let style = &self.style;
+        let (radius, thickness) = (style.radius * scale, style.thickness * scale);
+        let line_width = LINE_WIDTH * scale;
+        let colors = match self.state {
+            IndicatorState::Clearing => &style.clear,
+            IndicatorState::Verifying => &style.verifying,
+            IndicatorState::Wrong => &style.wrong,
+            _ if self.caps_lock => &style.caps_lock,
+            _ => &style.normal,
+        };
+        let (inner, outer) = (radius - thickness / 2.0, radius + thickness / 2.0);
+        canvas.fill_circle(cx, cy, inner, colors.inside);
+        canvas.stroke_circle(cx, cy, radius, thickness, colors.ring);
+
+        if let IndicatorState::Typing { backspace } = self.state {
+            let highlight = match (backspace, self.caps_lock) {
+                (false, false) => style.key_highlight,
+                (true, false) => style.backspace_highlight,
+                (false, true) => style.caps_lock_key_highlight,
+                (true, true) => style.caps_lock_backspace_highlight,
+            };
+            // Angles are measured from the positive x axis; ours start at the top.
+            let start = self.highlight - PI / 2.0;
+            let arc = |canvas: &mut Canvas, start, sweep, color| {
+                canvas.stroke_arc(cx, cy, radius, thickness, start, sweep, color);
+            };
+            arc(canvas, start, HIGHLIGHT_SWEEP, highlight);
+            arc(canvas, start, SEPARATOR_SWEEP, style.separator);
+            arc(
+                canvas,
+                start + HIGHLIGHT_SWEEP - SEPARATOR_SWEEP,
+                SEPARATOR_SWEEP,
+                style.separator,
+            );
+        }
+
+        canvas.stroke_circle(cx, cy, inner, line_width, colors.line);
+        canvas.stroke_circle(cx, cy, outer, line_width, colors.line);
+
+        if let (Some(font), Some(message)) = (&self.font, self.message()) {
+            let mut size = match style.font_size {
+                0.0 => radius / 3.0,
+                size => size * scale,
+            };
+            // Long messages shrink to stay inside the ring.
+            let room = inner * 1.8;
+            let width = font.measure(&message, size);
+            if width > room {
+                size *= room / width;
+            }
+            let width = font.measure(&message, size);
+            let baseline = cy + (font.ascent(size) - font.descent(size)) / 2.0;
+            font.draw(
+                canvas,
+                &message,
+                size,
+                cx - width / 2.0,
+                baseline,
+                colors.text,
+            );
+        }
+    }
+
+    fn message(&self) -> Option<Cow<'_, str>> {
+        if let Some(notice) = &self.notice {
+            return Some(Cow::Borrowed(notice));
+        }
+        if self.grace > 0 {
+            return Some(Cow::Owned(format!(
+                "Any input unlocks for {} s",
+                self.grace
+            )));
+        }
+        let ThrottleStatus {
+            failures,
+            wait,
+            locked_out,
+        } = self.throttle;
+        let attempts = match failures {
+            1 => "1 failed attempt".to_string(),
+            failures => format!("{failures} failed attempts"),
+        };
+        if wait > 0 {
+            let time = match wait {
+                0..60 => format!("{wait} s"),
+                _ => format!("{}:{:02}", wait / 60, wait % 60),
+            };
+            let action = if locked_out { "locked for" } else { "retry in" };
+            return Some(Cow::Owned(format!("{attempts}, {action} {time}")));
+        }
+        match self.state {
+            IndicatorState::Clearing => Some(Cow::Borrowed("Cleared")),
+            IndicatorState::Verifying => Some(Cow::Borrowed("Verifying…")),
+            IndicatorState::Wrong if failures > 1 => {
+                Some(Cow::Owned(format!("Wrong password, {attempts}")))
+            }
+            IndicatorState::Wrong => Some(Cow::Borrowed("Wrong password")),
+            IndicatorState::Idle if self.fingerprint.is_some() => {
+                self.fingerprint.map(Cow::Borrowed)
+            }
+            _ if self.caps_lock => Some(Cow::Borrowed("Caps Lock")),
+            _ => None,
+        }
+    }
+
+    fn set_state(&mut self, state: IndicatorState) -> bool {
+        let changed = self.state != state || matches!(state, IndicatorState::Typing { .. });
+        self.state = state;
+        // Verification ends when its result arrives, not after a timeout.
+        let timeout = match state {
+            IndicatorState::Idle | IndicatorState::Verifying => Duration::ZERO,
+            _ => FEEDBACK_TIMEOUT,
+        };
+        let armed = if timeout.is_zero() {
+            self.timer.disarm()
+        } else {
+            self.timer.arm(timeout, Duration::ZERO)
+        };
+        if let Err(e) = armed {
+            tracing::error!("Failed to set indicator timer: {e}");
+        }
+        changed
+    }
+}
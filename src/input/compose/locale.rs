@@ -0,0 +1,80 @@
+use crate::config::{env_path, xdg_dir};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const DEFAULT_XLOCALEDIR: &str = "/usr/share/X11/locale";
+
+/// The locale used for text input, following the precedence of `setlocale(LC_CTYPE, "")`.
+pub fn current() -> String {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| "C".to_string())
+}
+
+pub fn xlocaledir() -> PathBuf {
+    env_path("XLOCALEDIR").unwrap_or_else(|| PathBuf::from(DEFAULT_XLOCALEDIR))
+}
+
+/// The user's own compose file, which replaces the locale's table when present.
+pub fn user_compose_file() -> Option<PathBuf> {
+    if let Some(path) = env_path("XCOMPOSEFILE") {
+        return Some(path);
+    }
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+        .map(|dir| dir.join("XCompose"))
+        .into_iter()
+        .chain(env_path("HOME").map(|home| home.join(".XCompose")))
+        .find(|path| path.is_file())
+}
+
+/// The system compose file for `locale` in the X locale directory `dir`, resolved through
+/// `locale.alias` and `compose.dir`.
+pub fn system_compose_file(dir: &Path, locale: &str) -> Option<PathBuf> {
+    // The C locale has no UTF-8 table of its own, but users typing in it still expect one.
+    let locale = match locale {
+        "C" | "POSIX" => "en_US.UTF-8".to_string(),
+        _ => lookup(&dir.join("locale.alias"), |name, _| name == locale)
+            .map(|(_, target)| target)
+            .unwrap_or_else(|| locale.to_string()),
+    };
+    lookup(&dir.join("compose.dir"), |_, name| name == locale)
+        .map(|(file, _)| dir.join(file))
+        .filter(|path| path.is_file())
+}
+
+/// Finds the first `left: right` pair in an X locale database file accepted by `matches`.
+fn lookup(path: &Path, matches: impl Fn(&str, &str) -> bool) -> Option<(String, String)> {
+    let text = fs::read_to_string(path).ok()?;
+    text.lines().find_map(|line| {
+        let line = line.split('#').next()?;
+        let mut fields = line.split_whitespace();
+        let left = fields.next()?.trim_end_matches(':');
+        let right = fields.next()?;
+        matches(left, right).then(|| (left.to_string(), right.to_string()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/compose/locale")
+    }
+
+    #[test]
+    fn compose_files_by_locale() {
+        let dir = dir();
+        let en_us = Some(dir.join("en_US.UTF-8/Compose"));
+        assert_eq!(system_compose_file(&dir, "en_US.UTF-8"), en_us);
+        // Through locale.alias.
+        assert_eq!(system_compose_file(&dir, "en_US.utf8"), en_us);
+        assert_eq!(system_compose_file(&dir, "de_DE.UTF-8@euro"), en_us);
+        assert_eq!(system_compose_file(&dir, "C"), en_us);
+        assert_eq!(system_compose_file(&dir, "xx_XX.UTF-8"), None);
+    }
+}
@@ -0,0 +1,30 @@
+//! Compose and dead-key sequences, read from the same Compose files X11 and libxkbcommon use.
+
+mod locale;
+mod parser;
+mod state;
+mod table;
+
+use std::{fmt, io, path::PathBuf};
+
+pub use state::{Outcome, State};
+pub use table::{Output, Table};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    NotFound(String),
+    IncludeDepth(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "Failed to read compose file {}: {e}", path.display()),
+            Self::NotFound(locale) => write!(f, "No compose file found for locale {locale}"),
+            Self::IncludeDepth(path) => {
+                write!(f, "Compose includes nest too deeply at {}", path.display())
+            }
+        }
+    }
+}
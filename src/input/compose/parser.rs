@@ -0,0 +1,257 @@
+use crate::{
+    config,
+    input::{
+        compose::{Error, Output, Table, locale},
+        xkb::Keysym,
+    },
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// libX11 stops following includes at the same depth.
+const MAX_INCLUDE_DEPTH: usize = 5;
+
+pub fn parse_file(table: &mut Table, path: &Path, dir: &Path, locale: &str) -> Result<(), Error> {
+    Parser {
+        dir,
+        locale,
+        depth: 0,
+    }
+    .file(table, path)
+}
+
+struct Parser<'a> {
+    /// The X locale directory.
+    dir: &'a Path,
+    locale: &'a str,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn file(&mut self, table: &mut Table, path: &Path) -> Result<(), Error> {
+        if self.depth > MAX_INCLUDE_DEPTH {
+            return Err(Error::IncludeDepth(path.to_path_buf()));
+        }
+        let bytes = fs::read(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        self.text(table, &bytes, path)
+    }
+
+    fn text(&mut self, table: &mut Table, text: &[u8], path: &Path) -> Result<(), Error> {
+        for (index, line) in text.split(|b| *b == b'\n').enumerate() {
+            let line_number = index + 1;
+            // Legacy locales use 8-bit encodings, which only matter for the lines they appear on.
+            let parsed = std::str::from_utf8(line)
+                .map_err(|_| "line is not UTF-8".to_string())
+                .and_then(parse_line);
+            match parsed {
+                Ok(Line::Empty) => {}
+                Ok(Line::Include(include)) => {
+                    let Some(include) = self.expand(&include) else {
+                        tracing::warn!(
+                            "{}:{line_number}: cannot resolve include {include:?}",
+                            path.display()
+                        );
+                        continue;
+                    };
+                    self.depth += 1;
+                    let result = self.file(table, &include);
+                    self.depth -= 1;
+                    result?;
+                }
+                Ok(Line::Production(sequence, output)) => {
+                    if let Err(message) = table.insert(&sequence, output) {
+                        tracing::debug!("{}:{line_number}: {message}", path.display());
+                    }
+                }
+                Err(message) => {
+                    tracing::debug!("{}:{line_number}: {message}, skipping", path.display());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Substitutes `%H` (home), `%L` (the locale's compose file) and `%S` (the system directory).
+    fn expand(&self, include: &str) -> Option<PathBuf> {
+        let mut out = String::new();
+        let mut chars = include.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next()? {
+                '%' => out.push('%'),
+                'H' => out.push_str(config::env_path("HOME")?.to_str()?),
+                'L' => out.push_str(locale::system_compose_file(self.dir, self.locale)?.to_str()?),
+                'S' => out.push_str(self.dir.to_str()?),
+                _ => return None,
+            }
+        }
+        Some(PathBuf::from(out))
+    }
+}
+
+enum Line {
+    Empty,
+    Include(String),
+    Production(Vec<Keysym>, Output),
+}
+
+/// Parses one line of the grammar `<keysym>... : ["string"] [keysym]` or `include "path"`.
+fn parse_line(line: &str) -> Result<Line, String> {
+    let mut cursor = Cursor { line, pos: 0 };
+    cursor.skip_blank();
+    if cursor.at_end() {
+        return Ok(Line::Empty);
+    }
+    if cursor.rest().starts_with("include") {
+        cursor.pos += "include".len();
+        cursor.skip_blank();
+        let path = cursor.string()?;
+        cursor.expect_end()?;
+        return Ok(Line::Include(path));
+    }
+
+    let mut sequence = Vec::new();
+    loop {
+        cursor.skip_blank();
+        match cursor.peek() {
+            Some(':') => {
+                cursor.pos += 1;
+                break;
+            }
+            Some('<') => {
+                cursor.pos += 1;
+                let name = cursor.take_while(|c| c != '>');
+                if cursor.peek() != Some('>') {
+                    return Err("unterminated keysym".to_string());
+                }
+                cursor.pos += 1;
+                let keysym =
+                    Keysym::from_name(name).ok_or_else(|| format!("unknown keysym {name:?}"))?;
+                sequence.push(keysym);
+            }
+            // Modifier requirements such as `! Ctrl` are accepted but not enforced, as in
+            // libxkbcommon; a keysym alone decides each step.
+            Some('!' | '~') => cursor.pos += 1,
+            Some(c) if c.is_ascii_alphabetic() => {
+                cursor.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            }
+            _ => return Err("expected a keysym or ':'".to_string()),
+        }
+    }
+    if sequence.is_empty() {
+        return Err("sequence has no keysyms".to_string());
+    }
+
+    cursor.skip_blank();
+    let string = match cursor.peek() {
+        Some('"') => Some(cursor.string()?),
+        _ => None,
+    };
+    cursor.skip_blank();
+    let keysym = match cursor.take_while(|c| c.is_ascii_alphanumeric() || c == '_') {
+        "" => None,
+        name => Some(Keysym::from_name(name).ok_or_else(|| format!("unknown keysym {name:?}"))?),
+    };
+    cursor.expect_end()?;
+    if string.is_none() && keysym.is_none() {
+        return Err("production has no result".to_string());
+    }
+    Ok(Line::Production(sequence, Output { string, keysym }))
+}
+
+struct Cursor<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.line[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Whitespace and comments run to the end of the line.
+    fn at_end(&self) -> bool {
+        matches!(self.peek(), None | Some('#'))
+    }
+
+    fn skip_blank(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !accept(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn expect_end(&mut self) -> Result<(), String> {
+        self.skip_blank();
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(format!("unexpected {:?}", self.rest()))
+        }
+    }
+
+    /// A double-quoted string whose escapes may spell out UTF-8 bytes in octal or hex.
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek() != Some('"') {
+            return Err("expected a string".to_string());
+        }
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let c = self.peek().ok_or("unterminated string")?;
+            self.pos += c.len_utf8();
+            match c {
+                '"' => break,
+                '\\' => {
+                    let escape = self.peek().ok_or("unterminated string")?;
+                    match escape {
+                        'x' | 'X' => {
+                            self.pos += 1;
+                            let digits = self.take_digits(16, 2);
+                            bytes.push(u8::from_str_radix(digits, 16).map_err(|_| "bad escape")?);
+                        }
+                        '0'..='7' => {
+                            let digits = self.take_digits(8, 3);
+                            let value = u32::from_str_radix(digits, 8).map_err(|_| "bad escape")?;
+                            bytes.push(u8::try_from(value).map_err(|_| "bad escape")?);
+                        }
+                        _ => {
+                            self.pos += escape.len_utf8();
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(escape.encode_utf8(&mut buf).as_bytes());
+                        }
+                    }
+                }
+                _ => {
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "string is not UTF-8".to_string())
+    }
+
+    fn take_digits(&mut self, radix: u32, max: usize) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .chars()
+            .take(max)
+            .take_while(|c| c.is_digit(radix))
+            .count();
+        self.pos += len;
+        &rest[..len]
+    }
+}
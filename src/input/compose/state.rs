@@ -0,0 +1,144 @@
+use crate::input::{
+    compose::{Output, Table},
+    xkb::Keysym,
+};
+
+/// The effect of feeding one keysym into a compose sequence.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome<'a> {
+    /// Not part of any sequence; the keysym should be used as typed.
+    Passthrough,
+    /// Consumed as part of a sequence that is still incomplete.
+    Composing,
+    Composed(&'a Output),
+    /// The keysym did not continue the sequence; both are dropped.
+    Cancelled,
+}
+
+/// Progress through a compose table as keysyms arrive.
+#[derive(Debug)]
+pub struct State {
+    table: Table,
+    node: usize,
+}
+
+impl State {
+    pub fn new(table: Table) -> Self {
+        let node = table.root();
+        Self { table, node }
+    }
+
+    pub fn is_composing(&self) -> bool {
+        self.node != self.table.root()
+    }
+
+    pub fn reset(&mut self) {
+        self.node = self.table.root();
+    }
+
+    pub fn feed(&mut self, keysym: Keysym) -> Outcome<'_> {
+        // Holding Shift for a capital in the middle of a sequence must not cancel it.
+        if keysym.is_modifier() {
+            return Outcome::Passthrough;
+        }
+        let composing = self.is_composing();
+        let Some(next) = self.table.next(self.node, keysym) else {
+            self.reset();
+            return match composing {
+                true => Outcome::Cancelled,
+                false => Outcome::Passthrough,
+            };
+        };
+        match self.table.output(next) {
+            Some(output) => {
+                self.node = self.table.root();
+                Outcome::Composed(output)
+            }
+            None => {
+                self.node = next;
+                Outcome::Composing
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn state() -> State {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/compose/locale");
+        let path = dir.join("en_US.UTF-8/Compose");
+        State::new(Table::from_file(&path, &dir, "en_US.UTF-8").unwrap())
+    }
+
+    /// Feeds the keysyms `names`, expecting all but the last to be taken by the sequence, and
+    /// returns the string composed by the last.
+    fn compose(state: &mut State, names: &[&str]) -> Option<String> {
+        let (last, sequence) = names.split_last().unwrap();
+        for name in sequence {
+            let outcome = state.feed(Keysym::from_name(name).unwrap());
+            assert!(
+                matches!(outcome, Outcome::Composing | Outcome::Passthrough),
+                "{name} ended the sequence: {outcome:?}"
+            );
+        }
+        match state.feed(Keysym::from_name(last).unwrap()) {
+            Outcome::Composed(output) => output.string.clone(),
+            outcome => panic!("{names:?} did not compose: {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn dead_keys() {
+        let mut state = state();
+        assert_eq!(
+            compose(&mut state, &["dead_acute", "e"]).as_deref(),
+            Some("é")
+        );
+        assert_eq!(
+            compose(&mut state, &["dead_tilde", "n"]).as_deref(),
+            Some("ñ")
+        );
+        assert_eq!(
+            compose(&mut state, &["dead_acute", "space"]).as_deref(),
+            Some("'")
+        );
+        assert!(!state.is_composing());
+    }
+
+    #[test]
+    fn multi_key() {
+        let mut state = state();
+        let em_dash = ["Multi_key", "minus", "minus", "minus"];
+        assert_eq!(compose(&mut state, &em_dash).as_deref(), Some("—"));
+        let sequence = ["Multi_key", "backslash", "o", "slash"];
+        assert_eq!(compose(&mut state, &sequence).as_deref(), Some("🙌"));
+        // A dead key followed by Multi_key.
+        let sequence = ["dead_circumflex", "Multi_key", "underscore", "a"];
+        assert_eq!(compose(&mut state, &sequence).as_deref(), Some("ª"));
+    }
+
+    #[test]
+    fn shift_does_not_interrupt() {
+        let mut state = state();
+        let sequence = ["dead_acute", "Shift_L", "E"];
+        assert_eq!(compose(&mut state, &sequence).as_deref(), Some("É"));
+    }
+
+    #[test]
+    fn other_keys() {
+        let mut state = state();
+        let a = Keysym::from_name("a").unwrap();
+        assert_eq!(state.feed(a), Outcome::Passthrough);
+        state.feed(Keysym::from_name("dead_acute").unwrap());
+        assert!(state.is_composing());
+        assert_eq!(
+            state.feed(Keysym::from_name("x").unwrap()),
+            Outcome::Cancelled
+        );
+        assert!(!state.is_composing());
+        assert_eq!(state.feed(a), Outcome::Passthrough);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::input::{
+    compose::{Error, locale, parser},
+    xkb::Keysym,
+};
+use std::{collections::HashMap, path::Path};
+
+/// What a completed sequence produces: text, a keysym, or both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    pub string: Option<String>,
+    pub keysym: Option<Keysym>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<Keysym, usize>,
+    output: Option<Output>,
+}
+
+/// Compose sequences stored as a trie of keysyms, rooted at node 0.
+#[derive(Debug)]
+pub struct Table {
+    nodes: Vec<Node>,
+}
+
+impl Table {
+    /// Loads the user's compose file if there is one, otherwise the table for the current locale.
+    pub fn from_locale() -> Result<Self, Error> {
+        let locale = locale::current();
+        let dir = locale::xlocaledir();
+        let path = locale::user_compose_file()
+            .or_else(|| locale::system_compose_file(&dir, &locale))
+            .ok_or_else(|| Error::NotFound(locale.clone()))?;
+        Self::from_file(&path, &dir, &locale)
+    }
+
+    /// Loads a compose file, with `%S` includes resolving to the X locale directory `dir` and
+    /// `%L` to its table for `locale`.
+    pub fn from_file(path: &Path, dir: &Path, locale: &str) -> Result<Self, Error> {
+        let mut table = Self::empty();
+        parser::parse_file(&mut table, path, dir, locale)?;
+        Ok(table)
+    }
+
+    fn empty() -> Self {
+        Self {
+            nodes: vec![Node::default()],
+        }
+    }
+
+    pub fn root(&self) -> usize {
+        0
+    }
+
+    pub fn next(&self, node: usize, keysym: Keysym) -> Option<usize> {
+        self.nodes[node].children.get(&keysym).copied()
+    }
+
+    /// The output of `node` if it ends a sequence.
+    pub fn output(&self, node: usize) -> Option<&Output> {
+        self.nodes[node].output.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes[0].children.is_empty()
+    }
+
+    /// Adds a sequence, replacing any sequence it conflicts with as later lines do in libX11.
+    pub(super) fn insert(&mut self, sequence: &[Keysym], output: Output) -> Result<(), String> {
+        let Some((last, prefix)) = sequence.split_last() else {
+            return Err("empty sequence".to_string());
+        };
+        let mut node = 0;
+        for keysym in prefix {
+            node = self.child(node, *keysym);
+            if self.nodes[node].output.take().is_some() {
+                tracing::debug!("Compose sequence overrides a shorter one it extends");
+            }
+        }
+        let node = self.child(node, *last);
+        if !self.nodes[node].children.is_empty() {
+            return Err("sequence is a prefix of a longer one".to_string());
+        }
+        // An override only replaces the parts of the result it spells out.
+        let existing = self.nodes[node].output.take();
+        let (string, keysym) = existing.map_or((None, None), |e| (e.string, e.keysym));
+        self.nodes[node].output = Some(Output {
+            string: output.string.or(string),
+            keysym: output.keysym.or(keysym),
+        });
+        Ok(())
+    }
+
+    fn child(&mut self, node: usize, keysym: Keysym) -> usize {
+        if let Some(child) = self.next(node, keysym) {
+            return child;
+        }
+        let child = self.nodes.len();
+        self.nodes.push(Node::default());
+        self.nodes[node].children.insert(keysym, child);
+        child
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn testdata() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/compose")
+    }
+
+    fn load(file: &str) -> Result<Table, Error> {
+        let dir = testdata();
+        Table::from_file(&dir.join(file), &dir.join("locale"), "en_US.UTF-8")
+    }
+
+    /// The output of the sequence of keysyms `names`, if it is one.
+    fn lookup<'a>(table: &'a Table, names: &[&str]) -> Option<&'a Output> {
+        let node = names.iter().try_fold(table.root(), |node, name| {
+            table.next(node, Keysym::from_name(name).expect("unknown keysym"))
+        })?;
+        table.output(node)
+    }
+
+    fn string(table: &Table, names: &[&str]) -> Option<String> {
+        lookup(table, names).and_then(|output| output.string.clone())
+    }
+
+    #[test]
+    fn user_file_adds_to_the_locale() {
+        let table = load("XCompose").unwrap();
+        // From the locale's table, through `include "%L"`.
+        assert_eq!(string(&table, &["dead_acute", "e"]).as_deref(), Some("é"));
+        assert_eq!(
+            string(&table, &["Multi_key", "p", "w"]).as_deref(),
+            Some("pässwörd")
+        );
+        assert_eq!(
+            string(&table, &["Multi_key", "q", "q"]).as_deref(),
+            Some("\"quoted\"")
+        );
+        assert_eq!(
+            lookup(&table, &["Multi_key", "x", "x"]),
+            Some(&Output {
+                string: None,
+                keysym: Keysym::from_name("multiply"),
+            })
+        );
+        assert_eq!(
+            string(&table, &["Multi_key", "c", "c"]).as_deref(),
+            Some("ç")
+        );
+    }
+
+    #[test]
+    fn later_lines_override() {
+        let table = load("XCompose").unwrap();
+        assert_eq!(
+            lookup(&table, &["Multi_key", "s", "s"]),
+            Some(&Output {
+                string: Some("ẞ".to_string()),
+                keysym: Keysym::from_name("ssharp"),
+            })
+        );
+    }
+
+    #[test]
+    fn unusable_lines_are_skipped() {
+        let table = load("XCompose").unwrap();
+        assert_eq!(lookup(&table, &["Multi_key", "n", "o"]), None);
+        assert_eq!(
+            string(&table, &["Multi_key", "o", "c"]).as_deref(),
+            Some("©")
+        );
+    }
+
+    #[test]
+    fn includes_stop_nesting() {
+        assert!(matches!(load("loop"), Err(Error::IncludeDepth(_))));
+        assert!(matches!(load("missing"), Err(Error::Io(..))));
+    }
+}
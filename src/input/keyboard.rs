@@ -0,0 +1,387 @@
+use crate::{
+    input::{
+        compose::{self, Outcome},
+        xkb::{Keymap, Keysym, State},
+    },
+    timer::Timer,
+    wayland::{KeyState, KeyboardEvent},
+};
+use std::{fmt, io::Result, time::Duration};
+
+/// Repeat settings used until the compositor sends `wl_keyboard.repeat_info`.
+const DEFAULT_REPEAT_RATE: i32 = 25;
+const DEFAULT_REPEAT_DELAY: i32 = 600;
+
+/// The most characters one key press yields; longer Compose results are cut short.
+const MAX_CHARS: usize = 8;
+
+/// A key press translated through the current keymap and modifiers.
+#[derive(Debug)]
+pub struct KeyPress {
+    pub keysym: Keysym,
+    pub chars: Chars,
+}
+
+/// The text of one key press. It may be part of the password, so it is kept inline rather than
+/// on the heap and wiped when dropped.
+#[derive(Default)]
+pub struct Chars {
+    chars: [char; MAX_CHARS],
+    len: usize,
+}
+
+impl Chars {
+    pub fn as_slice(&self) -> &[char] {
+        &self.chars[..self.len]
+    }
+}
+
+impl FromIterator<char> for Chars {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut chars = Self::default();
+        for c in iter.into_iter().take(MAX_CHARS) {
+            chars.chars[chars.len] = c;
+            chars.len += 1;
+        }
+        chars
+    }
+}
+
+impl Drop for Chars {
+    fn drop(&mut self) {
+        for c in &mut self.chars {
+            // SAFETY: `c` is a valid, exclusive reference.
+            unsafe { std::ptr::write_volatile(c, '\0') };
+        }
+    }
+}
+
+impl fmt::Debug for Chars {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Chars({} hidden)", self.len)
+    }
+}
+
+/// Tracks the compositor's keymap and modifier state and turns raw key events into key presses,
+/// running them through the compose table so dead keys and Compose sequences yield their text.
+///
+/// Key repeat is done client-side: holding a repeating key arms `repeat_timer`, which the event
+/// loop polls and hands back to [`Keyboard::repeat`] when it fires.
+#[derive(Debug)]
+pub struct Keyboard {
+    state: Option<State>,
+    compose: Option<compose::State>,
+    repeat_timer: Timer,
+    repeat_rate: i32,
+    repeat_delay: i32,
+    repeating_key: Option<u32>,
+}
+
+impl Keyboard {
+    pub fn new(compose: Option<compose::Table>) -> Result<Self> {
+        Ok(Self {
+            state: None,
+            compose: compose.map(compose::State::new),
+            repeat_timer: Timer::new()?,
+            repeat_rate: DEFAULT_REPEAT_RATE,
+            repeat_delay: DEFAULT_REPEAT_DELAY,
+            repeating_key: None,
+        })
+    }
+
+    pub fn repeat_timer(&mut self) -> &mut Timer {
+        &mut self.repeat_timer
+    }
+
+    pub fn handle_event(&mut self, event: KeyboardEvent) -> Option<KeyPress> {
+        match event {
+            KeyboardEvent::Keymap(keymap) => {
+                self.state = keymap.and_then(|keymap| {
+                    let text = std::str::from_utf8(keymap.as_bytes())
+                        .inspect_err(|e| tracing::error!("Keymap is not valid UTF-8: {e}"))
+                        .ok()?;
+                    Keymap::from_text(text)
+                        .inspect(|keymap| {
+                            let layouts: Vec<_> = (0..keymap.num_groups())
+                                .map(|group| keymap.group_name(group).unwrap_or_default())
+                                .collect();
+                            tracing::debug!("Compiled keymap with layouts {layouts:?}");
+                        })
+                        .inspect_err(|e| tracing::error!("Failed to compile keymap: {e}"))
+                        .ok()
+                        .map(State::new)
+                });
+                self.reset();
+                None
+            }
+            KeyboardEvent::Modifiers {
+                depressed,
+                latched,
+                locked,
+                group,
+                ..
+            } => {
+                if let Some(state) = self.state.as_mut() {
+                    let previous = state.group();
+                    state.update_mask(depressed, latched, locked, group);
+                    if state.group() != previous {
+                        let name = state.keymap().group_name(state.group()).unwrap_or_default();
+                        tracing::debug!("Switched to layout {name:?}");
+                    }
+                }
+                None
+            }
+            KeyboardEvent::Key {
+                key,
+                state: KeyState::Pressed,
+                ..
+            } => {
+                let press = self.translate(key).and_then(|press| self.compose(press));
+                // Only keys typed as-is repeat; dead keys and finished sequences do not.
+                if press.is_some() && self.state.as_ref().is_some_and(|s| s.key_repeats(key)) {
+                    self.start_repeat(key);
+                } else {
+                    self.cancel_repeat();
+                }
+                press
+            }
+            // Compositors only send these when they repeat keys themselves, with a rate of 0.
+            KeyboardEvent::Key {
+                key,
+                state: KeyState::Repeated,
+                ..
+            } => self.translate(key).and_then(|press| self.compose(press)),
+            KeyboardEvent::Key {
+                key,
+                state: KeyState::Released,
+                ..
+            } => {
+                if self.repeating_key == Some(key) {
+                    self.cancel_repeat();
+                }
+                None
+            }
+            KeyboardEvent::RepeatInfo { rate, delay } => {
+                self.repeat_rate = rate;
+                self.repeat_delay = delay;
+                if let Some(key) = self.repeating_key {
+                    self.start_repeat(key);
+                }
+                None
+            }
+            KeyboardEvent::Leave { .. } => {
+                self.reset();
+                None
+            }
+            KeyboardEvent::Enter { .. } => None,
+        }
+    }
+
+    /// Whether caps lock is on, as far as the last modifiers event says.
+    pub fn caps_lock(&self) -> bool {
+        self.state
+            .as_ref()
+            .is_some_and(|state| state.mod_is_active("Lock"))
+    }
+
+    /// Called when the repeat timer is readable; yields the held key again if it is due.
+    pub fn repeat(&mut self) -> Option<KeyPress> {
+        if !self.repeat_timer.tick("key repeat") {
+            return None;
+        }
+        // Missed ticks are dropped rather than replayed in a burst.
+        self.translate(self.repeating_key?)
+    }
+
+    /// Stops any key repeat, e.g. when the surface receiving keys goes away.
+    pub fn cancel_repeat(&mut self) {
+        if self.repeating_key.take().is_some()
+            && let Err(e) = self.repeat_timer.disarm()
+        {
+            tracing::error!("Failed to disarm key repeat timer: {e}");
+        }
+    }
+
+    /// Abandons any half-typed compose sequence along with key repeat.
+    pub fn reset(&mut self) {
+        self.cancel_repeat();
+        if let Some(compose) = self.compose.as_mut() {
+            compose.reset();
+        }
+    }
+
+    fn start_repeat(&mut self, key: u32) {
+        if self.repeat_rate <= 0 {
+            self.cancel_repeat();
+            return;
+        }
+        let delay = Duration::from_millis(self.repeat_delay.max(0) as u64);
+        let interval = Duration::from_secs(1) / self.repeat_rate as u32;
+        match self.repeat_timer.arm(delay, interval) {
+            Ok(()) => self.repeating_key = Some(key),
+            Err(e) => {
+                tracing::error!("Failed to arm key repeat timer: {e}");
+                self.repeating_key = None;
+            }
+        }
+    }
+
+    /// Replaces a press with the result of the compose sequence it takes part in, if any.
+    fn compose(&mut self, press: KeyPress) -> Option<KeyPress> {
+        let Some(compose) = self.compose.as_mut() else {
+            return Some(press);
+        };
+        match compose.feed(press.keysym) {
+            Outcome::Passthrough => Some(press),
+            Outcome::Composing | Outcome::Cancelled => None,
+            Outcome::Composed(output) => {
+                let keysym = output.keysym.unwrap_or(Keysym::NO_SYMBOL);
+                let chars = match &output.string {
+                    Some(string) => string.chars().collect(),
+                    None => keysym.to_char().into_iter().collect(),
+                };
+                Some(KeyPress { keysym, chars })
+            }
+        }
+    }
+
+    fn translate(&self, key: u32) -> Option<KeyPress> {
+        let state = self.state.as_ref()?;
+        Some(KeyPress {
+            keysym: state.key_one_sym(key),
+            chars: state.key_chars(key).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wayland::Object;
+    use std::thread;
+
+    // Evdev codes of the keys used below.
+    const KEY_Q: u32 = 16;
+    const KEY_A: u32 = 30;
+    const KEY_LEFTSHIFT: u32 = 42;
+
+    const SHIFT: u32 = 1 << 0;
+
+    fn keyboard() -> Keyboard {
+        let mut keyboard = Keyboard::new(None).unwrap();
+        let keymap = Keymap::from_text(include_str!("../../testdata/keymaps/us.xkb")).unwrap();
+        keyboard.state = Some(State::new(keymap));
+        keyboard
+    }
+
+    fn key(keyboard: &mut Keyboard, key: u32, state: KeyState) -> Option<KeyPress> {
+        keyboard.handle_event(KeyboardEvent::Key {
+            serial: 0,
+            time: 0,
+            key,
+            state,
+        })
+    }
+
+    fn text(press: Option<KeyPress>) -> Option<String> {
+        press.map(|press| press.chars.as_slice().iter().collect())
+    }
+
+    fn repeat_info(keyboard: &mut Keyboard, rate: i32, delay: i32) {
+        keyboard.handle_event(KeyboardEvent::RepeatInfo { rate, delay });
+    }
+
+    /// Checks that the repeat timer fires within `delay` milliseconds, then every `interval`.
+    fn assert_armed(keyboard: &Keyboard, delay: u64, interval: u64) {
+        let (next, every) = keyboard.repeat_timer.setting().unwrap();
+        // Some of the delay has gone by since it was set.
+        let delay = Duration::from_millis(delay);
+        assert!(next <= delay && next > delay / 2, "fires in {next:?}");
+        assert_eq!(every, Duration::from_millis(interval));
+    }
+
+    fn assert_disarmed(keyboard: &Keyboard) {
+        assert_eq!(keyboard.repeating_key, None);
+        let setting = keyboard.repeat_timer.setting().unwrap();
+        assert_eq!(setting, (Duration::ZERO, Duration::ZERO));
+    }
+
+    #[test]
+    fn repeats_after_the_delay_at_the_rate() {
+        let mut keyboard = keyboard();
+        assert_disarmed(&keyboard);
+        assert_eq!(
+            text(key(&mut keyboard, KEY_A, KeyState::Pressed)).unwrap(),
+            "a"
+        );
+        assert_armed(&keyboard, 600, 40);
+        assert_eq!(keyboard.repeating_key, Some(KEY_A));
+
+        // New settings apply to the key already held, starting its delay over.
+        repeat_info(&mut keyboard, 50, 200);
+        assert_armed(&keyboard, 200, 20);
+
+        repeat_info(&mut keyboard, 1000, 1);
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(text(keyboard.repeat()).unwrap(), "a");
+    }
+
+    #[test]
+    fn follows_modifier_changes() {
+        let mut keyboard = keyboard();
+        key(&mut keyboard, KEY_A, KeyState::Pressed);
+        keyboard.handle_event(KeyboardEvent::Modifiers {
+            serial: 0,
+            depressed: SHIFT,
+            latched: 0,
+            locked: 0,
+            group: 0,
+        });
+        repeat_info(&mut keyboard, 1000, 1);
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(text(keyboard.repeat()).unwrap(), "A");
+
+        // Modifier keys themselves never repeat, and pressing one ends the repeat.
+        let shift = key(&mut keyboard, KEY_LEFTSHIFT, KeyState::Pressed).unwrap();
+        assert!(shift.chars.as_slice().is_empty());
+        assert_disarmed(&keyboard);
+    }
+
+    #[test]
+    fn stops_on_release() {
+        let mut keyboard = keyboard();
+        key(&mut keyboard, KEY_A, KeyState::Pressed);
+        key(&mut keyboard, KEY_Q, KeyState::Pressed);
+        assert_eq!(keyboard.repeating_key, Some(KEY_Q));
+
+        // Only releasing the repeating key stops it.
+        assert!(key(&mut keyboard, KEY_A, KeyState::Released).is_none());
+        assert_eq!(keyboard.repeating_key, Some(KEY_Q));
+        key(&mut keyboard, KEY_Q, KeyState::Released);
+        assert_disarmed(&keyboard);
+        assert!(keyboard.repeat().is_none());
+
+        // So does losing focus.
+        key(&mut keyboard, KEY_A, KeyState::Pressed);
+        keyboard.handle_event(KeyboardEvent::Leave {
+            serial: 0,
+            surface: Object::new(3),
+        });
+        assert_disarmed(&keyboard);
+    }
+
+    #[test]
+    fn a_rate_of_zero_disables_repeat() {
+        let mut keyboard = keyboard();
+        key(&mut keyboard, KEY_A, KeyState::Pressed);
+        repeat_info(&mut keyboard, 0, 600);
+        assert_disarmed(&keyboard);
+        key(&mut keyboard, KEY_Q, KeyState::Pressed);
+        assert_eq!(keyboard.repeating_key, None);
+        // The compositor repeats keys itself then.
+        assert_eq!(
+            text(key(&mut keyboard, KEY_Q, KeyState::Repeated)).unwrap(),
+            "q"
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod compose;
+pub mod keyboard;
+pub mod xkb;
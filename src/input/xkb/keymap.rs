@@ -0,0 +1,426 @@
+use crate::input::xkb::{
+    Error,
+    keysym::Keysym,
+    parser::{
+        Definitions, GroupAction, InterpretDef, KeyDef, ModMapEntry, ModMask, Parser, Predicate,
+        REAL_MOD_NAMES, TypeDef,
+    },
+};
+use std::collections::HashMap;
+
+const REAL_MODS: ModMask = 0xff;
+
+#[derive(Debug)]
+struct TypeEntry {
+    mods: ModMask,
+    level: usize,
+    preserve: ModMask,
+}
+
+/// A key type with every modifier already resolved to real modifiers.
+#[derive(Debug)]
+struct KeyType {
+    name: String,
+    mods: ModMask,
+    entries: Vec<TypeEntry>,
+}
+
+#[derive(Debug)]
+struct Group {
+    key_type: usize,
+    levels: Vec<Vec<Keysym>>,
+}
+
+#[derive(Debug)]
+struct Key {
+    groups: Vec<Group>,
+    repeats: bool,
+    group_action: GroupAction,
+}
+
+/// A compiled XKB keymap, as sent by the compositor in `wl_keyboard.keymap`.
+#[derive(Debug)]
+pub struct Keymap {
+    mod_names: Vec<String>,
+    /// Real modifiers each virtual modifier maps to, indexed from the first virtual modifier.
+    virtual_mods: Vec<ModMask>,
+    types: Vec<KeyType>,
+    keys: HashMap<u32, Key>,
+    num_groups: usize,
+    group_names: Vec<String>,
+}
+
+/// The level a key resolves to for a given modifier state, and the modifiers it consumed.
+pub struct Resolved<'a> {
+    pub syms: &'a [Keysym],
+    pub consumed: ModMask,
+}
+
+impl Keymap {
+    pub fn from_text(text: &str) -> Result<Self, Error> {
+        let defs = Parser::parse(text)?;
+        Self::compile(defs)
+    }
+
+    fn compile(defs: Definitions) -> Result<Self, Error> {
+        if defs.keycodes.is_empty() {
+            return Err(Error::Incomplete("xkb_keycodes"));
+        }
+        if defs.types.is_empty() {
+            return Err(Error::Incomplete("xkb_types"));
+        }
+
+        let keycode = |name: &str| {
+            let name = defs.aliases.get(name).map_or(name, String::as_str);
+            defs.keycodes.get(name).copied()
+        };
+
+        let key_defs: Vec<(u32, &KeyDef)> = {
+            let mut keys: Vec<_> = defs
+                .keys
+                .iter()
+                .filter_map(|key| match keycode(&key.name) {
+                    Some(code) => Some((code, key)),
+                    None => {
+                        tracing::debug!("Ignoring symbols for undefined key <{}>", key.name);
+                        None
+                    }
+                })
+                .collect();
+            keys.sort_by_key(|(code, _)| *code);
+            keys
+        };
+
+        let modmaps = resolve_modmap(&defs, &key_defs, &keycode);
+        let virtual_mods = resolve_virtual_mods(&defs, &key_defs, &modmaps);
+        let resolve = |mask: ModMask| effective_mask(mask, &virtual_mods);
+
+        let types: Vec<KeyType> = defs
+            .types
+            .iter()
+            .map(|def| compile_type(def, &resolve))
+            .collect();
+        let type_index = |name: &str| types.iter().position(|t| t.name == name);
+        let one_level = type_index("ONE_LEVEL");
+
+        let mut keys = HashMap::new();
+        for (code, def) in &key_defs {
+            let mut groups = Vec::new();
+            for (index, symbols) in def.symbols.iter().enumerate() {
+                let levels = symbols.clone().unwrap_or_default();
+                let name = def
+                    .types
+                    .get(&index)
+                    .or(def.default_type.as_ref())
+                    .cloned()
+                    .unwrap_or_else(|| automatic_type(&levels).to_string());
+                let key_type = type_index(&name)
+                    .or_else(|| {
+                        tracing::warn!("Key <{}> uses unknown type {name}", def.name);
+                        one_level
+                    })
+                    .unwrap_or(0);
+                groups.push(Group { key_type, levels });
+            }
+            while groups
+                .last()
+                .is_some_and(|g| g.levels.iter().all(Vec::is_empty))
+            {
+                groups.pop();
+            }
+            keys.insert(
+                *code,
+                Key {
+                    groups,
+                    repeats: key_repeats(
+                        &defs.interprets,
+                        def,
+                        modmaps.get(code).copied().unwrap_or(0),
+                    ),
+                    group_action: def.group_action,
+                },
+            );
+        }
+
+        let num_groups = keys.values().map(|k| k.groups.len()).max().unwrap_or(0);
+        let group_names = (0..num_groups)
+            .map(|g| defs.group_names.get(&g).cloned().unwrap_or_default())
+            .collect();
+        let mod_names = REAL_MOD_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .chain(defs.virtual_mods.iter().map(|vmod| vmod.name.clone()))
+            .collect();
+
+        Ok(Self {
+            mod_names,
+            virtual_mods,
+            types,
+            keys,
+            num_groups,
+            group_names,
+        })
+    }
+
+    pub fn num_groups(&self) -> usize {
+        self.num_groups
+    }
+
+    pub fn group_name(&self, group: usize) -> Option<&str> {
+        self.group_names.get(group).map(String::as_str)
+    }
+
+    pub fn mod_index(&self, name: &str) -> Option<usize> {
+        self.mod_names.iter().position(|mod_name| mod_name == name)
+    }
+
+    /// Converts a modifier mask as serialized by the compositor to real modifiers.
+    pub fn effective_mods(&self, mask: ModMask) -> ModMask {
+        effective_mask(mask, &self.virtual_mods)
+    }
+
+    pub fn key_num_groups(&self, keycode: u32) -> usize {
+        self.keys.get(&keycode).map_or(0, |key| key.groups.len())
+    }
+
+    pub fn key_repeats(&self, keycode: u32) -> bool {
+        self.keys.get(&keycode).is_some_and(|key| key.repeats)
+    }
+
+    /// Wraps a layout index into the range of groups the keymap defines.
+    pub fn wrap_group(&self, group: i64) -> usize {
+        if self.num_groups == 0 {
+            0
+        } else {
+            group.rem_euclid(self.num_groups as i64) as usize
+        }
+    }
+
+    /// Looks up the keysyms of `keycode` for an effective group and real modifier mask.
+    pub fn resolve(&self, keycode: u32, group: usize, mods: ModMask) -> Option<Resolved<'_>> {
+        let key = self.keys.get(&keycode)?;
+        let count = key.groups.len();
+        if count == 0 {
+            return None;
+        }
+        let group = if group < count {
+            group
+        } else {
+            match key.group_action {
+                GroupAction::Wrap => group % count,
+                GroupAction::Clamp => count - 1,
+                GroupAction::Redirect(target) if target < count => target,
+                GroupAction::Redirect(_) => 0,
+            }
+        };
+        let group = &key.groups[group];
+        let key_type = &self.types[group.key_type];
+        let entry = key_type
+            .entries
+            .iter()
+            .find(|entry| entry.mods == mods & key_type.mods);
+        let level = entry.map_or(0, |entry| entry.level);
+        let preserve = entry.map_or(0, |entry| entry.preserve);
+        let syms = group.levels.get(level).map_or(&[][..], Vec::as_slice);
+        Some(Resolved {
+            syms,
+            consumed: key_type.mods & !preserve,
+        })
+    }
+}
+
+fn effective_mask(mask: ModMask, virtual_mods: &[ModMask]) -> ModMask {
+    virtual_mods
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| mask & (1 << (index + REAL_MOD_NAMES.len())) != 0)
+        .fold(mask & REAL_MODS, |acc, (_, real)| acc | real)
+}
+
+fn compile_type(def: &TypeDef, resolve: &impl Fn(ModMask) -> ModMask) -> KeyType {
+    let mods = resolve(def.mods);
+    let entries = def
+        .map
+        .iter()
+        // An entry that only names unmapped virtual modifiers can never match.
+        .filter(|(entry_mods, _)| *entry_mods == 0 || resolve(*entry_mods) != 0)
+        .map(|(entry_mods, level)| TypeEntry {
+            mods: resolve(*entry_mods),
+            level: *level,
+            preserve: def
+                .preserve
+                .iter()
+                .find(|(preserve_mods, _)| preserve_mods == entry_mods)
+                .map_or(0, |(_, preserve)| resolve(*preserve)),
+        })
+        .collect();
+    KeyType {
+        name: def.name.clone(),
+        mods,
+        entries,
+    }
+}
+
+/// Picks a type for keys that don't name one, the same way xkbcomp does.
+fn automatic_type(levels: &[Vec<Keysym>]) -> &'static str {
+    let sym = |level: usize| {
+        levels
+            .get(level)
+            .and_then(|syms| syms.first().copied())
+            .unwrap_or(Keysym::NO_SYMBOL)
+    };
+    let alphabetic = |lower: usize, upper: usize| sym(lower).is_lower() && sym(upper).is_upper();
+    let keypad = sym(0).is_keypad() || sym(1).is_keypad();
+    match levels.len() {
+        0 | 1 => "ONE_LEVEL",
+        2 if alphabetic(0, 1) => "ALPHABETIC",
+        2 if keypad => "KEYPAD",
+        2 => "TWO_LEVEL",
+        3 | 4 if alphabetic(0, 1) && alphabetic(2, 3) => "FOUR_LEVEL_ALPHABETIC",
+        3 | 4 if alphabetic(0, 1) => "FOUR_LEVEL_SEMIALPHABETIC",
+        3 | 4 if keypad => "FOUR_LEVEL_KEYPAD",
+        3 | 4 => "FOUR_LEVEL",
+        _ if alphabetic(0, 1) => "EIGHT_LEVEL_SEMIALPHABETIC",
+        _ => "EIGHT_LEVEL",
+    }
+}
+
+/// Real modifiers bound to each key by `modifier_map` statements.
+fn resolve_modmap(
+    defs: &Definitions,
+    keys: &[(u32, &KeyDef)],
+    keycode: &impl Fn(&str) -> Option<u32>,
+) -> HashMap<u32, ModMask> {
+    let mut modmaps = HashMap::new();
+    for (index, entry) in &defs.modmap {
+        let code = match entry {
+            ModMapEntry::Key(name) => keycode(name),
+            ModMapEntry::Keysym(sym) => find_key_for_keysym(keys, *sym),
+        };
+        if let Some(code) = code {
+            *modmaps.entry(code).or_insert(0) |= 1 << index;
+        }
+    }
+    modmaps
+}
+
+/// The key that produces `sym` at the lowest group and level, lowest keycode first.
+fn find_key_for_keysym(keys: &[(u32, &KeyDef)], sym: Keysym) -> Option<u32> {
+    let max_groups = keys.iter().map(|(_, k)| k.symbols.len()).max()?;
+    for group in 0..max_groups {
+        let max_levels = keys
+            .iter()
+            .filter_map(|(_, k)| k.symbols.get(group)?.as_ref().map(Vec::len))
+            .max()
+            .unwrap_or(0);
+        for level in 0..max_levels {
+            for (code, key) in keys {
+                let syms = key
+                    .symbols
+                    .get(group)
+                    .and_then(Option::as_ref)
+                    .and_then(|levels| levels.get(level));
+                if syms.is_some_and(|syms| syms.as_slice() == [sym]) {
+                    return Some(*code);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Maps every virtual modifier to real modifiers, either from its declaration or through the
+/// keys that the compat interprets bind it to.
+fn resolve_virtual_mods(
+    defs: &Definitions,
+    keys: &[(u32, &KeyDef)],
+    modmaps: &HashMap<u32, ModMask>,
+) -> Vec<ModMask> {
+    let mut mapping: Vec<ModMask> = defs
+        .virtual_mods
+        .iter()
+        .map(|vmod| vmod.mapping.unwrap_or(0) & REAL_MODS)
+        .collect();
+
+    for (code, key) in keys {
+        let modmap = modmaps.get(code).copied().unwrap_or(0);
+        let vmodmap = match key.vmods {
+            Some(vmods) => vmods,
+            None if key.has_actions => 0,
+            None => interpret_vmods(&defs.interprets, key, modmap),
+        };
+        for (index, vmod) in defs.virtual_mods.iter().enumerate() {
+            if vmod.mapping.is_none() && vmodmap & (1 << (index + REAL_MOD_NAMES.len())) != 0 {
+                mapping[index] |= modmap;
+            }
+        }
+    }
+    mapping
+}
+
+fn interpret_vmods(interprets: &[InterpretDef], key: &KeyDef, modmap: ModMask) -> ModMask {
+    let mut vmodmap = 0;
+    for levels in key.symbols.iter().flatten() {
+        for (level, syms) in levels.iter().enumerate() {
+            if let Some(index) =
+                find_interpret(interprets, syms, level, modmap).and_then(|i| i.virtual_mod)
+            {
+                vmodmap |= 1 << index;
+            }
+        }
+    }
+    vmodmap
+}
+
+/// Whether a key repeats: explicitly, or as decided by the interpret bound to its first level.
+fn key_repeats(interprets: &[InterpretDef], key: &KeyDef, modmap: ModMask) -> bool {
+    if let Some(repeat) = key.repeat {
+        return repeat;
+    }
+    if key.has_actions {
+        return true;
+    }
+    match key
+        .symbols
+        .first()
+        .and_then(Option::as_ref)
+        .and_then(|levels| levels.first())
+    {
+        Some(syms) if !syms.is_empty() => {
+            find_interpret(interprets, syms, 0, modmap).is_none_or(|interpret| interpret.repeat)
+        }
+        _ => false,
+    }
+}
+
+/// The first interpret matching the keysym of a level, in the order the compat section lists
+/// them.
+fn find_interpret<'a>(
+    interprets: &'a [InterpretDef],
+    syms: &[Keysym],
+    level: usize,
+    modmap: ModMask,
+) -> Option<&'a InterpretDef> {
+    let [sym] = syms else {
+        return None;
+    };
+    let level_mods = if level == 0 { modmap } else { 0 };
+    interprets.iter().find(|interpret| {
+        if interpret.keysym.is_some_and(|keysym| keysym != *sym) {
+            return false;
+        }
+        let mods = if interpret.level_one_only {
+            level_mods
+        } else {
+            modmap
+        };
+        let wanted = interpret.mods & REAL_MODS;
+        match interpret.predicate {
+            Predicate::NoneOf => wanted & mods == 0,
+            Predicate::AnyOfOrNone => mods == 0 || wanted & mods != 0,
+            Predicate::AnyOf => wanted & mods != 0,
+            Predicate::AllOf => wanted & mods == wanted,
+            Predicate::Exactly => wanted == mods,
+        }
+    })
+}
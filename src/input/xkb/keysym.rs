@@ -0,0 +1,149 @@
+use crate::input::xkb::{
+    case_table::{TO_LOWER, TO_UPPER},
+    keysym_table::{NAMES, TO_UNICODE},
+};
+use std::fmt;
+
+/// An X11 keysym, the symbolic meaning of a key at a given level.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Keysym(pub u32);
+
+impl Keysym {
+    pub const NO_SYMBOL: Self = Self(0);
+    pub const BACKSPACE: Self = Self(0xff08);
+    pub const RETURN: Self = Self(0xff0d);
+    pub const ESCAPE: Self = Self(0xff1b);
+    pub const DELETE: Self = Self(0xffff);
+    pub const KP_ENTER: Self = Self(0xff8d);
+
+    const KP_SPACE: u32 = 0xff80;
+    const KP_EQUAL: u32 = 0xffbd;
+    const UNICODE_OFFSET: u32 = 0x0100_0000;
+
+    /// Resolves a keysym name as written in keymaps: a known name, `0x` hex, or `U` codepoint.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Ok(index) = NAMES.binary_search_by(|(n, _)| n.cmp(&name)) {
+            return Some(Self(NAMES[index].1));
+        }
+        if let Some(hex) = name.strip_prefix('U')
+            && !hex.is_empty()
+            && hex.len() <= 8
+            && let Ok(cp) = u32::from_str_radix(hex, 16)
+        {
+            return Some(match cp {
+                0x20..=0x7e | 0xa0..=0xff => Self(cp),
+                0x100..=0x10ffff => Self(cp | Self::UNICODE_OFFSET),
+                _ => return None,
+            });
+        }
+        if let Some(hex) = name.strip_prefix("0x")
+            && let Ok(value) = u32::from_str_radix(hex, 16)
+        {
+            return Some(Self(value));
+        }
+        NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| Self(*value))
+    }
+
+    pub fn from_char(c: char) -> Self {
+        let cp = c as u32;
+        match cp {
+            0x20..=0x7e | 0xa0..=0xff => Self(cp),
+            0x08 | 0x09 | 0x0a | 0x0b | 0x0d | 0x1b => Self(cp | 0xff00),
+            0x7f => Self::DELETE,
+            _ => TO_UNICODE
+                .iter()
+                .find(|(_, u)| *u == cp)
+                .map(|(ks, _)| Self(*ks))
+                .unwrap_or(Self(cp | Self::UNICODE_OFFSET)),
+        }
+    }
+
+    /// The character this keysym produces when typed, if any.
+    pub fn to_char(self) -> Option<char> {
+        let ks = self.0;
+        let cp = match ks {
+            0x20..=0x7e | 0xa0..=0xff => ks,
+            // Keypad keysyms mirror their ASCII counterparts.
+            0xff80 => 0x20,
+            0xffaa..=0xffb9 => ks & 0x7f,
+            0xff89 => 0x09,
+            0xff8d => 0x0d,
+            0xffbd => 0x3d,
+            // Control keys with ASCII equivalents.
+            0xff08..=0xff0b | 0xff0d | 0xff1b => ks & 0x7f,
+            0xffff => 0x7f,
+            0x0100_0020..=0x0110_ffff => ks - Self::UNICODE_OFFSET,
+            _ => match TO_UNICODE.binary_search_by(|(k, _)| k.cmp(&ks)) {
+                Ok(index) => TO_UNICODE[index].1,
+                Err(_) => return None,
+            },
+        };
+        char::from_u32(cp)
+    }
+
+    /// The uppercase form as libxkbcommon, and so the compositor, has it: X11's case tables,
+    /// which predate many case pairs Unicode has since added.
+    pub fn to_upper(self) -> Self {
+        match self.0 {
+            // X11 maps these Latin-1 keysyms to code points that are not keysyms with a
+            // character, so Caps Lock makes them type nothing.
+            0xb5 => Self(0x39c),
+            0xdf => Self(0x1e9e),
+            0xff => Self(0x178),
+            // Dotless i, the function sign and the Greek final sigma keep their case.
+            0x2b9 | 0x8f6 | 0x7f3 => self,
+            _ => self.convert_case(TO_UPPER),
+        }
+    }
+
+    pub fn to_lower(self) -> Self {
+        match self.0 {
+            // Nor does the dotted capital I.
+            0x2a9 => self,
+            _ => self.convert_case(TO_LOWER),
+        }
+    }
+
+    fn convert_case(self, table: &[(u32, u32)]) -> Self {
+        let converted = self.to_char().and_then(|c| {
+            let index = table.binary_search_by(|(from, _)| from.cmp(&(c as u32)));
+            index.ok().and_then(|index| char::from_u32(table[index].1))
+        });
+        converted.map_or(self, Self::from_char)
+    }
+
+    pub fn is_lower(self) -> bool {
+        self.to_upper() != self && self.to_lower() == self
+    }
+
+    pub fn is_upper(self) -> bool {
+        self.to_lower() != self && self.to_upper() == self
+    }
+
+    pub fn is_keypad(self) -> bool {
+        (Self::KP_SPACE..=Self::KP_EQUAL).contains(&self.0)
+    }
+
+    pub fn is_modifier(self) -> bool {
+        matches!(self.0, 0xffe1..=0xffee | 0xfe01..=0xfe0f | 0xfe11..=0xfe13 | 0xff7e | 0xff7f)
+    }
+
+    pub fn name(self) -> Option<&'static str> {
+        NAMES
+            .iter()
+            .find(|(_, value)| *value == self.0)
+            .map(|(name, _)| *name)
+    }
+}
+
+impl fmt::Debug for Keysym {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "Keysym({name})"),
+            None => write!(f, "Keysym({:#x})", self.0),
+        }
+    }
+}
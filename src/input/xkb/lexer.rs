@@ -0,0 +1,173 @@
+use crate::input::xkb::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    String(String),
+    Int(i64),
+    KeyName(String),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Semicolon,
+    Comma,
+    Equals,
+    Plus,
+    Minus,
+    Exclamation,
+    Tilde,
+    Dot,
+    Pipe,
+}
+
+/// Splits XKB keymap text into tokens, dropping whitespace and comments.
+pub fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, Error> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        match c {
+            b'\n' => {
+                line += 1;
+                pos += 1;
+            }
+            _ if c.is_ascii_whitespace() => pos += 1,
+            b'#' => pos = skip_line(bytes, pos),
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => pos = skip_line(bytes, pos),
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                let end = text[pos + 2..]
+                    .find("*/")
+                    .ok_or(Error::Syntax(line, "unterminated comment".to_string()))?;
+                line += text[pos..pos + 2 + end].matches('\n').count();
+                pos += end + 4;
+            }
+            b'"' => {
+                let (string, next) = lex_string(bytes, pos + 1, line)?;
+                tokens.push((Token::String(string), line));
+                pos = next;
+            }
+            b'<' => {
+                let end = bytes[pos..]
+                    .iter()
+                    .position(|b| *b == b'>')
+                    .ok_or(Error::Syntax(line, "unterminated key name".to_string()))?;
+                let name = &text[pos + 1..pos + end];
+                tokens.push((Token::KeyName(name.to_string()), line));
+                pos += end + 1;
+            }
+            b'0'..=b'9' => {
+                let start = pos;
+                let value = if c == b'0' && matches!(bytes.get(pos + 1), Some(b'x' | b'X')) {
+                    pos += 2;
+                    while pos < bytes.len() && bytes[pos].is_ascii_hexdigit() {
+                        pos += 1;
+                    }
+                    i64::from_str_radix(&text[start + 2..pos], 16)
+                } else {
+                    while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                    text[start..pos].parse()
+                };
+                let value = value.map_err(|_| Error::Syntax(line, "invalid number".to_string()))?;
+                tokens.push((Token::Int(value), line));
+            }
+            _ if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = pos;
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_')
+                {
+                    pos += 1;
+                }
+                tokens.push((Token::Ident(text[start..pos].to_string()), line));
+            }
+            _ => {
+                let token = match c {
+                    b'{' => Token::LBrace,
+                    b'}' => Token::RBrace,
+                    b'[' => Token::LBracket,
+                    b']' => Token::RBracket,
+                    b'(' => Token::LParen,
+                    b')' => Token::RParen,
+                    b';' => Token::Semicolon,
+                    b',' => Token::Comma,
+                    b'=' => Token::Equals,
+                    b'+' => Token::Plus,
+                    b'-' => Token::Minus,
+                    b'!' => Token::Exclamation,
+                    b'~' => Token::Tilde,
+                    b'.' => Token::Dot,
+                    b'|' => Token::Pipe,
+                    _ => {
+                        return Err(Error::Syntax(
+                            line,
+                            format!("unexpected character {:?}", c as char),
+                        ));
+                    }
+                };
+                tokens.push((token, line));
+                pos += 1;
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn skip_line(bytes: &[u8], pos: usize) -> usize {
+    bytes[pos..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or(bytes.len(), |end| pos + end)
+}
+
+fn lex_string(bytes: &[u8], mut pos: usize, line: usize) -> Result<(String, usize), Error> {
+    let mut out = Vec::new();
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'"' => {
+                let string = String::from_utf8(out)
+                    .map_err(|_| Error::Syntax(line, "string is not UTF-8".to_string()))?;
+                return Ok((string, pos + 1));
+            }
+            b'\\' if pos + 1 < bytes.len() => {
+                pos += 1;
+                match bytes[pos] {
+                    b'n' => out.push(b'\n'),
+                    b't' => out.push(b'\t'),
+                    b'r' => out.push(b'\r'),
+                    b'b' => out.push(0x08),
+                    b'f' => out.push(0x0c),
+                    b'v' => out.push(0x0b),
+                    b'e' => out.push(0x1b),
+                    b'0'..=b'7' => {
+                        let start = pos;
+                        while pos < bytes.len()
+                            && pos - start < 3
+                            && (b'0'..=b'7').contains(&bytes[pos])
+                        {
+                            pos += 1;
+                        }
+                        let octal = std::str::from_utf8(&bytes[start..pos]).unwrap_or("0");
+                        out.push(u8::from_str_radix(octal, 8).unwrap_or(0));
+                        continue;
+                    }
+                    other => out.push(other),
+                }
+                pos += 1;
+            }
+            b'\n' => return Err(Error::Syntax(line, "unterminated string".to_string())),
+            other => {
+                out.push(other);
+                pos += 1;
+            }
+        }
+    }
+    Err(Error::Syntax(line, "unterminated string".to_string()))
+}
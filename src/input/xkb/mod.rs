@@ -0,0 +1,33 @@
+//! A self-contained implementation of the parts of XKB a client needs: compiling the keymap text
+//! the compositor sends and translating keys to keysyms and text for the current modifiers.
+
+mod case_table;
+mod keymap;
+mod keysym;
+mod keysym_table;
+mod lexer;
+mod parser;
+mod state;
+
+use std::fmt;
+
+pub use keymap::Keymap;
+pub use keysym::Keysym;
+pub use state::State;
+
+#[derive(Debug)]
+pub enum Error {
+    Syntax(usize, String),
+    Incomplete(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(line, message) => {
+                write!(f, "Keymap syntax error on line {line}: {message}")
+            }
+            Self::Incomplete(section) => write!(f, "Keymap is missing {section}"),
+        }
+    }
+}
@@ -0,0 +1,736 @@
+use crate::input::xkb::{
+    Error,
+    keysym::Keysym,
+    lexer::{Token, tokenize},
+};
+use std::collections::HashMap;
+
+pub const REAL_MOD_NAMES: [&str; 8] = [
+    "Shift", "Lock", "Control", "Mod1", "Mod2", "Mod3", "Mod4", "Mod5",
+];
+
+/// Mod masks in the parser use bit `i` for modifier index `i`: the eight real modifiers first,
+/// then virtual modifiers in declaration order.
+pub type ModMask = u32;
+
+#[derive(Debug)]
+pub struct VirtualModDef {
+    pub name: String,
+    pub mapping: Option<ModMask>,
+}
+
+#[derive(Debug, Default)]
+pub struct TypeDef {
+    pub name: String,
+    pub mods: ModMask,
+    pub map: Vec<(ModMask, usize)>,
+    pub preserve: Vec<(ModMask, ModMask)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Predicate {
+    NoneOf,
+    AnyOfOrNone,
+    AnyOf,
+    AllOf,
+    Exactly,
+}
+
+#[derive(Debug)]
+pub struct InterpretDef {
+    /// `None` matches any keysym.
+    pub keysym: Option<Keysym>,
+    pub predicate: Predicate,
+    pub mods: ModMask,
+    pub virtual_mod: Option<usize>,
+    pub level_one_only: bool,
+    pub repeat: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupAction {
+    #[default]
+    Wrap,
+    Clamp,
+    Redirect(usize),
+}
+
+#[derive(Debug, Default)]
+pub struct KeyDef {
+    pub name: String,
+    pub default_type: Option<String>,
+    pub types: HashMap<usize, String>,
+    pub symbols: Vec<Option<Vec<Vec<Keysym>>>>,
+    pub repeat: Option<bool>,
+    pub vmods: Option<ModMask>,
+    pub has_actions: bool,
+    pub group_action: GroupAction,
+}
+
+#[derive(Debug)]
+pub enum ModMapEntry {
+    Key(String),
+    Keysym(Keysym),
+}
+
+/// Everything read from the keymap text, before names and virtual modifiers are resolved.
+#[derive(Debug, Default)]
+pub struct Definitions {
+    pub keycodes: HashMap<String, u32>,
+    pub aliases: HashMap<String, String>,
+    pub virtual_mods: Vec<VirtualModDef>,
+    pub types: Vec<TypeDef>,
+    pub interprets: Vec<InterpretDef>,
+    pub keys: Vec<KeyDef>,
+    pub modmap: Vec<(usize, ModMapEntry)>,
+    pub group_names: HashMap<usize, String>,
+}
+
+pub struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    defs: Definitions,
+    default_level_one_only: bool,
+    default_repeat: bool,
+}
+
+impl Parser {
+    pub fn parse(text: &str) -> Result<Definitions, Error> {
+        let mut parser = Self {
+            tokens: tokenize(text)?,
+            pos: 0,
+            defs: Definitions::default(),
+            default_level_one_only: false,
+            default_repeat: false,
+        };
+        parser.parse_keymap()?;
+        Ok(parser.defs)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_ident(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => Some(ident),
+            _ => None,
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos.min(self.tokens.len().saturating_sub(1)))
+            .map_or(0, |(_, line)| *line)
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::Syntax(self.line(), message.to_string())
+    }
+
+    fn next(&mut self) -> Result<Token, Error> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|(token, _)| token.clone())
+            .ok_or(Error::Syntax(
+                self.line(),
+                "unexpected end of keymap".to_string(),
+            ))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), Error> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {token:?}, found {:?}", self.peek())))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, Error> {
+        match self.next()? {
+            Token::Ident(ident) => Ok(ident),
+            other => Err(self.error(&format!("expected identifier, found {other:?}"))),
+        }
+    }
+
+    fn expect_string(&mut self) -> Result<String, Error> {
+        match self.next()? {
+            Token::String(string) => Ok(string),
+            other => Err(self.error(&format!("expected string, found {other:?}"))),
+        }
+    }
+
+    /// Skips to the end of the current statement, leaving a closing brace of the enclosing block
+    /// in place.
+    fn skip_statement(&mut self) -> Result<(), Error> {
+        let mut depth = 0usize;
+        loop {
+            match self.peek() {
+                None => return Ok(()),
+                Some(Token::RBrace | Token::RBracket | Token::RParen) if depth == 0 => {
+                    return Ok(());
+                }
+                Some(Token::Semicolon) if depth == 0 => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(Token::LBrace | Token::LBracket | Token::LParen) => depth += 1,
+                Some(Token::RBrace | Token::RBracket | Token::RParen) => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skips a value inside a key body, up to the next top-level `,` or the closing brace.
+    fn skip_value(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Comma | Token::RBrace | Token::Semicolon if depth == 0 => return,
+                Token::LBrace | Token::LBracket | Token::LParen => depth += 1,
+                Token::RBrace | Token::RBracket | Token::RParen => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn parse_keymap(&mut self) -> Result<(), Error> {
+        if self.peek_ident() == Some("xkb_keymap") {
+            self.pos += 1;
+            self.skip_header()?;
+            while !self.eat(&Token::RBrace) {
+                self.parse_section()?;
+            }
+            self.eat(&Token::Semicolon);
+        } else {
+            while self.peek().is_some() {
+                self.parse_section()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips section flags and the optional name up to and including the opening brace.
+    fn skip_header(&mut self) -> Result<(), Error> {
+        while matches!(self.peek(), Some(Token::Ident(_) | Token::String(_))) {
+            self.pos += 1;
+        }
+        self.expect(&Token::LBrace)
+    }
+
+    fn parse_section(&mut self) -> Result<(), Error> {
+        const FLAGS: [&str; 8] = [
+            "default",
+            "partial",
+            "hidden",
+            "alphanumeric_keys",
+            "modifier_keys",
+            "keypad_keys",
+            "function_keys",
+            "alternate_group",
+        ];
+        let mut kind = self.expect_ident()?;
+        while FLAGS.contains(&kind.as_str()) {
+            kind = self.expect_ident()?;
+        }
+        self.skip_header()?;
+        loop {
+            if self.eat(&Token::RBrace) {
+                break;
+            }
+            if self.peek().is_none() {
+                return Err(self.error("unterminated section"));
+            }
+            match kind.as_str() {
+                "xkb_keycodes" => self.parse_keycodes_statement()?,
+                "xkb_types" => self.parse_types_statement()?,
+                "xkb_compatibility" | "xkb_compat" | "xkb_compatibility_map" => {
+                    self.parse_compat_statement()?
+                }
+                "xkb_symbols" => self.parse_symbols_statement()?,
+                _ => self.skip_statement()?,
+            }
+            if self.peek() == Some(&Token::RBracket) || self.peek() == Some(&Token::RParen) {
+                return Err(self.error("unbalanced brackets"));
+            }
+        }
+        self.eat(&Token::Semicolon);
+        Ok(())
+    }
+
+    fn parse_keycodes_statement(&mut self) -> Result<(), Error> {
+        match self.peek() {
+            Some(Token::KeyName(_)) => {
+                let Token::KeyName(name) = self.next()? else {
+                    unreachable!()
+                };
+                self.expect(&Token::Equals)?;
+                match self.next()? {
+                    Token::Int(code) if (0..=u32::MAX as i64).contains(&code) => {
+                        self.defs.keycodes.insert(name, code as u32);
+                    }
+                    _ => return Err(self.error("expected keycode")),
+                }
+                self.expect(&Token::Semicolon)
+            }
+            Some(Token::Ident(ident)) if ident == "alias" => {
+                self.pos += 1;
+                let alias = self.expect_keyname()?;
+                self.expect(&Token::Equals)?;
+                let real = self.expect_keyname()?;
+                self.defs.aliases.insert(alias, real);
+                self.expect(&Token::Semicolon)
+            }
+            _ => self.skip_statement(),
+        }
+    }
+
+    fn expect_keyname(&mut self) -> Result<String, Error> {
+        match self.next()? {
+            Token::KeyName(name) => Ok(name),
+            other => Err(self.error(&format!("expected key name, found {other:?}"))),
+        }
+    }
+
+    fn parse_virtual_modifiers(&mut self) -> Result<(), Error> {
+        self.pos += 1;
+        loop {
+            let name = self.expect_ident()?;
+            let mapping = if self.eat(&Token::Equals) {
+                Some(self.parse_mod_mask()?)
+            } else {
+                None
+            };
+            match self.mod_index(&name) {
+                Some(index) if index >= REAL_MOD_NAMES.len() => {
+                    let vmod = &mut self.defs.virtual_mods[index - REAL_MOD_NAMES.len()];
+                    if mapping.is_some() {
+                        vmod.mapping = mapping;
+                    }
+                }
+                Some(_) => return Err(self.error("cannot redeclare a real modifier")),
+                None => {
+                    if self.defs.virtual_mods.len() + REAL_MOD_NAMES.len() >= 32 {
+                        return Err(self.error("too many virtual modifiers"));
+                    }
+                    self.defs.virtual_mods.push(VirtualModDef { name, mapping });
+                }
+            }
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::Semicolon)
+    }
+
+    fn mod_index(&self, name: &str) -> Option<usize> {
+        REAL_MOD_NAMES
+            .iter()
+            .position(|real| real.eq_ignore_ascii_case(name))
+            .or_else(|| {
+                self.defs
+                    .virtual_mods
+                    .iter()
+                    .position(|vmod| vmod.name.eq_ignore_ascii_case(name))
+                    .map(|index| index + REAL_MOD_NAMES.len())
+            })
+    }
+
+    /// Parses `Shift+Lock`, `none`, `all` or a numeric mask.
+    fn parse_mod_mask(&mut self) -> Result<ModMask, Error> {
+        let mut mask = 0;
+        loop {
+            match self.next()? {
+                Token::Int(value) => mask |= value as ModMask,
+                Token::Ident(name) if name.eq_ignore_ascii_case("none") => {}
+                Token::Ident(name) if name.eq_ignore_ascii_case("all") => mask = ModMask::MAX,
+                Token::Ident(name) => match self.mod_index(&name) {
+                    Some(index) => mask |= 1 << index,
+                    None => tracing::warn!("Ignoring unknown modifier {name} in keymap"),
+                },
+                other => return Err(self.error(&format!("expected modifier, found {other:?}"))),
+            }
+            if !(self.eat(&Token::Plus) || self.eat(&Token::Pipe)) {
+                return Ok(mask);
+            }
+        }
+    }
+
+    fn parse_bool(&mut self) -> Result<bool, Error> {
+        match self.expect_ident()?.to_ascii_lowercase().as_str() {
+            "yes" | "true" | "on" => Ok(true),
+            "no" | "false" | "off" => Ok(false),
+            other => Err(self.error(&format!("expected boolean, found {other}"))),
+        }
+    }
+
+    /// Parses `Level3` or `3` into a zero based level index.
+    fn parse_level(&mut self) -> Result<usize, Error> {
+        let level = match self.next()? {
+            Token::Int(level) => level,
+            Token::Ident(name) if name.len() > 5 && name[..5].eq_ignore_ascii_case("level") => {
+                name[5..].parse().map_err(|_| self.error("invalid level"))?
+            }
+            other => return Err(self.error(&format!("expected level, found {other:?}"))),
+        };
+        if !(1..=256).contains(&level) {
+            return Err(self.error("level out of range"));
+        }
+        Ok(level as usize - 1)
+    }
+
+    /// Parses `Group2` or `2` into a zero based group index.
+    fn parse_group(&mut self) -> Result<usize, Error> {
+        let group = match self.next()? {
+            Token::Int(group) => group,
+            Token::Ident(name) if name.len() > 5 && name[..5].eq_ignore_ascii_case("group") => {
+                name[5..].parse().map_err(|_| self.error("invalid group"))?
+            }
+            other => return Err(self.error(&format!("expected group, found {other:?}"))),
+        };
+        if !(1..=32).contains(&group) {
+            return Err(self.error("group out of range"));
+        }
+        Ok(group as usize - 1)
+    }
+
+    fn parse_types_statement(&mut self) -> Result<(), Error> {
+        match self.peek_ident() {
+            Some("virtual_modifiers") => self.parse_virtual_modifiers(),
+            Some("type") if self.tokens.get(self.pos + 1).map(|(t, _)| t) != Some(&Token::Dot) => {
+                self.pos += 1;
+                let mut key_type = TypeDef {
+                    name: self.expect_string()?,
+                    ..Default::default()
+                };
+                self.expect(&Token::LBrace)?;
+                while !self.eat(&Token::RBrace) {
+                    let field = self.expect_ident()?;
+                    match field.to_ascii_lowercase().as_str() {
+                        "modifiers" => {
+                            self.expect(&Token::Equals)?;
+                            key_type.mods = self.parse_mod_mask()?;
+                        }
+                        "map" => {
+                            self.expect(&Token::LBracket)?;
+                            let mods = self.parse_mod_mask()?;
+                            self.expect(&Token::RBracket)?;
+                            self.expect(&Token::Equals)?;
+                            let level = self.parse_level()?;
+                            key_type.map.push((mods, level));
+                        }
+                        "preserve" => {
+                            self.expect(&Token::LBracket)?;
+                            let mods = self.parse_mod_mask()?;
+                            self.expect(&Token::RBracket)?;
+                            self.expect(&Token::Equals)?;
+                            let preserve = self.parse_mod_mask()?;
+                            key_type.preserve.push((mods, preserve));
+                        }
+                        _ => {
+                            self.skip_statement()?;
+                            continue;
+                        }
+                    }
+                    self.expect(&Token::Semicolon)?;
+                }
+                self.eat(&Token::Semicolon);
+                self.defs.types.push(key_type);
+                Ok(())
+            }
+            _ => self.skip_statement(),
+        }
+    }
+
+    fn parse_compat_statement(&mut self) -> Result<(), Error> {
+        match self.peek_ident() {
+            Some("virtual_modifiers") => self.parse_virtual_modifiers(),
+            Some("interpret") => {
+                self.pos += 1;
+                if self.eat(&Token::Dot) {
+                    let field = self.expect_ident()?;
+                    match field.to_ascii_lowercase().as_str() {
+                        "usemodmapmods" | "usemodmap" => {
+                            self.expect(&Token::Equals)?;
+                            let value = self.expect_ident()?;
+                            self.default_level_one_only = is_level_one(&value);
+                        }
+                        "repeat" => {
+                            self.expect(&Token::Equals)?;
+                            self.default_repeat = self.parse_bool()?;
+                        }
+                        _ => return self.skip_statement(),
+                    }
+                    return self.expect(&Token::Semicolon);
+                }
+                let interpret = self.parse_interpret()?;
+                self.defs.interprets.push(interpret);
+                Ok(())
+            }
+            _ => self.skip_statement(),
+        }
+    }
+
+    fn parse_interpret(&mut self) -> Result<InterpretDef, Error> {
+        let mut interpret = InterpretDef {
+            keysym: None,
+            predicate: Predicate::AnyOfOrNone,
+            mods: ModMask::MAX,
+            virtual_mod: None,
+            level_one_only: self.default_level_one_only,
+            repeat: self.default_repeat,
+        };
+
+        let keysym = self.next()?;
+        interpret.keysym = match &keysym {
+            Token::Ident(name) if name.eq_ignore_ascii_case("any") => None,
+            Token::Ident(_) | Token::Int(_) => Some(self.keysym_from_token(keysym)?),
+            other => return Err(self.error(&format!("expected keysym, found {other:?}"))),
+        };
+
+        if self.eat(&Token::Plus) {
+            let name = self.expect_ident()?;
+            let predicate = match name.to_ascii_lowercase().as_str() {
+                "noneof" => Some(Predicate::NoneOf),
+                "anyofornone" => Some(Predicate::AnyOfOrNone),
+                "anyof" => Some(Predicate::AnyOf),
+                "allof" => Some(Predicate::AllOf),
+                "exactly" => Some(Predicate::Exactly),
+                "any" => {
+                    interpret.predicate = Predicate::AnyOf;
+                    interpret.mods = ModMask::MAX;
+                    None
+                }
+                _ => {
+                    self.pos -= 1;
+                    interpret.predicate = Predicate::Exactly;
+                    interpret.mods = self.parse_mod_mask()?;
+                    None
+                }
+            };
+            if let Some(predicate) = predicate {
+                interpret.predicate = predicate;
+                self.expect(&Token::LParen)?;
+                interpret.mods = self.parse_mod_mask()?;
+                self.expect(&Token::RParen)?;
+            }
+        }
+
+        self.expect(&Token::LBrace)?;
+        while !self.eat(&Token::RBrace) {
+            let field = self.expect_ident()?;
+            match field.to_ascii_lowercase().as_str() {
+                "virtualmodifier" | "virtualmod" => {
+                    self.expect(&Token::Equals)?;
+                    let name = self.expect_ident()?;
+                    interpret.virtual_mod = self.mod_index(&name);
+                    self.expect(&Token::Semicolon)?;
+                }
+                "usemodmapmods" | "usemodmap" => {
+                    self.expect(&Token::Equals)?;
+                    let value = self.expect_ident()?;
+                    interpret.level_one_only = is_level_one(&value);
+                    self.expect(&Token::Semicolon)?;
+                }
+                "repeat" => {
+                    self.expect(&Token::Equals)?;
+                    interpret.repeat = self.parse_bool()?;
+                    self.expect(&Token::Semicolon)?;
+                }
+                _ => self.skip_statement()?,
+            }
+        }
+        self.eat(&Token::Semicolon);
+        Ok(interpret)
+    }
+
+    fn keysym_from_token(&self, token: Token) -> Result<Keysym, Error> {
+        match token {
+            // Bare digits name the digit keysyms, larger numbers are raw keysym values.
+            Token::Int(digit @ 0..=9) => Ok(Keysym(b'0' as u32 + digit as u32)),
+            Token::Int(value) => Ok(Keysym(value as u32)),
+            Token::Ident(name) => Ok(Keysym::from_name(&name).unwrap_or_else(|| {
+                tracing::warn!("Unknown keysym {name} in keymap");
+                Keysym::NO_SYMBOL
+            })),
+            other => Err(self.error(&format!("expected keysym, found {other:?}"))),
+        }
+    }
+
+    fn parse_symbols_statement(&mut self) -> Result<(), Error> {
+        match self.peek_ident() {
+            Some("name")
+                if self.tokens.get(self.pos + 1).map(|(t, _)| t) == Some(&Token::LBracket) =>
+            {
+                self.pos += 1;
+                self.expect(&Token::LBracket)?;
+                let group = self.parse_group()?;
+                self.expect(&Token::RBracket)?;
+                self.expect(&Token::Equals)?;
+                let name = self.expect_string()?;
+                self.defs.group_names.insert(group, name);
+                self.expect(&Token::Semicolon)
+            }
+            Some("key") => {
+                self.pos += 1;
+                let key = self.parse_key()?;
+                self.defs.keys.push(key);
+                Ok(())
+            }
+            Some(ident)
+                if ["modifier_map", "modmap", "mod_map"]
+                    .iter()
+                    .any(|m| m.eq_ignore_ascii_case(ident)) =>
+            {
+                self.pos += 1;
+                let name = self.expect_ident()?;
+                let index = REAL_MOD_NAMES
+                    .iter()
+                    .position(|real| real.eq_ignore_ascii_case(&name))
+                    .ok_or_else(|| self.error("modifier_map must name a real modifier"))?;
+                self.expect(&Token::LBrace)?;
+                while !self.eat(&Token::RBrace) {
+                    let entry = match self.next()? {
+                        Token::KeyName(key) => ModMapEntry::Key(key),
+                        token => ModMapEntry::Keysym(self.keysym_from_token(token)?),
+                    };
+                    self.defs.modmap.push((index, entry));
+                    self.eat(&Token::Comma);
+                }
+                self.expect(&Token::Semicolon)
+            }
+            _ => self.skip_statement(),
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<KeyDef, Error> {
+        let mut key = KeyDef {
+            name: self.expect_keyname()?,
+            ..Default::default()
+        };
+        self.expect(&Token::LBrace)?;
+        let mut next_group = 0;
+
+        while !self.eat(&Token::RBrace) {
+            if self.peek() == Some(&Token::LBracket) {
+                let levels = self.parse_symbol_list()?;
+                set_group(&mut key.symbols, next_group, levels);
+                next_group += 1;
+            } else {
+                let field = self.expect_ident()?.to_ascii_lowercase();
+                let group = if self.eat(&Token::LBracket) {
+                    let group = self.parse_group()?;
+                    self.expect(&Token::RBracket)?;
+                    Some(group)
+                } else {
+                    None
+                };
+                let has_value = self.eat(&Token::Equals);
+                match field.as_str() {
+                    "type" if has_value => {
+                        let name = self.expect_string()?;
+                        match group {
+                            Some(group) => {
+                                key.types.insert(group, name);
+                            }
+                            None => key.default_type = Some(name),
+                        }
+                    }
+                    "symbols" if has_value => {
+                        let group = group.unwrap_or(next_group);
+                        let levels = self.parse_symbol_list()?;
+                        set_group(&mut key.symbols, group, levels);
+                        next_group = group + 1;
+                    }
+                    "actions" => {
+                        key.has_actions = true;
+                        self.skip_value();
+                    }
+                    "repeat" | "repeats" | "autorepeat" if has_value => {
+                        key.repeat = match self.peek_ident() {
+                            Some(value) if value.eq_ignore_ascii_case("default") => {
+                                self.pos += 1;
+                                None
+                            }
+                            _ => Some(self.parse_bool()?),
+                        };
+                    }
+                    "vmods" | "virtualmods" | "virtualmodifiers" if has_value => {
+                        key.vmods = Some(self.parse_mod_mask()?);
+                    }
+                    "groupswrap" | "wrapgroups" => {
+                        if has_value {
+                            self.skip_value();
+                        }
+                        key.group_action = GroupAction::Wrap;
+                    }
+                    "groupsclamp" | "clampgroups" => {
+                        if has_value {
+                            self.skip_value();
+                        }
+                        key.group_action = GroupAction::Clamp;
+                    }
+                    "groupsredirect" | "redirectgroups" if has_value => {
+                        key.group_action = GroupAction::Redirect(self.parse_group()?);
+                    }
+                    _ => self.skip_value(),
+                }
+            }
+            if !self.eat(&Token::Comma) && self.peek() != Some(&Token::RBrace) {
+                return Err(self.error("expected , or } in key definition"));
+            }
+        }
+        self.eat(&Token::Semicolon);
+        Ok(key)
+    }
+
+    /// Parses `[ a, A, { b, c } ]` into the keysyms of each level.
+    fn parse_symbol_list(&mut self) -> Result<Vec<Vec<Keysym>>, Error> {
+        self.expect(&Token::LBracket)?;
+        let mut levels = Vec::new();
+        while !self.eat(&Token::RBracket) {
+            let mut syms = Vec::new();
+            if self.eat(&Token::LBrace) {
+                while !self.eat(&Token::RBrace) {
+                    let token = self.next()?;
+                    syms.push(self.keysym_from_token(token)?);
+                    self.eat(&Token::Comma);
+                }
+            } else {
+                let token = self.next()?;
+                syms.push(self.keysym_from_token(token)?);
+            }
+            syms.retain(|sym| *sym != Keysym::NO_SYMBOL);
+            levels.push(syms);
+            if !self.eat(&Token::Comma) && self.peek() != Some(&Token::RBracket) {
+                return Err(self.error("expected , or ] in symbol list"));
+            }
+        }
+        Ok(levels)
+    }
+}
+
+fn set_group(symbols: &mut Vec<Option<Vec<Vec<Keysym>>>>, group: usize, levels: Vec<Vec<Keysym>>) {
+    if symbols.len() <= group {
+        symbols.resize(group + 1, None);
+    }
+    symbols[group] = Some(levels);
+}
+
+fn is_level_one(value: &str) -> bool {
+    value.eq_ignore_ascii_case("level1") || value.eq_ignore_ascii_case("levelone")
+}
@@ -0,0 +1,250 @@
+use crate::input::xkb::{
+    keymap::{Keymap, Resolved},
+    keysym::Keysym,
+    parser::ModMask,
+};
+
+const LOCK: ModMask = 1 << 1;
+const CONTROL: ModMask = 1 << 2;
+
+/// Offset between evdev scancodes sent by `wl_keyboard.key` and XKB keycodes.
+const EVDEV_OFFSET: u32 = 8;
+
+/// Keyboard state mirrored from the compositor's `wl_keyboard.modifiers` events.
+#[derive(Debug)]
+pub struct State {
+    keymap: Keymap,
+    mods: ModMask,
+    group: usize,
+}
+
+impl State {
+    pub fn new(keymap: Keymap) -> Self {
+        Self {
+            keymap,
+            mods: 0,
+            group: 0,
+        }
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    pub fn update_mask(&mut self, depressed: u32, latched: u32, locked: u32, group: u32) {
+        self.mods = self.keymap.effective_mods(depressed | latched | locked);
+        self.group = self.keymap.wrap_group(group as i32 as i64);
+    }
+
+    pub fn group(&self) -> usize {
+        self.group
+    }
+
+    /// Whether a modifier such as `"Lock"` or `"NumLock"` is currently active.
+    pub fn mod_is_active(&self, name: &str) -> bool {
+        self.keymap
+            .mod_index(name)
+            .is_some_and(|index| self.keymap.effective_mods(1 << index) & self.mods != 0)
+    }
+
+    pub fn key_repeats(&self, key: u32) -> bool {
+        self.keymap.key_repeats(key + EVDEV_OFFSET)
+    }
+
+    /// The single keysym produced by the evdev `key`, with Caps Lock applied when the key type
+    /// doesn't consume Lock. Keys producing several keysyms return `NO_SYMBOL`.
+    pub fn key_one_sym(&self, key: u32) -> Keysym {
+        let Some(resolved) = self
+            .keymap
+            .resolve(key + EVDEV_OFFSET, self.group, self.mods)
+        else {
+            return Keysym::NO_SYMBOL;
+        };
+        match resolved.syms {
+            [sym] if self.mods & LOCK & !resolved.consumed != 0 => sym.to_upper(),
+            [sym] => *sym,
+            _ => Keysym::NO_SYMBOL,
+        }
+    }
+
+    /// The text produced by the evdev `key`, with Control turning ASCII into control characters.
+    /// It is yielded rather than collected, as it may be part of a password.
+    pub fn key_chars(&self, key: u32) -> impl Iterator<Item = char> + '_ {
+        let keycode = key + EVDEV_OFFSET;
+        let resolved = self.keymap.resolve(keycode, self.group, self.mods);
+        let (single, several) = match resolved {
+            Some(Resolved {
+                syms: [_],
+                consumed,
+            }) => {
+                let control = self.mods & CONTROL & !consumed != 0;
+                let mut sym = self.key_one_sym(key);
+                // Control shortcuts on non-Latin layouts use the first Latin layout of the key.
+                if control && sym.0 > 0x7f {
+                    sym = (0..self.keymap.key_num_groups(keycode))
+                        .filter_map(|group| self.keymap.resolve(keycode, group, self.mods))
+                        .find_map(|resolved| match resolved.syms {
+                            [latin] if latin.0 <= 0x7f => Some(*latin),
+                            _ => None,
+                        })
+                        .unwrap_or(sym);
+                }
+                // Control with 2, space or @ makes NUL, which is no text.
+                let c = sym
+                    .to_char()
+                    .map(|c| if control { to_control(c) } else { c })
+                    .filter(|&c| c != '\0');
+                (c, &[][..])
+            }
+            Some(Resolved { syms, .. }) => (None, syms),
+            None => (None, &[][..]),
+        };
+        single
+            .into_iter()
+            .chain(several.iter().filter_map(|sym| sym.to_char()))
+    }
+}
+
+fn to_control(c: char) -> char {
+    let byte = match c {
+        '@'..='~' | ' ' => c as u8 & 0x1f,
+        '2' => 0,
+        '3'..='7' => c as u8 - b'3' + 0x1b,
+        '8' => 0x7f,
+        '/' => b'_' & 0x1f,
+        _ => return c,
+    };
+    byte as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Evdev codes of the keys used below.
+    const KEY_2: u32 = 3;
+    const KEY_7: u32 = 8;
+    const KEY_MINUS: u32 = 12;
+    const KEY_Q: u32 = 16;
+    const KEY_E: u32 = 18;
+    const KEY_Y: u32 = 21;
+    const KEY_I: u32 = 23;
+    const KEY_LEFTBRACE: u32 = 26;
+    const KEY_A: u32 = 30;
+    const KEY_LEFTSHIFT: u32 = 42;
+    const KEY_C: u32 = 46;
+    const KEY_M: u32 = 50;
+    const KEY_SPACE: u32 = 57;
+    const KEY_KP1: u32 = 79;
+
+    const SHIFT: u32 = 1 << 0;
+    const CAPS: u32 = 1 << 1;
+    const CTRL: u32 = 1 << 2;
+    const NUM_LOCK: u32 = 1 << 4;
+    const ALT_GR: u32 = 1 << 7;
+
+    fn state(keymap: &str) -> State {
+        State::new(Keymap::from_text(keymap).expect("keymap does not compile"))
+    }
+
+    fn us() -> State {
+        state(include_str!("../../../testdata/keymaps/us.xkb"))
+    }
+
+    fn de() -> State {
+        state(include_str!("../../../testdata/keymaps/de.xkb"))
+    }
+
+    fn gr() -> State {
+        state(include_str!("../../../testdata/keymaps/gr.xkb"))
+    }
+
+    fn us_ru() -> State {
+        state(include_str!("../../../testdata/keymaps/us-ru.xkb"))
+    }
+
+    /// The text `key` types with modifiers held down and locked.
+    fn typed(state: &mut State, held: u32, locked: u32, key: u32) -> String {
+        state.update_mask(held, 0, locked, 0);
+        state.key_chars(key).collect()
+    }
+
+    #[test]
+    fn shift_and_caps_lock() {
+        let mut us = us();
+        assert_eq!(typed(&mut us, 0, 0, KEY_A), "a");
+        assert_eq!(typed(&mut us, SHIFT, 0, KEY_A), "A");
+        assert_eq!(typed(&mut us, 0, CAPS, KEY_A), "A");
+        assert_eq!(typed(&mut us, SHIFT, CAPS, KEY_A), "a");
+        assert_eq!(typed(&mut us, 0, CAPS, KEY_2), "2");
+        assert_eq!(typed(&mut us, SHIFT, CAPS, KEY_2), "@");
+        assert!(us.mod_is_active("Lock"));
+        assert!(!us.mod_is_active("NumLock"));
+    }
+
+    #[test]
+    fn alt_gr_levels() {
+        let mut de = de();
+        assert_eq!(typed(&mut de, 0, 0, KEY_Y), "z");
+        assert_eq!(typed(&mut de, 0, 0, KEY_MINUS), "ß");
+        assert_eq!(typed(&mut de, ALT_GR, 0, KEY_Q), "@");
+        assert_eq!(typed(&mut de, ALT_GR, 0, KEY_E), "€");
+        assert_eq!(typed(&mut de, ALT_GR, 0, KEY_M), "µ");
+        assert_eq!(typed(&mut de, SHIFT | ALT_GR, 0, KEY_I), "ı");
+    }
+
+    /// Caps Lock follows the case tables libxkbcommon, and so the compositor, uses, so that a
+    /// password types the same here as when it was set.
+    #[test]
+    fn caps_lock_with_alt_gr() {
+        let mut de = de();
+        // X11 turns µ into a keysym that types nothing.
+        assert_eq!(typed(&mut de, ALT_GR, CAPS, KEY_M), "");
+        assert_eq!(typed(&mut de, SHIFT | ALT_GR, CAPS, KEY_I), "ı");
+        let mut gr = gr();
+        assert_eq!(typed(&mut gr, 0, 0, KEY_A), "α");
+        assert_eq!(typed(&mut gr, 0, CAPS, KEY_A), "Α");
+        // ϗ and ͻ have no capital form in X11, which also decides the key types.
+        assert_eq!(typed(&mut gr, ALT_GR, CAPS, KEY_7), "ϗ");
+        assert_eq!(typed(&mut gr, ALT_GR, CAPS, KEY_I), "ͻ");
+        assert_eq!(typed(&mut gr, SHIFT | ALT_GR, CAPS, KEY_I), "Ͻ");
+    }
+
+    #[test]
+    fn control_characters() {
+        let mut us = us();
+        assert_eq!(typed(&mut us, CTRL, 0, KEY_A), "\u{1}");
+        assert_eq!(typed(&mut us, CTRL, 0, KEY_LEFTBRACE), "\u{1b}");
+        // NUL is no text.
+        assert_eq!(typed(&mut us, CTRL, 0, KEY_2), "");
+        assert_eq!(typed(&mut us, CTRL, 0, KEY_SPACE), "");
+    }
+
+    #[test]
+    fn layout_groups() {
+        let mut us_ru = us_ru();
+        assert_eq!(typed(&mut us_ru, 0, 0, KEY_A), "a");
+        us_ru.update_mask(0, 0, 0, 1);
+        assert_eq!(us_ru.key_chars(KEY_A).collect::<String>(), "ф");
+        // Shortcuts take the Latin layout of the key.
+        us_ru.update_mask(CTRL, 0, 0, 1);
+        assert_eq!(us_ru.key_chars(KEY_C).collect::<String>(), "\u{3}");
+        // Groups past the last wrap around.
+        us_ru.update_mask(0, 0, 0, 2);
+        assert_eq!(us_ru.group(), 0);
+    }
+
+    #[test]
+    fn keypad_follows_num_lock() {
+        let mut us = us();
+        assert_eq!(typed(&mut us, 0, NUM_LOCK, KEY_KP1), "1");
+        assert_eq!(typed(&mut us, 0, 0, KEY_KP1), "");
+    }
+
+    #[test]
+    fn modifiers_do_not_repeat() {
+        let us = us();
+        assert!(us.key_repeats(KEY_A));
+        assert!(!us.key_repeats(KEY_LEFTSHIFT));
+    }
+}
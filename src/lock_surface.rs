@@ -0,0 +1,374 @@
+use crate::{
+    background::Background,
+    indicator::Indicator,
+    render::Canvas,
+    wayland::{
+        Format, FractionalScale, FractionalScaleEvent, Object, SCALE_DENOMINATOR,
+        SessionLockSurface, SurfaceEvent, Transform, Viewport, WlSurface, connection::Wayland,
+        scheduler::FrameScheduler, swapchain::Swapchain,
+    },
+};
+use std::io::Result;
+
+/// How one output's lock screen is arranged.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub background: Background,
+    /// Where to center the indicator, or the middle of the output for unset coordinates.
+    pub indicator_x: Option<f32>,
+    pub indicator_y: Option<f32>,
+    /// Whether this output shows the indicator regardless of keyboard focus.
+    pub primary: bool,
+}
+
+/// The lock screen shown on one output.
+pub struct LockSurface {
+    output: Object,
+    surface: WlSurface,
+    lock_surface: SessionLockSurface,
+    swapchain: Swapchain,
+    scheduler: FrameScheduler,
+    layout: Layout,
+    /// Only one output shows the indicator; the others just show their background.
+    indicator_visible: bool,
+    /// Set when more than the indicator needs drawing, such as after a configure.
+    full_redraw: bool,
+    /// Size in surface coordinates from the last configure.
+    size: (u32, u32),
+    /// Scale the buffers are drawn at, in 120ths like fractional scales.
+    scale: u32,
+    /// How buffers are turned to match the output, so the compositor need not turn them.
+    transform: Transform,
+    /// Upright content for turned buffers, kept between frames so partial redraws only need
+    /// to copy what changed. Empty while the transform is normal.
+    staging: Vec<u8>,
+    /// Present when the compositor supports fractional scaling; buffers are then always
+    /// attached at scale 1 and shrunk to the surface size by the viewport.
+    viewport: Option<Viewport>,
+    fractional_scale: Option<FractionalScale>,
+    preferred_fractional_scale: Option<u32>,
+    preferred_buffer_scale: Option<i32>,
+    preferred_transform: Option<Transform>,
+}
+
+impl LockSurface {
+    pub fn new(conn: &mut Wayland, output: Object, layout: Layout) -> Result<Self> {
+        let surface = conn.create_surface()?;
+        let lock_surface = conn.get_lock_surface(surface, output)?;
+        // XRGB buffers have no transparent pixels, so the whole surface is opaque. The part of
+        // the region outside the surface is ignored, and the surface keeps a copy of it.
+        let opaque = conn.create_region(&[(0, 0, i32::MAX, i32::MAX)])?;
+        conn.set_opaque_region(surface, Some(opaque))?;
+        conn.destroy_region(opaque)?;
+        let (viewport, fractional_scale) = if conn.has_fractional_scale() {
+            (
+                Some(conn.get_viewport(surface)?),
+                Some(conn.get_fractional_scale(surface)?),
+            )
+        } else {
+            (None, None)
+        };
+        Ok(Self {
+            output,
+            surface,
+            lock_surface,
+            swapchain: Swapchain::new(Format::XRGB8888),
+            scheduler: FrameScheduler::default(),
+            layout,
+            indicator_visible: false,
+            full_redraw: true,
+            size: (0, 0),
+            scale: SCALE_DENOMINATOR,
+            transform: Transform::Normal,
+            staging: Vec::new(),
+            viewport,
+            fractional_scale,
+            preferred_fractional_scale: None,
+            preferred_buffer_scale: None,
+            preferred_transform: None,
+        })
+    }
+
+    pub fn output(&self) -> Object {
+        self.output
+    }
+
+    pub fn surface(&self) -> WlSurface {
+        self.surface
+    }
+
+    pub fn lock_surface(&self) -> SessionLockSurface {
+        self.lock_surface
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.layout.primary
+    }
+
+    /// Moves the indicator onto or off this output, redrawing if that changes anything.
+    pub fn show_indicator(&mut self, visible: bool) {
+        if self.indicator_visible != visible {
+            self.indicator_visible = visible;
+            self.full_redraw = true;
+            self.scheduler.mark_dirty();
+        }
+    }
+
+    /// Acknowledges a new size from the compositor and redraws at it.
+    pub fn configure(
+        &mut self,
+        conn: &mut Wayland,
+        indicator: &Indicator,
+        serial: u32,
+        size: (u32, u32),
+    ) -> Result<()> {
+        conn.ack_configure(self.lock_surface, serial)?;
+        self.size = size;
+        self.resize(conn)?;
+        self.full_redraw = true;
+        self.scheduler.force_redraw();
+        self.render(conn, indicator)
+    }
+
+    /// Takes note of the integer scale and transform the compositor prefers for this surface.
+    pub fn handle_surface_event(&mut self, event: SurfaceEvent) {
+        match event {
+            SurfaceEvent::PreferredBufferScale(scale) => {
+                self.preferred_buffer_scale = Some(scale.max(1))
+            }
+            SurfaceEvent::PreferredBufferTransform(transform) => {
+                self.preferred_transform = Some(transform)
+            }
+            SurfaceEvent::Enter | SurfaceEvent::Leave => {}
+        }
+    }
+
+    /// Returns whether the event was for this surface, taking note of the scale it carries.
+    pub fn handle_fractional_scale(
+        &mut self,
+        scale: FractionalScale,
+        event: FractionalScaleEvent,
+    ) -> bool {
+        if self.fractional_scale != Some(scale) {
+            return false;
+        }
+        let FractionalScaleEvent::PreferredScale(scale) = event;
+        self.preferred_fractional_scale = Some(scale.max(1));
+        true
+    }
+
+    /// Schedules a redraw of the indicator for the next frame callback, if this output shows it.
+    pub fn mark_dirty(&mut self) {
+        if self.indicator_visible {
+            self.scheduler.mark_dirty();
+        }
+    }
+
+    /// Returns whether `callback` was this surface's frame callback.
+    pub fn frame_done(&mut self, callback: u32) -> bool {
+        self.scheduler.callback_done(callback)
+    }
+
+    /// Draws if something changed since the last frame and the compositor asked for a new one.
+    pub fn render(&mut self, conn: &mut Wayland, indicator: &Indicator) -> Result<()> {
+        let wanted = (self.wanted_scale(conn), self.wanted_transform(conn));
+        if self.size != (0, 0) && wanted != (self.scale, self.transform) {
+            self.resize(conn)?;
+            self.full_redraw = true;
+            self.scheduler.mark_dirty();
+        }
+        if self.scheduler.should_draw() {
+            self.draw(conn, indicator)?;
+        }
+        Ok(())
+    }
+
+    /// The scale to draw at, preferring what the compositor asked for this surface over the
+    /// scale of its output.
+    fn wanted_scale(&self, conn: &Wayland) -> u32 {
+        let integer = || {
+            let scale = self
+                .preferred_buffer_scale
+                .unwrap_or_else(|| conn.output(self.output).map_or(1, |output| output.scale()));
+            scale.max(1) as u32 * SCALE_DENOMINATOR
+        };
+        match (self.viewport, self.preferred_fractional_scale) {
+            (Some(_), Some(scale)) => scale,
+            // Without a viewport the buffer scale must be a whole number the surface supports.
+            (None, _) if self.surface.version() < 3 => SCALE_DENOMINATOR,
+            _ => integer(),
+        }
+    }
+
+    /// The orientation to draw in: the one the compositor asked for, or else the output's.
+    fn wanted_transform(&self, conn: &Wayland) -> Transform {
+        if self.surface.version() < 2 {
+            return Transform::Normal;
+        }
+        self.preferred_transform.unwrap_or_else(|| {
+            conn.output(self.output)
+                .map_or(Transform::Normal, |output| output.transform())
+        })
+    }
+
+    /// Sizes the buffers for the configured size at the wanted scale and transform, and tells
+    /// the compositor how to map them back onto the surface from the next commit.
+    fn resize(&mut self, conn: &mut Wayland) -> Result<()> {
+        let scale = self.wanted_scale(conn);
+        let transform = self.wanted_transform(conn);
+        let (width, height) = self.size;
+        let scaled = |length: u32| ((length as u64 * scale as u64 + 60) / 120) as u32;
+        let (content_width, content_height) = (scaled(width), scaled(height));
+        let (buffer_width, buffer_height) = if transform.swaps_axes() {
+            (content_height, content_width)
+        } else {
+            (content_width, content_height)
+        };
+        match self.viewport {
+            Some(viewport) => {
+                conn.set_viewport_destination(viewport, width as i32, height as i32)?
+            }
+            None if self.surface.version() >= 3 => {
+                conn.set_buffer_scale(self.surface, (scale / SCALE_DENOMINATOR) as i32)?
+            }
+            None => {}
+        }
+        if self.surface.version() >= 2 {
+            conn.set_buffer_transform(self.surface, transform)?;
+        }
+        if (scale, transform) != (self.scale, self.transform) {
+            tracing::info!(
+                "Drawing {:?} at {}x scale, transform {transform:?}",
+                self.surface,
+                scale as f32 / SCALE_DENOMINATOR as f32
+            );
+        }
+        self.scale = scale;
+        self.transform = transform;
+        self.staging = match transform {
+            Transform::Normal => Vec::new(),
+            _ => vec![0; content_width as usize * content_height as usize * 4],
+        };
+        self.swapchain.resize(conn, buffer_width, buffer_height)
+    }
+
+    fn draw(&mut self, conn: &mut Wayland, indicator: &Indicator) -> Result<()> {
+        let Some(frame) = self.swapchain.acquire(conn)? else {
+            // Still dirty, so the next release brings us back here.
+            tracing::debug!("No free buffer for {:?}, deferring frame", self.surface);
+            return Ok(());
+        };
+        let buffer = frame.buffer;
+        let (width, height) = if self.transform.swaps_axes() {
+            (buffer.height(), buffer.width())
+        } else {
+            (buffer.width(), buffer.height())
+        };
+        let mut canvas = match self.transform {
+            Transform::Normal => Canvas::new(
+                self.swapchain.pixels(&buffer),
+                width,
+                height,
+                buffer.stride(),
+                buffer.format(),
+            ),
+            _ => Canvas::new(&mut self.staging, width, height, width * 4, buffer.format()),
+        };
+        // Layout is in surface coordinates; the buffer has `scale` pixels for each of them.
+        let scale = self.scale as f32 / SCALE_DENOMINATOR as f32;
+        let place = |position: Option<f32>, length: u32| {
+            position.map_or(length as f32 / 2.0, |position| position * scale)
+        };
+        let cx = place(self.layout.indicator_x, width);
+        let cy = place(self.layout.indicator_y, height);
+        let region = self
+            .indicator_visible
+            .then(|| indicator.bounds(cx, cy, scale));
+        // A reused buffer already shows the background; only the indicator moved on since.
+        let partial = region.filter(|_| frame.reused && !self.full_redraw);
+        canvas.set_clip(partial);
+        self.layout.background.draw(&mut canvas);
+        if self.indicator_visible {
+            indicator.draw(&mut canvas, cx, cy, scale);
+        }
+
+        let area = match partial {
+            Some(region) => clamp_area(region.pixels(), width, height),
+            None => (0, 0, width, height),
+        };
+        let (x, y, damage_width, damage_height) = match self.transform {
+            Transform::Normal => area,
+            transform => copy_transformed(
+                &self.staging,
+                (width, height),
+                self.swapchain.pixels(&buffer),
+                buffer.stride(),
+                transform,
+                area,
+            ),
+        };
+        conn.attach(self.surface, Some(&buffer))?;
+        conn.damage_buffer(
+            self.surface,
+            x as i32,
+            y as i32,
+            damage_width as i32,
+            damage_height as i32,
+        )?;
+        self.scheduler.frame_drawn(conn, self.surface)?;
+        self.full_redraw = false;
+        conn.commit(self.surface)
+    }
+
+    pub fn destroy(mut self, conn: &mut Wayland) -> Result<()> {
+        if let Some(viewport) = self.viewport {
+            conn.destroy_viewport(viewport)?;
+        }
+        if let Some(scale) = self.fractional_scale {
+            conn.destroy_fractional_scale(scale)?;
+        }
+        conn.destroy_lock_surface(self.lock_surface)?;
+        conn.destroy_surface(self.surface)?;
+        self.swapchain.destroy(conn)
+    }
+}
+
+/// The part of a pixel rectangle inside a `width` by `height` area.
+fn clamp_area(
+    (x, y, area_width, area_height): (i32, i32, i32, i32),
+    width: u32,
+    height: u32,
+) -> (u32, u32, u32, u32) {
+    let clamp = |v: i32, limit: u32| v.clamp(0, limit as i32) as u32;
+    let (x0, y0) = (clamp(x, width), clamp(y, height));
+    let (x1, y1) = (clamp(x + area_width, width), clamp(y + area_height, height));
+    (x0, y0, x1 - x0, y1 - y0)
+}
+
+/// Copies `area` of the upright `src` into the turned buffer `dst`, returning where it ended up
+/// in the buffer.
+fn copy_transformed(
+    src: &[u8],
+    (width, height): (u32, u32),
+    dst: &mut [u8],
+    dst_stride: u32,
+    transform: Transform,
+    (x, y, area_width, area_height): (u32, u32, u32, u32),
+) -> (u32, u32, u32, u32) {
+    if area_width == 0 || area_height == 0 {
+        return (0, 0, 0, 0);
+    }
+    for sy in y..y + area_height {
+        for sx in x..x + area_width {
+            let (dx, dy) = transform.buffer_point(sx, sy, width, height);
+            let from = (sy * width + sx) as usize * 4;
+            let to = (dy * dst_stride + dx * 4) as usize;
+            dst[to..to + 4].copy_from_slice(&src[from..from + 4]);
+        }
+    }
+    let first = transform.buffer_point(x, y, width, height);
+    let last = transform.buffer_point(x + area_width - 1, y + area_height - 1, width, height);
+    let (x0, y0) = (first.0.min(last.0), first.1.min(last.1));
+    let (x1, y1) = (first.0.max(last.0), first.1.max(last.1));
+    (x0, y0, x1 - x0 + 1, y1 - y0 + 1)
+}
@@ -1,8 +1,56 @@
+mod auth;
+mod background;
+mod config;
+mod dbus;
+mod fingerprint;
+mod grace;
+mod hardening;
+mod image;
+mod indicator;
+mod input;
+mod lock_surface;
+mod password;
+mod render;
+#[cfg(test)]
+mod testing;
+mod throttle;
+mod timer;
 mod wayland;
-use std::io::Result;
+use mio::Token;
+use std::{
+    io::{Error, Result},
+    path::{Path, PathBuf},
+};
 use tracing_subscriber::FmtSubscriber;
 
-use crate::wayland::connection::Wayland;
+use crate::{
+    auth::{Authenticator, Backend, Helper, Pam, Shadow, Totp, Verifier},
+    background::{Background, ImageCache},
+    config::Config,
+    fingerprint::Fingerprint,
+    grace::Grace,
+    hardening::Report,
+    indicator::Indicator,
+    input::{
+        compose,
+        keyboard::{KeyPress, Keyboard},
+    },
+    lock_surface::{Layout, LockSurface},
+    password::{Edit, Password},
+    render::text::Font,
+    throttle::Throttle,
+    wayland::{
+        Interface, KeyState, KeyboardEvent, Object, PointerEvent, SessionLockSurfaceEvent,
+        connection::Wayland,
+    },
+};
+
+const KEY_REPEAT: Token = Token(1);
+const AUTH: Token = Token(2);
+const INDICATOR: Token = Token(3);
+const THROTTLE: Token = Token(4);
+const GRACE: Token = Token(5);
+const FINGERPRINT: Token = Token(6);
 
 fn setup_logs(level: tracing::Level) {
     let subscriber = FmtSubscriber::builder()
@@ -12,18 +60,463 @@ fn setup_logs(level: tracing::Level) {
     tracing::subscriber::set_global_default(subscriber).expect("Failed to set logger");
 }
 
+/// Command line options.
+#[derive(Debug, Default)]
+struct Options {
+    /// `--idle`: the lock was started by an idle timeout, such as swayidle's `timeout` command,
+    /// which allows the configured grace period. Locks started by hand or before suspending
+    /// leave it out and always need the password.
+    idle: bool,
+    /// `--check-hardening`: take the hardening measures, print how each went and exit, without
+    /// locking. Fails if a measure that should apply did not.
+    check_hardening: bool,
+}
+
+impl Options {
+    fn parse() -> Result<Self> {
+        let mut options = Self::default();
+        for arg in std::env::args().skip(1) {
+            match arg.as_str() {
+                "--idle" => options.idle = true,
+                "--check-hardening" => options.check_hardening = true,
+                _ => return Err(Error::other(format!("unknown option {arg}"))),
+            }
+        }
+        Ok(options)
+    }
+}
+
 fn main() -> Result<()> {
     setup_logs(tracing::Level::INFO);
+    let mut hardening = Report::default();
+    // Before anything secret is in memory, and before the helper is forked so it inherits it.
+    hardening.forbid_dumps();
+    let options = Options::parse()?;
+    // An unreadable config or a bad security option stops the locker rather than locking with
+    // weaker settings than configured.
+    let config = Config::load().map_err(|e| Error::other(e.to_string()))?;
+    let (readable, writable) = confinement(&config);
+    if options.check_hardening {
+        // SAFETY: no other thread has been started.
+        unsafe { hardening.clear_environment() };
+        auth::drop_privileges()?;
+        hardening.forbid_dumps();
+        hardening.lock_memory();
+        hardening.restrict_files(&config.hardening, &readable, &writable);
+        hardening.filter_calls(&config.hardening);
+        hardening.print();
+        if hardening.has_failures() {
+            return Err(Error::other("some hardening measures could not be taken"));
+        }
+        return Ok(());
+    }
+    // Without a way to check passwords the session could never be unlocked again. The check runs
+    // in a helper forked before anything talks to the compositor, so this process can give up
+    // whatever privileges reading the password database takes.
+    let user = auth::current_user()?;
+    // Setuid or setgid, the helper keeps privileges the user lacks, and a PAM stack or password
+    // file of the user's choosing would hand them over.
+    let settings = if auth::is_privileged() {
+        Config::system()
+            .map_err(|e| Error::other(e.to_string()))?
+            .auth
+    } else {
+        config.auth.clone()
+    };
+    let use_fingerprint = config.fingerprint && settings.allows_fingerprint();
+    if config.fingerprint && !use_fingerprint {
+        tracing::warn!("Fingerprints cannot unlock while verification codes are required");
+    }
+    let helper_user = user.clone();
+    let helper = Helper::spawn(move || {
+        let backend = match settings.backend {
+            Backend::Pam => Box::new(Pam::new(
+                &settings.pam_service,
+                settings.pam_config_dir.as_deref(),
+                &helper_user,
+            )?) as Box<dyn Authenticator>,
+            Backend::Shadow => Box::new(Shadow::new(settings.shadow_file, &helper_user)),
+        };
+        // The code is asked for only once the password is right.
+        Ok(if settings.totp.enabled {
+            Box::new(Totp::new(backend, settings.totp)?)
+        } else {
+            backend
+        })
+    })
+    .map_err(|e| Error::other(e.to_string()))?;
+    // Only now, as the helper's PAM modules may need what leads to credentials.
+    // SAFETY: the helper was forked, and no thread has been started yet.
+    unsafe { hardening.clear_environment() };
+    auth::drop_privileges()?;
+    // Changing credentials made the process dumpable again.
+    hardening.forbid_dumps();
+    hardening.lock_memory();
     let mut conn = Wayland::connect()?;
     tracing::info!("Wayland Connection Established");
     conn.setup()?;
     tracing::info!("Received all globals!");
-    conn.bind("ext_session_lock_manager_v1".to_string())?;
+    conn.bind(Interface::SessionLockManager)?;
+    conn.bind(Interface::Compositor)?;
+    conn.bind(Interface::Shm)?;
+    if conn.has_global(Interface::Seat) {
+        conn.bind(Interface::Seat)?;
+    }
+    if conn.has_global(Interface::FractionalScaleManager) && conn.has_global(Interface::Viewporter)
+    {
+        conn.bind(Interface::FractionalScaleManager)?;
+        conn.bind(Interface::Viewporter)?;
+    }
+    let compose = compose::Table::from_locale()
+        .inspect_err(|e| tracing::warn!("Compose sequences unavailable: {e}"))
+        .ok()
+        .filter(|table| !table.is_empty());
+    let mut keyboard = Keyboard::new(compose)?;
+    conn.register(keyboard.repeat_timer(), KEY_REPEAT)?;
+    let font = Font::find(&config.indicator.font)
+        .inspect_err(|e| tracing::warn!("Indicator text unavailable: {e}"))
+        .ok();
+    let mut indicator = Indicator::new(config.indicator.clone(), font)?;
+    conn.register(indicator.timer(), INDICATOR)?;
+    let mut images = ImageCache::default();
+    let mut throttle = Throttle::load(config.throttle.clone())?;
+    conn.register(throttle.timer(), THROTTLE)?;
+    indicator.set_throttle(throttle.status());
+    let mut password = Password::new()?;
+    let mut prompt: Option<Prompt> = None;
+    // The password keeps working when the reader cannot be used.
+    let mut fingerprint = None;
+    if use_fingerprint {
+        match Fingerprint::start(&user) {
+            Ok(mut reader) => {
+                conn.register(reader.bus(), FINGERPRINT)?;
+                fingerprint = Some(reader);
+            }
+            Err(e) => tracing::warn!("Fingerprints unavailable: {e}"),
+        }
+    }
+    // Landlock binds only the thread applying it and those started later, so it comes before
+    // the verification thread.
+    hardening.restrict_files(&config.hardening, &readable, &writable);
+    let mut verifier = Verifier::new(Box::new(helper), conn.waker(AUTH)?)?;
     conn.lock()?;
-    std::thread::sleep(std::time::Duration::from_secs(5));
-    conn.unlock()?;
+    let mut surfaces = Vec::new();
+    add_outputs(&mut conn, &config, &mut images, &mut surfaces)?;
+    let mut focus: Option<Object> = None;
+    // Starts when the compositor confirms the lock.
+    let mut grace_period = Some(config.grace).filter(|grace| options.idle && !grace.is_zero());
+    let mut grace: Option<Grace> = None;
+    // Everything is set up; no input has been read yet.
+    hardening.filter_calls(&config.hardening);
 
     loop {
-        conn.poll_events()?;
+        let mut changed = false;
+        for token in conn.poll_events()? {
+            match token {
+                KEY_REPEAT => {
+                    if let Some(press) = keyboard.repeat() {
+                        changed |= handle_key(
+                            &press,
+                            &mut password,
+                            &mut prompt,
+                            &mut indicator,
+                            &mut verifier,
+                            &throttle,
+                        );
+                    }
+                }
+                INDICATOR => changed |= indicator.timeout(),
+                GRACE => {
+                    if let Some(running) = grace.as_mut()
+                        && running.tick()
+                    {
+                        let remaining = running.remaining();
+                        changed |= indicator.set_grace(remaining);
+                        if remaining == 0 {
+                            tracing::info!("Grace period over");
+                            grace = None;
+                        }
+                    }
+                }
+                FINGERPRINT => match fingerprint.as_mut().map(Fingerprint::dispatch) {
+                    Some(Ok(true)) => {
+                        tracing::info!("Fingerprint matched, unlocking");
+                        throttle.succeeded();
+                        keyboard.cancel_repeat();
+                        drop(fingerprint);
+                        return unlock(&mut conn, &mut surfaces);
+                    }
+                    Some(Ok(false)) => {
+                        let status = fingerprint.as_ref().and_then(Fingerprint::status);
+                        changed |= indicator.set_fingerprint(status);
+                    }
+                    Some(Err(e)) => {
+                        tracing::warn!("Fingerprints unavailable: {e}");
+                        fingerprint = None;
+                        changed |= indicator.set_fingerprint(None);
+                    }
+                    None => {}
+                },
+                THROTTLE if throttle.tick() => {
+                    changed |= indicator.set_throttle(throttle.status());
+                }
+                AUTH => {
+                    while let Some(event) = verifier.next_event() {
+                        match event {
+                            auth::Event::Prompt { text, echo } => {
+                                password.answer(echo);
+                                changed |= indicator.prompt(text.clone());
+                                prompt = Some(Prompt { text });
+                            }
+                            auth::Event::Message { text, error } => {
+                                if error {
+                                    tracing::warn!("{text}");
+                                } else {
+                                    tracing::info!("{text}");
+                                }
+                                changed |= indicator.set_notice(Some(text));
+                            }
+                            auth::Event::Done(Ok(())) => {
+                                tracing::info!("Password accepted, unlocking");
+                                throttle.succeeded();
+                                keyboard.cancel_repeat();
+                                return unlock(&mut conn, &mut surfaces);
+                            }
+                            auth::Event::Done(Err(e)) => {
+                                tracing::warn!("{e}");
+                                prompt = None;
+                                changed |= indicator.wrong();
+                                if matches!(e, auth::Error::Rejected) {
+                                    throttle.failed();
+                                    changed |= indicator.set_throttle(throttle.status());
+                                } else {
+                                    changed |= indicator.set_notice(Some(e.to_string()));
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if conn.is_lock_finished() {
+            return Err(Error::other("compositor refused to lock the session"));
+        }
+        if conn.is_locked()
+            && let Some(period) = grace_period.take()
+        {
+            let mut running = Grace::start(period)?;
+            conn.register(running.timer(), GRACE)?;
+            changed |= indicator.set_grace(running.remaining());
+            grace = Some(running);
+        }
+        // The deadline can pass before the timer says so, with input queued in the same batch.
+        if grace.as_ref().is_some_and(Grace::is_over) {
+            tracing::info!("Grace period over");
+            grace = None;
+            changed |= indicator.set_grace(0);
+        }
+        let mut present = conn.pointer_events().any(|event| {
+            matches!(
+                event,
+                PointerEvent::Motion | PointerEvent::Button | PointerEvent::Axis
+            )
+        });
+        for event in conn.keyboard_events() {
+            if let KeyboardEvent::Enter { surface, .. } = &event {
+                focus = Some(*surface);
+            }
+            if grace.is_some()
+                && let KeyboardEvent::Key {
+                    state: KeyState::Pressed,
+                    ..
+                } = event
+            {
+                // The key only shows the user is there; it is not part of a password.
+                present = true;
+                continue;
+            }
+            if let Some(press) = keyboard.handle_event(event) {
+                changed |= handle_key(
+                    &press,
+                    &mut password,
+                    &mut prompt,
+                    &mut indicator,
+                    &mut verifier,
+                    &throttle,
+                );
+            }
+        }
+        if present && grace.as_ref().is_some_and(|running| !running.is_over()) {
+            tracing::info!("Input during the grace period, unlocking");
+            keyboard.cancel_repeat();
+            return unlock(&mut conn, &mut surfaces);
+        }
+        changed |= indicator.set_caps_lock(keyboard.caps_lock());
+        let removed: Vec<_> = conn.removed_outputs().collect();
+        for output in removed {
+            if let Some(index) = surfaces.iter().position(|s| s.output() == output) {
+                surfaces.remove(index).destroy(&mut conn)?;
+            }
+        }
+        add_outputs(&mut conn, &config, &mut images, &mut surfaces)?;
+        let shown = surfaces
+            .iter()
+            .position(LockSurface::is_primary)
+            .or_else(|| {
+                surfaces
+                    .iter()
+                    .position(|s| Some(s.surface().id()) == focus)
+            })
+            .unwrap_or(0);
+        for (index, surface) in surfaces.iter_mut().enumerate() {
+            surface.show_indicator(index == shown);
+            if changed {
+                surface.mark_dirty();
+            }
+        }
+        let surface_events: Vec<_> = conn.surface_events().collect();
+        for (wl_surface, event) in surface_events {
+            if let Some(surface) = surfaces.iter_mut().find(|s| s.surface() == wl_surface) {
+                surface.handle_surface_event(event);
+            }
+        }
+        let scale_events: Vec<_> = conn.fractional_scale_events().collect();
+        for (scale, event) in scale_events {
+            surfaces
+                .iter_mut()
+                .any(|surface| surface.handle_fractional_scale(scale, event));
+        }
+        for callback in conn.frame_callbacks() {
+            surfaces
+                .iter_mut()
+                .any(|surface| surface.frame_done(callback));
+        }
+        let configures: Vec<_> = conn.lock_surface_events().collect();
+        for (lock_surface, event) in configures {
+            let SessionLockSurfaceEvent::Configure {
+                serial,
+                width,
+                height,
+            } = event;
+            if let Some(surface) = surfaces
+                .iter_mut()
+                .find(|surface| surface.lock_surface() == lock_surface)
+            {
+                surface.configure(&mut conn, &indicator, serial, (width, height))?;
+            }
+        }
+        for surface in &mut surfaces {
+            surface.render(&mut conn, &indicator)?;
+        }
+    }
+}
+
+/// Ends the lock and takes down its surfaces.
+fn unlock(conn: &mut Wayland, surfaces: &mut Vec<LockSurface>) -> Result<()> {
+    conn.unlock()?;
+    for surface in surfaces.drain(..) {
+        surface.destroy(conn)?;
+    }
+    Ok(())
+}
+
+/// A question from the authenticator, answered by what is typed until the next Enter.
+struct Prompt {
+    text: String,
+}
+
+/// Edits the password or answer being typed, submitting it on Enter, and returns whether the
+/// indicator changed. Escape gives up on a pending prompt.
+fn handle_key(
+    press: &KeyPress,
+    password: &mut Password,
+    prompt: &mut Option<Prompt>,
+    indicator: &mut Indicator,
+    verifier: &mut Verifier,
+    throttle: &Throttle,
+) -> bool {
+    let edit = password.edit(press);
+    match edit {
+        Edit::Submitted => {
+            if prompt.take().is_some() {
+                verifier.answer(Some(password.take()));
+            } else if verifier.is_busy() || !throttle.allowed() {
+                // One check at a time, and none while held back after wrong passwords; the
+                // password typed meanwhile waits for the next Enter.
+                return false;
+            } else {
+                verifier.verify(password.take());
+            }
+        }
+        Edit::Cancelled if prompt.take().is_some() => verifier.answer(None),
+        _ => {}
+    }
+    let mut changed = indicator.edited(edit);
+    // Input is only ever turned into text when the question lets it be shown.
+    if let Some(Prompt { text }) = prompt
+        && let Some(typed) = password.shown()
+    {
+        let shown = if typed.is_empty() {
+            text.clone()
+        } else {
+            format!("{text} {typed}")
+        };
+        changed |= indicator.set_notice(Some(shown));
+    }
+    changed
+}
+
+/// What the locker still touches on disk once running, for the Landlock ruleset: images of
+/// outputs that may be plugged in later, and the directory the attempt count is saved in.
+fn confinement(config: &Config) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let images = std::iter::once(&config.output)
+        .chain(config.outputs.iter().map(|(_, output)| output))
+        .filter_map(|output| output.image.clone())
+        .collect();
+    let state = config
+        .throttle
+        .state_file
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .into_iter()
+        .collect();
+    (images, state)
+}
+
+/// Creates lock surfaces for outputs that appeared since the last call, laid out as configured
+/// for each.
+fn add_outputs(
+    conn: &mut Wayland,
+    config: &Config,
+    images: &mut ImageCache,
+    surfaces: &mut Vec<LockSurface>,
+) -> Result<()> {
+    let outputs = conn.bind_outputs()?;
+    if outputs.is_empty() {
+        return Ok(());
+    }
+    // Outputs announce their name and description right after being bound.
+    conn.roundtrip()?;
+    for output in outputs {
+        let (name, description) = conn
+            .output(output)
+            .map_or((None, None), |info| (info.name(), info.description()));
+        let settings = config.for_output(name, description);
+        let image = settings.image.as_deref().and_then(|path| images.get(path));
+        let layout = Layout {
+            background: Background::new(settings.background, image, settings.scaling),
+            indicator_x: settings.indicator_x,
+            indicator_y: settings.indicator_y,
+            primary: config.is_primary(name, description),
+        };
+        tracing::info!(
+            "Locking output {} ({})",
+            name.unwrap_or("unnamed"),
+            description.unwrap_or("no description")
+        );
+        surfaces.push(LockSurface::new(conn, output, layout)?);
     }
+    Ok(())
 }
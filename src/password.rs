@@ -0,0 +1,320 @@
+//! The buffer the password is typed into. It lives in a page of its own that is locked in memory
+//! and left out of core dumps, and every byte it held is wiped as soon as it is not needed.
+
+use crate::input::{keyboard::KeyPress, xkb::Keysym};
+use std::{io, ptr::NonNull, slice};
+
+/// The most bytes a password may have, PAM's `PAM_MAX_RESP_SIZE`.
+const MAX_LENGTH: usize = 512;
+
+/// Control characters produced by Ctrl+U and Ctrl+W.
+const CLEAR_LINE: char = '\u{15}';
+const DELETE_WORD: char = '\u{17}';
+
+/// What a key press did to the [`Password`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    /// Characters were added.
+    Inserted,
+    /// Characters were removed, and some are left.
+    Removed,
+    /// Nothing is left, by backspace or Ctrl+U.
+    Cleared,
+    /// Escape: the input and any question it answers are abandoned.
+    Cancelled,
+    /// Enter: the buffer is ready for [`Password::take`].
+    Submitted,
+    /// The key does not edit, or the buffer is full.
+    Unchanged,
+}
+
+pub struct Password {
+    page: NonNull<u8>,
+    page_size: usize,
+    length: usize,
+    /// Whether the input answers a question that lets it be shown, such as a one-time code.
+    echo: bool,
+}
+
+impl Password {
+    pub fn new() -> io::Result<Self> {
+        // SAFETY: sysconf has no preconditions.
+        let page_size =
+            unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(MAX_LENGTH as i64) as usize;
+        // SAFETY: a fresh anonymous mapping aliases nothing.
+        let page = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                page_size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if page == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `page` is the mapping just made, `page_size` long.
+        unsafe {
+            if libc::mlock(page, page_size) != 0 {
+                let e = io::Error::last_os_error();
+                tracing::warn!("Failed to lock the password buffer in memory: {e}");
+            }
+            if libc::madvise(page, page_size, libc::MADV_DONTDUMP) != 0 {
+                let e = io::Error::last_os_error();
+                tracing::warn!("Failed to keep the password buffer out of core dumps: {e}");
+            }
+        }
+        Ok(Self {
+            page: NonNull::new(page.cast()).ok_or_else(|| io::Error::other("null mapping"))?,
+            page_size,
+            length: 0,
+            echo: false,
+        })
+    }
+
+    /// Applies a key press: text is appended, backspace removes a character, Ctrl+W a word,
+    /// Ctrl+U everything and Escape everything too. Enter leaves the buffer for the caller.
+    pub fn edit(&mut self, press: &KeyPress) -> Edit {
+        match press.keysym {
+            Keysym::RETURN | Keysym::KP_ENTER => return Edit::Submitted,
+            Keysym::ESCAPE => {
+                self.clear();
+                self.echo = false;
+                return Edit::Cancelled;
+            }
+            Keysym::BACKSPACE | Keysym::DELETE => return self.remove_char(),
+            _ => {}
+        }
+        match press.chars.as_slice() {
+            [CLEAR_LINE] => {
+                self.clear();
+                Edit::Cleared
+            }
+            [DELETE_WORD] => self.remove_word(),
+            chars => {
+                let mut edit = Edit::Unchanged;
+                for &c in chars.iter().filter(|c| !c.is_control()) {
+                    if !self.push(c) {
+                        break;
+                    }
+                    edit = Edit::Inserted;
+                }
+                edit
+            }
+        }
+    }
+
+    /// Starts the answer to a question, which is shown as it is typed if `echo`.
+    pub fn answer(&mut self, echo: bool) {
+        self.clear();
+        self.echo = echo;
+    }
+
+    /// The text typed so far, only if it answers a question that lets it be shown.
+    pub fn shown(&self) -> Option<&str> {
+        self.echo.then(|| self.as_str())
+    }
+
+    fn as_str(&self) -> &str {
+        // Only whole characters are ever added or removed.
+        std::str::from_utf8(self.bytes()).unwrap_or_default()
+    }
+
+    /// Hands over the password for checking and wipes the buffer. The caller wipes the copy.
+    pub fn take(&mut self) -> Vec<u8> {
+        let password = self.bytes().to_vec();
+        self.clear();
+        self.echo = false;
+        password
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    fn push(&mut self, c: char) -> bool {
+        let (start, end) = (self.length, self.length + c.len_utf8());
+        if end > MAX_LENGTH {
+            return false;
+        }
+        c.encode_utf8(&mut self.page_mut()[start..end]);
+        self.length = end;
+        true
+    }
+
+    fn remove_char(&mut self) -> Edit {
+        let start = self
+            .as_str()
+            .char_indices()
+            .next_back()
+            .map_or(0, |(index, _)| index);
+        self.truncate(start)
+    }
+
+    /// Removes the last word and any whitespace after it.
+    fn remove_word(&mut self) -> Edit {
+        let start = self
+            .as_str()
+            .trim_end_matches(char::is_whitespace)
+            .trim_end_matches(|c: char| !c.is_whitespace())
+            .len();
+        self.truncate(start)
+    }
+
+    fn truncate(&mut self, length: usize) -> Edit {
+        let end = self.length;
+        wipe(&mut self.page_mut()[length..end]);
+        self.length = length;
+        if length == 0 {
+            Edit::Cleared
+        } else {
+            Edit::Removed
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: the mapping outlives `self` and `length` never exceeds it.
+        unsafe { slice::from_raw_parts(self.page.as_ptr(), self.length) }
+    }
+
+    fn page_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the mapping is `page_size` long and only reachable through `self`.
+        unsafe { slice::from_raw_parts_mut(self.page.as_ptr(), self.page_size) }
+    }
+}
+
+impl Drop for Password {
+    fn drop(&mut self) {
+        self.clear();
+        // SAFETY: the mapping is not used after this.
+        unsafe { libc::munmap(self.page.as_ptr().cast(), self.page_size) };
+    }
+}
+
+/// Zeroes `bytes` in a way the compiler cannot skip because they are never read again.
+fn wipe(bytes: &mut [u8]) {
+    for byte in bytes {
+        // SAFETY: `byte` is a valid, exclusive reference.
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A key typing `text`, as a Compose sequence may.
+    fn typed(text: &str) -> KeyPress {
+        KeyPress {
+            keysym: Keysym::NO_SYMBOL,
+            chars: text.chars().collect(),
+        }
+    }
+
+    fn key(keysym: Keysym) -> KeyPress {
+        KeyPress {
+            keysym,
+            chars: Default::default(),
+        }
+    }
+
+    /// Ctrl with a letter, which keymaps turn into a control character.
+    fn ctrl(letter: char) -> KeyPress {
+        KeyPress {
+            keysym: Keysym(letter as u32),
+            chars: [char::from(letter as u8 & 0x1f)].into_iter().collect(),
+        }
+    }
+
+    fn password(text: &str) -> Password {
+        let mut password = Password::new().unwrap();
+        for c in text.chars() {
+            assert_eq!(password.edit(&typed(&c.to_string())), Edit::Inserted);
+        }
+        password
+    }
+
+    /// Whether every byte past the text is zero, so nothing removed lingers in the page.
+    fn wiped(password: &mut Password) -> bool {
+        let length = password.length;
+        password.page_mut()[length..].iter().all(|&byte| byte == 0)
+    }
+
+    #[test]
+    fn backspace_removes_whole_characters() {
+        let mut password = password("pä€😀");
+        assert_eq!(password.as_str().len(), 10);
+        for expected in ["pä€", "pä", "p"] {
+            assert_eq!(password.edit(&key(Keysym::BACKSPACE)), Edit::Removed);
+            assert_eq!(password.as_str(), expected);
+            assert!(wiped(&mut password));
+        }
+        assert_eq!(password.edit(&key(Keysym::DELETE)), Edit::Cleared);
+        assert_eq!(password.edit(&key(Keysym::BACKSPACE)), Edit::Cleared);
+        assert_eq!(password.as_str(), "");
+    }
+
+    #[test]
+    fn ctrl_w_removes_the_last_word() {
+        let mut password = password("correct horse  bätterý ");
+        assert_eq!(password.edit(&ctrl('w')), Edit::Removed);
+        assert_eq!(password.as_str(), "correct horse  ");
+        assert_eq!(password.edit(&ctrl('w')), Edit::Removed);
+        assert_eq!(password.as_str(), "correct ");
+        assert!(wiped(&mut password));
+        assert_eq!(password.edit(&ctrl('w')), Edit::Cleared);
+        assert_eq!(password.edit(&ctrl('w')), Edit::Cleared);
+    }
+
+    #[test]
+    fn ctrl_u_and_escape_clear_everything() {
+        let mut password = password("correct horse");
+        assert_eq!(password.edit(&ctrl('u')), Edit::Cleared);
+        assert_eq!(password.as_str(), "");
+        assert!(wiped(&mut password));
+
+        // Escape abandons a shown answer too.
+        password.answer(true);
+        password.edit(&typed("42"));
+        assert_eq!(password.shown(), Some("42"));
+        assert_eq!(password.edit(&key(Keysym::ESCAPE)), Edit::Cancelled);
+        assert_eq!(password.shown(), None);
+        assert_eq!(password.as_str(), "");
+        assert!(wiped(&mut password));
+    }
+
+    #[test]
+    fn stops_at_the_maximum_length() {
+        let mut password = password(&"a".repeat(MAX_LENGTH - 1));
+        // A character that does not fit whole is left out, and so is the rest of the press.
+        assert_eq!(password.edit(&typed("ä")), Edit::Unchanged);
+        assert_eq!(password.edit(&typed("bä")), Edit::Inserted);
+        assert_eq!(password.as_str().len(), MAX_LENGTH);
+        assert_eq!(password.edit(&typed("c")), Edit::Unchanged);
+        assert!(password.as_str().ends_with("ab"));
+    }
+
+    #[test]
+    fn ignores_control_characters_and_other_keys() {
+        let mut password = password("pw");
+        assert_eq!(password.edit(&typed("\t")), Edit::Unchanged);
+        assert_eq!(password.edit(&key(Keysym(0xffe1))), Edit::Unchanged);
+        assert_eq!(password.edit(&typed("\u{1b}x")), Edit::Inserted);
+        assert_eq!(password.as_str(), "pwx");
+    }
+
+    #[test]
+    fn submitting_leaves_the_buffer_to_take() {
+        let mut password = password("pw");
+        assert_eq!(password.edit(&key(Keysym::RETURN)), Edit::Submitted);
+        assert_eq!(password.edit(&key(Keysym::KP_ENTER)), Edit::Submitted);
+        assert_eq!(password.shown(), None);
+        let mut taken = password.take();
+        assert_eq!(taken, b"pw");
+        taken.fill(0);
+        assert_eq!(password.length, 0);
+        assert!(wiped(&mut password));
+    }
+}
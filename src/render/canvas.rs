@@ -0,0 +1,529 @@
+use crate::{
+    image::Image,
+    render::{
+        color::{Color, div255},
+        shape,
+    },
+    wayland::Format,
+};
+
+/// An axis-aligned rectangle in pixels, whose edges may fall between pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The square around a circle.
+    pub fn around(cx: f32, cy: f32, radius: f32) -> Self {
+        Self::new(cx - radius, cy - radius, radius * 2.0, radius * 2.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0.0 || self.height <= 0.0
+    }
+
+    #[allow(dead_code, reason = "kept with the rectangle primitives")]
+    pub fn intersect(&self, other: &Self) -> Self {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        Self::new(x, y, (right - x).max(0.0), (bottom - y).max(0.0))
+    }
+
+    /// The same rectangle grown by `amount` on every side.
+    pub fn inflate(&self, amount: f32) -> Self {
+        Self::new(
+            self.x - amount,
+            self.y - amount,
+            self.width + amount * 2.0,
+            self.height + amount * 2.0,
+        )
+    }
+
+    /// The smallest whole-pixel rectangle containing this one, as `(x, y, width, height)`.
+    pub fn pixels(&self) -> (i32, i32, i32, i32) {
+        let x = self.x.floor() as i32;
+        let y = self.y.floor() as i32;
+        let right = (self.x + self.width).ceil() as i32;
+        let bottom = (self.y + self.height).ceil() as i32;
+        (x, y, (right - x).max(0), (bottom - y).max(0))
+    }
+
+    fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    fn half(&self) -> (f32, f32) {
+        (self.width / 2.0, self.height / 2.0)
+    }
+}
+
+/// Whole pixels drawing may touch, as half-open ranges.
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+}
+
+/// Draws into the pixels of one ARGB8888 or XRGB8888 buffer.
+///
+/// ARGB8888 pixels are premultiplied, as `wl_shm` expects. XRGB8888 buffers are treated as
+/// opaque, so their alpha byte is always written as 0xff.
+pub struct Canvas<'a> {
+    pixels: &'a mut [u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    opaque: bool,
+    clip: Bounds,
+}
+
+impl<'a> Canvas<'a> {
+    pub fn new(pixels: &'a mut [u8], width: u32, height: u32, stride: u32, format: Format) -> Self {
+        assert!(
+            format == Format::ARGB8888 || format == Format::XRGB8888,
+            "cannot draw into {format:?}"
+        );
+        assert!(stride >= width * 4 && pixels.len() >= stride as usize * height as usize);
+        Self {
+            pixels,
+            width,
+            height,
+            stride: stride as usize,
+            opaque: format == Format::XRGB8888,
+            clip: Bounds {
+                x0: 0,
+                y0: 0,
+                x1: width,
+                y1: height,
+            },
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Restricts drawing to the whole pixels overlapping `clip`, or lifts the restriction.
+    pub fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = Bounds {
+            x0: 0,
+            y0: 0,
+            x1: self.width,
+            y1: self.height,
+        };
+        if let Some(clip) = clip {
+            self.clip = self.bounds(clip);
+        }
+    }
+
+    /// Replaces every pixel in the clip with `color`, without blending.
+    pub fn clear(&mut self, color: Color) {
+        let pixel = self
+            .finish(color.premultiplied(color.a as u32))
+            .to_le_bytes();
+        let Bounds { x0, y0, x1, y1 } = self.clip;
+        for y in y0..y1 {
+            let row = y as usize * self.stride;
+            let row = &mut self.pixels[row + x0 as usize * 4..row + x1 as usize * 4];
+            for chunk in row.chunks_exact_mut(4) {
+                chunk.copy_from_slice(&pixel);
+            }
+        }
+    }
+
+    #[allow(dead_code, reason = "nothing on the lock screen is a rectangle yet")]
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.fill_rounded_rect(rect, 0.0, color);
+    }
+
+    /// Outlines `rect` with a line of `line_width` centered on its edges.
+    #[allow(dead_code, reason = "nothing on the lock screen is a rectangle yet")]
+    pub fn stroke_rect(&mut self, rect: Rect, line_width: f32, color: Color) {
+        self.stroke_rounded_rect(rect, 0.0, line_width, color);
+    }
+
+    pub fn fill_rounded_rect(&mut self, rect: Rect, radius: f32, color: Color) {
+        let (center, half) = (rect.center(), rect.half());
+        self.fill(rect, color, |x, y| {
+            shape::rounded_box(x, y, center, half, radius)
+        });
+    }
+
+    pub fn stroke_rounded_rect(&mut self, rect: Rect, radius: f32, line_width: f32, color: Color) {
+        let (center, half) = (rect.center(), rect.half());
+        self.fill(rect.inflate(line_width / 2.0), color, |x, y| {
+            shape::stroke(shape::rounded_box(x, y, center, half, radius), line_width)
+        });
+    }
+
+    pub fn fill_circle(&mut self, cx: f32, cy: f32, radius: f32, color: Color) {
+        self.fill(Rect::around(cx, cy, radius), color, |x, y| {
+            shape::circle(x, y, (cx, cy), radius)
+        });
+    }
+
+    pub fn stroke_circle(&mut self, cx: f32, cy: f32, radius: f32, line_width: f32, color: Color) {
+        self.stroke_arc(
+            cx,
+            cy,
+            radius,
+            line_width,
+            0.0,
+            std::f32::consts::TAU,
+            color,
+        );
+    }
+
+    /// Strokes the part of a circle from `start` to `start + sweep`, in radians clockwise from
+    /// the positive x axis, with flat ends.
+    #[allow(clippy::too_many_arguments)]
+    pub fn stroke_arc(
+        &mut self,
+        cx: f32,
+        cy: f32,
+        radius: f32,
+        line_width: f32,
+        start: f32,
+        sweep: f32,
+        color: Color,
+    ) {
+        if sweep <= 0.0 {
+            return;
+        }
+        let outer = Rect::around(cx, cy, radius + line_width / 2.0);
+        self.fill(outer, color, |x, y| {
+            let ring = shape::stroke(shape::circle(x, y, (cx, cy), radius), line_width);
+            ring.max(shape::wedge(x, y, (cx, cy), start, sweep))
+        });
+    }
+
+    /// Blends `color` over a `width` by `height` block at (`x`, `y`), weighted by `coverage`,
+    /// one byte per pixel in rows. Used for text.
+    pub fn fill_mask(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: usize,
+        height: usize,
+        coverage: &[u8],
+        color: Color,
+    ) {
+        let area = Rect::new(x as f32, y as f32, width as f32, height as f32);
+        let Bounds { x0, y0, x1, y1 } = self.bounds(area);
+        for py in y0..y1 {
+            let row = (py as i32 - y) as usize * width;
+            for px in x0..x1 {
+                let value = coverage[row + (px as i32 - x) as usize];
+                if value > 0 {
+                    self.blend(px, py, color, value as f32 / 255.0);
+                }
+            }
+        }
+    }
+
+    /// Draws `image` scaled to cover `dest`, averaging the source pixels under each output
+    /// pixel when shrinking and interpolating between them when enlarging.
+    pub fn draw_image(&mut self, image: &Image, dest: Rect) {
+        if dest.is_empty() || image.width() == 0 || image.height() == 0 {
+            return;
+        }
+        let scale_x = image.width() as f32 / dest.width;
+        let scale_y = image.height() as f32 / dest.height;
+        let Bounds { x0, y0, x1, y1 } = self.bounds(dest);
+        for y in y0..y1 {
+            let top = (y as f32 - dest.y) * scale_y;
+            if top + scale_y / 2.0 < 0.0 || top + scale_y / 2.0 >= image.height() as f32 {
+                continue;
+            }
+            for x in x0..x1 {
+                let left = (x as f32 - dest.x) * scale_x;
+                if left + scale_x / 2.0 < 0.0 || left + scale_x / 2.0 >= image.width() as f32 {
+                    continue;
+                }
+                let pixel = if scale_x > 1.0 || scale_y > 1.0 {
+                    box_average(image, left, top, scale_x, scale_y)
+                } else {
+                    bilinear(image, left + scale_x / 2.0 - 0.5, top + scale_y / 2.0 - 0.5)
+                };
+                self.composite(x, y, pixel);
+            }
+        }
+    }
+
+    /// Repeats `image` at its own size across the canvas, starting in the top left corner.
+    pub fn tile_image(&mut self, image: &Image) {
+        if image.width() == 0 || image.height() == 0 {
+            return;
+        }
+        let Bounds { x0, y0, x1, y1 } = self.clip;
+        for y in y0..y1 {
+            let row = y as usize % image.height();
+            for x in x0..x1 {
+                self.composite(x, y, image.pixel(x as usize % image.width(), row));
+            }
+        }
+    }
+
+    /// Blends `color` over every pixel near `area`, weighted by how much of the pixel lies
+    /// inside the shape described by `distance`.
+    fn fill(&mut self, area: Rect, color: Color, distance: impl Fn(f32, f32) -> f32) {
+        if color.a == 0 || area.is_empty() {
+            return;
+        }
+        let Bounds { x0, y0, x1, y1 } = self.bounds(area.inflate(1.0));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let coverage = shape::coverage(distance(x as f32 + 0.5, y as f32 + 0.5));
+                if coverage > 0.0 {
+                    self.blend(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: Color, coverage: f32) {
+        let alpha = (color.a as f32 * coverage).round() as u32;
+        if alpha > 0 {
+            self.composite(x, y, color.premultiplied(alpha));
+        }
+    }
+
+    /// Draws the premultiplied pixel `src` over the one at (`x`, `y`).
+    fn composite(&mut self, x: u32, y: u32, src: u32) {
+        let alpha = src >> 24;
+        if alpha == 0 {
+            return;
+        }
+        let offset = y as usize * self.stride + x as usize * 4;
+        let out = if alpha == 0xff {
+            src
+        } else {
+            let dst = &self.pixels[offset..offset + 4];
+            let dst = u32::from_le_bytes([dst[0], dst[1], dst[2], dst[3]]);
+            [0, 8, 16, 24].into_iter().fold(0, |out, shift| {
+                let d = dst >> shift & 0xff;
+                out | ((src >> shift & 0xff) + div255(d * (0xff - alpha))) << shift
+            })
+        };
+        let out = self.finish(out);
+        self.pixels[offset..offset + 4].copy_from_slice(&out.to_le_bytes());
+    }
+
+    fn finish(&self, pixel: u32) -> u32 {
+        if self.opaque {
+            pixel | 0xff00_0000
+        } else {
+            pixel
+        }
+    }
+
+    /// The whole pixels overlapping `rect`, limited to the clip.
+    fn bounds(&self, rect: Rect) -> Bounds {
+        let (x, y, width, height) = rect.pixels();
+        let clamp = |v: i32, lo: u32, hi: u32| (v.max(0) as u32).clamp(lo, hi);
+        let x0 = clamp(x, self.clip.x0, self.clip.x1);
+        let y0 = clamp(y, self.clip.y0, self.clip.y1);
+        Bounds {
+            x0,
+            y0,
+            x1: clamp(x + width, x0, self.clip.x1),
+            y1: clamp(y + height, y0, self.clip.y1),
+        }
+    }
+}
+
+/// The mean of the source pixels whose centers lie in the `width` by `height` footprint at
+/// (`left`, `top`), or the nearest one if the footprint falls between centers.
+fn box_average(image: &Image, left: f32, top: f32, width: f32, height: f32) -> u32 {
+    let span = |start: f32, length: f32, limit: usize| {
+        let first = (start.max(0.0).round() as usize).min(limit - 1);
+        let last = ((start + length).round() as usize).clamp(first + 1, limit);
+        first..last
+    };
+    let (columns, rows) = (
+        span(left, width, image.width()),
+        span(top, height, image.height()),
+    );
+    let mut sums = [0u32; 4];
+    for y in rows.clone() {
+        for x in columns.clone() {
+            let pixel = image.pixel(x, y);
+            for (i, sum) in sums.iter_mut().enumerate() {
+                *sum += pixel >> (i * 8) & 0xff;
+            }
+        }
+    }
+    let count = (columns.len() * rows.len()) as u32;
+    sums.iter().enumerate().fold(0, |out, (i, sum)| {
+        out | ((sum + count / 2) / count) << (i * 8)
+    })
+}
+
+/// The image sampled between pixel centers at (`x`, `y`), clamped at the edges.
+fn bilinear(image: &Image, x: f32, y: f32) -> u32 {
+    let clamp = |v: f32, limit: usize| v.clamp(0.0, (limit - 1) as f32);
+    let (x, y) = (clamp(x, image.width()), clamp(y, image.height()));
+    let (x0, y0) = (x as usize, y as usize);
+    let x1 = (x0 + 1).min(image.width() - 1);
+    let y1 = (y0 + 1).min(image.height() - 1);
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let corners = [
+        (image.pixel(x0, y0), (1.0 - fx) * (1.0 - fy)),
+        (image.pixel(x1, y0), fx * (1.0 - fy)),
+        (image.pixel(x0, y1), (1.0 - fx) * fy),
+        (image.pixel(x1, y1), fx * fy),
+    ];
+    (0..4).fold(0, |out, i| {
+        let shift = i * 8;
+        let value: f32 = corners
+            .iter()
+            .map(|(pixel, weight)| (pixel >> shift & 0xff) as f32 * weight)
+            .sum();
+        out | (value.round() as u32).min(0xff) << shift
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::testing::{buffer, check};
+    use std::f32::consts::PI;
+
+    const BACKGROUND: Color = Color::rgb(0x20, 0x24, 0x30);
+    const ACCENT: Color = Color::rgb(0x4c, 0xaf, 0x50);
+
+    #[test]
+    fn circles_and_arcs() {
+        let (mut pixels, stride) = buffer(64, 48, 0);
+        let mut canvas = Canvas::new(&mut pixels, 64, 48, stride, Format::XRGB8888);
+        canvas.clear(BACKGROUND);
+        canvas.fill_circle(16.0, 16.0, 10.5, ACCENT);
+        canvas.stroke_circle(46.25, 16.75, 11.0, 3.0, Color::rgb(0xe0, 0xe0, 0xe0));
+        // A quarter turn starting at the top, and a translucent arc over a filled circle.
+        canvas.stroke_arc(16.0, 36.0, 8.0, 4.0, -PI / 2.0, PI / 2.0, ACCENT);
+        canvas.fill_circle(46.0, 36.0, 7.0, Color::rgb(0xdb, 0x44, 0x37));
+        canvas.stroke_arc(
+            46.0,
+            36.0,
+            7.0,
+            5.0,
+            PI / 4.0,
+            PI,
+            Color::rgba(0xff, 0xff, 0xff, 0x80),
+        );
+        check("circles_and_arcs", &pixels, 64, 48, stride);
+    }
+
+    #[test]
+    fn rectangles() {
+        let (mut pixels, stride) = buffer(64, 48, 0);
+        let mut canvas = Canvas::new(&mut pixels, 64, 48, stride, Format::XRGB8888);
+        canvas.clear(BACKGROUND);
+        // Edges between pixels are anti-aliased; the stroke straddles the edges.
+        canvas.fill_rect(Rect::new(4.5, 4.25, 20.0, 14.5), ACCENT);
+        canvas.stroke_rect(
+            Rect::new(36.0, 4.0, 22.0, 15.0),
+            2.5,
+            Color::rgb(0xe0, 0xe0, 0xe0),
+        );
+        canvas.fill_rounded_rect(
+            Rect::new(4.0, 26.0, 22.0, 16.0),
+            5.0,
+            Color::rgb(0xdb, 0x44, 0x37),
+        );
+        // A radius past half the shorter side is clamped to it, rounding the ends fully.
+        canvas.stroke_rounded_rect(
+            Rect::new(34.5, 28.0, 24.0, 12.0),
+            20.0,
+            3.0,
+            Color::rgba(0xff, 0xff, 0xff, 0x80),
+        );
+        check("rectangles", &pixels, 64, 48, stride);
+    }
+
+    #[test]
+    fn intersects_rects() {
+        let rect = Rect::new(2.0, 4.0, 10.0, 6.0);
+        assert_eq!(
+            rect.intersect(&Rect::new(8.5, 1.0, 10.0, 5.0)),
+            Rect::new(8.5, 4.0, 3.5, 2.0)
+        );
+        assert_eq!(rect.intersect(&Rect::new(0.0, 0.0, 20.0, 20.0)), rect);
+        // Disjoint rectangles meet in an empty one.
+        assert!(rect.intersect(&Rect::new(13.0, 4.0, 2.0, 2.0)).is_empty());
+    }
+
+    #[test]
+    fn blends_premultiplied_alpha() {
+        let (mut pixels, stride) = buffer(48, 32, 0);
+        let mut canvas = Canvas::new(&mut pixels, 48, 32, stride, Format::ARGB8888);
+        canvas.clear(Color::rgba(0, 0, 0, 0));
+        canvas.fill_circle(18.0, 16.0, 12.0, Color::rgba(0xdb, 0x44, 0x37, 0xc0));
+        canvas.fill_circle(30.0, 16.0, 12.0, Color::rgba(0x42, 0x85, 0xf4, 0x80));
+        check("blends_premultiplied_alpha", &pixels, 48, 32, stride);
+    }
+
+    #[test]
+    fn opaque_buffers_ignore_alpha() {
+        // The same translucent drawing as above, into a buffer that cannot show transparency.
+        let (mut pixels, stride) = buffer(48, 32, 0);
+        let mut canvas = Canvas::new(&mut pixels, 48, 32, stride, Format::XRGB8888);
+        canvas.clear(Color::rgba(0, 0, 0, 0));
+        canvas.fill_circle(18.0, 16.0, 12.0, Color::rgba(0xdb, 0x44, 0x37, 0xc0));
+        canvas.fill_circle(30.0, 16.0, 12.0, Color::rgba(0x42, 0x85, 0xf4, 0x80));
+        assert!(pixels.chunks_exact(4).all(|pixel| pixel[3] == 0xff));
+        check("opaque_buffers_ignore_alpha", &pixels, 48, 32, stride);
+    }
+
+    #[test]
+    fn clips_to_whole_pixels() {
+        // Rows are padded, as shm buffers may be, and the padding must stay untouched.
+        let (mut pixels, stride) = buffer(40, 40, 8);
+        let mut canvas = Canvas::new(&mut pixels, 40, 40, stride, Format::XRGB8888);
+        canvas.clear(BACKGROUND);
+        canvas.set_clip(Some(Rect::new(10.5, 4.0, 20.0, 24.25)));
+        canvas.clear(Color::rgb(0x30, 0x34, 0x40));
+        canvas.fill_circle(20.0, 20.0, 14.0, ACCENT);
+        canvas.set_clip(None);
+        canvas.stroke_circle(20.0, 20.0, 17.0, 1.5, Color::rgb(0xe0, 0xe0, 0xe0));
+        let padding = (0..40).flat_map(|y| {
+            let row = y * stride as usize;
+            &pixels[row + 160..row + stride as usize]
+        });
+        assert!(padding.into_iter().all(|&byte| byte == 0));
+        check("clips_to_whole_pixels", &pixels, 40, 40, stride);
+    }
+
+    #[test]
+    fn fills_masks() {
+        // A horizontal ramp of coverage, partly off the left edge of the canvas.
+        let mask: Vec<u8> = (0..8).flat_map(|_| (0..16).map(|x| x * 17)).collect();
+        let (mut pixels, stride) = buffer(24, 16, 0);
+        let mut canvas = Canvas::new(&mut pixels, 24, 16, stride, Format::XRGB8888);
+        canvas.clear(BACKGROUND);
+        canvas.fill_mask(12, 2, 16, 8, &mask, Color::rgb(0xff, 0xff, 0xff));
+        canvas.fill_mask(-4, 8, 16, 8, &mask, Color::rgba(0xdb, 0x44, 0x37, 0x80));
+        check("fills_masks", &pixels, 24, 16, stride);
+    }
+}
@@ -0,0 +1,43 @@
+/// A straight-alpha RGBA color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 0xff)
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Parses `rrggbb` or `rrggbbaa`, with or without a leading `#`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if !matches!(hex.len(), 6 | 8) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        let a = if hex.len() == 8 { channel(6)? } else { 0xff };
+        Some(Self::rgba(channel(0)?, channel(2)?, channel(4)?, a))
+    }
+
+    /// This color as a premultiplied ARGB8888 pixel, with its alpha replaced by `alpha`.
+    pub(super) fn premultiplied(self, alpha: u32) -> u32 {
+        let channel = |c: u8| div255(c as u32 * alpha);
+        alpha << 24 | channel(self.r) << 16 | channel(self.g) << 8 | channel(self.b)
+    }
+}
+
+/// `x / 255`, rounded, for `x` up to `255 * 255`.
+pub(super) fn div255(x: u32) -> u32 {
+    let x = x + 128;
+    (x + (x >> 8)) >> 8
+}
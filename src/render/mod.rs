@@ -0,0 +1,14 @@
+//! A small CPU rasterizer for drawing the lock screen into shm buffers.
+//!
+//! Shapes are anti-aliased by estimating each pixel's coverage from its signed distance to the
+//! shape's outline, then blended over the existing pixels.
+
+mod canvas;
+mod color;
+mod shape;
+#[cfg(test)]
+mod testing;
+pub mod text;
+
+pub use canvas::{Canvas, Rect};
+pub use color::Color;
@@ -0,0 +1,39 @@
+//! Signed distances from a point to shape outlines: negative inside, positive outside.
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// An axis-aligned box given by its center and half extents, with corners rounded by `radius`.
+pub fn rounded_box(x: f32, y: f32, center: (f32, f32), half: (f32, f32), radius: f32) -> f32 {
+    let radius = radius.clamp(0.0, half.0.min(half.1));
+    let qx = (x - center.0).abs() - half.0 + radius;
+    let qy = (y - center.1).abs() - half.1 + radius;
+    qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - radius
+}
+
+pub fn circle(x: f32, y: f32, center: (f32, f32), radius: f32) -> f32 {
+    (x - center.0).hypot(y - center.1) - radius
+}
+
+/// Turns a filled shape's distance into that of a line of `width` centered on its outline.
+pub fn stroke(distance: f32, width: f32) -> f32 {
+    distance.abs() - width / 2.0
+}
+
+/// The wedge between the rays at `start` and `start + sweep` radians from `center`, measured
+/// clockwise from the positive x axis. Distances are exact near the rays, which is all the
+/// anti-aliasing of an arc's ends needs.
+pub fn wedge(x: f32, y: f32, center: (f32, f32), start: f32, sweep: f32) -> f32 {
+    if sweep >= TAU {
+        return f32::NEG_INFINITY;
+    }
+    let (dx, dy) = (x - center.0, y - center.1);
+    let mid = start + sweep / 2.0;
+    let offset = (dy.atan2(dx) - mid + PI).rem_euclid(TAU) - PI;
+    let outside = offset.abs() - sweep / 2.0;
+    dx.hypot(dy) * outside.clamp(-FRAC_PI_2, FRAC_PI_2).sin()
+}
+
+/// Coverage of the pixel centered on a point at `distance` from an outline.
+pub fn coverage(distance: f32) -> f32 {
+    (0.5 - distance).clamp(0.0, 1.0)
+}
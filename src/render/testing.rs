@@ -0,0 +1,90 @@
+//! Comparing drawings with reference images, for tests.
+
+use crate::render::color::div255;
+use std::{fs, path::PathBuf};
+
+/// A blank `width` by `height` buffer with `padding` unused bytes at the end of each row.
+pub fn buffer(width: u32, height: u32, padding: u32) -> (Vec<u8>, u32) {
+    let stride = width * 4 + padding;
+    (vec![0; (stride * height) as usize], stride)
+}
+
+/// Compares the drawing with `testdata/render/<name>.pam`, allowing each sample to be off
+/// by one for differences in floating point. With `LOCK_UPDATE_REFERENCES` set, the
+/// reference is written instead.
+///
+/// References are stored with straight alpha so that image viewers show them as drawn;
+/// premultiplying them again gives back the buffer exactly.
+pub fn check(name: &str, pixels: &[u8], width: u32, height: u32, stride: u32) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata/render")
+        .join(format!("{name}.pam"));
+    let drawn: Vec<[u8; 4]> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (y * stride + x * 4) as usize))
+        .map(|offset| {
+            let [b, g, r, a] = pixels[offset..offset + 4].try_into().unwrap();
+            [r, g, b, a]
+        })
+        .collect();
+    if std::env::var_os("LOCK_UPDATE_REFERENCES").is_some() {
+        let mut file = format!(
+            "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n"
+        )
+        .into_bytes();
+        file.extend(drawn.iter().flat_map(|&pixel| straight(pixel)));
+        fs::write(&path, file).unwrap();
+        return;
+    }
+    let file = fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    let (size, data) = parse_pam(&file);
+    assert_eq!(size, (width, height), "{name}: size differs");
+    let expected = data.chunks_exact(4).map(|pixel| {
+        let [r, g, b, a] = pixel.try_into().unwrap();
+        let channel = |c: u8| div255(c as u32 * a as u32) as u8;
+        [channel(r), channel(g), channel(b), a]
+    });
+    let mut differences =
+        drawn
+            .iter()
+            .zip(expected)
+            .enumerate()
+            .filter(|(_, (drawn, expected))| {
+                drawn.iter().zip(expected).any(|(d, e)| d.abs_diff(*e) > 1)
+            });
+    if let Some((i, (drawn, expected))) = differences.next() {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        panic!(
+            "{name}: differs at {} of {} pixels, first at ({x}, {y}): drew {drawn:?}, expected {expected:?}",
+            differences.count() + 1,
+            width * height
+        );
+    }
+}
+
+/// A premultiplied RGBA pixel with its color divided by its alpha again.
+fn straight([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    let channel = |c: u8| match a {
+        0 => 0,
+        a => ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8,
+    };
+    [channel(r), channel(g), channel(b), a]
+}
+
+/// The size and samples of a PAM image as `check` writes them.
+fn parse_pam(file: &[u8]) -> ((u32, u32), &[u8]) {
+    const END: &[u8] = b"ENDHDR\n";
+    let end = file
+        .windows(END.len())
+        .position(|window| window == END)
+        .expect("no PAM header")
+        + END.len();
+    let header = std::str::from_utf8(&file[..end]).unwrap();
+    let field = |name: &str| -> u32 {
+        header
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.trim().parse().ok())
+            .unwrap_or_else(|| panic!("no {name} in the PAM header"))
+    };
+    assert!(header.starts_with("P7\n") && field("DEPTH") == 4 && field("MAXVAL") == 255);
+    ((field("WIDTH"), field("HEIGHT")), &file[end..])
+}
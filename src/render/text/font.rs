@@ -0,0 +1,418 @@
+use crate::render::{
+    Canvas, Color,
+    text::{
+        Error,
+        kern::Kerning,
+        lookup,
+        outline::{Glyphs, Point, Segment},
+        raster::Rasterizer,
+        sfnt::{self, Tag, i16_at, u16_at, u32_at},
+    },
+};
+use std::{cell::RefCell, collections::HashMap, fs, ops::Range, path::Path, rc::Rc};
+
+/// Rasterized glyphs kept before the cache starts over; a lock screen shows few distinct ones.
+const CACHE_LIMIT: usize = 1024;
+
+/// A glyph's coverage mask, placed relative to the pen position on the baseline.
+#[derive(Debug)]
+struct Bitmap {
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+    coverage: Vec<u8>,
+}
+
+/// One face of a TrueType font file, with a cache of the glyphs drawn from it.
+#[derive(Debug)]
+pub struct Font {
+    data: Vec<u8>,
+    tables: HashMap<Tag, Range<usize>>,
+    units_per_em: f32,
+    long_loca: bool,
+    num_glyphs: u16,
+    num_h_metrics: u16,
+    ascender: i16,
+    descender: i16,
+    /// The preferred Unicode subtable of `cmap` and its format.
+    cmap: (usize, u16),
+    kerning: Kerning,
+    /// Keyed by glyph id and pixel size.
+    cache: RefCell<HashMap<(u16, u32), Rc<Bitmap>>>,
+}
+
+impl Font {
+    /// Finds an installed font by family name, or one of the generic `sans-serif`, `serif` and
+    /// `monospace` families. A path to a font file is also accepted.
+    pub fn find(family: &str) -> Result<Self, Error> {
+        if family.contains('/') {
+            return Self::from_file(Path::new(family), 0);
+        }
+        let (path, index) = lookup::find(family)?;
+        Self::from_file(&path, index)
+    }
+
+    /// Loads face `index` of a font file or collection.
+    pub fn from_file(path: &Path, index: u32) -> Result<Self, Error> {
+        let data = fs::read(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        Self::from_bytes(data, index)
+    }
+
+    pub fn from_bytes(data: Vec<u8>, index: u32) -> Result<Self, Error> {
+        let offset = sfnt::face_offset(&data, index).ok_or(Error::Malformed("header"))?;
+        let tables = sfnt::tables(&data, offset).ok_or(Error::Malformed("table directory"))?;
+        if tables.values().any(|range| range.end > data.len()) {
+            return Err(Error::Malformed("table directory"));
+        }
+        if !tables.contains_key(b"glyf") {
+            return Err(Error::Unsupported(if tables.contains_key(b"CFF ") {
+                "CFF outlines"
+            } else {
+                "no outlines"
+            }));
+        }
+        let table = |tag: &Tag| tables.get(tag).cloned().ok_or(Error::Malformed("required"));
+        let head = table(b"head")?.start;
+        let maxp = table(b"maxp")?.start;
+        let hhea = table(b"hhea")?.start;
+        table(b"hmtx")?;
+        table(b"loca")?;
+
+        let units_per_em = u16_at(&data, head + 18).ok_or(Error::Malformed("head"))?;
+        let long_loca = i16_at(&data, head + 50).ok_or(Error::Malformed("head"))? != 0;
+        let num_glyphs = u16_at(&data, maxp + 4).ok_or(Error::Malformed("maxp"))?;
+        let (Some(ascender), Some(descender), Some(num_h_metrics)) = (
+            i16_at(&data, hhea + 4),
+            i16_at(&data, hhea + 6),
+            u16_at(&data, hhea + 34),
+        ) else {
+            return Err(Error::Malformed("hhea"));
+        };
+        if units_per_em == 0 || num_h_metrics == 0 {
+            return Err(Error::Malformed("head"));
+        }
+        let cmap = unicode_cmap(&data, table(b"cmap")?.start).ok_or(Error::Malformed("cmap"))?;
+        let kerning = Kerning::new(
+            &data,
+            tables.get(b"GPOS").cloned(),
+            tables.get(b"kern").cloned(),
+        );
+
+        Ok(Self {
+            data,
+            tables,
+            units_per_em: units_per_em as f32,
+            long_loca,
+            num_glyphs,
+            num_h_metrics,
+            ascender,
+            descender,
+            cmap,
+            kerning,
+            cache: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Distance from the baseline to the top of tall glyphs, in pixels at `size`.
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.ascender as f32 * self.scale(size)
+    }
+
+    /// Distance from the baseline to the bottom of descenders, as a positive number of pixels.
+    pub fn descent(&self, size: f32) -> f32 {
+        -(self.descender as f32) * self.scale(size)
+    }
+
+    /// Width of `text` set at `size` pixels per em.
+    pub fn measure(&self, text: &str, size: f32) -> f32 {
+        self.layout(text, size).last().map_or(0.0, |&(glyph, x)| {
+            x + self.advance(glyph) * self.scale(size)
+        })
+    }
+
+    /// Draws `text` with its baseline starting at (`x`, `y`).
+    pub fn draw(&self, canvas: &mut Canvas, text: &str, size: f32, x: f32, y: f32, color: Color) {
+        let size_key = size.to_bits();
+        for (glyph, offset) in self.layout(text, size) {
+            let bitmap = self.bitmap(glyph, size, size_key);
+            if bitmap.width == 0 {
+                continue;
+            }
+            canvas.fill_mask(
+                (x + offset).round() as i32 + bitmap.left,
+                y.round() as i32 + bitmap.top,
+                bitmap.width,
+                bitmap.height,
+                &bitmap.coverage,
+                color,
+            );
+        }
+    }
+
+    /// Each glyph of `text` with its pen position relative to the start, kerning applied.
+    fn layout(&self, text: &str, size: f32) -> Vec<(u16, f32)> {
+        let scale = self.scale(size);
+        let mut pen = 0.0;
+        let mut previous = None;
+        text.chars()
+            .map(|c| {
+                let glyph = self.glyph_index(c);
+                if let Some(previous) = previous {
+                    pen += (self.advance(previous)
+                        + self.kerning.pair(&self.data, previous, glyph) as f32)
+                        * scale;
+                }
+                previous = Some(glyph);
+                (glyph, pen)
+            })
+            .collect()
+    }
+
+    fn scale(&self, size: f32) -> f32 {
+        size / self.units_per_em
+    }
+
+    fn table(&self, tag: &Tag) -> &[u8] {
+        self.tables
+            .get(tag)
+            .map_or(&[], |range| &self.data[range.clone()])
+    }
+
+    fn glyphs(&self) -> Glyphs<'_> {
+        Glyphs {
+            glyf: self.table(b"glyf"),
+            loca: self.table(b"loca"),
+            long_offsets: self.long_loca,
+        }
+    }
+
+    /// The glyph for `c`, or the missing glyph 0.
+    fn glyph_index(&self, c: char) -> u16 {
+        let (subtable, format) = self.cmap;
+        let glyph = match format {
+            4 => cmap4(&self.data, subtable, c as u32),
+            12 => cmap12(&self.data, subtable, c as u32),
+            _ => None,
+        };
+        glyph.filter(|&glyph| glyph < self.num_glyphs).unwrap_or(0)
+    }
+
+    /// Advance width in font units.
+    fn advance(&self, glyph: u16) -> f32 {
+        let hmtx = self.table(b"hmtx");
+        let index = glyph.min(self.num_h_metrics - 1) as usize;
+        u16_at(hmtx, index * 4).unwrap_or(0) as f32
+    }
+
+    fn bitmap(&self, glyph: u16, size: f32, size_key: u32) -> Rc<Bitmap> {
+        if let Some(bitmap) = self.cache.borrow().get(&(glyph, size_key)) {
+            return bitmap.clone();
+        }
+        let bitmap = Rc::new(self.rasterize(glyph, size));
+        let mut cache = self.cache.borrow_mut();
+        if cache.len() >= CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert((glyph, size_key), bitmap.clone());
+        bitmap
+    }
+
+    fn rasterize(&self, glyph: u16, size: f32) -> Bitmap {
+        let glyphs = self.glyphs();
+        let empty = Bitmap {
+            left: 0,
+            top: 0,
+            width: 0,
+            height: 0,
+            coverage: Vec::new(),
+        };
+        let Some((x_min, y_min, x_max, y_max)) = glyphs.bounds(glyph) else {
+            return empty;
+        };
+        let scale = self.scale(size);
+        // Font units grow upwards, pixels downwards.
+        let left = (x_min as f32 * scale).floor() as i32;
+        let top = (-y_max as f32 * scale).floor() as i32;
+        let right = (x_max as f32 * scale).ceil() as i32 + 1;
+        let bottom = (-y_min as f32 * scale).ceil() as i32 + 1;
+        if right <= left || bottom <= top {
+            return empty;
+        }
+        let (width, height) = ((right - left) as usize, (bottom - top) as usize);
+
+        let mut rasterizer = Rasterizer::new(width, height);
+        let to_pixels = |p: Point| Point {
+            x: p.x * scale - left as f32,
+            y: -p.y * scale - top as f32,
+        };
+        for segment in glyphs.outline(glyph) {
+            match segment {
+                Segment::Line(p0, p1) => rasterizer.line(to_pixels(p0), to_pixels(p1)),
+                Segment::Quad(p0, control, p1) => {
+                    rasterizer.quad(to_pixels(p0), to_pixels(control), to_pixels(p1))
+                }
+            }
+        }
+        Bitmap {
+            left,
+            top,
+            width,
+            height,
+            coverage: rasterizer.coverage(),
+        }
+    }
+}
+
+/// The best Unicode subtable: full repertoire (format 12) before BMP-only (format 4).
+fn unicode_cmap(data: &[u8], cmap: usize) -> Option<(usize, u16)> {
+    let count = u16_at(data, cmap + 2)? as usize;
+    let mut best: Option<(usize, u16)> = None;
+    for i in 0..count {
+        let record = cmap + 4 + i * 8;
+        let platform = u16_at(data, record)?;
+        let encoding = u16_at(data, record + 2)?;
+        let subtable = cmap + u32_at(data, record + 4)? as usize;
+        let unicode = platform == 0 || (platform == 3 && matches!(encoding, 1 | 10));
+        let format = u16_at(data, subtable)?;
+        if !unicode || !matches!(format, 4 | 12) {
+            continue;
+        }
+        if best.is_none_or(|(_, best)| format > best) {
+            best = Some((subtable, format));
+        }
+    }
+    best
+}
+
+fn cmap4(data: &[u8], subtable: usize, c: u32) -> Option<u16> {
+    let c = u16::try_from(c).ok()?;
+    let segments = u16_at(data, subtable + 6)? as usize / 2;
+    let ends = subtable + 14;
+    let starts = ends + segments * 2 + 2;
+    let deltas = starts + segments * 2;
+    let range_offsets = deltas + segments * 2;
+    let (mut low, mut high) = (0, segments);
+    while low < high {
+        let mid = (low + high) / 2;
+        if u16_at(data, ends + mid * 2)? < c {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    let segment = low;
+    if segment >= segments {
+        return None;
+    }
+    let start = u16_at(data, starts + segment * 2)?;
+    if c < start {
+        return None;
+    }
+    let delta = u16_at(data, deltas + segment * 2)?;
+    let range_offset_at = range_offsets + segment * 2;
+    let range_offset = u16_at(data, range_offset_at)?;
+    if range_offset == 0 {
+        return Some(c.wrapping_add(delta));
+    }
+    // The offset is relative to where it is stored, into the glyph id array that follows.
+    let glyph = u16_at(
+        data,
+        range_offset_at + range_offset as usize + (c - start) as usize * 2,
+    )?;
+    (glyph != 0).then(|| glyph.wrapping_add(delta))
+}
+
+fn cmap12(data: &[u8], subtable: usize, c: u32) -> Option<u16> {
+    let groups = u32_at(data, subtable + 12)? as usize;
+    let (mut low, mut high) = (0, groups);
+    while low < high {
+        let mid = (low + high) / 2;
+        let group = subtable + 16 + mid * 12;
+        let (start, end) = (u32_at(data, group)?, u32_at(data, group + 4)?);
+        if c < start {
+            high = mid;
+        } else if c > end {
+            low = mid + 1;
+        } else {
+            return u16::try_from(u32_at(data, group + 8)? + (c - start)).ok();
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        render::testing::{buffer, check},
+        wayland::Format,
+    };
+    use std::path::PathBuf;
+
+    const BACKGROUND: Color = Color::rgb(0x20, 0x24, 0x30);
+    const TEXT: Color = Color::rgb(0xe0, 0xe0, 0xe0);
+
+    fn dejavu_sans() -> Font {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/fonts/DejaVuSans.ttf");
+        Font::from_file(&path, 0).unwrap()
+    }
+
+    /// Draws each line of `lines` at its size and baseline on a `width` by `height` canvas and
+    /// compares the result with the reference `name`.
+    fn render(name: &str, width: u32, height: u32, lines: &[(&str, f32, f32)]) {
+        let font = dejavu_sans();
+        let (mut pixels, stride) = buffer(width, height, 0);
+        let mut canvas = Canvas::new(&mut pixels, width, height, stride, Format::XRGB8888);
+        canvas.clear(BACKGROUND);
+        for &(text, size, baseline) in lines {
+            font.draw(&mut canvas, text, size, 4.0, baseline, TEXT);
+        }
+        check(name, &pixels, width, height, stride);
+    }
+
+    #[test]
+    fn draws_messages() {
+        render(
+            "text_messages",
+            160,
+            64,
+            &[
+                ("Wrong password", 16.0, 20.0),
+                // Messages shrink to fit the ring, so sizes are seldom whole.
+                ("Caps Lock", 21.7, 48.3),
+            ],
+        );
+    }
+
+    #[test]
+    fn draws_composite_glyphs() {
+        // Accented letters are built from a base glyph and a positioned accent.
+        render("text_composites", 112, 36, &[("Déjà Ŵü", 18.0, 26.0)]);
+    }
+
+    #[test]
+    fn draws_missing_glyphs_as_boxes() {
+        render("text_missing", 64, 32, &[("a\u{1f512}b", 18.0, 22.0)]);
+    }
+
+    #[test]
+    fn kerns_pairs() {
+        let font = dejavu_sans();
+        let apart = font.measure("A", 20.0) + font.measure("V", 20.0);
+        assert!(font.measure("AV", 20.0) < apart - 1.0);
+        assert_eq!(
+            font.measure("AB", 20.0),
+            font.measure("A", 20.0) + font.measure("B", 20.0)
+        );
+    }
+
+    #[test]
+    fn measures_metrics() {
+        let font = dejavu_sans();
+        // DejaVu Sans has 2048 units per em, so at that size pixels are font units.
+        assert_eq!(font.ascent(2048.0), 1901.0);
+        assert_eq!(font.descent(2048.0), 483.0);
+        assert_eq!(font.measure("", 20.0), 0.0);
+    }
+}
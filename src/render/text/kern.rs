@@ -0,0 +1,222 @@
+//! Pair kerning from GPOS pair adjustment lookups, or the older `kern` table for fonts without
+//! them. Only horizontal advance adjustments are applied.
+
+use crate::render::text::sfnt::{i16_at, u16_at, u32_at};
+use std::{cmp::Ordering, ops::Range};
+
+const PAIR_ADJUSTMENT: u16 = 2;
+const EXTENSION: u16 = 9;
+/// Value record fields that come before the x advance.
+const X_PLACEMENT: u16 = 0x0001;
+const Y_PLACEMENT: u16 = 0x0002;
+const X_ADVANCE: u16 = 0x0004;
+
+#[derive(Debug, Default)]
+pub struct Kerning {
+    /// Offsets of the GPOS pair adjustment subtables reachable from `kern` features.
+    pair_subtables: Vec<usize>,
+    /// Offset of a horizontal format 0 subtable in the `kern` table.
+    kern_pairs: Option<usize>,
+}
+
+impl Kerning {
+    pub fn new(data: &[u8], gpos: Option<Range<usize>>, kern: Option<Range<usize>>) -> Self {
+        let pair_subtables = gpos
+            .and_then(|gpos| pair_subtables(data, gpos.start))
+            .unwrap_or_default();
+        let kern_pairs = if pair_subtables.is_empty() {
+            kern.and_then(|kern| kern_subtable(data, kern.start))
+        } else {
+            None
+        };
+        Self {
+            pair_subtables,
+            kern_pairs,
+        }
+    }
+
+    /// The adjustment, in font units, to the advance of `left` when `right` follows it.
+    pub fn pair(&self, data: &[u8], left: u16, right: u16) -> i16 {
+        if let Some(value) = self
+            .pair_subtables
+            .iter()
+            .find_map(|&subtable| pair_adjustment(data, subtable, left, right))
+        {
+            return value;
+        }
+        self.kern_pairs
+            .and_then(|subtable| kern_pair(data, subtable, left, right))
+            .unwrap_or(0)
+    }
+}
+
+fn pair_subtables(data: &[u8], gpos: usize) -> Option<Vec<usize>> {
+    let features = gpos + u16_at(data, gpos + 6)? as usize;
+    let lookups = gpos + u16_at(data, gpos + 8)? as usize;
+
+    let mut indices = Vec::new();
+    for i in 0..u16_at(data, features)? as usize {
+        let record = features + 2 + i * 6;
+        if data.get(record..record + 4)? != b"kern" {
+            continue;
+        }
+        let feature = features + u16_at(data, record + 4)? as usize;
+        for j in 0..u16_at(data, feature + 2)? as usize {
+            let index = u16_at(data, feature + 4 + j * 2)?;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+    }
+    indices.sort_unstable();
+
+    let mut subtables = Vec::new();
+    for index in indices {
+        let lookup = lookups + u16_at(data, lookups + 2 + index as usize * 2)? as usize;
+        let kind = u16_at(data, lookup)?;
+        for i in 0..u16_at(data, lookup + 4)? as usize {
+            let subtable = lookup + u16_at(data, lookup + 6 + i * 2)? as usize;
+            match kind {
+                PAIR_ADJUSTMENT => subtables.push(subtable),
+                EXTENSION if u16_at(data, subtable + 2)? == PAIR_ADJUSTMENT => {
+                    subtables.push(subtable + u32_at(data, subtable + 4)? as usize);
+                }
+                _ => {}
+            }
+        }
+    }
+    Some(subtables)
+}
+
+fn pair_adjustment(data: &[u8], subtable: usize, left: u16, right: u16) -> Option<i16> {
+    let format = u16_at(data, subtable)?;
+    let coverage_index = coverage(data, subtable + u16_at(data, subtable + 2)? as usize, left)?;
+    let value_format1 = u16_at(data, subtable + 4)?;
+    let value_format2 = u16_at(data, subtable + 6)?;
+    let size1 = value_format1.count_ones() as usize * 2;
+    let size2 = value_format2.count_ones() as usize * 2;
+    let record = match format {
+        1 => {
+            let set_offset = u16_at(data, subtable + 10 + coverage_index as usize * 2)?;
+            let set = subtable + set_offset as usize;
+            let count = u16_at(data, set)? as usize;
+            let stride = 2 + size1 + size2;
+            let (mut low, mut high) = (0, count);
+            loop {
+                if low >= high {
+                    return None;
+                }
+                let mid = (low + high) / 2;
+                let record = set + 2 + mid * stride;
+                match u16_at(data, record)?.cmp(&right) {
+                    Ordering::Less => low = mid + 1,
+                    Ordering::Greater => high = mid,
+                    Ordering::Equal => break record + 2,
+                }
+            }
+        }
+        2 => {
+            let class1 = class(data, subtable + u16_at(data, subtable + 8)? as usize, left)?;
+            let class2 = class(
+                data,
+                subtable + u16_at(data, subtable + 10)? as usize,
+                right,
+            )?;
+            let class2_count = u16_at(data, subtable + 14)? as usize;
+            subtable + 16 + (class1 as usize * class2_count + class2 as usize) * (size1 + size2)
+        }
+        _ => return None,
+    };
+    if value_format1 & X_ADVANCE == 0 {
+        return Some(0);
+    }
+    let before = (value_format1 & (X_PLACEMENT | Y_PLACEMENT)).count_ones() as usize * 2;
+    i16_at(data, record + before)
+}
+
+/// Index of `glyph` in a coverage table, if it is covered.
+fn coverage(data: &[u8], table: usize, glyph: u16) -> Option<u16> {
+    let count = u16_at(data, table + 2)? as usize;
+    match u16_at(data, table)? {
+        1 => {
+            let (mut low, mut high) = (0, count);
+            while low < high {
+                let mid = (low + high) / 2;
+                match u16_at(data, table + 4 + mid * 2)?.cmp(&glyph) {
+                    Ordering::Less => low = mid + 1,
+                    Ordering::Greater => high = mid,
+                    Ordering::Equal => return Some(mid as u16),
+                }
+            }
+            None
+        }
+        2 => (0..count).find_map(|i| {
+            let range = table + 4 + i * 6;
+            let (start, end) = (u16_at(data, range)?, u16_at(data, range + 2)?);
+            (start..=end)
+                .contains(&glyph)
+                .then(|| Some(u16_at(data, range + 4)? + (glyph - start)))
+                .flatten()
+        }),
+        _ => None,
+    }
+}
+
+/// The class of `glyph` in a class definition table; glyphs not listed are class 0.
+fn class(data: &[u8], table: usize, glyph: u16) -> Option<u16> {
+    match u16_at(data, table)? {
+        1 => {
+            let start = u16_at(data, table + 2)?;
+            let count = u16_at(data, table + 4)?;
+            if glyph < start || glyph - start >= count {
+                return Some(0);
+            }
+            u16_at(data, table + 6 + (glyph - start) as usize * 2)
+        }
+        2 => {
+            let count = u16_at(data, table + 2)? as usize;
+            for i in 0..count {
+                let range = table + 4 + i * 6;
+                let (start, end) = (u16_at(data, range)?, u16_at(data, range + 2)?);
+                if (start..=end).contains(&glyph) {
+                    return u16_at(data, range + 4);
+                }
+            }
+            Some(0)
+        }
+        _ => None,
+    }
+}
+
+/// The first horizontal, non-cross-stream format 0 subtable of a version 0 `kern` table.
+fn kern_subtable(data: &[u8], kern: usize) -> Option<usize> {
+    if u16_at(data, kern)? != 0 {
+        return None;
+    }
+    let mut subtable = kern + 4;
+    for _ in 0..u16_at(data, kern + 2)? {
+        let len = u16_at(data, subtable + 2)? as usize;
+        let coverage = u16_at(data, subtable + 4)?;
+        // Horizontal, not minimum values, not cross-stream, format 0.
+        if coverage & 0x0007 == 0x0001 && coverage >> 8 == 0 {
+            return Some(subtable + 6);
+        }
+        subtable += len;
+    }
+    None
+}
+
+fn kern_pair(data: &[u8], pairs: usize, left: u16, right: u16) -> Option<i16> {
+    let key = (left as u32) << 16 | right as u32;
+    let (mut low, mut high) = (0, u16_at(data, pairs)? as usize);
+    while low < high {
+        let mid = (low + high) / 2;
+        let record = pairs + 8 + mid * 6;
+        match u32_at(data, record)?.cmp(&key) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return i16_at(data, record + 4),
+        }
+    }
+    None
+}
@@ -0,0 +1,174 @@
+//! Finding font files by family name in the XDG font directories, without fontconfig.
+
+use crate::{
+    config::{env_path, xdg_dir},
+    render::text::{Error, sfnt},
+};
+use std::{
+    env,
+    fs::{self, File},
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+};
+
+/// Families tried, in order, for the generic names fontconfig would resolve.
+const SANS_SERIF: &[&str] = &[
+    "DejaVu Sans",
+    "Noto Sans",
+    "Liberation Sans",
+    "Cantarell",
+    "Roboto",
+    "FreeSans",
+];
+const SERIF: &[&str] = &[
+    "DejaVu Serif",
+    "Noto Serif",
+    "Liberation Serif",
+    "FreeSerif",
+];
+const MONOSPACE: &[&str] = &[
+    "DejaVu Sans Mono",
+    "Noto Sans Mono",
+    "Liberation Mono",
+    "FreeMono",
+];
+/// Style names of the upright, regular weight face, which is preferred within a family.
+const REGULAR: &[&str] = &["Regular", "Book", "Normal", "Roman", "Medium"];
+/// Directories nest, but a loop of symlinks should not hang the locker.
+const MAX_DEPTH: u32 = 8;
+
+/// A face found on disk: its file, index in that file, family and style names.
+struct Face {
+    path: PathBuf,
+    index: u32,
+    families: Vec<String>,
+    styles: Vec<String>,
+}
+
+/// The file and face index of `family`, preferring its regular face.
+pub fn find(family: &str) -> Result<(PathBuf, u32), Error> {
+    let candidates: Vec<&str> = match family.to_ascii_lowercase().as_str() {
+        "sans-serif" | "sans" => SANS_SERIF.to_vec(),
+        "serif" => SERIF.to_vec(),
+        "monospace" | "mono" => MONOSPACE.to_vec(),
+        _ => vec![family],
+    };
+    let mut faces = Vec::new();
+    for dir in font_dirs() {
+        scan(&dir, 0, &mut faces);
+    }
+    candidates
+        .iter()
+        .find_map(|family| {
+            let mut matching = faces.iter().filter(|face| {
+                face.families
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(family))
+            });
+            let first = matching.clone().next()?;
+            Some(
+                matching
+                    .find(|face| {
+                        face.styles
+                            .iter()
+                            .any(|style| REGULAR.iter().any(|r| style.eq_ignore_ascii_case(r)))
+                    })
+                    .unwrap_or(first),
+            )
+        })
+        .map(|face| (face.path.clone(), face.index))
+        .ok_or_else(|| Error::NotFound(family.to_string()))
+}
+
+/// `$XDG_DATA_HOME/fonts`, `~/.fonts` and `fonts` under each of `$XDG_DATA_DIRS`.
+fn font_dirs() -> Vec<PathBuf> {
+    let data_home = xdg_dir("XDG_DATA_HOME", ".local/share");
+    let data_dirs =
+        env_path("XDG_DATA_DIRS").unwrap_or_else(|| PathBuf::from("/usr/local/share:/usr/share"));
+    data_home
+        .into_iter()
+        .chain(env::split_paths(&data_dirs))
+        .map(|dir| dir.join("fonts"))
+        .chain(env_path("HOME").map(|home| home.join(".fonts")))
+        .collect()
+}
+
+fn scan(dir: &Path, depth: u32, faces: &mut Vec<Face>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            if depth < MAX_DEPTH {
+                scan(&path, depth + 1, faces);
+            }
+            continue;
+        }
+        let is_font = path.extension().is_some_and(|ext| {
+            ["ttf", "otf", "ttc"]
+                .iter()
+                .any(|font| ext.eq_ignore_ascii_case(font))
+        });
+        if is_font {
+            read_faces(&path, faces);
+        }
+    }
+}
+
+/// Reads only the headers and `name` tables, as font directories can hold gigabytes.
+fn read_faces(path: &Path, faces: &mut Vec<Face>) {
+    let Ok(file) = File::open(path) else {
+        return;
+    };
+    let read = |offset: usize, len: usize| {
+        let mut buf = vec![0; len];
+        file.read_exact_at(&mut buf, offset as u64)
+            .ok()
+            .map(|()| buf)
+    };
+    let Some(header) = read(0, 12) else {
+        return;
+    };
+    let count = sfnt::face_count(&header).min(256);
+    let offsets = if count > 1 || header.starts_with(b"ttcf") {
+        read(0, 12 + count as usize * 4)
+    } else {
+        Some(header)
+    };
+    let Some(offsets) = offsets else {
+        return;
+    };
+    for index in 0..count {
+        let Some(offset) = sfnt::face_offset(&offsets, index) else {
+            continue;
+        };
+        let Some(num_tables) = read(offset + 4, 2).and_then(|n| sfnt::u16_at(&n, 0)) else {
+            continue;
+        };
+        let Some(directory) = read(offset, 12 + num_tables as usize * 16) else {
+            continue;
+        };
+        let Some(tables) = sfnt::tables(&directory, 0) else {
+            continue;
+        };
+        // Only faces this renderer can draw are worth finding.
+        if !tables.contains_key(b"glyf") {
+            continue;
+        }
+        let Some(name) = tables
+            .get(b"name")
+            .and_then(|range| read(range.start, range.len()))
+        else {
+            continue;
+        };
+        let (families, styles) = sfnt::names(&name);
+        faces.push(Face {
+            path: path.to_path_buf(),
+            index,
+            families,
+            styles,
+        });
+    }
+}
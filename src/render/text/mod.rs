@@ -0,0 +1,34 @@
+//! TrueType text: fonts with `glyf` outlines are parsed directly from their tables, glyphs are
+//! rasterized with anti-aliasing into coverage masks, and masks are cached per size.
+//!
+//! OpenType fonts with CFF outlines are rejected, as are shaping features beyond pair kerning.
+
+mod font;
+mod kern;
+mod lookup;
+mod outline;
+mod raster;
+mod sfnt;
+
+use std::{fmt, io, path::PathBuf};
+
+pub use font::Font;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Malformed(&'static str),
+    Unsupported(&'static str),
+    NotFound(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "Failed to read font {}: {e}", path.display()),
+            Self::Malformed(table) => write!(f, "Font has a malformed {table} table"),
+            Self::Unsupported(what) => write!(f, "Fonts with {what} are not supported"),
+            Self::NotFound(family) => write!(f, "No font found for family {family}"),
+        }
+    }
+}
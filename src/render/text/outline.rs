@@ -0,0 +1,268 @@
+//! Glyph outlines from the `glyf` table, as lines and quadratic curves in font units.
+
+use crate::render::text::sfnt::{Stream, i16_at, u16_at, u32_at};
+
+/// Composite glyphs may nest, but not without bound.
+const MAX_DEPTH: u32 = 8;
+
+const ON_CURVE: u8 = 0x01;
+const X_SHORT: u8 = 0x02;
+const Y_SHORT: u8 = 0x04;
+const REPEAT: u8 = 0x08;
+const X_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+const ARGS_ARE_WORDS: u16 = 0x0001;
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const HAVE_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const HAVE_XY_SCALE: u16 = 0x0040;
+const HAVE_TWO_BY_TWO: u16 = 0x0080;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    fn midpoint(self, other: Self) -> Self {
+        Self {
+            x: (self.x + other.x) / 2.0,
+            y: (self.y + other.y) / 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Segment {
+    Line(Point, Point),
+    Quad(Point, Point, Point),
+}
+
+/// A 2x3 affine matrix, `[xx, yx, xy, yy, dx, dy]`, as composite glyphs place their parts.
+#[derive(Debug, Clone, Copy)]
+struct Transform([f32; 6]);
+
+impl Transform {
+    const IDENTITY: Self = Self([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn apply(&self, x: f32, y: f32) -> Point {
+        let [xx, yx, xy, yy, dx, dy] = self.0;
+        Point {
+            x: xx * x + xy * y + dx,
+            y: yx * x + yy * y + dy,
+        }
+    }
+
+    /// `inner` applied first, then `self`.
+    fn then(&self, inner: &Self) -> Self {
+        let [a, b, c, d, e, f] = self.0;
+        let [ia, ib, ic, id, ie, if_] = inner.0;
+        Self([
+            a * ia + c * ib,
+            b * ia + d * ib,
+            a * ic + c * id,
+            b * ic + d * id,
+            a * ie + c * if_ + e,
+            b * ie + d * if_ + f,
+        ])
+    }
+}
+
+/// Where each glyph's data lives in `glyf`, from `loca`.
+pub struct Glyphs<'a> {
+    pub glyf: &'a [u8],
+    pub loca: &'a [u8],
+    pub long_offsets: bool,
+}
+
+impl Glyphs<'_> {
+    /// The glyph's data, or `None` for an empty glyph such as a space.
+    pub fn data(&self, glyph: u16) -> Option<&[u8]> {
+        let index = glyph as usize;
+        let (start, end) = if self.long_offsets {
+            let at = |i: usize| u32_at(self.loca, i * 4).map(|v| v as usize);
+            (at(index)?, at(index + 1)?)
+        } else {
+            let at = |i: usize| u16_at(self.loca, i * 2).map(|v| v as usize * 2);
+            (at(index)?, at(index + 1)?)
+        };
+        (end > start).then(|| self.glyf.get(start..end)).flatten()
+    }
+
+    /// The glyph's bounding box as `(x_min, y_min, x_max, y_max)`.
+    pub fn bounds(&self, glyph: u16) -> Option<(i16, i16, i16, i16)> {
+        let data = self.data(glyph)?;
+        Some((
+            i16_at(data, 2)?,
+            i16_at(data, 4)?,
+            i16_at(data, 6)?,
+            i16_at(data, 8)?,
+        ))
+    }
+
+    pub fn outline(&self, glyph: u16) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        self.append(glyph, &Transform::IDENTITY, 0, &mut segments);
+        segments
+    }
+
+    /// Adds a glyph's outline to `segments`; malformed data ends the outline early.
+    fn append(&self, glyph: u16, transform: &Transform, depth: u32, segments: &mut Vec<Segment>) {
+        let Some(data) = self.data(glyph) else {
+            return;
+        };
+        let Some(contours) = i16_at(data, 0) else {
+            return;
+        };
+        if contours >= 0 {
+            simple(data, contours as usize, transform, segments);
+        } else if depth < MAX_DEPTH {
+            self.composite(data, transform, depth, segments);
+        }
+    }
+
+    fn composite(
+        &self,
+        data: &[u8],
+        transform: &Transform,
+        depth: u32,
+        segments: &mut Vec<Segment>,
+    ) -> Option<()> {
+        let f2dot14 = |v: i16| v as f32 / 16384.0;
+        let mut stream = Stream::new(data, 10);
+        loop {
+            let flags = stream.u16()?;
+            let component = stream.u16()?;
+            let (arg1, arg2) = if flags & ARGS_ARE_WORDS != 0 {
+                (stream.i16()? as f32, stream.i16()? as f32)
+            } else {
+                (stream.u8()? as i8 as f32, stream.u8()? as i8 as f32)
+            };
+            // Aligning matched points instead of offsetting is rare enough to ignore.
+            let (dx, dy) = if flags & ARGS_ARE_XY_VALUES != 0 {
+                (arg1, arg2)
+            } else {
+                (0.0, 0.0)
+            };
+            let [xx, yx, xy, yy] = if flags & HAVE_SCALE != 0 {
+                let scale = f2dot14(stream.i16()?);
+                [scale, 0.0, 0.0, scale]
+            } else if flags & HAVE_XY_SCALE != 0 {
+                [f2dot14(stream.i16()?), 0.0, 0.0, f2dot14(stream.i16()?)]
+            } else if flags & HAVE_TWO_BY_TWO != 0 {
+                [
+                    f2dot14(stream.i16()?),
+                    f2dot14(stream.i16()?),
+                    f2dot14(stream.i16()?),
+                    f2dot14(stream.i16()?),
+                ]
+            } else {
+                [1.0, 0.0, 0.0, 1.0]
+            };
+            let component_transform = transform.then(&Transform([xx, yx, xy, yy, dx, dy]));
+            self.append(component, &component_transform, depth + 1, segments);
+            if flags & MORE_COMPONENTS == 0 {
+                return Some(());
+            }
+        }
+    }
+}
+
+fn simple(
+    data: &[u8],
+    contours: usize,
+    transform: &Transform,
+    segments: &mut Vec<Segment>,
+) -> Option<()> {
+    let mut stream = Stream::new(data, 10);
+    let ends = (0..contours)
+        .map(|_| stream.u16().map(usize::from))
+        .collect::<Option<Vec<_>>>()?;
+    let points = ends.last().map_or(0, |last| last + 1);
+    let instructions = stream.u16()?;
+    stream.skip(instructions as usize);
+
+    let mut flags = Vec::with_capacity(points);
+    while flags.len() < points {
+        let flag = stream.u8()?;
+        let repeat = if flag & REPEAT != 0 { stream.u8()? } else { 0 };
+        for _ in 0..=repeat {
+            flags.push(flag);
+        }
+    }
+    flags.truncate(points);
+
+    let mut coordinates = |short: u8, same_or_positive: u8| -> Option<Vec<f32>> {
+        let mut value = 0i32;
+        flags
+            .iter()
+            .map(|&flag| {
+                if flag & short != 0 {
+                    let delta = stream.u8()? as i32;
+                    value += if flag & same_or_positive != 0 {
+                        delta
+                    } else {
+                        -delta
+                    };
+                } else if flag & same_or_positive == 0 {
+                    value += stream.i16()? as i32;
+                }
+                Some(value as f32)
+            })
+            .collect()
+    };
+    let xs = coordinates(X_SHORT, X_SAME_OR_POSITIVE)?;
+    let ys = coordinates(Y_SHORT, Y_SAME_OR_POSITIVE)?;
+
+    let mut start = 0;
+    for end in ends {
+        if end < start || end >= points {
+            return None;
+        }
+        let contour: Vec<_> = (start..=end)
+            .map(|i| (transform.apply(xs[i], ys[i]), flags[i] & ON_CURVE != 0))
+            .collect();
+        contour_segments(&contour, segments);
+        start = end + 1;
+    }
+    Some(())
+}
+
+/// Turns one closed contour of on- and off-curve points into segments. Two off-curve points in
+/// a row imply an on-curve point halfway between them.
+fn contour_segments(contour: &[(Point, bool)], segments: &mut Vec<Segment>) {
+    let Some(&(first, first_on)) = contour.first() else {
+        return;
+    };
+    let (last, last_on) = contour[contour.len() - 1];
+    let start = match (first_on, last_on) {
+        (true, _) => first,
+        (false, true) => last,
+        (false, false) => first.midpoint(last),
+    };
+    let mut current = start;
+    let mut control: Option<Point> = None;
+    let rest = if first_on { &contour[1..] } else { contour };
+    for &(point, on_curve) in rest.iter().chain([&(start, true)]) {
+        match (control, on_curve) {
+            (None, true) => {
+                segments.push(Segment::Line(current, point));
+                current = point;
+            }
+            (None, false) => control = Some(point),
+            (Some(c), true) => {
+                segments.push(Segment::Quad(current, c, point));
+                current = point;
+                control = None;
+            }
+            (Some(c), false) => {
+                let mid = c.midpoint(point);
+                segments.push(Segment::Quad(current, c, mid));
+                current = mid;
+                control = Some(point);
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! An accumulation rasterizer: each edge adds the signed area it covers to the cells it crosses,
+//! and a running sum along every row turns those into exact pixel coverage.
+
+use crate::render::text::outline::Point;
+
+pub struct Rasterizer {
+    width: usize,
+    height: usize,
+    /// One spare cell per row end, where edges touching the right border deposit their area.
+    cells: Vec<f32>,
+}
+
+impl Rasterizer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0.0; width * height + 2],
+        }
+    }
+
+    pub fn line(&mut self, p0: Point, p1: Point) {
+        if (p0.y - p1.y).abs() < f32::EPSILON {
+            return;
+        }
+        let (direction, top, bottom) = if p0.y < p1.y {
+            (1.0, p0, p1)
+        } else {
+            (-1.0, p1, p0)
+        };
+        let clamp_x = |x: f32| x.clamp(0.0, self.width as f32);
+        let dxdy = (bottom.x - top.x) / (bottom.y - top.y);
+        let y_start = top.y.max(0.0);
+        let mut x = top.x + (y_start - top.y) * dxdy;
+        let rows = y_start as usize..(bottom.y.ceil() as usize).min(self.height);
+        for row in rows {
+            let dy = ((row + 1) as f32).min(bottom.y) - (row as f32).max(top.y);
+            let x_next = x + dxdy * dy;
+            let area = dy * direction;
+            let (x0, x1) = if x < x_next {
+                (clamp_x(x), clamp_x(x_next))
+            } else {
+                (clamp_x(x_next), clamp_x(x))
+            };
+            let line = row * self.width;
+            let x0_floor = x0.floor();
+            let x0_cell = x0_floor as usize;
+            let x1_cell = x1.ceil() as usize;
+            if x1_cell <= x0_cell + 1 {
+                // The edge stays within one cell: split its area by where it crosses on average.
+                let fraction = (x0 + x1) / 2.0 - x0_floor;
+                self.cells[line + x0_cell] += area * (1.0 - fraction);
+                self.cells[line + x0_cell + 1] += area * fraction;
+            } else {
+                let slope = (x1 - x0).recip();
+                let x0_fraction = x0 - x0_floor;
+                let first = 0.5 * slope * (1.0 - x0_fraction) * (1.0 - x0_fraction);
+                let x1_fraction = x1 - x1.ceil() + 1.0;
+                let last = 0.5 * slope * x1_fraction * x1_fraction;
+                self.cells[line + x0_cell] += area * first;
+                if x1_cell == x0_cell + 2 {
+                    self.cells[line + x0_cell + 1] += area * (1.0 - first - last);
+                } else {
+                    let second = slope * (1.5 - x0_fraction);
+                    self.cells[line + x0_cell + 1] += area * (second - first);
+                    for cell in x0_cell + 2..x1_cell - 1 {
+                        self.cells[line + cell] += area * slope;
+                    }
+                    let before_last = second + (x1_cell - x0_cell - 3) as f32 * slope;
+                    self.cells[line + x1_cell - 1] += area * (1.0 - before_last - last);
+                }
+                self.cells[line + x1_cell] += area * last;
+            }
+            x = x_next;
+        }
+    }
+
+    /// Flattens the curve into enough lines that none strays visibly from it.
+    pub fn quad(&mut self, p0: Point, control: Point, p1: Point) {
+        let dx = p0.x - 2.0 * control.x + p1.x;
+        let dy = p0.y - 2.0 * control.y + p1.y;
+        let deviation = dx * dx + dy * dy;
+        if deviation < 0.333 {
+            self.line(p0, p1);
+            return;
+        }
+        let steps = 1 + (3.0 * deviation).sqrt().sqrt() as usize;
+        let mut previous = p0;
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let u = 1.0 - t;
+            let point = Point {
+                x: u * u * p0.x + 2.0 * u * t * control.x + t * t * p1.x,
+                y: u * u * p0.y + 2.0 * u * t * control.y + t * t * p1.y,
+            };
+            self.line(previous, point);
+            previous = point;
+        }
+    }
+
+    /// Coverage of every pixel, row by row, with overlapping contours adding up (non-zero fill).
+    pub fn coverage(self) -> Vec<u8> {
+        let mut sum = 0.0;
+        self.cells[..self.width * self.height]
+            .iter()
+            .map(|cell| {
+                sum += cell;
+                (sum.abs().min(1.0) * 255.0).round() as u8
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,135 @@
+//! The table directory shared by TrueType and OpenType files and collections, and big-endian
+//! reads that fail softly on truncated data.
+
+use std::{collections::HashMap, ops::Range};
+
+pub type Tag = [u8; 4];
+
+pub fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+pub fn i16_at(data: &[u8], offset: usize) -> Option<i16> {
+    u16_at(data, offset).map(|v| v as i16)
+}
+
+pub fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Sequential reads through a table.
+#[derive(Debug, Clone, Copy)]
+pub struct Stream<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Stream<'a> {
+    pub fn new(data: &'a [u8], offset: usize) -> Self {
+        Self { data, offset }
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.offset)?;
+        self.offset += 1;
+        Some(value)
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        let value = u16_at(self.data, self.offset)?;
+        self.offset += 2;
+        Some(value)
+    }
+
+    pub fn i16(&mut self) -> Option<i16> {
+        self.u16().map(|v| v as i16)
+    }
+
+    pub fn skip(&mut self, len: usize) {
+        self.offset += len;
+    }
+}
+
+/// Offset of the table directory for face `index`, looking through a `ttcf` collection header.
+pub fn face_offset(data: &[u8], index: u32) -> Option<usize> {
+    if data.get(..4)? != b"ttcf" {
+        return (index == 0).then_some(0);
+    }
+    let count = u32_at(data, 8)?;
+    if index >= count {
+        return None;
+    }
+    u32_at(data, 12 + index as usize * 4).map(|offset| offset as usize)
+}
+
+/// Number of faces in a file: the collection size, or 1 for a plain font.
+pub fn face_count(data: &[u8]) -> u32 {
+    match data.get(..4) {
+        Some(b"ttcf") => u32_at(data, 8).unwrap_or(0),
+        _ => 1,
+    }
+}
+
+/// Table locations from the directory at `offset`, without checking they fit the file.
+pub fn tables(data: &[u8], offset: usize) -> Option<HashMap<Tag, Range<usize>>> {
+    let count = u16_at(data, offset + 4)? as usize;
+    (0..count)
+        .map(|i| {
+            let record = offset + 12 + i * 16;
+            let tag = data.get(record..record + 4)?.try_into().ok()?;
+            let start = u32_at(data, record + 8)? as usize;
+            let len = u32_at(data, record + 12)? as usize;
+            Some((tag, start..start + len))
+        })
+        .collect()
+}
+
+/// Family and style names from a `name` table, preferring the typographic ones that group
+/// weights under a single family.
+pub fn names(name: &[u8]) -> (Vec<String>, Vec<String>) {
+    let mut families = Vec::new();
+    let mut styles = Vec::new();
+    let (Some(count), Some(storage)) = (u16_at(name, 2), u16_at(name, 4)) else {
+        return (families, styles);
+    };
+    for i in 0..count as usize {
+        let record = 6 + i * 12;
+        let (Some(platform), Some(id), Some(len), Some(offset)) = (
+            u16_at(name, record),
+            u16_at(name, record + 6),
+            u16_at(name, record + 8),
+            u16_at(name, record + 10),
+        ) else {
+            break;
+        };
+        let start = storage as usize + offset as usize;
+        let Some(bytes) = name.get(start..start + len as usize) else {
+            continue;
+        };
+        let text = match platform {
+            // Unicode and Windows names are UTF-16BE.
+            0 | 3 => String::from_utf16_lossy(
+                &bytes
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect::<Vec<_>>(),
+            ),
+            // Mac Roman; family names are ASCII in practice.
+            1 => bytes.iter().map(|&b| b as char).collect(),
+            _ => continue,
+        };
+        let list = match id {
+            1 | 16 => &mut families,
+            2 | 17 => &mut styles,
+            _ => continue,
+        };
+        if !list.contains(&text) {
+            list.push(text);
+        }
+    }
+    (families, styles)
+}
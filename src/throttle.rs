@@ -0,0 +1,349 @@
+//! Slows down password guessing: after a number of wrong passwords each further attempt waits
+//! twice as long as the last, and a lockout can refuse attempts for a while altogether. The
+//! count is kept in a file so restarting the locker does not reset it.
+
+use crate::{config, timer::Timer};
+use std::{
+    fs,
+    io::Result,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const TICK: Duration = Duration::from_secs(1);
+
+/// When and how long password attempts are held back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrottlePolicy {
+    /// Wrong passwords allowed before the first delay.
+    pub free_attempts: u32,
+    /// The first delay, doubled with each further wrong password up to `max_delay`.
+    pub delay: Duration,
+    pub max_delay: Duration,
+    /// Wrong passwords after which nothing is accepted for `lockout`; `None` never locks out.
+    pub lockout_attempts: Option<u32>,
+    pub lockout: Duration,
+    /// Where the count survives restarts; `None` keeps it in memory only.
+    pub state_file: Option<PathBuf>,
+}
+
+impl Default for ThrottlePolicy {
+    fn default() -> Self {
+        Self {
+            free_attempts: 3,
+            delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(300),
+            lockout_attempts: None,
+            lockout: Duration::from_secs(600),
+            state_file: state_file(),
+        }
+    }
+}
+
+/// What the indicator shows about throttling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ThrottleStatus {
+    /// Wrong passwords since the last unlock.
+    pub failures: u32,
+    /// Whole seconds, rounded up, until the next attempt is accepted.
+    pub wait: u64,
+    pub locked_out: bool,
+}
+
+/// Counts wrong passwords and says when the next attempt may be made.
+///
+/// While an attempt is held back `timer` fires every second and the event loop hands it back to
+/// [`Throttle::tick`] so the countdown can be redrawn.
+#[derive(Debug)]
+pub struct Throttle {
+    policy: ThrottlePolicy,
+    failures: u32,
+    last_failure: Instant,
+    timer: Timer,
+}
+
+impl Throttle {
+    /// Picks up the count left by an earlier run, if the policy keeps one.
+    pub fn load(policy: ThrottlePolicy) -> Result<Self> {
+        let saved = policy.state_file.as_ref().and_then(|path| {
+            let text = fs::read_to_string(path).ok()?;
+            let parsed = text.split_once(' ').and_then(|(failures, last)| {
+                Some((failures.parse().ok()?, last.trim().parse().ok()?))
+            });
+            if parsed.is_none() {
+                tracing::warn!("Ignoring malformed attempt count in {}", path.display());
+            }
+            parsed
+        });
+        let (failures, last) = saved.unwrap_or((0, 0));
+        // The file has wall clock time; a failure dated in the future counts as just now.
+        let ago = SystemTime::now()
+            .duration_since(UNIX_EPOCH + Duration::from_secs(last))
+            .unwrap_or_default();
+        let now = Instant::now();
+        let throttle = Self {
+            policy,
+            failures,
+            last_failure: now.checked_sub(ago).unwrap_or(now),
+            timer: Timer::new()?,
+        };
+        throttle.arm();
+        Ok(throttle)
+    }
+
+    pub fn timer(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+
+    pub fn status(&self) -> ThrottleStatus {
+        let (_, locked_out) = self.delay();
+        let remaining = self.remaining();
+        ThrottleStatus {
+            failures: self.failures,
+            wait: remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0),
+            locked_out: locked_out && !remaining.is_zero(),
+        }
+    }
+
+    /// Whether a password may be checked now.
+    pub fn allowed(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// Counts a wrong password, made just now.
+    pub fn failed(&mut self) {
+        let (lockout, locked_out) = self.delay();
+        // A lockout that ran out starts the count afresh.
+        if locked_out && self.last_failure.elapsed() >= lockout {
+            self.failures = 0;
+        }
+        self.failures = self.failures.saturating_add(1);
+        self.last_failure = Instant::now();
+        self.save();
+        self.arm();
+    }
+
+    /// Forgets the wrong passwords once the right one unlocked the session.
+    pub fn succeeded(&mut self) {
+        self.failures = 0;
+        if let Some(path) = &self.policy.state_file
+            && let Err(e) = fs::remove_file(path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::error!("Failed to reset attempt count in {}: {e}", path.display());
+        }
+    }
+
+    /// Called when the timer is readable; returns whether a second of the wait went by.
+    pub fn tick(&mut self) -> bool {
+        if !self.timer.tick("attempt") {
+            return false;
+        }
+        if self.allowed()
+            && let Err(e) = self.timer.disarm()
+        {
+            tracing::error!("Failed to disarm attempt timer: {e}");
+        }
+        true
+    }
+
+    /// How long attempts wait after the last wrong password, and whether that is a lockout.
+    fn delay(&self) -> (Duration, bool) {
+        let policy = &self.policy;
+        if policy
+            .lockout_attempts
+            .is_some_and(|limit| self.failures >= limit)
+        {
+            return (policy.lockout, true);
+        }
+        let delay = match self.failures.checked_sub(policy.free_attempts) {
+            Some(doublings) if self.failures > 0 => {
+                let factor = 1u32.checked_shl(doublings).unwrap_or(u32::MAX);
+                policy.delay.saturating_mul(factor).min(policy.max_delay)
+            }
+            _ => Duration::ZERO,
+        };
+        (delay, false)
+    }
+
+    fn remaining(&self) -> Duration {
+        let (delay, _) = self.delay();
+        delay.saturating_sub(self.last_failure.elapsed())
+    }
+
+    /// Ticks on every whole second left of the wait, if there is one.
+    fn arm(&self) {
+        let remaining = self.remaining();
+        if remaining.is_zero() {
+            return;
+        }
+        let fraction = remaining - Duration::from_secs(remaining.as_secs());
+        let first = if fraction.is_zero() { TICK } else { fraction };
+        if let Err(e) = self.timer.arm(first, TICK) {
+            tracing::error!("Failed to arm attempt timer: {e}");
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.policy.state_file else {
+            return;
+        };
+        let last = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let saved = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(path, format!("{} {last}\n", self.failures)));
+        if let Err(e) = saved {
+            tracing::error!("Failed to save attempt count to {}: {e}", path.display());
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/lock/attempts`, falling back to `~/.local/state`.
+fn state_file() -> Option<PathBuf> {
+    config::xdg_dir("XDG_STATE_HOME", ".local/state").map(|dir| dir.join("lock").join("attempts"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn policy() -> ThrottlePolicy {
+        ThrottlePolicy {
+            free_attempts: 2,
+            delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(10),
+            lockout_attempts: None,
+            lockout: Duration::from_secs(60),
+            state_file: None,
+        }
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn doubles_the_delay() {
+        let mut throttle = Throttle::load(policy()).unwrap();
+        assert!(throttle.allowed());
+        let mut waits = Vec::new();
+        for _ in 0..7 {
+            throttle.failed();
+            waits.push(throttle.status().wait);
+        }
+        assert_eq!(waits, [0, 2, 4, 8, 10, 10, 10]);
+        assert!(!throttle.allowed());
+        assert!(!throttle.status().locked_out);
+
+        // Far more failures than the factor has bits still stop at the maximum.
+        throttle.failures = 100;
+        assert_eq!(throttle.delay(), (Duration::from_secs(10), false));
+
+        throttle.last_failure -= Duration::from_secs(10);
+        assert!(throttle.allowed());
+        assert_eq!(throttle.status().wait, 0);
+    }
+
+    #[test]
+    fn locks_out_at_the_threshold() {
+        let mut throttle = Throttle::load(ThrottlePolicy {
+            lockout_attempts: Some(4),
+            ..policy()
+        })
+        .unwrap();
+        for _ in 0..3 {
+            throttle.failed();
+        }
+        assert!(!throttle.status().locked_out);
+        throttle.failed();
+        let status = throttle.status();
+        assert_eq!((status.failures, status.wait), (4, 60));
+        assert!(status.locked_out);
+        assert!(!throttle.allowed());
+
+        // Once the lockout ran out the next wrong password counts from one again.
+        throttle.last_failure -= Duration::from_secs(60);
+        assert!(throttle.allowed());
+        assert!(!throttle.status().locked_out);
+        throttle.failed();
+        assert_eq!(
+            throttle.status(),
+            ThrottleStatus {
+                failures: 1,
+                wait: 0,
+                locked_out: false
+            }
+        );
+    }
+
+    #[test]
+    fn success_resets_the_count() {
+        let dir = TempDir::new();
+        let path = dir.path().join("attempts");
+        let mut throttle = Throttle::load(ThrottlePolicy {
+            state_file: Some(path.clone()),
+            ..policy()
+        })
+        .unwrap();
+        for _ in 0..3 {
+            throttle.failed();
+        }
+        assert!(path.exists());
+        throttle.succeeded();
+        assert_eq!(throttle.status(), ThrottleStatus::default());
+        assert!(throttle.allowed());
+        assert!(!path.exists());
+        // Nothing to remove is fine.
+        throttle.succeeded();
+    }
+
+    #[test]
+    fn keeps_the_count_in_the_state_file() {
+        let dir = TempDir::new();
+        let path = dir.path().join("state/lock/attempts");
+        let policy = ThrottlePolicy {
+            state_file: Some(path.clone()),
+            ..policy()
+        };
+
+        // A missing file, or a missing directory for it, starts from nothing.
+        let mut throttle = Throttle::load(policy.clone()).unwrap();
+        assert_eq!(throttle.status(), ThrottleStatus::default());
+        for _ in 0..3 {
+            throttle.failed();
+        }
+        let saved = fs::read_to_string(&path).unwrap();
+        let (failures, last) = saved.trim_end().split_once(' ').unwrap();
+        assert_eq!(failures, "3");
+        assert!(now() - last.parse::<u64>().unwrap() <= 1);
+
+        let restarted = Throttle::load(policy.clone()).unwrap();
+        assert_eq!(restarted.status().failures, 3);
+        assert!(!restarted.allowed());
+
+        // The wait counts from when the failure was saved, not from the restart.
+        fs::write(&path, format!("3 {}\n", now() - 100)).unwrap();
+        let restarted = Throttle::load(policy.clone()).unwrap();
+        assert_eq!(restarted.status().failures, 3);
+        assert!(restarted.allowed());
+
+        // A clock that went back counts the failure as just made.
+        fs::write(&path, format!("3 {}\n", now() + 1000)).unwrap();
+        let restarted = Throttle::load(policy.clone()).unwrap();
+        assert_eq!(restarted.status().wait, 4);
+
+        for corrupt in ["", "3", "three 0", "3 yesterday", "-1 0\n"] {
+            fs::write(&path, corrupt).unwrap();
+            let restarted = Throttle::load(policy.clone()).unwrap();
+            assert_eq!(restarted.status(), ThrottleStatus::default(), "{corrupt:?}");
+        }
+    }
+}
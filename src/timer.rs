@@ -0,0 +1,122 @@
+use mio::{Interest, Registry, Token, event::Source, unix::SourceFd};
+use std::{
+    io::{Error, ErrorKind, Result},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    time::Duration,
+};
+
+/// A monotonic timerfd that can be registered with the event loop's poll.
+#[derive(Debug)]
+pub struct Timer {
+    fd: OwnedFd,
+}
+
+impl Timer {
+    pub fn new() -> Result<Self> {
+        // SAFETY: timerfd_create has no pointer arguments.
+        let fd = unsafe {
+            libc::timerfd_create(
+                libc::CLOCK_MONOTONIC,
+                libc::TFD_NONBLOCK | libc::TFD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(Error::last_os_error());
+        }
+        // SAFETY: the fd was just created and nothing else owns it.
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    /// Fires once after `delay`, then every `interval` if it is non-zero.
+    pub fn arm(&self, delay: Duration, interval: Duration) -> Result<()> {
+        // A zero initial expiration would disarm the timer instead.
+        self.set(delay.max(Duration::from_nanos(1)), interval)
+    }
+
+    pub fn disarm(&self) -> Result<()> {
+        self.set(Duration::ZERO, Duration::ZERO)
+    }
+
+    /// Number of expirations since the last read, zero if the timer has not fired.
+    fn read(&self) -> Result<u64> {
+        let mut expirations = 0u64;
+        // SAFETY: timerfd reads are exactly eight bytes into a valid u64.
+        let n = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                &mut expirations as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            )
+        };
+        if n < 0 {
+            let err = Error::last_os_error();
+            return match err.kind() {
+                ErrorKind::WouldBlock => Ok(0),
+                _ => Err(err),
+            };
+        }
+        Ok(expirations)
+    }
+
+    /// Reads the timer once it polled readable, returning whether it fired. Errors are logged
+    /// as being with the `what` timer and count as not firing.
+    pub fn tick(&self, what: &str) -> bool {
+        match self.read() {
+            Ok(expirations) => expirations > 0,
+            Err(e) => {
+                tracing::error!("Failed to read {what} timer: {e}");
+                false
+            }
+        }
+    }
+
+    /// Time until the timer next fires and the interval after that, both zero when disarmed.
+    #[cfg(test)]
+    pub fn setting(&self) -> Result<(Duration, Duration)> {
+        // SAFETY: itimerspec is plain old data, all-zero is a valid initial state.
+        let mut spec: libc::itimerspec = unsafe { std::mem::zeroed() };
+        // SAFETY: `spec` is valid for writing.
+        if unsafe { libc::timerfd_gettime(self.fd.as_raw_fd(), &mut spec) } < 0 {
+            return Err(Error::last_os_error());
+        }
+        let duration = |t: libc::timespec| Duration::new(t.tv_sec as u64, t.tv_nsec as u32);
+        Ok((duration(spec.it_value), duration(spec.it_interval)))
+    }
+
+    fn set(&self, delay: Duration, interval: Duration) -> Result<()> {
+        let spec = libc::itimerspec {
+            it_interval: timespec(interval),
+            it_value: timespec(delay),
+        };
+        // SAFETY: spec is a valid itimerspec and the old value is not requested.
+        let ret =
+            unsafe { libc::timerfd_settime(self.fd.as_raw_fd(), 0, &spec, std::ptr::null_mut()) };
+        if ret < 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+fn timespec(duration: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: duration.as_secs() as libc::time_t,
+        tv_nsec: duration.subsec_nanos() as libc::c_long,
+    }
+}
+
+impl Source for Timer {
+    fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).deregister(registry)
+    }
+}
@@ -1,27 +1,69 @@
-use crate::wayland::types::{
-    EventMessage, NewId, Object, RequestMessage, SessionLock, SessionLockManager, WlDisplay,
-    WlRegistry,
+use crate::wayland::{
+    shm::{Buffer, ShmPool},
+    types::{
+        BufferEvent, Capability, Event, EventMessage, Format, FractionalScale,
+        FractionalScaleEvent, FractionalScaleManager, Interface, KeyboardEvent, NewId, Object,
+        OutputEvent, PointerEvent, RequestMessage, SeatEvent, SessionLock, SessionLockEvent,
+        SessionLockManager, SessionLockSurface, SessionLockSurfaceEvent, ShmEvent, SurfaceEvent,
+        Transform, Viewport, Viewporter, WlBuffer, WlCompositor, WlDisplay, WlKeyboard, WlOutput,
+        WlPointer, WlRegion, WlRegistry, WlSeat, WlShm, WlShmPool, WlSurface,
+    },
 };
-use mio::{Events, Interest, Poll, Token, net::UnixStream};
+use mio::{Events, Interest, Poll, Token, Waker, event::Source, net::UnixStream};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     env,
-    io::{Read, Result, Write},
+    io::{Error, ErrorKind, Result, Write},
+    os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd, RawFd},
     path::PathBuf,
+    time::Duration,
 };
 
 const WAYLAND_SOCKET: Token = Token(0);
 
+/// libwayland never sends more than this many fds in a single message batch.
+const MAX_FDS: usize = 28;
+
 pub struct Wayland {
     stream: UnixStream,
     display: WlDisplay,
     registry: Option<WlRegistry>,
     session_lock_manager: Option<SessionLockManager>,
     session_lock: Option<SessionLock>,
-    is_setup: bool,
+    lock_state: Option<SessionLockEvent>,
+    seat: Option<WlSeat>,
+    keyboard: Option<WlKeyboard>,
+    pointer: Option<WlPointer>,
+    compositor: Option<WlCompositor>,
+    fractional_scale_manager: Option<FractionalScaleManager>,
+    viewporter: Option<Viewporter>,
+    /// Bound outputs by global name.
+    outputs: HashMap<u32, WlOutput>,
+    /// Bound outputs whose globals went away, such as unplugged monitors.
+    removed_outputs: VecDeque<Object>,
+    shm: Option<WlShm>,
+    shm_formats: Vec<Format>,
+    busy_buffers: HashSet<u32>,
+    /// Buffers attached to each surface but not yet committed.
+    pending_buffers: HashMap<u32, u32>,
+    /// `wl_display.sync` callbacks still waiting for their `done` event.
+    pending_syncs: HashSet<u32>,
     poll: Poll,
+    /// Other sources that fired but were not yet returned by `poll_events`, such as during a
+    /// roundtrip.
+    ready_tokens: Vec<Token>,
     next_id: u32,
     interface_map: HashMap<u32, (String, u32)>,
+    objects: HashMap<u32, Interface>,
+    in_buf: Vec<u8>,
+    in_fds: VecDeque<OwnedFd>,
+    keyboard_events: VecDeque<KeyboardEvent>,
+    pointer_events: VecDeque<PointerEvent>,
+    surface_events: VecDeque<(WlSurface, SurfaceEvent)>,
+    surfaces: HashMap<u32, WlSurface>,
+    lock_surface_events: VecDeque<(SessionLockSurface, SessionLockSurfaceEvent)>,
+    frame_callbacks: VecDeque<u32>,
+    fractional_scale_events: VecDeque<(FractionalScale, FractionalScaleEvent)>,
 }
 
 impl Wayland {
@@ -30,10 +72,25 @@ impl Wayland {
         let display_name = env::var("WAYLAND_DISPLAY").expect("WAYLAND_DISPLAY not set");
         let socket_path = PathBuf::from(runtime_dir).join(display_name);
         println!("Connecting to wayland socket at path: {:?}", socket_path);
+        Self::with_stream(UnixStream::connect(&socket_path)?)
+    }
 
-        let poll = Poll::new()?;
-        let mut stream = UnixStream::connect(&socket_path)?;
+    /// A connection to a fake compositor on the other end of `stream`, as if `wl_compositor`
+    /// and `wl_shm` were bound and the usual formats announced.
+    #[cfg(test)]
+    pub fn fake(stream: std::os::unix::net::UnixStream) -> Result<Self> {
+        stream.set_nonblocking(true)?;
+        let mut conn = Self::with_stream(UnixStream::from_std(stream))?;
+        let id = conn.new_object(Interface::Compositor);
+        conn.compositor = Some(WlCompositor::new(Object::new(id), 6));
+        let id = conn.new_object(Interface::Shm);
+        conn.shm = Some(WlShm::new(Object::new(id)));
+        conn.shm_formats = vec![Format::ARGB8888, Format::XRGB8888];
+        Ok(conn)
+    }
 
+    fn with_stream(mut stream: UnixStream) -> Result<Self> {
+        let poll = Poll::new()?;
         poll.registry().register(
             &mut stream,
             WAYLAND_SOCKET,
@@ -46,47 +103,95 @@ impl Wayland {
             registry: None,
             session_lock_manager: None,
             session_lock: None,
-            is_setup: false,
+            lock_state: None,
+            seat: None,
+            keyboard: None,
+            pointer: None,
+            compositor: None,
+            fractional_scale_manager: None,
+            viewporter: None,
+            outputs: HashMap::new(),
+            removed_outputs: VecDeque::new(),
+            shm: None,
+            shm_formats: Vec::new(),
+            busy_buffers: HashSet::new(),
+            pending_buffers: HashMap::new(),
+            pending_syncs: HashSet::new(),
             poll,
+            ready_tokens: Vec::new(),
             next_id: 2,
             interface_map: HashMap::new(),
+            objects: HashMap::from([(1, Interface::Display)]),
+            in_buf: Vec::new(),
+            in_fds: VecDeque::new(),
+            keyboard_events: VecDeque::new(),
+            pointer_events: VecDeque::new(),
+            surface_events: VecDeque::new(),
+            surfaces: HashMap::new(),
+            lock_surface_events: VecDeque::new(),
+            frame_callbacks: VecDeque::new(),
+            fractional_scale_events: VecDeque::new(),
         })
     }
 
-    pub fn poll_events(&mut self) -> Result<()> {
+    /// Adds another source, such as a timer, to the poll driving the connection.
+    pub fn register(&self, source: &mut impl Source, token: Token) -> Result<()> {
+        assert_ne!(
+            token, WAYLAND_SOCKET,
+            "token is reserved for the Wayland socket"
+        );
+        self.poll
+            .registry()
+            .register(source, token, Interest::READABLE)
+    }
+
+    /// A handle other threads can use to make `poll_events` return `token`.
+    pub fn waker(&self, token: Token) -> Result<Waker> {
+        assert_ne!(
+            token, WAYLAND_SOCKET,
+            "token is reserved for the Wayland socket"
+        );
+        Waker::new(self.poll.registry(), token)
+    }
+
+    /// Waits for activity, dispatching socket messages and returning the other tokens that fired.
+    pub fn poll_events(&mut self) -> Result<Vec<Token>> {
+        let timeout = (!self.ready_tokens.is_empty()).then_some(Duration::ZERO);
+        self.dispatch(timeout)?;
+        Ok(std::mem::take(&mut self.ready_tokens))
+    }
+
+    /// Handles socket activity and collects the other tokens that fired. Sources are
+    /// edge-triggered, so every token must be kept until the caller sees it.
+    fn dispatch(&mut self, timeout: Option<Duration>) -> Result<()> {
         let mut events = Events::with_capacity(128);
-        self.poll.poll(&mut events, None)?;
+        self.poll.poll(&mut events, timeout)?;
         for event in events.iter() {
-            if event.token() == WAYLAND_SOCKET {
-                if event.is_readable() {
-                    loop {
-                        match self.handle_readable()? {
-                            true => continue,
-                            false => break,
-                        }
-                    }
-                }
+            match event.token() {
+                WAYLAND_SOCKET if event.is_readable() => while self.handle_readable()? {},
+                WAYLAND_SOCKET => {}
+                token if !self.ready_tokens.contains(&token) => self.ready_tokens.push(token),
+                _ => {}
             }
         }
         Ok(())
     }
 
+    /// Keyboard events received since the last call, in the order the compositor sent them.
+    pub fn keyboard_events(&mut self) -> impl Iterator<Item = KeyboardEvent> + '_ {
+        self.keyboard_events.drain(..)
+    }
+
+    /// Pointer events received since the last call, in the order the compositor sent them.
+    pub fn pointer_events(&mut self) -> impl Iterator<Item = PointerEvent> + '_ {
+        self.pointer_events.drain(..)
+    }
+
     fn handle_readable(&mut self) -> Result<bool> {
         match self.read_messages()? {
             Some(messages) => {
                 for message in messages {
-                    if message.is_global() {
-                        let interface = message
-                            .payload
-                            .get_global()
-                            .expect("Global event should have interface");
-                        self.interface_map
-                            .insert(interface.0, (interface.1, interface.2));
-                    } else {
-                        if message.is_callback_done() {
-                            self.is_setup = true;
-                        }
-                    }
+                    self.handle_message(message)?;
                 }
                 Ok(true)
             }
@@ -94,59 +199,335 @@ impl Wayland {
         }
     }
 
+    fn handle_message(&mut self, message: EventMessage) -> Result<()> {
+        let object_id = message.object_id();
+        match message.payload {
+            Event::Interface { id, name, version } => {
+                self.interface_map.insert(id, (name, version));
+            }
+            Event::GlobalRemove { id } => {
+                self.interface_map.remove(&id);
+                if let Some(output) = self.outputs.remove(&id) {
+                    self.removed_outputs.push_back(output.id());
+                }
+            }
+            Event::Callback => {
+                if !self.pending_syncs.remove(&object_id.inner()) {
+                    self.frame_callbacks.push_back(object_id.inner());
+                }
+            }
+            Event::DeleteId { id } => {
+                self.objects.remove(&id);
+            }
+            Event::Error {
+                object_id,
+                code,
+                message,
+            } => {
+                tracing::error!("Protocol error on object {object_id}, code {code}: {message}");
+                return Err(Error::other(message));
+            }
+            Event::Seat(event) => self.handle_seat_event(event)?,
+            Event::Keyboard(event) => {
+                if self.keyboard.is_some_and(|k| k.id() == object_id) {
+                    self.keyboard_events.push_back(event);
+                }
+            }
+            Event::Pointer(event) => {
+                if self.pointer.is_some_and(|p| p.id() == object_id) {
+                    self.pointer_events.push_back(event);
+                }
+            }
+            Event::Output(event) => self.handle_output_event(object_id, event),
+            Event::Shm(ShmEvent::Format(format)) => {
+                if !self.shm_formats.contains(&format) {
+                    self.shm_formats.push(format);
+                }
+            }
+            Event::Buffer(BufferEvent::Release) => {
+                self.busy_buffers.remove(&object_id.inner());
+            }
+            Event::Surface(event) => {
+                if let Some(surface) = self.surfaces.get(&object_id.inner()) {
+                    self.surface_events.push_back((*surface, event));
+                }
+            }
+            Event::SessionLock(event) => {
+                if self.session_lock.is_some_and(|lock| lock.id() == object_id) {
+                    self.lock_state = Some(event);
+                }
+            }
+            Event::SessionLockSurface(event) => {
+                let lock_surface = SessionLockSurface::new(object_id);
+                self.lock_surface_events.push_back((lock_surface, event));
+            }
+            Event::FractionalScale(event) => {
+                let scale = FractionalScale::new(object_id);
+                self.fractional_scale_events.push_back((scale, event));
+            }
+            Event::Other => {}
+        }
+        Ok(())
+    }
+
+    fn handle_output_event(&mut self, id: Object, event: OutputEvent) {
+        if let Some(output) = self.outputs.values_mut().find(|output| output.id() == id) {
+            output.handle_event(event);
+        }
+    }
+
+    fn handle_seat_event(&mut self, event: SeatEvent) -> Result<()> {
+        let Some(seat) = self.seat else {
+            return Ok(());
+        };
+        match event {
+            SeatEvent::Capabilities(capabilities) => {
+                match (capabilities.has(Capability::Keyboard), self.keyboard) {
+                    (true, None) => {
+                        let id = self.new_object(Interface::Keyboard);
+                        self.send_message(seat.get_keyboard(id))?;
+                        self.keyboard = Some(WlKeyboard::new(Object::new(id), seat.version()));
+                    }
+                    (false, Some(keyboard)) => {
+                        if let Some(release) = keyboard.release() {
+                            self.send_message(release)?;
+                        }
+                        self.keyboard = None;
+                    }
+                    _ => {}
+                }
+                match (capabilities.has(Capability::Pointer), self.pointer) {
+                    (true, None) => {
+                        let id = self.new_object(Interface::Pointer);
+                        self.send_message(seat.get_pointer(id))?;
+                        self.pointer = Some(WlPointer::new(Object::new(id), seat.version()));
+                    }
+                    (false, Some(pointer)) => {
+                        if let Some(release) = pointer.release() {
+                            self.send_message(release)?;
+                        }
+                        self.pointer = None;
+                    }
+                    _ => {}
+                }
+            }
+            SeatEvent::Name(name) => tracing::debug!("Seat name: {name}"),
+        }
+        Ok(())
+    }
+
+    fn new_object(&mut self, interface: Interface) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.objects.insert(id, interface);
+        id
+    }
+
     fn send_message(&mut self, request: RequestMessage) -> Result<()> {
         let request_bytes = request.to_vec().unwrap();
-        self.stream.write_all(&request_bytes)?;
+        let fds = request.fds();
+        if fds.is_empty() {
+            self.stream.write_all(&request_bytes)?;
+        } else {
+            self.send_with_fds(&request_bytes, &fds)?;
+        }
         self.stream.flush()
     }
 
+    /// Writes a whole message in one `sendmsg` so the fds arrive with its first byte.
+    fn send_with_fds(&mut self, bytes: &[u8], fds: &[RawFd]) -> Result<()> {
+        let mut iov = libc::iovec {
+            iov_base: bytes.as_ptr() as *mut libc::c_void,
+            iov_len: bytes.len(),
+        };
+        let fds_len = std::mem::size_of_val(fds);
+        // SAFETY: CMSG_SPACE is a pure size computation.
+        let cmsg_space = unsafe { libc::CMSG_SPACE(fds_len as u32) } as usize;
+        let mut cmsg_buf = vec![0u8; cmsg_space];
+        // SAFETY: msghdr is plain old data, all-zero is a valid initial state.
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = cmsg_buf.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = cmsg_space as _;
+
+        // SAFETY: the control buffer was sized for exactly one header carrying `fds`.
+        let n = unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len as u32) as _;
+            std::ptr::copy_nonoverlapping(
+                fds.as_ptr() as *const u8,
+                libc::CMSG_DATA(cmsg),
+                fds_len,
+            );
+            libc::sendmsg(self.stream.as_raw_fd(), &msg, libc::MSG_NOSIGNAL)
+        };
+        if n < 0 {
+            return Err(Error::last_os_error());
+        }
+        // The fds went out with the first byte, the rest of the message can follow normally.
+        self.stream.write_all(&bytes[n as usize..])
+    }
+
     pub fn read_messages(&mut self) -> Result<Option<Vec<EventMessage>>> {
         let mut buf = [0u8; 8192];
-        match self.stream.read(&mut buf) {
-            Ok(0) => Ok(None),
+        match self.recv(&mut buf) {
+            Ok(0) => Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "compositor closed the connection",
+            )),
             Ok(n) => {
                 tracing::trace!("Read {} bytes from socket", n);
-                let messages = EventMessage::parse_messages(&buf[..n]);
-                if messages.is_empty() {
-                    Ok(None)
-                } else {
-                    Ok(Some(messages))
-                }
+                self.in_buf.extend_from_slice(&buf[..n]);
+                let (messages, consumed) =
+                    EventMessage::parse_messages(&self.in_buf, &self.objects, &mut self.in_fds)
+                        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+                self.in_buf.drain(..consumed);
+                Ok(Some(messages))
             }
-            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
             Err(e) => Err(e),
         }
     }
 
-    pub fn bind(&mut self, interface: String) -> Result<()> {
+    /// Reads from the socket, queueing any file descriptors passed alongside the data.
+    fn recv(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        // SAFETY: CMSG_SPACE is a pure size computation.
+        let cmsg_space =
+            unsafe { libc::CMSG_SPACE((MAX_FDS * std::mem::size_of::<RawFd>()) as u32) } as usize;
+        let mut cmsg_buf = vec![0u8; cmsg_space];
+        // SAFETY: msghdr is plain old data, all-zero is a valid initial state.
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = cmsg_buf.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = cmsg_space as _;
+
+        // SAFETY: msg points at buffers that outlive the call.
+        let n = unsafe {
+            libc::recvmsg(
+                self.stream.as_raw_fd(),
+                &mut msg,
+                libc::MSG_CMSG_CLOEXEC | libc::MSG_DONTWAIT,
+            )
+        };
+        if n < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        // SAFETY: the kernel filled in the control buffer described by msg.
+        unsafe {
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                    let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+                    let len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                    for i in 0..len / std::mem::size_of::<RawFd>() {
+                        let fd = std::ptr::read_unaligned(data.add(i));
+                        self.in_fds.push_back(OwnedFd::from_raw_fd(fd));
+                    }
+                }
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+        Ok(n as usize)
+    }
+
+    /// Binds the advertised global for `interface` at the highest version both sides support.
+    pub fn bind(&mut self, interface: Interface) -> Result<()> {
         match self
             .interface_map
             .iter()
-            .find(|(_, (iface, _))| *iface == interface)
+            .find(|(_, (iface, _))| iface == interface.name())
+            .map(|(name, (_, version))| (*name, *version))
         {
-            Some(interface) => {
-                let id = self.next_id;
-                self.next_id += 1;
-                let new_id = Object::new(id);
-                self.send_message(
-                    self.registry
-                        .expect("WlRegistry not setup yet, called get_registry first?")
-                        .bind(
-                            *interface.0,
-                            NewId::new((interface.1.0.clone(), interface.1.1), new_id),
-                        ),
-                )?;
-                self.session_lock_manager = Some(SessionLockManager::new(new_id));
-                tracing::trace!("Sent bind request");
+            Some((name, advertised)) => {
+                let (new_id, version) = self.bind_global(name, advertised, interface)?;
+                match interface {
+                    Interface::SessionLockManager => {
+                        self.session_lock_manager = Some(SessionLockManager::new(new_id))
+                    }
+                    Interface::Seat => self.seat = Some(WlSeat::new(new_id, version)),
+                    Interface::Compositor => {
+                        self.compositor = Some(WlCompositor::new(new_id, version))
+                    }
+                    Interface::Shm => self.shm = Some(WlShm::new(new_id)),
+                    Interface::FractionalScaleManager => {
+                        self.fractional_scale_manager = Some(FractionalScaleManager::new(new_id))
+                    }
+                    Interface::Viewporter => self.viewporter = Some(Viewporter::new(new_id)),
+                    _ => panic!("{} is not a global", interface.name()),
+                }
                 Ok(())
             }
-            None => panic!("Unable to bind to interface: {}", interface),
+            None => panic!("Unable to bind to interface: {}", interface.name()),
         }
     }
 
+    /// Binds every advertised `wl_output` not bound yet, returning the new ones.
+    pub fn bind_outputs(&mut self) -> Result<Vec<Object>> {
+        let mut unbound: Vec<(u32, u32)> = self
+            .interface_map
+            .iter()
+            .filter(|(name, (iface, _))| {
+                iface == Interface::Output.name() && !self.outputs.contains_key(name)
+            })
+            .map(|(name, (_, version))| (*name, *version))
+            .collect();
+        unbound.sort_unstable();
+        let mut bound = Vec::new();
+        for (name, advertised) in unbound {
+            let (id, _) = self.bind_global(name, advertised, Interface::Output)?;
+            self.outputs.insert(name, WlOutput::new(id));
+            bound.push(id);
+        }
+        Ok(bound)
+    }
+
+    /// What the compositor told us about a bound output so far.
+    pub fn output(&self, id: Object) -> Option<&WlOutput> {
+        self.outputs.values().find(|output| output.id() == id)
+    }
+
+    /// Outputs removed since the last call.
+    pub fn removed_outputs(&mut self) -> impl Iterator<Item = Object> + '_ {
+        self.removed_outputs.drain(..)
+    }
+
+    fn bind_global(
+        &mut self,
+        name: u32,
+        advertised: u32,
+        interface: Interface,
+    ) -> Result<(Object, u32)> {
+        let version = advertised.min(interface.max_version());
+        let new_id = Object::new(self.new_object(interface));
+        self.send_message(
+            self.registry
+                .expect("WlRegistry not setup yet, called get_registry first?")
+                .bind(
+                    name,
+                    NewId::new((interface.name().to_string(), version), new_id),
+                ),
+        )?;
+        tracing::trace!("Sent bind request");
+        Ok((new_id, version))
+    }
+
+    pub fn has_global(&self, interface: Interface) -> bool {
+        self.interface_map
+            .values()
+            .any(|(iface, _)| iface == interface.name())
+    }
+
     pub fn lock(&mut self) -> Result<()> {
-        let id = self.next_id;
-        self.next_id += 1;
+        let id = self.new_object(Interface::SessionLock);
 
         self.send_message(self.session_lock_manager.unwrap().lock(id))?;
 
@@ -155,30 +536,330 @@ impl Wayland {
         Ok(())
     }
 
+    /// Unlocks the session, or abandons the lock if the compositor never confirmed it.
     pub fn unlock(&mut self) -> Result<()> {
-        self.send_message(self.session_lock.unwrap().unlock_and_destroy())?;
-        Ok(())
+        let lock = self.session_lock.take().unwrap();
+        match self.is_locked() {
+            true => self.send_message(lock.unlock_and_destroy()),
+            false => self.send_message(lock.destroy()),
+        }
+    }
+
+    /// Whether the compositor confirmed the session is locked.
+    pub fn is_locked(&self) -> bool {
+        self.lock_state == Some(SessionLockEvent::Locked)
+    }
+
+    /// Whether the compositor ended the lock on its own, e.g. because another locker holds it.
+    pub fn is_lock_finished(&self) -> bool {
+        self.lock_state == Some(SessionLockEvent::Finished)
+    }
+
+    /// Creates the lock surface role for `surface` on `output`; one is needed for every output.
+    pub fn get_lock_surface(
+        &mut self,
+        surface: WlSurface,
+        output: Object,
+    ) -> Result<SessionLockSurface> {
+        let lock = self.session_lock.expect("session not locked");
+        let id = self.new_object(Interface::SessionLockSurface);
+        self.send_message(lock.get_lock_surface(id, surface.id(), output))?;
+        Ok(SessionLockSurface::new(Object::new(id)))
+    }
+
+    pub fn ack_configure(&mut self, lock_surface: SessionLockSurface, serial: u32) -> Result<()> {
+        self.send_message(lock_surface.ack_configure(serial))
+    }
+
+    pub fn destroy_lock_surface(&mut self, lock_surface: SessionLockSurface) -> Result<()> {
+        self.send_message(lock_surface.destroy())
+    }
+
+    /// Whether surfaces can be drawn at fractional scales, which takes both a preferred scale
+    /// from the compositor and a viewport to scale the buffer down by it.
+    pub fn has_fractional_scale(&self) -> bool {
+        self.fractional_scale_manager.is_some() && self.viewporter.is_some()
+    }
+
+    pub fn get_fractional_scale(&mut self, surface: WlSurface) -> Result<FractionalScale> {
+        let manager = self
+            .fractional_scale_manager
+            .expect("wp_fractional_scale_manager_v1 not bound");
+        let id = self.new_object(Interface::FractionalScale);
+        self.send_message(manager.get_fractional_scale(id, surface.id()))?;
+        Ok(FractionalScale::new(Object::new(id)))
+    }
+
+    pub fn destroy_fractional_scale(&mut self, scale: FractionalScale) -> Result<()> {
+        self.send_message(scale.destroy())
+    }
+
+    /// Preferred scales received since the last call.
+    pub fn fractional_scale_events(
+        &mut self,
+    ) -> impl Iterator<Item = (FractionalScale, FractionalScaleEvent)> + '_ {
+        self.fractional_scale_events.drain(..)
+    }
+
+    pub fn get_viewport(&mut self, surface: WlSurface) -> Result<Viewport> {
+        let viewporter = self.viewporter.expect("wp_viewporter not bound");
+        let id = self.new_object(Interface::Viewport);
+        self.send_message(viewporter.get_viewport(id, surface.id()))?;
+        Ok(Viewport::new(Object::new(id)))
+    }
+
+    pub fn set_viewport_destination(
+        &mut self,
+        viewport: Viewport,
+        width: i32,
+        height: i32,
+    ) -> Result<()> {
+        self.send_message(viewport.set_destination(width, height))
+    }
+
+    pub fn destroy_viewport(&mut self, viewport: Viewport) -> Result<()> {
+        self.send_message(viewport.destroy())
+    }
+
+    /// Lock surface configure events received since the last call.
+    pub fn lock_surface_events(
+        &mut self,
+    ) -> impl Iterator<Item = (SessionLockSurface, SessionLockSurfaceEvent)> + '_ {
+        self.lock_surface_events.drain(..)
     }
 
     pub fn setup(&mut self) -> Result<()> {
-        let id = self.next_id;
-        self.next_id += 1;
+        let id = self.new_object(Interface::Registry);
         self.send_message(self.display.get_registry(id))?;
         self.registry = Some(WlRegistry::new(Object::new(id)));
         tracing::trace!("Created: {:?}", self.registry);
-        self.sync()?;
+        self.roundtrip()
+    }
 
-        while self.is_setup == false {
-            self.poll_events()?;
+    /// Blocks until the compositor has handled every request sent so far.
+    pub fn roundtrip(&mut self) -> Result<()> {
+        let id = self.sync()?;
+        while self.pending_syncs.contains(&id) {
+            self.dispatch(None)?;
         }
         Ok(())
     }
 
-    pub fn sync(&mut self) -> Result<()> {
-        let id = self.next_id;
-        self.next_id += 1;
+    pub fn sync(&mut self) -> Result<u32> {
+        let id = self.new_object(Interface::Callback);
         self.send_message(self.display.sync(id))?;
+        self.pending_syncs.insert(id);
         tracing::trace!("Sent sync request");
-        Ok(())
+        Ok(id)
+    }
+
+    /// Frame callbacks that fired since the last call, by callback id.
+    pub fn frame_callbacks(&mut self) -> impl Iterator<Item = u32> + '_ {
+        self.frame_callbacks.drain(..)
+    }
+}
+
+impl Wayland {
+    /// Surface events received since the last call, such as outputs entered or left.
+    pub fn surface_events(&mut self) -> impl Iterator<Item = (WlSurface, SurfaceEvent)> + '_ {
+        self.surface_events.drain(..)
+    }
+
+    pub fn create_surface(&mut self) -> Result<WlSurface> {
+        let compositor = self.compositor.expect("wl_compositor not bound");
+        let id = self.new_object(Interface::Surface);
+        self.send_message(compositor.create_surface(id))?;
+        let surface = WlSurface::new(Object::new(id), compositor.version());
+        self.surfaces.insert(id, surface);
+        Ok(surface)
+    }
+
+    pub fn destroy_surface(&mut self, surface: WlSurface) -> Result<()> {
+        self.surfaces.remove(&surface.id().inner());
+        self.pending_buffers.remove(&surface.id().inner());
+        self.send_message(surface.destroy())
+    }
+
+    /// Creates a region covering the given rectangles, e.g. for an opaque region.
+    pub fn create_region(&mut self, rectangles: &[(i32, i32, i32, i32)]) -> Result<WlRegion> {
+        let compositor = self.compositor.expect("wl_compositor not bound");
+        let id = self.new_object(Interface::Region);
+        self.send_message(compositor.create_region(id))?;
+        let region = WlRegion::new(Object::new(id));
+        for (x, y, width, height) in rectangles {
+            self.send_message(region.add(*x, *y, *width, *height))?;
+        }
+        Ok(region)
+    }
+
+    pub fn destroy_region(&mut self, region: WlRegion) -> Result<()> {
+        self.send_message(region.destroy())
+    }
+
+    /// Attaches `buffer` as the surface's pending content; `None` unmaps it on commit.
+    pub fn attach(&mut self, surface: WlSurface, buffer: Option<&Buffer>) -> Result<()> {
+        let buffer = buffer.map(|buffer| buffer.buffer().id());
+        match buffer {
+            Some(buffer) => self
+                .pending_buffers
+                .insert(surface.id().inner(), buffer.inner()),
+            None => self.pending_buffers.remove(&surface.id().inner()),
+        };
+        self.send_message(surface.attach(buffer, 0, 0))
+    }
+
+    /// Damages a rectangle in buffer coordinates, falling back to the whole surface on
+    /// compositors older than `damage_buffer`.
+    pub fn damage_buffer(
+        &mut self,
+        surface: WlSurface,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<()> {
+        let request = surface
+            .damage_buffer(x, y, width, height)
+            .unwrap_or_else(|| surface.damage(0, 0, i32::MAX, i32::MAX));
+        self.send_message(request)
+    }
+
+    /// Requests a `wl_callback` that fires when it is a good time to draw the next frame.
+    pub fn frame(&mut self, surface: WlSurface) -> Result<u32> {
+        let id = self.new_object(Interface::Callback);
+        self.send_message(surface.frame(id))?;
+        Ok(id)
+    }
+
+    pub fn set_opaque_region(
+        &mut self,
+        surface: WlSurface,
+        region: Option<WlRegion>,
+    ) -> Result<()> {
+        self.send_message(surface.set_opaque_region(region.map(|region| region.id())))
+    }
+
+    pub fn set_buffer_scale(&mut self, surface: WlSurface, scale: i32) -> Result<()> {
+        let request = surface
+            .set_buffer_scale(scale)
+            .ok_or_else(|| unsupported(surface, "set_buffer_scale"))?;
+        self.send_message(request)
+    }
+
+    pub fn set_buffer_transform(&mut self, surface: WlSurface, transform: Transform) -> Result<()> {
+        let request = surface
+            .set_buffer_transform(transform)
+            .ok_or_else(|| unsupported(surface, "set_buffer_transform"))?;
+        self.send_message(request)
+    }
+
+    #[allow(dead_code, reason = "lock surfaces fill their output and never move")]
+    pub fn offset(&mut self, surface: WlSurface, x: i32, y: i32) -> Result<()> {
+        let request = surface
+            .offset(x, y)
+            .ok_or_else(|| unsupported(surface, "offset"))?;
+        self.send_message(request)
+    }
+
+    /// Applies the surface's pending state. An attached buffer stays busy until released.
+    pub fn commit(&mut self, surface: WlSurface) -> Result<()> {
+        if let Some(buffer) = self.pending_buffers.remove(&surface.id().inner()) {
+            self.busy_buffers.insert(buffer);
+        }
+        self.send_message(surface.commit())
+    }
+
+    pub fn create_pool(&mut self, size: usize) -> Result<ShmPool> {
+        let shm = self.shm.expect("wl_shm not bound");
+        let memory = ShmPool::allocate(size)?;
+        let id = self.new_object(Interface::ShmPool);
+        self.send_message(shm.create_pool(id, memory.as_fd().as_raw_fd(), pool_size(size)?))?;
+        Ok(ShmPool::new(WlShmPool::new(Object::new(id)), memory))
     }
+
+    /// Grows `pool` to `size` bytes. Existing buffers keep their place in it.
+    pub fn resize_pool(&mut self, pool: &mut ShmPool, size: usize) -> Result<()> {
+        if size <= pool.size() {
+            return Ok(());
+        }
+        pool.grow(size)?;
+        self.send_message(pool.pool().resize(pool_size(size)?))
+    }
+
+    /// The compositor keeps the memory alive for buffers still using it.
+    pub fn destroy_pool(&mut self, pool: ShmPool) -> Result<()> {
+        self.send_message(pool.pool().destroy())
+    }
+
+    pub fn create_buffer(
+        &mut self,
+        pool: &ShmPool,
+        offset: usize,
+        width: u32,
+        height: u32,
+        format: Format,
+    ) -> Result<Buffer> {
+        let bytes_per_pixel = format.bytes_per_pixel().ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unsupported,
+                format!("cannot draw into {format:?}"),
+            )
+        })?;
+        if !self.shm_formats.contains(&format) {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("compositor does not support {format:?}"),
+            ));
+        }
+        let stride = width * bytes_per_pixel as u32;
+        let end = offset + stride as usize * height as usize;
+        if end > pool.size() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("buffer ends at {end}, past the {} byte pool", pool.size()),
+            ));
+        }
+        let id = self.new_object(Interface::Buffer);
+        self.send_message(pool.pool().create_buffer(
+            id,
+            pool_size(offset)?,
+            width as i32,
+            height as i32,
+            stride as i32,
+            format,
+        ))?;
+        Ok(Buffer::new(
+            WlBuffer::new(Object::new(id)),
+            offset,
+            width,
+            height,
+            stride,
+            format,
+        ))
+    }
+
+    pub fn destroy_buffer(&mut self, buffer: Buffer) -> Result<()> {
+        self.busy_buffers.remove(&buffer.buffer().id().inner());
+        self.send_message(buffer.buffer().destroy())
+    }
+
+    /// Whether the compositor may still be reading `buffer`, i.e. it was attached and committed
+    /// and no `wl_buffer.release` has arrived since.
+    pub fn is_buffer_busy(&self, buffer: &Buffer) -> bool {
+        self.busy_buffers.contains(&buffer.buffer().id().inner())
+    }
+}
+
+fn unsupported(surface: WlSurface, request: &str) -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "wl_surface.{request} needs a newer version than {}",
+            surface.version()
+        ),
+    )
+}
+
+fn pool_size(size: usize) -> Result<i32> {
+    i32::try_from(size).map_err(|_| Error::new(ErrorKind::InvalidInput, "pool too large"))
 }
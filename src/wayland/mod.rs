@@ -1,2 +1,13 @@
-mod types;
 pub mod connection;
+pub mod scheduler;
+pub mod shm;
+pub mod swapchain;
+#[cfg(test)]
+pub mod testing;
+mod types;
+
+pub use types::{
+    Format, FractionalScale, FractionalScaleEvent, Interface, KeyState, KeyboardEvent, Object,
+    PointerEvent, SCALE_DENOMINATOR, SessionLockSurface, SessionLockSurfaceEvent, SurfaceEvent,
+    Transform, Viewport, WlSurface,
+};
@@ -0,0 +1,134 @@
+use crate::wayland::{WlSurface, connection::Wayland};
+use std::io::Result;
+
+/// Throttles a surface's redraws to the compositor's frame callbacks: any number of changes
+/// between two callbacks result in a single redraw.
+#[derive(Debug, Default)]
+pub struct FrameScheduler {
+    dirty: bool,
+    /// The frame callback requested with the last drawn frame, until it fires.
+    pending: Option<u32>,
+}
+
+impl FrameScheduler {
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether there is something new to show and the compositor is ready for it.
+    pub fn should_draw(&self) -> bool {
+        self.dirty && self.pending.is_none()
+    }
+
+    /// Requests the callback that gates the next frame; call before committing a drawn frame.
+    pub fn frame_drawn(&mut self, conn: &mut Wayland, surface: WlSurface) -> Result<()> {
+        self.pending = Some(conn.frame(surface)?);
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Handles a fired frame callback, returning whether it belonged to this surface.
+    pub fn callback_done(&mut self, callback: u32) -> bool {
+        if self.pending == Some(callback) {
+            self.pending = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Draws the next frame without waiting, for a configure that must be answered with a
+    /// buffer of the new size. A callback still in flight is ignored when it fires.
+    pub fn force_redraw(&mut self) {
+        self.pending = None;
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wayland::testing::FakeCompositor;
+
+    /// `wl_callback.done`.
+    const DONE: u16 = 0;
+
+    /// Fires every frame callback the compositor was asked for, returning whether one belonged
+    /// to `scheduler`.
+    fn fire(
+        compositor: &mut FakeCompositor,
+        conn: &mut Wayland,
+        scheduler: &mut FrameScheduler,
+    ) -> bool {
+        let callback = scheduler.pending.expect("no frame callback requested");
+        compositor.send(conn, callback, DONE, &[0]);
+        let fired: Vec<_> = conn.frame_callbacks().collect();
+        assert_eq!(fired, [callback]);
+        scheduler.callback_done(callback)
+    }
+
+    #[test]
+    fn keeps_one_frame_in_flight() {
+        let (mut compositor, mut conn) = FakeCompositor::connect();
+        let surface = conn.create_surface().unwrap();
+        let mut scheduler = FrameScheduler::default();
+        assert!(!scheduler.should_draw());
+
+        scheduler.mark_dirty();
+        assert!(scheduler.should_draw());
+        scheduler.frame_drawn(&mut conn, surface).unwrap();
+        assert!(!scheduler.should_draw());
+
+        // A change before the compositor is ready waits for the callback.
+        scheduler.mark_dirty();
+        assert!(!scheduler.should_draw());
+        assert!(fire(&mut compositor, &mut conn, &mut scheduler));
+        assert!(scheduler.should_draw());
+
+        scheduler.frame_drawn(&mut conn, surface).unwrap();
+        assert!(fire(&mut compositor, &mut conn, &mut scheduler));
+        // Nothing changed meanwhile, so there is nothing to draw.
+        assert!(!scheduler.should_draw());
+    }
+
+    #[test]
+    fn coalesces_changes_into_one_frame() {
+        let (mut compositor, mut conn) = FakeCompositor::connect();
+        let surface = conn.create_surface().unwrap();
+        let mut scheduler = FrameScheduler::default();
+        scheduler.mark_dirty();
+        scheduler.frame_drawn(&mut conn, surface).unwrap();
+        for _ in 0..5 {
+            scheduler.mark_dirty();
+        }
+        assert!(fire(&mut compositor, &mut conn, &mut scheduler));
+        assert!(scheduler.should_draw());
+        scheduler.frame_drawn(&mut conn, surface).unwrap();
+        assert!(!scheduler.should_draw());
+    }
+
+    #[test]
+    fn ignores_other_and_stale_callbacks() {
+        let (mut compositor, mut conn) = FakeCompositor::connect();
+        let surface = conn.create_surface().unwrap();
+        let mut scheduler = FrameScheduler::default();
+        let mut other = FrameScheduler::default();
+        scheduler.mark_dirty();
+        scheduler.frame_drawn(&mut conn, surface).unwrap();
+        other.mark_dirty();
+        other.frame_drawn(&mut conn, surface).unwrap();
+        let stale = scheduler.pending.unwrap();
+        assert!(!other.callback_done(stale));
+        assert!(other.pending.is_some());
+
+        // A configure cannot wait for the callback; when it fires later it is not ours.
+        scheduler.force_redraw();
+        assert!(scheduler.should_draw());
+        scheduler.frame_drawn(&mut conn, surface).unwrap();
+        assert!(!scheduler.callback_done(stale));
+        assert!(!scheduler.should_draw());
+        scheduler.mark_dirty();
+        assert!(fire(&mut compositor, &mut conn, &mut scheduler));
+        assert!(scheduler.should_draw());
+    }
+}
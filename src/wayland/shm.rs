@@ -0,0 +1,123 @@
+use crate::wayland::types::{Format, MappedFile, WlBuffer, WlShmPool};
+use std::{
+    io::{Error, Result},
+    os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd},
+};
+
+/// Shared memory the compositor reads buffers from, backed by an anonymous memfd.
+#[derive(Debug)]
+pub struct ShmPool {
+    pool: WlShmPool,
+    memory: MappedFile,
+}
+
+impl ShmPool {
+    pub(super) fn new(pool: WlShmPool, memory: MappedFile) -> Self {
+        Self { pool, memory }
+    }
+
+    /// Creates the memfd for a pool of `size` bytes, sealed so it can grow but never shrink.
+    pub(super) fn allocate(size: usize) -> Result<MappedFile> {
+        // SAFETY: the name is a valid C string and no other pointers are passed.
+        let fd = unsafe {
+            libc::memfd_create(
+                c"lock-shm".as_ptr(),
+                libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+            )
+        };
+        if fd < 0 {
+            return Err(Error::last_os_error());
+        }
+        // SAFETY: memfd_create returned a new fd that nothing else owns.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        truncate(&fd, size)?;
+        // SAFETY: plain fcntl on an fd we own.
+        if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_ADD_SEALS, libc::F_SEAL_SHRINK) } < 0 {
+            return Err(Error::last_os_error());
+        }
+        MappedFile::map_shared(fd, size)
+    }
+
+    /// Grows the memfd and our mapping; the compositor is told separately.
+    pub(super) fn grow(&mut self, size: usize) -> Result<()> {
+        truncate(&self.memory, size)?;
+        self.memory.remap(size)
+    }
+
+    pub fn pool(&self) -> WlShmPool {
+        self.pool
+    }
+
+    pub fn size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// The pixels of `buffer`, which must have been created from this pool.
+    pub fn pixels(&mut self, buffer: &Buffer) -> &mut [u8] {
+        &mut self.memory.as_bytes_mut()[buffer.offset..buffer.offset + buffer.len()]
+    }
+}
+
+fn truncate(fd: &impl AsFd, size: usize) -> Result<()> {
+    // SAFETY: plain ftruncate on a valid fd.
+    if unsafe { libc::ftruncate(fd.as_fd().as_raw_fd(), size as libc::off_t) } < 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// A `wl_buffer` covering a region of a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Buffer {
+    buffer: WlBuffer,
+    offset: usize,
+    width: u32,
+    height: u32,
+    stride: u32,
+    format: Format,
+}
+
+impl Buffer {
+    pub(super) fn new(
+        buffer: WlBuffer,
+        offset: usize,
+        width: u32,
+        height: u32,
+        stride: u32,
+        format: Format,
+    ) -> Self {
+        Self {
+            buffer,
+            offset,
+            width,
+            height,
+            stride,
+            format,
+        }
+    }
+
+    pub fn buffer(&self) -> WlBuffer {
+        self.buffer
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Size of the buffer's pixel data in bytes.
+    pub fn len(&self) -> usize {
+        self.stride as usize * self.height as usize
+    }
+}
@@ -0,0 +1,235 @@
+use crate::wayland::{
+    connection::Wayland,
+    shm::{Buffer, ShmPool},
+    types::Format,
+};
+use std::io::Result;
+
+/// Two buffers let us draw while the compositor shows the other one.
+const INITIAL_BUFFERS: usize = 2;
+/// A third covers compositors that hold on to both until well after the next commit.
+const MAX_BUFFERS: usize = 3;
+
+/// A buffer handed out for drawing the next frame.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    pub buffer: Buffer,
+    /// Whether the buffer still holds an earlier frame of this size, so only what changed since
+    /// then needs drawing.
+    pub reused: bool,
+}
+
+/// Per-surface buffers in one shm pool, handed out only once the compositor released them.
+#[derive(Debug)]
+pub struct Swapchain {
+    format: Format,
+    width: u32,
+    height: u32,
+    pool: Option<ShmPool>,
+    buffers: Vec<Buffer>,
+    /// Parallel to `buffers`: whether each has been handed out since it was created.
+    drawn: Vec<bool>,
+}
+
+impl Swapchain {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            width: 0,
+            height: 0,
+            pool: None,
+            buffers: Vec::new(),
+            drawn: Vec::new(),
+        }
+    }
+
+    /// Replaces all buffers with ones of the new size. Old buffers still held by the compositor
+    /// stay valid on its side until it is done with them.
+    pub fn resize(&mut self, conn: &mut Wayland, width: u32, height: u32) -> Result<()> {
+        if (width, height) == (self.width, self.height) && self.pool.is_some() {
+            return Ok(());
+        }
+        self.destroy(conn)?;
+        self.width = width;
+        self.height = height;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        let pool = conn.create_pool(self.buffer_len() * INITIAL_BUFFERS)?;
+        for _ in 0..INITIAL_BUFFERS {
+            let buffer = self.create_buffer(conn, &pool)?;
+            self.buffers.push(buffer);
+            self.drawn.push(false);
+        }
+        self.pool = Some(pool);
+        Ok(())
+    }
+
+    /// A buffer the compositor is not reading, growing the pool by one buffer if all are busy.
+    /// `None` means every buffer is in use; try again after the next release.
+    pub fn acquire(&mut self, conn: &mut Wayland) -> Result<Option<Frame>> {
+        if let Some(index) = self.buffers.iter().position(|b| !conn.is_buffer_busy(b)) {
+            let reused = std::mem::replace(&mut self.drawn[index], true);
+            return Ok(Some(Frame {
+                buffer: self.buffers[index],
+                reused,
+            }));
+        }
+        if self.buffers.len() >= MAX_BUFFERS {
+            return Ok(None);
+        }
+        let Some(mut pool) = self.pool.take() else {
+            return Ok(None);
+        };
+        let grown = conn
+            .resize_pool(&mut pool, self.buffer_len() * (self.buffers.len() + 1))
+            .and_then(|()| self.create_buffer(conn, &pool));
+        self.pool = Some(pool);
+        let buffer = grown?;
+        tracing::debug!(
+            "Compositor holds every buffer, growing to {}",
+            self.buffers.len() + 1
+        );
+        self.buffers.push(buffer);
+        self.drawn.push(true);
+        Ok(Some(Frame {
+            buffer,
+            reused: false,
+        }))
+    }
+
+    /// Pixel memory of a buffer handed out by `acquire`.
+    pub fn pixels(&mut self, buffer: &Buffer) -> &mut [u8] {
+        self.pool
+            .as_mut()
+            .expect("buffer from a swapchain without a pool")
+            .pixels(buffer)
+    }
+
+    pub fn destroy(&mut self, conn: &mut Wayland) -> Result<()> {
+        self.drawn.clear();
+        for buffer in self.buffers.drain(..) {
+            conn.destroy_buffer(buffer)?;
+        }
+        if let Some(pool) = self.pool.take() {
+            conn.destroy_pool(pool)?;
+        }
+        Ok(())
+    }
+
+    fn buffer_len(&self) -> usize {
+        let bytes_per_pixel = self.format.bytes_per_pixel().unwrap_or(4);
+        self.width as usize * self.height as usize * bytes_per_pixel
+    }
+
+    fn create_buffer(&self, conn: &mut Wayland, pool: &ShmPool) -> Result<Buffer> {
+        let offset = self.buffer_len() * self.buffers.len();
+        conn.create_buffer(pool, offset, self.width, self.height, self.format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wayland::{WlSurface, testing::FakeCompositor};
+
+    /// `wl_buffer.release`.
+    const RELEASE: u16 = 0;
+
+    /// Shows `frame`'s buffer on `surface`, after which the compositor holds it.
+    fn show(conn: &mut Wayland, surface: WlSurface, frame: &Frame) {
+        conn.attach(surface, Some(&frame.buffer)).unwrap();
+        conn.commit(surface).unwrap();
+    }
+
+    fn release(compositor: &mut FakeCompositor, conn: &mut Wayland, buffer: Buffer) {
+        compositor.send(conn, buffer.buffer().id().inner(), RELEASE, &[]);
+    }
+
+    #[test]
+    fn reuses_buffers_the_compositor_released() {
+        let (mut compositor, mut conn) = FakeCompositor::connect();
+        let surface = conn.create_surface().unwrap();
+        let mut swapchain = Swapchain::new(Format::ARGB8888);
+        swapchain.resize(&mut conn, 16, 8).unwrap();
+
+        // Nothing committed yet, so the first buffer comes back.
+        let first = swapchain.acquire(&mut conn).unwrap().unwrap();
+        assert!(!first.reused);
+        assert_eq!(swapchain.pixels(&first.buffer).len(), 16 * 8 * 4);
+        let again = swapchain.acquire(&mut conn).unwrap().unwrap();
+        assert_eq!(again.buffer, first.buffer);
+        assert!(again.reused);
+
+        show(&mut conn, surface, &first);
+        let second = swapchain.acquire(&mut conn).unwrap().unwrap();
+        assert_ne!(second.buffer, first.buffer);
+        assert!(!second.reused);
+
+        show(&mut conn, surface, &second);
+        release(&mut compositor, &mut conn, first.buffer);
+        let frame = swapchain.acquire(&mut conn).unwrap().unwrap();
+        assert_eq!(frame.buffer, first.buffer);
+        assert!(frame.reused);
+    }
+
+    #[test]
+    fn grows_when_the_compositor_holds_every_buffer() {
+        let (mut compositor, mut conn) = FakeCompositor::connect();
+        let surface = conn.create_surface().unwrap();
+        let mut swapchain = Swapchain::new(Format::XRGB8888);
+        swapchain.resize(&mut conn, 10, 10).unwrap();
+        let len = 10 * 10 * 4;
+        assert_eq!(swapchain.pool.as_ref().unwrap().size(), 2 * len);
+
+        let mut shown = Vec::new();
+        for _ in 0..MAX_BUFFERS {
+            let frame = swapchain.acquire(&mut conn).unwrap().unwrap();
+            assert!(!frame.reused);
+            assert!(!shown.contains(&frame.buffer));
+            show(&mut conn, surface, &frame);
+            shown.push(frame.buffer);
+        }
+        assert_eq!(swapchain.buffers.len(), MAX_BUFFERS);
+        assert_eq!(swapchain.pool.as_ref().unwrap().size(), MAX_BUFFERS * len);
+        // The third buffer sits after the first two in the grown pool.
+        assert_eq!(shown[2].len(), len);
+        swapchain.pixels(&shown[2]).fill(0xff);
+
+        // Beyond the limit it waits for a release instead.
+        assert!(swapchain.acquire(&mut conn).unwrap().is_none());
+        release(&mut compositor, &mut conn, shown[1]);
+        let frame = swapchain.acquire(&mut conn).unwrap().unwrap();
+        assert_eq!(frame.buffer, shown[1]);
+        assert!(frame.reused);
+    }
+
+    #[test]
+    fn resizing_starts_over() {
+        let (_compositor, mut conn) = FakeCompositor::connect();
+        let surface = conn.create_surface().unwrap();
+        let mut swapchain = Swapchain::new(Format::ARGB8888);
+        swapchain.resize(&mut conn, 4, 4).unwrap();
+        let frame = swapchain.acquire(&mut conn).unwrap().unwrap();
+        show(&mut conn, surface, &frame);
+
+        // The same size again keeps the buffers and what they hold.
+        swapchain.resize(&mut conn, 4, 4).unwrap();
+        let second = swapchain.acquire(&mut conn).unwrap().unwrap();
+        assert_ne!(second.buffer, frame.buffer);
+
+        // A new size replaces them, busy or not, with undrawn ones.
+        swapchain.resize(&mut conn, 8, 2).unwrap();
+        assert!(!conn.is_buffer_busy(&frame.buffer));
+        for _ in 0..INITIAL_BUFFERS {
+            let frame = swapchain.acquire(&mut conn).unwrap().unwrap();
+            assert_eq!((frame.buffer.width(), frame.buffer.height()), (8, 2));
+            assert!(!frame.reused);
+            show(&mut conn, surface, &frame);
+        }
+
+        // Without a size there is nothing to draw into.
+        swapchain.resize(&mut conn, 0, 2).unwrap();
+        assert!(swapchain.acquire(&mut conn).unwrap().is_none());
+    }
+}
@@ -0,0 +1,28 @@
+//! A stand-in for a compositor, for tests: the test sends whatever events it likes and the
+//! requests it gets are left unread.
+
+use crate::wayland::connection::Wayland;
+use std::{io::Write, os::unix::net::UnixStream};
+
+pub struct FakeCompositor {
+    stream: UnixStream,
+}
+
+impl FakeCompositor {
+    /// A connected compositor and client.
+    pub fn connect() -> (Self, Wayland) {
+        let (stream, client) = UnixStream::pair().expect("cannot create a socket pair");
+        let conn = Wayland::fake(client).expect("cannot set up the client");
+        (Self { stream }, conn)
+    }
+
+    /// Sends event `opcode` of `object` with the given arguments, then has `conn` handle it.
+    pub fn send(&mut self, conn: &mut Wayland, object: u32, opcode: u16, args: &[u32]) {
+        let size = 8 + 4 * args.len() as u32;
+        let mut message = vec![object, size << 16 | opcode as u32];
+        message.extend_from_slice(args);
+        let bytes: Vec<u8> = message.iter().flat_map(|word| word.to_ne_bytes()).collect();
+        self.stream.write_all(&bytes).unwrap();
+        conn.poll_events().unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use crate::wayland::types::common::parse_utils::WaylandResult;
+use crate::wayland::types::common::parse_utils::{Error, WaylandResult};
 use std::io::{Cursor, Read};
 
 pub trait Argument: Sized {
@@ -26,6 +26,23 @@ impl Argument for u32 {
     }
 }
 
+impl Argument for i32 {
+    fn decode(data: &mut Cursor<&[u8]>) -> WaylandResult<Self> {
+        let mut bytes = [0u8; 4];
+        data.read_exact(&mut bytes)?;
+        Ok(i32::from_le_bytes(bytes))
+    }
+
+    fn encode(&self) -> WaylandResult<Vec<u8>> {
+        Ok(self.to_le_bytes().to_vec())
+    }
+
+    fn encode_extend(&self, mut buffer: Vec<u8>) -> WaylandResult<Vec<u8>> {
+        buffer.extend_from_slice(&self.to_le_bytes());
+        Ok(buffer)
+    }
+}
+
 impl Argument for u16 {
     fn decode(data: &mut Cursor<&[u8]>) -> WaylandResult<Self> {
         let mut bytes = [0u8; 2];
@@ -83,7 +100,36 @@ impl Argument for String {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// The `array` wire type: a length prefixed blob padded to 32 bits.
+impl Argument for Vec<u8> {
+    fn decode(data: &mut Cursor<&[u8]>) -> WaylandResult<Self> {
+        let len = u32::decode(data)? as usize;
+        let start = data.position() as usize;
+        let padded = (len + 3) & !3;
+        if start + padded > data.get_ref().len() {
+            return Err(Error::UnexpectedEndOfBuffer);
+        }
+        let bytes = data.get_ref()[start..start + len].to_vec();
+        data.set_position((start + padded) as u64);
+        Ok(bytes)
+    }
+
+    fn encode(&self) -> WaylandResult<Vec<u8>> {
+        let mut buffer = (self.len() as u32).encode()?;
+        buffer.extend_from_slice(self);
+        while buffer.len() % 4 != 0 {
+            buffer.push(0);
+        }
+        Ok(buffer)
+    }
+
+    fn encode_extend(&self, mut buffer: Vec<u8>) -> WaylandResult<Vec<u8>> {
+        buffer.extend_from_slice(&self.encode()?);
+        Ok(buffer)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Object(u32);
 
 impl Object {
@@ -125,8 +171,10 @@ impl NewId {
         Self(interface.0, interface.1, new_id)
     }
 
+    /// Encoded size: the length-prefixed, NUL-terminated and padded interface name, the
+    /// version and the id.
     pub fn len(&self) -> usize {
-        self.0.len() + 4 + self.2.len() + 1
+        4 + (self.0.len() + 1).next_multiple_of(4) + 4 + self.2.len()
     }
 }
 
@@ -0,0 +1,98 @@
+use std::{
+    io,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd},
+    ptr::NonNull,
+};
+
+/// A memory mapping of a file descriptor: read-only and private for files received from the
+/// compositor, or shared and writable for memory we hand to it.
+#[derive(Debug)]
+pub struct MappedFile {
+    ptr: NonNull<u8>,
+    len: usize,
+    writable: bool,
+    fd: OwnedFd,
+}
+
+impl MappedFile {
+    pub fn map(fd: OwnedFd, len: usize) -> io::Result<Self> {
+        Self::new(fd, len, false)
+    }
+
+    pub fn map_shared(fd: OwnedFd, len: usize) -> io::Result<Self> {
+        Self::new(fd, len, true)
+    }
+
+    fn new(fd: OwnedFd, len: usize, writable: bool) -> io::Result<Self> {
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "refusing to map an empty file",
+            ));
+        }
+        let (prot, flags) = match writable {
+            true => (libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED),
+            false => (libc::PROT_READ, libc::MAP_PRIVATE),
+        };
+        // SAFETY: we request a fresh mapping, the kernel picks the address.
+        let ptr = unsafe { libc::mmap(std::ptr::null_mut(), len, prot, flags, fd.as_raw_fd(), 0) };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: NonNull::new(ptr as *mut u8).expect("mmap returned null"),
+            len,
+            writable,
+            fd,
+        })
+    }
+
+    /// Grows or shrinks the mapping to `len` bytes, possibly moving it.
+    pub fn remap(&mut self, len: usize) -> io::Result<()> {
+        // SAFETY: ptr/len describe our current mapping, which is replaced on success.
+        let ptr = unsafe {
+            libc::mremap(
+                self.ptr.as_ptr() as *mut libc::c_void,
+                self.len,
+                len,
+                libc::MREMAP_MAYMOVE,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        self.ptr = NonNull::new(ptr as *mut u8).expect("mremap returned null");
+        self.len = len;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        // SAFETY: the mapping is valid for `len` bytes until drop.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        assert!(self.writable, "mapping is read-only");
+        // SAFETY: the mapping is writable and valid for `len` bytes until drop.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsFd for MappedFile {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        // SAFETY: ptr/len describe the mapping created in `new` or `remap`.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len);
+        }
+    }
+}
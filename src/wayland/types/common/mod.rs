@@ -1,3 +1,4 @@
 pub mod argument;
-pub mod parse_utils;
 pub mod header;
+pub mod mmap;
+pub mod parse_utils;
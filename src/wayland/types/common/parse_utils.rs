@@ -2,12 +2,14 @@ use std::fmt;
 use std::io;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     ParseError(String),
     Io(io::Error),
     Utf8(std::string::FromUtf8Error),
     UnexpectedEndOfBuffer,
     InvalidArgument,
+    MissingFd,
 }
 
 impl fmt::Display for Error {
@@ -18,6 +20,7 @@ impl fmt::Display for Error {
             Self::Utf8(err) => write!(f, "UTF-8 conversion error: {}", err),
             Self::UnexpectedEndOfBuffer => write!(f, "Unexpected end of buffer"),
             Self::InvalidArgument => write!(f, "Argument invalid"),
+            Self::MissingFd => write!(f, "Expected a file descriptor but none was received"),
         }
     }
 }
@@ -0,0 +1,74 @@
+use crate::wayland::types::{
+    common::argument::Object,
+    request::{Message, RequestMessage},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WlCompositor {
+    id: Object,
+    version: u32,
+}
+
+impl WlCompositor {
+    pub fn new(id: Object, version: u32) -> Self {
+        Self { id, version }
+    }
+
+    /// Surfaces share the compositor's version.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn create_surface(&self, surface: u32) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            0,
+            12,
+            Message::CreateSurface {
+                surface: Object::new(surface),
+            },
+        )
+    }
+
+    pub fn create_region(&self, region: u32) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            1,
+            12,
+            Message::CreateRegion {
+                region: Object::new(region),
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WlRegion(Object);
+
+impl WlRegion {
+    pub fn new(id: Object) -> Self {
+        Self(id)
+    }
+
+    pub fn id(&self) -> Object {
+        self.0
+    }
+
+    pub fn destroy(&self) -> RequestMessage {
+        RequestMessage::build(self.0, 0, 8, Message::Empty)
+    }
+
+    pub fn add(&self, x: i32, y: i32, width: i32, height: i32) -> RequestMessage {
+        RequestMessage::build(
+            self.0,
+            1,
+            24,
+            Message::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            },
+        )
+    }
+}
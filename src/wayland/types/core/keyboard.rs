@@ -1,35 +1,349 @@
 use crate::wayland::types::{
-    common::argument::Object,
+    common::{
+        argument::{Argument, Object},
+        mmap::MappedFile,
+        parse_utils::{Error, WaylandResult},
+    },
     request::{Message, RequestMessage},
 };
+use std::{collections::VecDeque, io::Cursor, os::fd::OwnedFd};
 
-
-#[derive(Debug, Clone, Copy)]
-enum KeymapFormat {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeymapFormat {
     NoKeymap,
-    XKBV1
+    XKBV1,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum KeyState {
+impl TryFrom<u32> for KeymapFormat {
+    type Error = Error;
+
+    fn try_from(value: u32) -> WaylandResult<Self> {
+        match value {
+            0 => Ok(Self::NoKeymap),
+            1 => Ok(Self::XKBV1),
+            _ => Err(Error::InvalidArgument),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
     Released,
     Pressed,
     // since version 10
-    Repeated
+    Repeated,
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Keymap {
-    // implement fd first
-    format: KeymapFormat,
-    fd: u32,
-    size: u32
+impl TryFrom<u32> for KeyState {
+    type Error = Error;
+
+    fn try_from(value: u32) -> WaylandResult<Self> {
+        match value {
+            0 => Ok(Self::Released),
+            1 => Ok(Self::Pressed),
+            2 => Ok(Self::Repeated),
+            _ => Err(Error::InvalidArgument),
+        }
+    }
+}
+
+/// The keymap shared by the compositor, mapped read-only for as long as it is in use.
+#[derive(Debug)]
+pub struct Keymap {
+    map: MappedFile,
+}
+
+impl Keymap {
+    /// The keymap text without the trailing NUL terminator.
+    pub fn as_bytes(&self) -> &[u8] {
+        let bytes = self.map.as_bytes();
+        match bytes.iter().position(|b| *b == 0) {
+            Some(end) => &bytes[..end],
+            None => bytes,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code, reason = "serials, timestamps and held keys go unread")]
+pub enum KeyboardEvent {
+    /// `None` when the compositor sent `no_keymap`.
+    Keymap(Option<Keymap>),
+    Enter {
+        serial: u32,
+        surface: Object,
+        keys: Vec<u32>,
+    },
+    Leave {
+        serial: u32,
+        surface: Object,
+    },
+    Key {
+        serial: u32,
+        time: u32,
+        key: u32,
+        state: KeyState,
+    },
+    Modifiers {
+        serial: u32,
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+        group: u32,
+    },
+    /// `rate` is in characters per second, `delay` in milliseconds. A rate of zero disables repeat.
+    RepeatInfo {
+        rate: i32,
+        delay: i32,
+    },
+}
+
+impl KeyboardEvent {
+    pub fn parse(
+        opcode: u16,
+        payload: &mut Cursor<&[u8]>,
+        fds: &mut VecDeque<OwnedFd>,
+    ) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => {
+                let format = KeymapFormat::try_from(u32::decode(payload)?)?;
+                let fd = fds.pop_front().ok_or(Error::MissingFd)?;
+                let size = u32::decode(payload)? as usize;
+                match format {
+                    KeymapFormat::NoKeymap => Ok(Some(Self::Keymap(None))),
+                    KeymapFormat::XKBV1 => {
+                        let map = MappedFile::map(fd, size)?;
+                        Ok(Some(Self::Keymap(Some(Keymap { map }))))
+                    }
+                }
+            }
+            1 => {
+                let serial = u32::decode(payload)?;
+                let surface = Object::decode(payload)?;
+                let raw = Vec::<u8>::decode(payload)?;
+                if !raw.len().is_multiple_of(4) {
+                    return Err(Error::InvalidArgument);
+                }
+                let keys = raw
+                    .chunks_exact(4)
+                    .map(|key| u32::from_le_bytes([key[0], key[1], key[2], key[3]]))
+                    .collect();
+                Ok(Some(Self::Enter {
+                    serial,
+                    surface,
+                    keys,
+                }))
+            }
+            2 => {
+                let serial = u32::decode(payload)?;
+                let surface = Object::decode(payload)?;
+                Ok(Some(Self::Leave { serial, surface }))
+            }
+            3 => {
+                let serial = u32::decode(payload)?;
+                let time = u32::decode(payload)?;
+                let key = u32::decode(payload)?;
+                let state = KeyState::try_from(u32::decode(payload)?)?;
+                Ok(Some(Self::Key {
+                    serial,
+                    time,
+                    key,
+                    state,
+                }))
+            }
+            4 => {
+                let serial = u32::decode(payload)?;
+                let depressed = u32::decode(payload)?;
+                let latched = u32::decode(payload)?;
+                let locked = u32::decode(payload)?;
+                let group = u32::decode(payload)?;
+                Ok(Some(Self::Modifiers {
+                    serial,
+                    depressed,
+                    latched,
+                    locked,
+                    group,
+                }))
+            }
+            5 => {
+                let rate = i32::decode(payload)?;
+                let delay = i32::decode(payload)?;
+                Ok(Some(Self::RepeatInfo { rate, delay }))
+            }
+            _ => Ok(None),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct WlKeyboard{
+pub struct WlKeyboard {
     id: Object,
-    keymap: Option<Keymap>
+    version: u32,
 }
 
+impl WlKeyboard {
+    pub fn new(id: Object, version: u32) -> Self {
+        Self { id, version }
+    }
 
+    pub fn id(&self) -> Object {
+        self.id
+    }
+
+    /// `wl_keyboard.release`, only available since version 3.
+    pub fn release(&self) -> Option<RequestMessage> {
+        (self.version >= 3).then(|| RequestMessage::build(self.id, 0, 8, Message::Empty))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs::File, io::Write, os::fd::FromRawFd};
+
+    fn parse(
+        opcode: u16,
+        payload: &[u8],
+        fds: Vec<OwnedFd>,
+    ) -> WaylandResult<Option<KeyboardEvent>> {
+        KeyboardEvent::parse(opcode, &mut Cursor::new(payload), &mut fds.into())
+    }
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    /// An anonymous file holding `contents`, as compositors share keymaps.
+    fn memfd(contents: &[u8]) -> OwnedFd {
+        // SAFETY: the name is a valid C string and no other pointers are passed.
+        let fd = unsafe { libc::memfd_create(c"keymap".as_ptr(), libc::MFD_CLOEXEC) };
+        assert!(fd >= 0, "memfd_create failed");
+        // SAFETY: memfd_create returned a new fd that nothing else owns.
+        let mut file = File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        file.write_all(contents).unwrap();
+        file.into()
+    }
+
+    #[test]
+    fn keymap() {
+        let text = b"xkb_keymap { };\0";
+        let fd = memfd(text);
+        let event = parse(0, &words(&[1, text.len() as u32]), vec![fd]).unwrap();
+        let Some(KeyboardEvent::Keymap(Some(keymap))) = event else {
+            panic!("expected a keymap, got {event:?}");
+        };
+        assert_eq!(keymap.as_bytes(), b"xkb_keymap { };");
+
+        // `no_keymap` still comes with an fd, which is dropped unread.
+        let event = parse(0, &words(&[0, 0]), vec![memfd(b"")]).unwrap();
+        assert!(matches!(event, Some(KeyboardEvent::Keymap(None))));
+        assert!(matches!(
+            parse(0, &words(&[1, 16]), Vec::new()),
+            Err(Error::MissingFd)
+        ));
+        assert!(matches!(
+            parse(0, &words(&[2, 16]), vec![memfd(text)]),
+            Err(Error::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn enter() {
+        let mut payload = words(&[7, 3]);
+        payload.extend(words(&[30, 42]).encode().unwrap());
+        let event = parse(1, &payload, Vec::new()).unwrap();
+        let Some(KeyboardEvent::Enter {
+            serial,
+            surface,
+            keys,
+        }) = event
+        else {
+            panic!("expected enter, got {event:?}");
+        };
+        assert_eq!((serial, surface, keys), (7, Object::new(3), vec![30, 42]));
+
+        // Keys are 32-bit, so the array must hold a whole number of them.
+        let mut payload = words(&[7, 3]);
+        payload.extend(vec![30u8, 0, 0, 0, 42, 0].encode().unwrap());
+        assert!(matches!(
+            parse(1, &payload, Vec::new()),
+            Err(Error::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn leave() {
+        let event = parse(2, &words(&[8, 3]), Vec::new()).unwrap();
+        let Some(KeyboardEvent::Leave { serial, surface }) = event else {
+            panic!("expected leave, got {event:?}");
+        };
+        assert_eq!((serial, surface), (8, Object::new(3)));
+    }
+
+    #[test]
+    fn key() {
+        let event = parse(3, &words(&[9, 1000, 30, 1]), Vec::new()).unwrap();
+        let Some(KeyboardEvent::Key {
+            serial,
+            time,
+            key,
+            state,
+        }) = event
+        else {
+            panic!("expected key, got {event:?}");
+        };
+        assert_eq!((serial, time, key, state), (9, 1000, 30, KeyState::Pressed));
+
+        let event = parse(3, &words(&[9, 1000, 30, 2]), Vec::new()).unwrap();
+        assert!(matches!(
+            event,
+            Some(KeyboardEvent::Key {
+                state: KeyState::Repeated,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(3, &words(&[9, 1000, 30, 3]), Vec::new()),
+            Err(Error::InvalidArgument)
+        ));
+        assert!(matches!(
+            parse(3, &words(&[9, 1000, 30]), Vec::new()),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn modifiers() {
+        let event = parse(4, &words(&[10, 1, 2, 4, 1]), Vec::new()).unwrap();
+        let Some(KeyboardEvent::Modifiers {
+            serial,
+            depressed,
+            latched,
+            locked,
+            group,
+        }) = event
+        else {
+            panic!("expected modifiers, got {event:?}");
+        };
+        assert_eq!(
+            (serial, depressed, latched, locked, group),
+            (10, 1, 2, 4, 1)
+        );
+    }
+
+    #[test]
+    fn repeat_info() {
+        let event = parse(5, &words(&[25, 600]), Vec::new()).unwrap();
+        assert!(matches!(
+            event,
+            Some(KeyboardEvent::RepeatInfo {
+                rate: 25,
+                delay: 600
+            })
+        ));
+        assert!(parse(6, &[], Vec::new()).unwrap().is_none());
+    }
+}
@@ -1,6 +1,9 @@
+pub mod compositor;
 pub mod display;
+pub mod keyboard;
 pub mod output;
+pub mod pointer;
 pub mod registry;
-pub mod surface;
 pub mod seat;
-pub mod keyboard;
+pub mod shm;
+pub mod surface;
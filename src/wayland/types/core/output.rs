@@ -1,18 +1,12 @@
-use crate::wayland::types::{
-    common::argument::Object,
-    request::{Message, RequestMessage},
+use crate::wayland::types::common::{
+    argument::{Argument, Object},
+    parse_utils::{Error, WaylandResult},
 };
+use std::io::Cursor;
 
-pub enum SubPixel {
-    Unkown,
-    None,
-    HorizontalRGB,
-    HorizontalBGR,
-    VerticalRGB,
-    VerticalBGR,
-}
-
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Transform {
+    #[default]
     Normal,
     D90,
     D180,
@@ -23,34 +17,197 @@ pub enum Transform {
     Flipped270,
 }
 
-pub enum ModeType {
-    Current,
-    Preferred,
+impl Transform {
+    /// Whether the transform turns the content by 90 or 270 degrees, swapping width and height.
+    pub fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Self::D90 | Self::D270 | Self::Flipped90 | Self::Flipped270
+        )
+    }
+
+    /// Where the pixel at (`x`, `y`) of `width` by `height` surface content lands in a buffer
+    /// that has this transform applied, as `wl_surface.set_buffer_transform` describes it.
+    pub fn buffer_point(&self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        let (right, bottom) = (width - 1 - x, height - 1 - y);
+        match self {
+            Self::Normal => (x, y),
+            Self::D90 => (y, right),
+            Self::D180 => (right, bottom),
+            Self::D270 => (bottom, x),
+            Self::Flipped => (right, y),
+            Self::Flipped90 => (y, x),
+            Self::Flipped180 => (x, bottom),
+            Self::Flipped270 => (bottom, right),
+        }
+    }
 }
 
-pub struct Mode {
-    flags: ModeType,
-    width: i32,
-    height: i32,
-    refresh: i32
+impl TryFrom<u32> for Transform {
+    type Error = Error;
+
+    fn try_from(value: u32) -> WaylandResult<Self> {
+        match value {
+            0 => Ok(Self::Normal),
+            1 => Ok(Self::D90),
+            2 => Ok(Self::D180),
+            3 => Ok(Self::D270),
+            4 => Ok(Self::Flipped),
+            5 => Ok(Self::Flipped90),
+            6 => Ok(Self::Flipped180),
+            7 => Ok(Self::Flipped270),
+            _ => Err(Error::InvalidArgument),
+        }
+    }
 }
 
-pub struct OutputGeometry {
-    x: i32,
-    y: i32,
-    physical_width: i32,
-    physical_height: i32,
-    subpixel: SubPixel,
-    make: String,
-    model: String,
-    transform: Transform,
+/// Only the properties the lock screen is drawn with are kept.
+#[derive(Debug)]
+pub enum OutputEvent {
+    Geometry { transform: Transform },
+    Mode,
+    Done,
+    Scale(i32),
+    Name(String),
+    Description(String),
+}
+
+impl OutputEvent {
+    pub fn parse(opcode: u16, payload: &mut Cursor<&[u8]>) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => {
+                // Position, physical size and subpixel layout, then make and model.
+                for _ in 0..5 {
+                    i32::decode(payload)?;
+                }
+                String::decode(payload)?;
+                String::decode(payload)?;
+                let transform = Transform::try_from(i32::decode(payload)? as u32)?;
+                Ok(Some(Self::Geometry { transform }))
+            }
+            1 => Ok(Some(Self::Mode)),
+            2 => Ok(Some(Self::Done)),
+            3 => Ok(Some(Self::Scale(i32::decode(payload)?))),
+            4 => Ok(Some(Self::Name(String::decode(payload)?))),
+            5 => Ok(Some(Self::Description(String::decode(payload)?))),
+            _ => Ok(None),
+        }
+    }
 }
 
 pub struct WlOutput {
     id: Object,
-    geometry: Option<OutputGeometry>,
+    transform: Transform,
     factor: Option<i32>,
     name: Option<String>,
     description: Option<String>,
-    modes: Vec<Mode>
+}
+
+impl WlOutput {
+    pub fn new(id: Object) -> Self {
+        Self {
+            id,
+            transform: Transform::Normal,
+            factor: None,
+            name: None,
+            description: None,
+        }
+    }
+
+    pub fn id(&self) -> Object {
+        self.id
+    }
+
+    /// How the output is rotated or flipped, normal until announced.
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    /// The integer scale the compositor renders this output's surfaces at, 1 until announced.
+    pub fn scale(&self) -> i32 {
+        self.factor.unwrap_or(1)
+    }
+
+    /// The connector name, such as `DP-1`, from version 4.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// A human-readable description, usually make, model and serial, from version 4.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Records a property the compositor announced.
+    pub fn handle_event(&mut self, event: OutputEvent) {
+        match event {
+            OutputEvent::Geometry { transform } => self.transform = transform,
+            OutputEvent::Mode | OutputEvent::Done => {}
+            OutputEvent::Scale(factor) => self.factor = Some(factor),
+            OutputEvent::Name(name) => self.name = Some(name),
+            OutputEvent::Description(description) => self.description = Some(description),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transform;
+
+    /// Where each pixel of a 3 by 2 surface lands, row by row.
+    fn map(transform: Transform) -> Vec<(u32, u32)> {
+        (0..2)
+            .flat_map(|y| (0..3).map(move |x| transform.buffer_point(x, y, 3, 2)))
+            .collect()
+    }
+
+    #[test]
+    fn normal() {
+        let expected = [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)];
+        assert_eq!(map(Transform::Normal), expected);
+    }
+
+    #[test]
+    fn rotated_90() {
+        // The top row becomes the left column of the 2 by 3 buffer, read bottom to top.
+        let expected = [(0, 2), (0, 1), (0, 0), (1, 2), (1, 1), (1, 0)];
+        assert_eq!(map(Transform::D90), expected);
+    }
+
+    #[test]
+    fn rotated_180() {
+        let expected = [(2, 1), (1, 1), (0, 1), (2, 0), (1, 0), (0, 0)];
+        assert_eq!(map(Transform::D180), expected);
+    }
+
+    #[test]
+    fn rotated_270() {
+        // The top row becomes the right column of the 2 by 3 buffer, read top to bottom.
+        let expected = [(1, 0), (1, 1), (1, 2), (0, 0), (0, 1), (0, 2)];
+        assert_eq!(map(Transform::D270), expected);
+    }
+
+    #[test]
+    fn flipped() {
+        let expected = [(2, 0), (1, 0), (0, 0), (2, 1), (1, 1), (0, 1)];
+        assert_eq!(map(Transform::Flipped), expected);
+    }
+
+    #[test]
+    fn flipped_90() {
+        let expected = [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)];
+        assert_eq!(map(Transform::Flipped90), expected);
+    }
+
+    #[test]
+    fn flipped_180() {
+        let expected = [(0, 1), (1, 1), (2, 1), (0, 0), (1, 0), (2, 0)];
+        assert_eq!(map(Transform::Flipped180), expected);
+    }
+
+    #[test]
+    fn flipped_270() {
+        let expected = [(1, 2), (1, 1), (1, 0), (0, 2), (0, 1), (0, 0)];
+        assert_eq!(map(Transform::Flipped270), expected);
+    }
 }
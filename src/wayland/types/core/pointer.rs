@@ -0,0 +1,54 @@
+use crate::wayland::types::{
+    common::{argument::Object, parse_utils::WaylandResult},
+    request::{Message, RequestMessage},
+};
+
+/// The locker only cares that the pointer is being used, not where it is or what it does.
+#[derive(Debug)]
+pub enum PointerEvent {
+    Enter,
+    Leave,
+    Motion,
+    Button,
+    Axis,
+    Frame,
+    /// `axis_source`, `axis_stop`, `axis_discrete`, `axis_value120` or
+    /// `axis_relative_direction`, which only refine `Axis`.
+    AxisDetail,
+}
+
+impl PointerEvent {
+    pub fn parse(opcode: u16) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => Ok(Some(Self::Enter)),
+            1 => Ok(Some(Self::Leave)),
+            2 => Ok(Some(Self::Motion)),
+            3 => Ok(Some(Self::Button)),
+            4 => Ok(Some(Self::Axis)),
+            5 => Ok(Some(Self::Frame)),
+            6..=10 => Ok(Some(Self::AxisDetail)),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WlPointer {
+    id: Object,
+    version: u32,
+}
+
+impl WlPointer {
+    pub fn new(id: Object, version: u32) -> Self {
+        Self { id, version }
+    }
+
+    pub fn id(&self) -> Object {
+        self.id
+    }
+
+    /// `wl_pointer.release`, only available since version 3.
+    pub fn release(&self) -> Option<RequestMessage> {
+        (self.version >= 3).then(|| RequestMessage::build(self.id, 1, 8, Message::Empty))
+    }
+}
@@ -14,7 +14,7 @@ impl WlRegistry {
         RequestMessage::build(
             self.0,
             0,
-            16 + id.len() as u16,
+            12 + id.len() as u16,
             Message::Bind {
                 name: Object::new(name),
                 id,
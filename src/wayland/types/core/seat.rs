@@ -1,17 +1,79 @@
 use crate::wayland::types::{
-    common::argument::Object,
+    common::{
+        argument::{Argument, Object},
+        parse_utils::WaylandResult,
+    },
     request::{Message, RequestMessage},
 };
+use std::io::Cursor;
 
-enum Capability {
-    Pointer,
-    Keyboard,
-    Touch
+#[derive(Debug, Clone, Copy)]
+pub enum Capability {
+    Pointer = 1,
+    Keyboard = 2,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    pub fn has(&self, capability: Capability) -> bool {
+        self.0 & capability as u32 != 0
+    }
+}
+
+#[derive(Debug)]
+pub enum SeatEvent {
+    Capabilities(Capabilities),
+    Name(String),
+}
+
+impl SeatEvent {
+    pub fn parse(opcode: u16, payload: &mut Cursor<&[u8]>) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => Ok(Some(Self::Capabilities(Capabilities(u32::decode(
+                payload,
+            )?)))),
+            1 => Ok(Some(Self::Name(String::decode(payload)?))),
+            _ => Ok(None),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct WlSeat{
+pub struct WlSeat {
     id: Object,
+    version: u32,
 }
 
+impl WlSeat {
+    pub fn new(id: Object, version: u32) -> Self {
+        Self { id, version }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
 
+    pub fn get_pointer(&self, pointer: u32) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            0,
+            12,
+            Message::GetPointer {
+                pointer: Object::new(pointer),
+            },
+        )
+    }
+
+    pub fn get_keyboard(&self, keyboard: u32) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            1,
+            12,
+            Message::GetKeyboard {
+                keyboard: Object::new(keyboard),
+            },
+        )
+    }
+}
@@ -0,0 +1,153 @@
+use crate::wayland::types::{
+    common::{
+        argument::{Argument, Object},
+        parse_utils::WaylandResult,
+    },
+    request::{Message, RequestMessage},
+};
+use std::{fmt, io::Cursor, os::fd::RawFd};
+
+/// A `wl_shm` pixel format: 0 and 1 for the two mandatory formats, DRM fourcc codes otherwise.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Format(pub u32);
+
+impl Format {
+    pub const ARGB8888: Self = Self(0);
+    pub const XRGB8888: Self = Self(1);
+
+    /// Bytes per pixel for the 32-bit formats this client draws into.
+    pub fn bytes_per_pixel(&self) -> Option<usize> {
+        matches!(*self, Self::ARGB8888 | Self::XRGB8888).then_some(4)
+    }
+}
+
+impl fmt::Debug for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::ARGB8888 => write!(f, "Format(ARGB8888)"),
+            Self::XRGB8888 => write!(f, "Format(XRGB8888)"),
+            Self(code) => match code.to_le_bytes() {
+                bytes if bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') => {
+                    write!(f, "Format({})", String::from_utf8_lossy(&bytes).trim_end())
+                }
+                _ => write!(f, "Format({code:#x})"),
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShmEvent {
+    Format(Format),
+}
+
+impl ShmEvent {
+    pub fn parse(opcode: u16, payload: &mut Cursor<&[u8]>) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => Ok(Some(Self::Format(Format(u32::decode(payload)?)))),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WlShm {
+    id: Object,
+}
+
+impl WlShm {
+    pub fn new(id: Object) -> Self {
+        Self { id }
+    }
+
+    /// The fd is sent alongside the message; it must stay open until the request is written.
+    pub fn create_pool(&self, pool: u32, fd: RawFd, size: i32) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            0,
+            16,
+            Message::CreatePool {
+                pool: Object::new(pool),
+                fd,
+                size,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WlShmPool {
+    id: Object,
+}
+
+impl WlShmPool {
+    pub fn new(id: Object) -> Self {
+        Self { id }
+    }
+
+    pub fn create_buffer(
+        &self,
+        buffer: u32,
+        offset: i32,
+        width: i32,
+        height: i32,
+        stride: i32,
+        format: Format,
+    ) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            0,
+            32,
+            Message::CreateBuffer {
+                buffer: Object::new(buffer),
+                offset,
+                width,
+                height,
+                stride,
+                format: format.0,
+            },
+        )
+    }
+
+    pub fn destroy(&self) -> RequestMessage {
+        RequestMessage::build(self.id, 1, 8, Message::Empty)
+    }
+
+    /// Pools can only grow.
+    pub fn resize(&self, size: i32) -> RequestMessage {
+        RequestMessage::build(self.id, 2, 12, Message::Resize { size })
+    }
+}
+
+#[derive(Debug)]
+pub enum BufferEvent {
+    Release,
+}
+
+impl BufferEvent {
+    pub fn parse(opcode: u16) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => Ok(Some(Self::Release)),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WlBuffer {
+    id: Object,
+}
+
+impl WlBuffer {
+    pub fn new(id: Object) -> Self {
+        Self { id }
+    }
+
+    pub fn id(&self) -> Object {
+        self.id
+    }
+
+    pub fn destroy(&self) -> RequestMessage {
+        RequestMessage::build(self.id, 0, 8, Message::Empty)
+    }
+}
@@ -1,21 +1,163 @@
- use crate::wayland::types:: {
-    common:: argument::Object,
-    request::{Message, RequestMessage}
- };
+use crate::wayland::types::{
+    common::{
+        argument::{Argument, Object},
+        parse_utils::WaylandResult,
+    },
+    core::output::Transform,
+    request::{Message, RequestMessage},
+};
+use std::io::Cursor;
 
-pub struct WlSurface{
-    id: Object
+#[derive(Debug)]
+pub enum SurfaceEvent {
+    /// The surface entered or left an output; which one is of no interest to lock surfaces,
+    /// which each belong to one output.
+    Enter,
+    Leave,
+    PreferredBufferScale(i32),
+    PreferredBufferTransform(Transform),
 }
 
-impl WlSurface {
-    pub fn new(id: Object) -> Self {
-        Self {
-            id
+impl SurfaceEvent {
+    pub fn parse(opcode: u16, payload: &mut Cursor<&[u8]>) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => {
+                Object::decode(payload)?;
+                Ok(Some(Self::Enter))
+            }
+            1 => {
+                Object::decode(payload)?;
+                Ok(Some(Self::Leave))
+            }
+            2 => Ok(Some(Self::PreferredBufferScale(i32::decode(payload)?))),
+            3 => Ok(Some(Self::PreferredBufferTransform(Transform::try_from(
+                u32::decode(payload)?,
+            )?))),
+            _ => Ok(None),
         }
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WlSurface {
+    id: Object,
+    version: u32,
+}
+
+impl WlSurface {
+    pub fn new(id: Object, version: u32) -> Self {
+        Self { id, version }
+    }
+
+    pub fn id(&self) -> Object {
+        self.id
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
 
     pub fn destroy(&self) -> RequestMessage {
         RequestMessage::build(self.id, 0, 8, Message::Empty)
     }
 
+    /// Attaches `buffer`, or removes the surface's content when it is `None`. The `x`/`y` offset
+    /// must be zero from version 5, where `offset` replaces it.
+    pub fn attach(&self, buffer: Option<Object>, x: i32, y: i32) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            1,
+            20,
+            Message::Attach {
+                buffer: buffer.unwrap_or(Object::new(0)),
+                x,
+                y,
+            },
+        )
+    }
+
+    /// Damage in surface coordinates, superseded by `damage_buffer` from version 4.
+    pub fn damage(&self, x: i32, y: i32, width: i32, height: i32) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            2,
+            24,
+            Message::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            },
+        )
+    }
+
+    pub fn frame(&self, callback: u32) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            3,
+            12,
+            Message::Frame {
+                callback: Object::new(callback),
+            },
+        )
+    }
+
+    /// `None` resets the opaque region to empty.
+    pub fn set_opaque_region(&self, region: Option<Object>) -> RequestMessage {
+        RequestMessage::build(
+            self.id,
+            4,
+            12,
+            Message::SetRegion {
+                region: region.unwrap_or(Object::new(0)),
+            },
+        )
+    }
+
+    pub fn commit(&self) -> RequestMessage {
+        RequestMessage::build(self.id, 6, 8, Message::Empty)
+    }
+
+    /// `wl_surface.set_buffer_transform`, only available since version 2.
+    pub fn set_buffer_transform(&self, transform: Transform) -> Option<RequestMessage> {
+        (self.version >= 2).then(|| {
+            RequestMessage::build(
+                self.id,
+                7,
+                12,
+                Message::SetBufferTransform {
+                    transform: transform as i32,
+                },
+            )
+        })
+    }
+
+    /// `wl_surface.set_buffer_scale`, only available since version 3.
+    pub fn set_buffer_scale(&self, scale: i32) -> Option<RequestMessage> {
+        (self.version >= 3)
+            .then(|| RequestMessage::build(self.id, 8, 12, Message::SetBufferScale { scale }))
+    }
+
+    /// `wl_surface.damage_buffer`, in buffer coordinates, only available since version 4.
+    pub fn damage_buffer(&self, x: i32, y: i32, width: i32, height: i32) -> Option<RequestMessage> {
+        (self.version >= 4).then(|| {
+            RequestMessage::build(
+                self.id,
+                9,
+                24,
+                Message::Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+            )
+        })
+    }
+
+    /// `wl_surface.offset`, only available since version 5.
+    pub fn offset(&self, x: i32, y: i32) -> Option<RequestMessage> {
+        (self.version >= 5)
+            .then(|| RequestMessage::build(self.id, 10, 16, Message::Offset { x, y }))
+    }
 }
@@ -1,10 +1,27 @@
-use crate::wayland::types::common::{
-    argument::{Argument, Object},
-    header::Header,
-    parse_utils::{Error, WaylandResult},
+use crate::wayland::types::{
+    common::{
+        argument::{Argument, Object},
+        header::Header,
+        parse_utils::{Error, WaylandResult},
+    },
+    core::{
+        keyboard::KeyboardEvent,
+        output::OutputEvent,
+        pointer::PointerEvent,
+        seat::SeatEvent,
+        shm::{BufferEvent, ShmEvent},
+        surface::SurfaceEvent,
+    },
+    ext::session_lock::{SessionLockEvent, SessionLockSurfaceEvent},
+    interface::Interface,
+    wp::fractional_scale::FractionalScaleEvent,
 };
 
-use std::io::Cursor;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Cursor,
+    os::fd::OwnedFd,
+};
 
 #[derive(Debug)]
 pub enum Event {
@@ -21,90 +38,92 @@ pub enum Event {
         name: String,
         version: u32,
     },
-    Callback {
-        callback_data: u32,
+    GlobalRemove {
+        id: u32,
     },
+    /// `wl_callback.done`, for frame callbacks and syncs alike; neither needs its data.
+    Callback,
+    Seat(SeatEvent),
+    Keyboard(KeyboardEvent),
+    Pointer(PointerEvent),
+    Output(OutputEvent),
+    Shm(ShmEvent),
+    Buffer(BufferEvent),
+    Surface(SurfaceEvent),
+    SessionLock(SessionLockEvent),
+    SessionLockSurface(SessionLockSurfaceEvent),
+    FractionalScale(FractionalScaleEvent),
     Other,
 }
 
 impl Event {
-    fn parse(header: Header, payload: &mut Cursor<&[u8]>) -> WaylandResult<Self> {
-        match header.object_id.inner() {
-            1 => match header.opcode {
-                0 => {
-                    let object_id = u32::decode(payload)?;
-                    let code = u32::decode(payload)?;
-                    let message = String::decode(payload)?;
-                    Ok(Self::Error {
-                        object_id,
-                        code,
-                        message,
-                    })
-                }
-                1 => {
-                    let id = u32::decode(payload)?;
-                    Ok(Self::DeleteId { id })
-                }
-                _ => {
-                    tracing::warn!(
-                        "Unhandleable event!: id {}, opcode {}",
-                        header.object_id.inner(),
-                        header.opcode
-                    );
-                    Ok(Self::Other)
-                }
-            },
-            2 => match header.opcode {
-                0 => {
-                    if payload.get_ref().len() < 8 {
-                        Err(Error::UnexpectedEndOfBuffer)
-                    } else {
-                        let id = u32::decode(payload)?;
-                        let name = String::decode(payload)?;
-                        let version = u32::decode(payload)?;
-                        Ok(Event::Interface { id, name, version })
-                    }
-                }
-                _ => {
-                    tracing::warn!(
-                        "Unhandleable event!: id {}, opcode {}",
-                        header.object_id.inner(),
-                        header.opcode
-                    );
-                    Ok(Self::Other)
-                }
-            },
-            3 => match header.opcode {
-                0 => {
-                    let callback_data = u32::decode(payload)?;
-                    let event = Self::Callback { callback_data };
-                    Ok(event)
-                }
-                _ => {
-                    tracing::warn!(
-                        "Unhandleable event!: id {}, opcode {}",
-                        header.object_id.inner(),
-                        header.opcode
-                    );
-                    Ok(Self::Other)
+    fn parse(
+        header: &Header,
+        interface: Interface,
+        payload: &mut Cursor<&[u8]>,
+        fds: &mut VecDeque<OwnedFd>,
+    ) -> WaylandResult<Self> {
+        let event = match (interface, header.opcode) {
+            (Interface::Display, 0) => {
+                let object_id = u32::decode(payload)?;
+                let code = u32::decode(payload)?;
+                let message = String::decode(payload)?;
+                Some(Self::Error {
+                    object_id,
+                    code,
+                    message,
+                })
+            }
+            (Interface::Display, 1) => {
+                let id = u32::decode(payload)?;
+                Some(Self::DeleteId { id })
+            }
+            (Interface::Registry, 0) => {
+                if payload.get_ref().len() < 8 {
+                    return Err(Error::UnexpectedEndOfBuffer);
                 }
-            },
-            _ => {
-                tracing::warn!(
-                    "Unhandleable event!: id {}, opcode {}",
-                    header.object_id.inner(),
-                    header.opcode
-                );
-                Ok(Self::Other)
+                let id = u32::decode(payload)?;
+                let name = String::decode(payload)?;
+                let version = u32::decode(payload)?;
+                Some(Self::Interface { id, name, version })
+            }
+            (Interface::Registry, 1) => {
+                let id = u32::decode(payload)?;
+                Some(Self::GlobalRemove { id })
+            }
+            (Interface::Callback, 0) => Some(Self::Callback),
+            (Interface::Seat, opcode) => SeatEvent::parse(opcode, payload)?.map(Self::Seat),
+            (Interface::Keyboard, opcode) => {
+                KeyboardEvent::parse(opcode, payload, fds)?.map(Self::Keyboard)
+            }
+            (Interface::Pointer, opcode) => PointerEvent::parse(opcode)?.map(Self::Pointer),
+            (Interface::Output, opcode) => OutputEvent::parse(opcode, payload)?.map(Self::Output),
+            (Interface::Shm, opcode) => ShmEvent::parse(opcode, payload)?.map(Self::Shm),
+            (Interface::Buffer, opcode) => BufferEvent::parse(opcode)?.map(Self::Buffer),
+            (Interface::Surface, opcode) => {
+                SurfaceEvent::parse(opcode, payload)?.map(Self::Surface)
+            }
+            (Interface::SessionLock, opcode) => {
+                SessionLockEvent::parse(opcode)?.map(Self::SessionLock)
+            }
+            (Interface::SessionLockSurface, opcode) => {
+                SessionLockSurfaceEvent::parse(opcode, payload)?.map(Self::SessionLockSurface)
+            }
+            (Interface::FractionalScale, opcode) => {
+                FractionalScaleEvent::parse(opcode, payload)?.map(Self::FractionalScale)
             }
-        }
-    }
-
-    pub fn get_global(&self) -> Option<(u32, String, u32)> {
-        match self {
-            Self::Interface { id, name, version } => Some((*id, name.to_string(), *version)),
             _ => None,
-        }
+        };
+
+        Ok(event.unwrap_or_else(|| {
+            tracing::warn!(
+                "Unhandleable event!: id {} ({}), opcode {}",
+                header.object_id.inner(),
+                interface.name(),
+                header.opcode
+            );
+            Self::Other
+        }))
     }
 }
 
@@ -114,49 +133,60 @@ pub struct EventMessage {
 }
 
 impl EventMessage {
-    pub fn parse_messages(buffer: &[u8]) -> Vec<Self> {
+    /// Parses every complete message at the start of `buffer`, returning them together with the
+    /// number of bytes consumed. A trailing partial message is left for the next read.
+    pub fn parse_messages(
+        buffer: &[u8],
+        objects: &HashMap<u32, Interface>,
+        fds: &mut VecDeque<OwnedFd>,
+    ) -> WaylandResult<(Vec<Self>, usize)> {
         let mut messages = Vec::new();
-        let mut buf = Cursor::new(buffer);
         let mut offset = 0;
 
-        while offset + 8 < buf.get_ref().len() {
-            let message = Self::parse(&mut buf).unwrap();
-            offset += message.header.size as usize;
-            messages.push(message);
+        while buffer.len() - offset >= 8 {
+            let size = u16::from_le_bytes([buffer[offset + 6], buffer[offset + 7]]) as usize;
+            if size < 8 || !size.is_multiple_of(4) {
+                return Err(Error::ParseError(format!("invalid message size {size}")));
+            }
+            if buffer.len() - offset < size {
+                break;
+            }
+            messages.push(Self::parse(&buffer[offset..offset + size], objects, fds)?);
+            offset += size;
         }
 
-        messages
+        Ok((messages, offset))
     }
 
-    fn parse(buffer: &mut Cursor<&[u8]>) -> WaylandResult<Self> {
-        let object_id = Object::decode(buffer)?;
-        let opcode = u16::decode(buffer)?;
-        let message_size = u16::decode(buffer)?;
+    fn parse(
+        message: &[u8],
+        objects: &HashMap<u32, Interface>,
+        fds: &mut VecDeque<OwnedFd>,
+    ) -> WaylandResult<Self> {
+        let mut buffer = Cursor::new(message);
+        let object_id = Object::decode(&mut buffer)?;
+        let opcode = u16::decode(&mut buffer)?;
+        let message_size = u16::decode(&mut buffer)?;
         let header = Header::new(object_id, opcode, message_size);
-        if message_size > buffer.get_ref().len() as u16 {
-            Err(Error::UnexpectedEndOfBuffer)
-        } else {
-            let payload = Event::parse(header.clone(), buffer)?;
-            tracing::info!("{:?}", payload);
-            Ok(Self { header, payload })
-        }
-    }
 
-    pub fn is_global(&self) -> bool {
-        match self.payload {
-            Event::Interface {
-                id: _,
-                name: _,
-                version: _,
-            } => true,
-            _ => false,
+        let payload = match objects.get(&object_id.inner()) {
+            Some(interface) => {
+                let mut body = Cursor::new(&message[8..]);
+                Event::parse(&header, *interface, &mut body, fds)?
+            }
+            None => {
+                tracing::warn!("Event for unknown object {}", object_id.inner());
+                Event::Other
+            }
+        };
+        // Key events say what is being typed, which may be the password.
+        if !matches!(payload, Event::Keyboard(_)) {
+            tracing::trace!("{payload:?}");
         }
+        Ok(Self { header, payload })
     }
 
-    pub fn is_callback_done(&self) -> bool {
-        match self.payload {
-            Event::Callback { callback_data: _ } => true,
-            _ => false,
-        }
+    pub fn object_id(&self) -> Object {
+        self.header.object_id
     }
 }
@@ -1,7 +1,11 @@
 use crate::wayland::types::{
-    common::argument::Object,
+    common::{
+        argument::{Argument, Object},
+        parse_utils::WaylandResult,
+    },
     request::{Message, RequestMessage},
 };
+use std::io::Cursor;
 
 #[derive(Debug, Clone, Copy)]
 pub struct SessionLockManager(Object);
@@ -11,10 +15,6 @@ impl SessionLockManager {
         Self(id)
     }
 
-    pub fn destroy(&self) -> RequestMessage {
-        RequestMessage::build(self.0, 0, 8, Message::Empty)
-    }
-
     pub fn lock(&self, ext_session_lock_v1: u32) -> RequestMessage {
         RequestMessage::build(
             self.0,
@@ -27,6 +27,24 @@ impl SessionLockManager {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionLockEvent {
+    /// Every output is now covered by lock surfaces or blanked.
+    Locked,
+    /// The compositor refused or ended the lock; no unlock is needed.
+    Finished,
+}
+
+impl SessionLockEvent {
+    pub fn parse(opcode: u16) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => Ok(Some(Self::Locked)),
+            1 => Ok(Some(Self::Finished)),
+            _ => Ok(None),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct SessionLock(Object);
 
@@ -35,13 +53,72 @@ impl SessionLock {
         Self(id)
     }
 
+    pub fn id(&self) -> Object {
+        self.0
+    }
+
     pub fn destroy(&self) -> RequestMessage {
         RequestMessage::build(self.0, 0, 8, Message::Empty)
     }
 
-    pub fn get_lock_surface(&self) {}
+    pub fn get_lock_surface(&self, id: u32, surface: Object, output: Object) -> RequestMessage {
+        RequestMessage::build(
+            self.0,
+            1,
+            20,
+            Message::GetLockSurface {
+                id: Object::new(id),
+                surface,
+                output,
+            },
+        )
+    }
 
     pub fn unlock_and_destroy(&self) -> RequestMessage {
         RequestMessage::build(self.0, 2, 8, Message::Empty)
     }
 }
+
+#[derive(Debug)]
+pub enum SessionLockSurfaceEvent {
+    Configure {
+        serial: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl SessionLockSurfaceEvent {
+    pub fn parse(opcode: u16, payload: &mut Cursor<&[u8]>) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => {
+                let serial = u32::decode(payload)?;
+                let width = u32::decode(payload)?;
+                let height = u32::decode(payload)?;
+                Ok(Some(Self::Configure {
+                    serial,
+                    width,
+                    height,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionLockSurface(Object);
+
+impl SessionLockSurface {
+    pub fn new(id: Object) -> Self {
+        Self(id)
+    }
+
+    pub fn destroy(&self) -> RequestMessage {
+        RequestMessage::build(self.0, 0, 8, Message::Empty)
+    }
+
+    pub fn ack_configure(&self, serial: u32) -> RequestMessage {
+        RequestMessage::build(self.0, 1, 12, Message::AckConfigure { serial })
+    }
+}
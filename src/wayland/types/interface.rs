@@ -0,0 +1,62 @@
+/// The interface an object id was created with, used to pick the right event decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interface {
+    Display,
+    Registry,
+    Callback,
+    Seat,
+    Keyboard,
+    Pointer,
+    Compositor,
+    Surface,
+    Region,
+    Output,
+    Shm,
+    ShmPool,
+    Buffer,
+    SessionLockManager,
+    SessionLock,
+    SessionLockSurface,
+    FractionalScaleManager,
+    FractionalScale,
+    Viewporter,
+    Viewport,
+}
+
+impl Interface {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Display => "wl_display",
+            Self::Registry => "wl_registry",
+            Self::Callback => "wl_callback",
+            Self::Seat => "wl_seat",
+            Self::Keyboard => "wl_keyboard",
+            Self::Pointer => "wl_pointer",
+            Self::Compositor => "wl_compositor",
+            Self::Surface => "wl_surface",
+            Self::Region => "wl_region",
+            Self::Output => "wl_output",
+            Self::Shm => "wl_shm",
+            Self::ShmPool => "wl_shm_pool",
+            Self::Buffer => "wl_buffer",
+            Self::SessionLockManager => "ext_session_lock_manager_v1",
+            Self::SessionLock => "ext_session_lock_v1",
+            Self::SessionLockSurface => "ext_session_lock_surface_v1",
+            Self::FractionalScaleManager => "wp_fractional_scale_manager_v1",
+            Self::FractionalScale => "wp_fractional_scale_v1",
+            Self::Viewporter => "wp_viewporter",
+            Self::Viewport => "wp_viewport",
+        }
+    }
+
+    /// Highest version of the interface this client implements.
+    pub fn max_version(&self) -> u32 {
+        match self {
+            Self::Seat | Self::Keyboard | Self::Pointer => 10,
+            Self::Compositor => 6,
+            Self::Output => 4,
+            Self::Shm => 2,
+            _ => 1,
+        }
+    }
+}
@@ -2,13 +2,34 @@ mod common;
 mod core;
 mod event;
 mod ext;
+mod interface;
 mod request;
+mod wp;
 
 pub use common::{
-    argument::{Argument, NewId, Object},
-    parse_utils::{Error, WaylandResult},
+    argument::{NewId, Object},
+    mmap::MappedFile,
 };
-pub use core::{display::WlDisplay, registry::WlRegistry};
-pub use event::EventMessage;
-pub use ext::session_lock::{SessionLock, SessionLockManager};
+pub use core::{
+    compositor::{WlCompositor, WlRegion},
+    display::WlDisplay,
+    keyboard::{KeyState, KeyboardEvent, WlKeyboard},
+    output::{OutputEvent, Transform, WlOutput},
+    pointer::{PointerEvent, WlPointer},
+    registry::WlRegistry,
+    seat::{Capability, SeatEvent, WlSeat},
+    shm::{BufferEvent, Format, ShmEvent, WlBuffer, WlShm, WlShmPool},
+    surface::{SurfaceEvent, WlSurface},
+};
+pub use event::{Event, EventMessage};
+pub use ext::session_lock::{
+    SessionLock, SessionLockEvent, SessionLockManager, SessionLockSurface, SessionLockSurfaceEvent,
+};
+pub use interface::Interface;
 pub use request::RequestMessage;
+pub use wp::{
+    fractional_scale::{
+        FractionalScale, FractionalScaleEvent, FractionalScaleManager, SCALE_DENOMINATOR,
+    },
+    viewporter::{Viewport, Viewporter},
+};
@@ -3,13 +3,95 @@ use crate::wayland::types::common::{
     header::Header,
     parse_utils::WaylandResult,
 };
+use std::os::fd::RawFd;
 
 #[derive(Debug)]
 pub enum Message {
-    GetRegistry { registry: Object },
-    Sync { callback: u32 },
-    Bind { name: Object, id: NewId },
-    Lock { ext_session_lock_v1: Object },
+    GetRegistry {
+        registry: Object,
+    },
+    Sync {
+        callback: u32,
+    },
+    Bind {
+        name: Object,
+        id: NewId,
+    },
+    Lock {
+        ext_session_lock_v1: Object,
+    },
+    GetLockSurface {
+        id: Object,
+        surface: Object,
+        output: Object,
+    },
+    AckConfigure {
+        serial: u32,
+    },
+    /// Creates an object extending a surface, such as a viewport.
+    GetSurfaceExtension {
+        id: Object,
+        surface: Object,
+    },
+    SetDestination {
+        width: i32,
+        height: i32,
+    },
+    GetPointer {
+        pointer: Object,
+    },
+    GetKeyboard {
+        keyboard: Object,
+    },
+    CreatePool {
+        pool: Object,
+        fd: RawFd,
+        size: i32,
+    },
+    CreateBuffer {
+        buffer: Object,
+        offset: i32,
+        width: i32,
+        height: i32,
+        stride: i32,
+        format: u32,
+    },
+    Resize {
+        size: i32,
+    },
+    CreateSurface {
+        surface: Object,
+    },
+    CreateRegion {
+        region: Object,
+    },
+    Attach {
+        buffer: Object,
+        x: i32,
+        y: i32,
+    },
+    Frame {
+        callback: Object,
+    },
+    SetRegion {
+        region: Object,
+    },
+    SetBufferTransform {
+        transform: i32,
+    },
+    SetBufferScale {
+        scale: i32,
+    },
+    Offset {
+        x: i32,
+        y: i32,
+    },
+    Rectangle {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
     Empty,
 }
 
@@ -25,9 +107,83 @@ impl Message {
             Self::Lock {
                 ext_session_lock_v1,
             } => ext_session_lock_v1.encode(),
+            Self::GetLockSurface {
+                id,
+                surface,
+                output,
+            } => {
+                let bytes = id.encode_extend(Vec::new())?;
+                let bytes = surface.encode_extend(bytes)?;
+                output.encode_extend(bytes)
+            }
+            Self::AckConfigure { serial } => serial.encode(),
+            Self::GetSurfaceExtension { id, surface } => {
+                let bytes = id.encode_extend(Vec::new())?;
+                surface.encode_extend(bytes)
+            }
+            Self::SetDestination { width, height } => {
+                let bytes = width.encode_extend(Vec::new())?;
+                height.encode_extend(bytes)
+            }
+            Self::GetPointer { pointer } => pointer.encode(),
+            Self::GetKeyboard { keyboard } => keyboard.encode(),
+            // The fd travels as ancillary data, not in the message body.
+            Self::CreatePool { pool, size, .. } => {
+                let buffer = pool.encode_extend(Vec::new())?;
+                size.encode_extend(buffer)
+            }
+            Self::CreateBuffer {
+                buffer,
+                offset,
+                width,
+                height,
+                stride,
+                format,
+            } => {
+                let mut bytes = buffer.encode_extend(Vec::new())?;
+                for value in [offset, width, height, stride] {
+                    bytes = value.encode_extend(bytes)?;
+                }
+                format.encode_extend(bytes)
+            }
+            Self::Resize { size } => size.encode(),
+            Self::CreateSurface { surface } => surface.encode(),
+            Self::CreateRegion { region } => region.encode(),
+            Self::Attach { buffer, x, y } => {
+                let bytes = buffer.encode_extend(Vec::new())?;
+                let bytes = x.encode_extend(bytes)?;
+                y.encode_extend(bytes)
+            }
+            Self::Frame { callback } => callback.encode(),
+            Self::SetRegion { region } => region.encode(),
+            Self::SetBufferTransform { transform } => transform.encode(),
+            Self::SetBufferScale { scale } => scale.encode(),
+            Self::Offset { x, y } => {
+                let bytes = x.encode_extend(Vec::new())?;
+                y.encode_extend(bytes)
+            }
+            Self::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let mut bytes = Vec::new();
+                for value in [x, y, width, height] {
+                    bytes = value.encode_extend(bytes)?;
+                }
+                Ok(bytes)
+            }
             Self::Empty => Ok(Vec::new()),
         }
     }
+
+    fn fds(&self) -> Vec<RawFd> {
+        match self {
+            Self::CreatePool { fd, .. } => vec![*fd],
+            _ => Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -50,4 +206,9 @@ impl RequestMessage {
         request.extend(self.payload.to_vec()?);
         Ok(request)
     }
+
+    /// File descriptors to pass alongside the encoded message.
+    pub fn fds(&self) -> Vec<RawFd> {
+        self.payload.fds()
+    }
 }
@@ -0,0 +1,60 @@
+use crate::wayland::types::{
+    common::{
+        argument::{Argument, Object},
+        parse_utils::WaylandResult,
+    },
+    request::{Message, RequestMessage},
+};
+use std::io::Cursor;
+
+/// Scales are sent as multiples of 1/120.
+pub const SCALE_DENOMINATOR: u32 = 120;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FractionalScaleManager(Object);
+
+impl FractionalScaleManager {
+    pub fn new(id: Object) -> Self {
+        Self(id)
+    }
+
+    pub fn get_fractional_scale(&self, id: u32, surface: Object) -> RequestMessage {
+        RequestMessage::build(
+            self.0,
+            1,
+            16,
+            Message::GetSurfaceExtension {
+                id: Object::new(id),
+                surface,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractionalScaleEvent {
+    /// The scale the compositor would like the surface rendered at, in 120ths.
+    PreferredScale(u32),
+}
+
+impl FractionalScaleEvent {
+    pub fn parse(opcode: u16, payload: &mut Cursor<&[u8]>) -> WaylandResult<Option<Self>> {
+        match opcode {
+            0 => Ok(Some(Self::PreferredScale(u32::decode(payload)?))),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FractionalScale(Object);
+
+impl FractionalScale {
+    pub fn new(id: Object) -> Self {
+        Self(id)
+    }
+
+    pub fn destroy(&self) -> RequestMessage {
+        RequestMessage::build(self.0, 0, 8, Message::Empty)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod fractional_scale;
+pub mod viewporter;
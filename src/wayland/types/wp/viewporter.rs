@@ -0,0 +1,44 @@
+use crate::wayland::types::{
+    common::argument::Object,
+    request::{Message, RequestMessage},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Viewporter(Object);
+
+impl Viewporter {
+    pub fn new(id: Object) -> Self {
+        Self(id)
+    }
+
+    pub fn get_viewport(&self, id: u32, surface: Object) -> RequestMessage {
+        RequestMessage::build(
+            self.0,
+            1,
+            16,
+            Message::GetSurfaceExtension {
+                id: Object::new(id),
+                surface,
+            },
+        )
+    }
+}
+
+/// Crops and scales a surface's buffer, independently of its buffer scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport(Object);
+
+impl Viewport {
+    pub fn new(id: Object) -> Self {
+        Self(id)
+    }
+
+    pub fn destroy(&self) -> RequestMessage {
+        RequestMessage::build(self.0, 0, 8, Message::Empty)
+    }
+
+    /// Sets the surface size the whole buffer is scaled to, in surface coordinates.
+    pub fn set_destination(&self, width: i32, height: i32) -> RequestMessage {
+        RequestMessage::build(self.0, 2, 16, Message::SetDestination { width, height })
+    }
+}
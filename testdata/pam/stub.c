@@ -0,0 +1,113 @@
+/*
+ * A PAM module for testing the PAM backend without real accounts. The tests build it and
+ * point a service at it with pam_start_confdir.
+ *
+ * Authentication greets the user, asks for the password "hunter2" and then, visibly, for the
+ * code "42". Account management fails with PAM_ACCT_EXPIRED when given the "expired" argument
+ * and with PAM_NEW_AUTHTOK_REQD when given "new-password". Refreshing credentials says so.
+ *
+ * It declares what it needs from the PAM headers itself, so that only libpam.so.0 has to be
+ * installed to build it.
+ */
+
+#include <stdlib.h>
+#include <string.h>
+
+#define PAM_SUCCESS 0
+#define PAM_AUTH_ERR 7
+#define PAM_CRED_ERR 17
+#define PAM_NEW_AUTHTOK_REQD 12
+#define PAM_ACCT_EXPIRED 13
+#define PAM_CONV_ERR 19
+#define PAM_CONV 5
+#define PAM_REFRESH_CRED 0x0010
+
+#define PAM_PROMPT_ECHO_OFF 1
+#define PAM_PROMPT_ECHO_ON 2
+#define PAM_ERROR_MSG 3
+#define PAM_TEXT_INFO 4
+
+struct pam_message {
+    int msg_style;
+    const char *msg;
+};
+
+struct pam_response {
+    char *resp;
+    int resp_retcode;
+};
+
+struct pam_conv {
+    int (*conv)(int, const struct pam_message **, struct pam_response **, void *);
+    void *appdata_ptr;
+};
+
+extern int pam_get_item(const void *pamh, int item_type, const void **item);
+
+/* Puts one message to the application, storing the answer in `answer` if one is wanted. */
+static int converse(void *pamh, int style, const char *text, char **answer) {
+    const struct pam_conv *conv;
+    if (pam_get_item(pamh, PAM_CONV, (const void **)&conv) != PAM_SUCCESS || !conv)
+        return PAM_CONV_ERR;
+    struct pam_message message = {style, text};
+    const struct pam_message *messages = &message;
+    struct pam_response *response = NULL;
+    int status = conv->conv(1, &messages, &response, conv->appdata_ptr);
+    if (status != PAM_SUCCESS)
+        return status;
+    char *text_answer = response ? response->resp : NULL;
+    free(response);
+    if (answer)
+        *answer = text_answer;
+    else
+        free(text_answer);
+    return PAM_SUCCESS;
+}
+
+/* Whether `answer` is `expected`, wiping and freeing the answer. */
+static int check(char *answer, const char *expected) {
+    if (!answer)
+        return 0;
+    int matches = strcmp(answer, expected) == 0;
+    memset(answer, 0, strlen(answer));
+    free(answer);
+    return matches;
+}
+
+static int has_argument(int argc, const char **argv, const char *name) {
+    for (int i = 0; i < argc; i++)
+        if (strcmp(argv[i], name) == 0)
+            return 1;
+    return 0;
+}
+
+int pam_sm_authenticate(void *pamh, int flags, int argc, const char **argv) {
+    char *answer = NULL;
+    int status = converse(pamh, PAM_TEXT_INFO, "Welcome to the stub", NULL);
+    if (status == PAM_SUCCESS)
+        status = converse(pamh, PAM_PROMPT_ECHO_OFF, "Password: ", &answer);
+    if (status != PAM_SUCCESS)
+        return status;
+    if (!check(answer, "hunter2")) {
+        converse(pamh, PAM_ERROR_MSG, "Wrong stub password", NULL);
+        return PAM_AUTH_ERR;
+    }
+    status = converse(pamh, PAM_PROMPT_ECHO_ON, "Code: ", &answer);
+    if (status != PAM_SUCCESS)
+        return status;
+    return check(answer, "42") ? PAM_SUCCESS : PAM_AUTH_ERR;
+}
+
+int pam_sm_acct_mgmt(void *pamh, int flags, int argc, const char **argv) {
+    if (has_argument(argc, argv, "expired"))
+        return PAM_ACCT_EXPIRED;
+    if (has_argument(argc, argv, "new-password"))
+        return PAM_NEW_AUTHTOK_REQD;
+    return PAM_SUCCESS;
+}
+
+int pam_sm_setcred(void *pamh, int flags, int argc, const char **argv) {
+    if (!(flags & PAM_REFRESH_CRED))
+        return PAM_CRED_ERR;
+    return converse(pamh, PAM_TEXT_INFO, "Credentials refreshed", NULL);
+}